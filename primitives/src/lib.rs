@@ -0,0 +1,336 @@
+//! Types, traits and runtime API of the DEX pallet, for node-side code and other pallets
+//! which shouldn't depend on the pallet itself (and its FRAME version).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode, MaxEncodedLen};
+use scale_info::prelude::format;
+use sp_runtime::traits::{MaybeDisplay, NumberFor};
+use sp_runtime::{ArithmeticError, DispatchError, ModuleError, Permill, TokenError};
+use sp_std::vec::Vec;
+
+/// Asset price, i.e. amount of currency per one unit of the asset. Prices computed by the pallet
+/// are always rounded down.
+pub type Price = sp_runtime::FixedU128;
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum RpcError {
+    ExchangeNotFound,
+    NotEnoughLiquidity,
+    Overflow,
+    Unexpected(Vec<u8>),
+    /// An error of a pallet (e.g. assets or balances, or a DEX pallet error other than the above)
+    Module {
+        index: u8,
+        error: [u8; 4],
+    },
+    Arithmetic(ArithmeticError),
+    Token(TokenError),
+    /// The exchange has no liquidity (zero reserves), i.e. it hasn't been seeded yet
+    NoLiquidity,
+}
+
+pub type RpcResult<T> = Result<T, RpcError>;
+
+impl RpcError {
+    /// Whether the error is transient, i.e. caused by the current state of the pool or accounts
+    /// (not enough liquidity for this size, pool not seeded yet, frozen or missing account,
+    /// insufficient balance etc.), so the same query may succeed later. Pallet errors depend on
+    /// the state of the accounts and pools (e.g. `InsufficientBalance` of the balances pallet),
+    /// so they are retryable. Permanent errors (exchange not found, asset destroyed or
+    /// unsupported, overflow, arithmetic and unexpected errors) are not worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::NotEnoughLiquidity | Self::NoLiquidity | Self::Module { .. } => true,
+            Self::Token(err) => !matches!(err, TokenError::UnknownAsset | TokenError::Unsupported),
+            Self::ExchangeNotFound | Self::Overflow | Self::Unexpected(_) | Self::Arithmetic(_) => {
+                false
+            }
+        }
+    }
+}
+
+impl From<DispatchError> for RpcError {
+    fn from(err: DispatchError) -> Self {
+        match err {
+            DispatchError::Module(ModuleError { index, error, .. }) => {
+                Self::Module { index, error }
+            }
+            DispatchError::Arithmetic(err) => Self::Arithmetic(err),
+            DispatchError::Token(err) => Self::Token(err),
+            err => Self::Unexpected(format!("{:?}", err).into_bytes()),
+        }
+    }
+}
+
+/// Reserves and spot price of an exchange.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct ExchangeSnapshot<AssetId, Balance, AssetBalance> {
+    pub asset_id: AssetId,
+    pub currency_reserve: Balance,
+    pub token_reserve: AssetBalance,
+    pub spot_price: Price,
+}
+
+/// Reserves of an exchange and the status of its asset for a recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct AssetStatus<Balance, AssetBalance> {
+    pub currency_reserve: Balance,
+    pub token_reserve: AssetBalance,
+    /// Minimum balance of the asset an account can hold
+    pub min_balance: AssetBalance,
+    /// Whether the recipient can receive the amount (or the reason it can't, e.g. the asset is
+    /// not sufficient and the recipient account doesn't exist)
+    pub can_receive: RpcResult<()>,
+}
+
+/// Position of an account in an exchange, returned by the `get_account_positions` runtime API
+/// method (since version 2 of [`DexAccountApi`]).
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct AccountPosition<AssetId, Balance, AssetBalance> {
+    pub asset_id: AssetId,
+    /// Liquidity token balance of the account
+    pub liquidity: AssetBalance,
+    /// Part of `liquidity` the account can burn, i.e. which is not frozen (locked)
+    pub withdrawable_liquidity: AssetBalance,
+    /// Currency and tokens escrowed by a liquidity stream of the account, not streamed yet
+    pub streamed: Option<(Balance, AssetBalance)>,
+    /// Rebate funds left, if the account sponsors the exchange
+    pub sponsorship: Option<Balance>,
+}
+
+/// A page of the positions of an account, see [`AccountPosition`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct AccountPositions<AssetId, Balance, AssetBalance> {
+    pub positions: Vec<AccountPosition<AssetId, Balance, AssetBalance>>,
+    /// Asset ID of the last visited exchange, to continue the query from (`None` if all
+    /// exchanges have been visited)
+    pub next: Option<AssetId>,
+}
+
+/// Outcome of a call dry-run by the `simulate_calls` runtime API method: its result and the events
+/// it would emit (none if it fails).
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SimulatedCall<Event> {
+    pub result: RpcResult<()>,
+    pub events: Vec<Event>,
+}
+
+/// Trade to quote with the `quote` runtime API method (since version 6 of [`DexApi`]).
+/// New quote parameters are added here, rather than to the runtime API method signature.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen)]
+pub enum QuoteRequest<AssetId, Balance, AssetBalance> {
+    /// Fixed-input currency-to-asset trade
+    CurrencyToAssetInput {
+        asset_id: AssetId,
+        currency_amount: Balance,
+    },
+    /// Fixed-output currency-to-asset trade
+    CurrencyToAssetOutput {
+        asset_id: AssetId,
+        token_amount: AssetBalance,
+    },
+    /// Fixed-input asset-to-currency trade
+    AssetToCurrencyInput {
+        asset_id: AssetId,
+        token_amount: AssetBalance,
+    },
+    /// Fixed-output asset-to-currency trade
+    AssetToCurrencyOutput {
+        asset_id: AssetId,
+        currency_amount: Balance,
+    },
+}
+
+impl<AssetId, Balance, AssetBalance> QuoteRequest<AssetId, Balance, AssetBalance> {
+    /// ID of the asset of the quoted exchange.
+    pub fn asset_id(&self) -> &AssetId {
+        match self {
+            Self::CurrencyToAssetInput { asset_id, .. }
+            | Self::CurrencyToAssetOutput { asset_id, .. }
+            | Self::AssetToCurrencyInput { asset_id, .. }
+            | Self::AssetToCurrencyOutput { asset_id, .. } => asset_id,
+        }
+    }
+}
+
+/// Currency and token amounts of a quoted trade (one of them as requested), and the number of the
+/// block at whose state the quote was computed (since version 10 of [`DexApi`]). The hash of that
+/// block is the one the runtime API was called at. Passing the block number as `quoted_at` of the
+/// price limit of an extrinsic lets it reject execution once the quote is too old.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct Quote<Balance, AssetBalance, BlockNumber> {
+    pub currency_amount: Balance,
+    pub token_amount: AssetBalance,
+    pub block_number: BlockNumber,
+}
+
+/// Currency and token amounts of a quoted trade, as returned by the quote methods of [`DexApi`]
+/// before version 10 (without the block number).
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct QuoteBeforeV10<Balance, AssetBalance> {
+    pub currency_amount: Balance,
+    pub token_amount: AssetBalance,
+}
+
+impl<Balance, AssetBalance> QuoteBeforeV10<Balance, AssetBalance> {
+    /// The quote, computed at the state of block `block_number`.
+    pub fn at<BlockNumber>(
+        self,
+        block_number: BlockNumber,
+    ) -> Quote<Balance, AssetBalance, BlockNumber> {
+        Quote {
+            currency_amount: self.currency_amount,
+            token_amount: self.token_amount,
+            block_number,
+        }
+    }
+}
+
+/// How the amounts of a [`SourcedQuote`] were computed.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum QuoteSource {
+    /// From the reserves of the exchange
+    Amm,
+    /// From the peg reference of the exchange (plus or minus its band), as the exchange liquidity
+    /// is too thin for a meaningful AMM quote
+    PegFallback,
+}
+
+/// Quote returned by the `quote_with_fallback` runtime API method (since version 9 of
+/// [`DexApi`]), marked with its source.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SourcedQuote<Balance, AssetBalance, BlockNumber> {
+    pub quote: Quote<Balance, AssetBalance, BlockNumber>,
+    pub source: QuoteSource,
+}
+
+/// Quote returned by the `quote_with_fallback` runtime API method before version 10 of
+/// [`DexApi`] (without the block number).
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SourcedQuoteBeforeV10<Balance, AssetBalance> {
+    pub quote: QuoteBeforeV10<Balance, AssetBalance>,
+    pub source: QuoteSource,
+}
+
+/// Configuration of the pallet (`Config` constants and governance-set parameters), returned by
+/// the `dex_parameters` runtime API method (since version 7 of [`DexApi`]), so frontends don't
+/// need to hardcode values which may drift from the runtime.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct DexParameters<Balance, BlockNumber> {
+    /// Liquidity provider fee numerator
+    pub provider_fee_numerator: Balance,
+    /// Liquidity provider fee denominator
+    pub provider_fee_denominator: Balance,
+    /// Minimum currency deposit for a new exchange
+    pub min_deposit: Balance,
+    /// Fee charged for creating an exchange
+    pub creation_fee: Balance,
+    /// Deadline (number of blocks after the current block) of operations submitted without one
+    pub default_deadline: BlockNumber,
+    /// Maximum number of blocks after the current block the deadline of an operation can be
+    pub max_deadline: BlockNumber,
+    /// Maximum number of blocks after the current block an exchange can be in trade-only mode
+    pub max_trade_only_period: BlockNumber,
+    /// Maximum number of accounts whitelisted for the bootstrap phase of an exchange
+    pub max_bootstrap_accounts: u32,
+    /// Maximum number of snapshot deposits seeded by a single call
+    pub max_seed_deposits: u32,
+    /// Maximum number of operations in a single `batch_liquidity` call
+    pub max_liquidity_ops: u32,
+    /// Share of the token reserve by which the pallet account balance may diverge from the reserve
+    pub reserve_mismatch_tolerance: Permill,
+    /// Amount of currency reserved for each user-created storage item (e.g. an operator approval)
+    pub storage_deposit: Balance,
+    /// Number of blocks liquidity can be withdrawn from a delisted exchange
+    pub delisting_grace_period: BlockNumber,
+}
+
+/// Handler for newly created exchanges. Can be used by registry, listing or incentive pallets
+/// to react to a new pool at creation time. Implemented for tuples, so multiple listeners
+/// can be notified.
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+pub trait OnPoolCreated<AccountId, AssetId> {
+    /// Called after an exchange for `asset_id` (with `liquidity_token_id` liquidity token)
+    /// has been created by `creator`.
+    fn on_pool_created(asset_id: &AssetId, liquidity_token_id: &AssetId, creator: &AccountId);
+}
+
+/// Read-only view of the exchanges, for pallets making decisions based on them (e.g. whether
+/// there is a pool for an asset before accepting the asset for fee payment).
+pub trait InspectDex<AssetId, Balance, AssetBalance> {
+    /// Whether an exchange for `asset_id` exists.
+    fn exchange_exists(asset_id: &AssetId) -> bool;
+    /// Currency and token reserves of the exchange for `asset_id` (if it exists).
+    fn reserves(asset_id: &AssetId) -> Option<(Balance, AssetBalance)>;
+    /// ID of the liquidity token of the exchange for `asset_id` (if it exists).
+    fn lp_token_of(asset_id: &AssetId) -> Option<AssetId>;
+}
+
+sp_api::decl_runtime_apis! {
+    /// Price queries. Versions:
+    ///   1. Quote methods for each trade type (`get_*_price`).
+    ///   2. `get_spot_price`.
+    ///   3. `get_exchange_by_liquidity_token`.
+    ///   4. `get_exchanges_snapshot` ([`ExchangeSnapshot`]).
+    ///   5. `get_reserves` and `get_reserves_batch`, with the block of the last reserves update.
+    ///   6. `quote` taking a [`QuoteRequest`]. The quote methods of version 1 are kept
+    ///      (and implemented as shims over `quote`), so existing node RPC keeps working.
+    ///   7. `dex_parameters` returning the pallet configuration ([`DexParameters`]).
+    ///   8. `quote_with_reserves`, quoting a trade against hypothetical reserves.
+    ///   9. `quote_with_fallback`, quoting the peg reference of thin exchanges ([`SourcedQuote`]).
+    ///   10. Quotes ([`Quote`]) include the number of the block they were computed at. The previous
+    ///       signatures of the quote methods are kept (`*_before_version_10`), so node code can
+    ///       still decode the quotes of older runtimes.
+    #[api_version(10)]
+    pub trait DexApi<AssetId, Balance, AssetBalance> where
+        AssetId: Codec + MaybeDisplay,
+        Balance: Codec + MaybeDisplay,
+        AssetBalance: Codec + MaybeDisplay,
+    {
+        fn get_currency_to_asset_input_price(asset_id: AssetId, currency_amount: Balance) -> RpcResult<AssetBalance>;
+        fn get_currency_to_asset_output_price(asset_id: AssetId, token_amount: AssetBalance) -> RpcResult<Balance>;
+        fn get_asset_to_currency_input_price(asset_id: AssetId, token_amount: AssetBalance) -> RpcResult<Balance>;
+        fn get_asset_to_currency_output_price(asset_id: AssetId, currency_amount: Balance) -> RpcResult<AssetBalance>;
+        fn get_spot_price(asset_id: AssetId) -> RpcResult<Price>;
+        fn get_exchange_by_liquidity_token(liquidity_token_id: AssetId) -> RpcResult<AssetId>;
+        fn get_reserves(asset_id: AssetId) -> RpcResult<(Balance, AssetBalance, NumberFor<Block>)>;
+        fn get_reserves_batch(asset_ids: Vec<AssetId>) -> Vec<RpcResult<(Balance, AssetBalance, NumberFor<Block>)>>;
+        fn get_exchanges_snapshot(asset_ids: Vec<AssetId>) -> Vec<RpcResult<ExchangeSnapshot<AssetId, Balance, AssetBalance>>>;
+        fn quote(request: QuoteRequest<AssetId, Balance, AssetBalance>) -> RpcResult<Quote<Balance, AssetBalance, NumberFor<Block>>>;
+        #[changed_in(10)]
+        fn quote(request: QuoteRequest<AssetId, Balance, AssetBalance>) -> RpcResult<QuoteBeforeV10<Balance, AssetBalance>>;
+        fn dex_parameters() -> DexParameters<Balance, NumberFor<Block>>;
+        fn quote_with_reserves(request: QuoteRequest<AssetId, Balance, AssetBalance>, currency_reserve: Balance, token_reserve: AssetBalance) -> RpcResult<Quote<Balance, AssetBalance, NumberFor<Block>>>;
+        #[changed_in(10)]
+        fn quote_with_reserves(request: QuoteRequest<AssetId, Balance, AssetBalance>, currency_reserve: Balance, token_reserve: AssetBalance) -> RpcResult<QuoteBeforeV10<Balance, AssetBalance>>;
+        fn quote_with_fallback(request: QuoteRequest<AssetId, Balance, AssetBalance>) -> RpcResult<SourcedQuote<Balance, AssetBalance, NumberFor<Block>>>;
+        #[changed_in(10)]
+        fn quote_with_fallback(request: QuoteRequest<AssetId, Balance, AssetBalance>) -> RpcResult<SourcedQuoteBeforeV10<Balance, AssetBalance>>;
+    }
+
+    /// Queries depending on an account. Separate from [`DexApi`], so that its users don't need
+    /// to be generic over the account ID type. Versions:
+    ///   1. `get_asset_status`.
+    ///   2. `get_account_positions`, paginated by exchange.
+    #[api_version(2)]
+    pub trait DexAccountApi<AccountId, AssetId, Balance, AssetBalance> where
+        AccountId: Codec,
+        AssetId: Codec + MaybeDisplay,
+        Balance: Codec + MaybeDisplay,
+        AssetBalance: Codec + MaybeDisplay,
+    {
+        fn get_asset_status(asset_id: AssetId, recipient: AccountId, token_amount: AssetBalance) -> RpcResult<AssetStatus<Balance, AssetBalance>>;
+        fn get_account_positions(account: AccountId, start_after: Option<AssetId>, limit: u32) -> AccountPositions<AssetId, Balance, AssetBalance>;
+    }
+
+    /// Dry-running DEX calls. Separate from [`DexAccountApi`], as it is generic over the call type
+    /// of the pallet and the event type of the runtime.
+    pub trait DexSimulationApi<AccountId, DexCall, Event> where
+        AccountId: Codec,
+        DexCall: Codec,
+        Event: Codec,
+    {
+        fn simulate_calls(caller: AccountId, calls: Vec<DexCall>) -> Vec<SimulatedCall<Event>>;
+    }
+}
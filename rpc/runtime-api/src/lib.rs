@@ -1,18 +1,6 @@
-#![cfg_attr(not(feature = "std"), no_std)]
+//! Runtime API of the DEX pallet, declared in `substrate-dex-primitives` and re-exported
+//! (with the generated `runtime_decl_for_*` modules) for existing runtimes and node RPC.
 
-use codec::Codec;
-pub use pallet_dex::rpc::{RpcError, RpcResult};
-use sp_runtime::traits::MaybeDisplay;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-sp_api::decl_runtime_apis! {
-    pub trait DexApi<AssetId, Balance, AssetBalance> where
-        AssetId: Codec + MaybeDisplay,
-        Balance: Codec + MaybeDisplay,
-        AssetBalance: Codec + MaybeDisplay,
-    {
-        fn get_currency_to_asset_input_price(asset_id: AssetId, currency_amount: Balance) -> RpcResult<AssetBalance>;
-        fn get_currency_to_asset_output_price(asset_id: AssetId, token_amount: AssetBalance) -> RpcResult<Balance>;
-        fn get_asset_to_currency_input_price(asset_id: AssetId, token_amount: AssetBalance) -> RpcResult<Balance>;
-        fn get_asset_to_currency_output_price(asset_id: AssetId, currency_amount: Balance) -> RpcResult<AssetBalance>;
-    }
-}
+pub use substrate_dex_primitives::*;
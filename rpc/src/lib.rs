@@ -4,20 +4,34 @@ use jsonrpsee::{
     proc_macros::rpc,
     types::error::{CallError, ErrorObject},
 };
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::MaybeDisplay;
+use sp_runtime::traits::{MaybeDisplay, NumberFor};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-pub use pallet_dex_rpc_runtime_api::{DexApi as DexRuntimeApi, RpcError as DexRpcError};
+pub use pallet_dex_rpc_runtime_api::{
+    DexApi as DexRuntimeApi, Price, Quote, QuoteRequest, RpcError as DexRpcError,
+};
 
 const RUNTIME_ERROR: i32 = 1;
 const EXCHANGE_NOT_FOUND: i32 = 2;
 const NOT_ENOUGH_LIQUIDITY: i32 = 3;
 const OVERFLOW: i32 = 4;
+const MODULE_ERROR: i32 = 5;
+const ARITHMETIC_ERROR: i32 = 6;
+const TOKEN_ERROR: i32 = 7;
+const NO_LIQUIDITY: i32 = 8;
+const UNSUPPORTED_METHOD: i32 = 9;
+
+/// Versions of the runtime API which added the methods exposed after version 1.
+const SPOT_PRICE_VERSION: u32 = 2;
+const LIQUIDITY_TOKEN_LOOKUP_VERSION: u32 = 3;
+const QUOTE_VERSION: u32 = 6;
+/// Version of the runtime API since which quotes include the block number.
+const QUOTE_BLOCK_NUMBER_VERSION: u32 = 10;
 
 #[cfg(test)]
 mod tests;
@@ -55,6 +69,16 @@ pub trait DexApi<BlockHash, AssetId, Balance, AssetBalance> {
         currency_amount: Balance,
         at: Option<BlockHash>,
     ) -> RpcResult<AssetBalance>;
+
+    #[method(name = "dex_get_spot_price")]
+    fn get_spot_price(&self, asset_id: AssetId, at: Option<BlockHash>) -> RpcResult<Price>;
+
+    #[method(name = "dex_get_exchange_by_liquidity_token")]
+    fn get_exchange_by_liquidity_token(
+        &self,
+        liquidity_token_id: AssetId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<AssetId>;
 }
 
 pub struct Dex<Client, Block> {
@@ -84,6 +108,94 @@ where
     }
 }
 
+impl<Client, Block> Dex<Client, Block>
+where
+    Block: sp_runtime::traits::Block,
+    Client: ProvideRuntimeApi<Block>,
+{
+    /// Check that the runtime at the given block implements `version` (or a later version)
+    /// of the runtime API, so that calling a method it doesn't implement yet (e.g. at a block
+    /// before the runtime upgrade which added it) fails with a meaningful error.
+    fn ensure_api_version<AssetId, Balance, AssetBalance>(
+        &self,
+        at: &BlockId<Block>,
+        version: u32,
+    ) -> RpcResult<()>
+    where
+        Client::Api: DexRuntimeApi<Block, AssetId, Balance, AssetBalance>,
+    {
+        if self.has_api_version::<AssetId, Balance, AssetBalance>(at, version)? {
+            Ok(())
+        } else {
+            Err(unsupported_method_error(version))
+        }
+    }
+
+    /// Whether the runtime at the given block implements `version` (or a later version) of the
+    /// runtime API.
+    fn has_api_version<AssetId, Balance, AssetBalance>(
+        &self,
+        at: &BlockId<Block>,
+        version: u32,
+    ) -> RpcResult<bool>
+    where
+        Client::Api: DexRuntimeApi<Block, AssetId, Balance, AssetBalance>,
+    {
+        self.client
+            .runtime_api()
+            .has_api_with::<dyn DexRuntimeApi<Block, AssetId, Balance, AssetBalance>, _>(
+                at,
+                |runtime_version| runtime_version >= version,
+            )
+            .map_err(runtime_error)
+    }
+}
+
+impl<Client, Block> Dex<Client, Block>
+where
+    Block: sp_runtime::traits::Block,
+    Client: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+{
+    /// Quote a trade with the `quote` method of the runtime API (not exposed as an RPC method).
+    /// Runtimes before version 10 of the runtime API return the quote without the block number,
+    /// which is then read from the header of the queried block.
+    pub fn quote<AssetId, Balance, AssetBalance>(
+        &self,
+        request: QuoteRequest<AssetId, Balance, AssetBalance>,
+        at: Option<HashOf<Block>>,
+    ) -> RpcResult<Quote<Balance, AssetBalance, NumberFor<Block>>>
+    where
+        Client::Api: DexRuntimeApi<Block, AssetId, Balance, AssetBalance>,
+        AssetId: Codec + MaybeDisplay,
+        Balance: Codec + MaybeDisplay,
+        AssetBalance: Codec + MaybeDisplay,
+    {
+        let hash = at.unwrap_or_else(|| self.client.info().best_hash);
+        let at = BlockId::hash(hash);
+        self.ensure_api_version::<AssetId, Balance, AssetBalance>(&at, QUOTE_VERSION)?;
+        let api = self.client.runtime_api();
+        if self
+            .has_api_version::<AssetId, Balance, AssetBalance>(&at, QUOTE_BLOCK_NUMBER_VERSION)?
+        {
+            return api
+                .quote(&at, request)
+                .map_err(runtime_error)?
+                .map_err(dex_rpc_error);
+        }
+        let block_number = self
+            .client
+            .number(hash)
+            .map_err(runtime_error)?
+            .ok_or_else(|| runtime_error("Unknown block"))?;
+        #[allow(deprecated)]
+        let quote = api
+            .quote_before_version_10(&at, request)
+            .map_err(runtime_error)?
+            .map_err(dex_rpc_error)?;
+        Ok(quote.at(block_number))
+    }
+}
+
 #[async_trait]
 impl<Client, Block, AssetId, Balance, AssetBalance>
     DexApiServer<HashOf<Block>, AssetId, Balance, AssetBalance> for Dex<Client, Block>
@@ -150,6 +262,33 @@ where
             .map_err(runtime_error)?
             .map_err(dex_rpc_error)
     }
+
+    fn get_spot_price(&self, asset_id: AssetId, at: Option<Block::Hash>) -> RpcResult<Price> {
+        let at = self.block_id(at);
+        self.ensure_api_version::<AssetId, Balance, AssetBalance>(&at, SPOT_PRICE_VERSION)?;
+        self.client
+            .runtime_api()
+            .get_spot_price(&at, asset_id)
+            .map_err(runtime_error)?
+            .map_err(dex_rpc_error)
+    }
+
+    fn get_exchange_by_liquidity_token(
+        &self,
+        liquidity_token_id: AssetId,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<AssetId> {
+        let at = self.block_id(at);
+        self.ensure_api_version::<AssetId, Balance, AssetBalance>(
+            &at,
+            LIQUIDITY_TOKEN_LOOKUP_VERSION,
+        )?;
+        self.client
+            .runtime_api()
+            .get_exchange_by_liquidity_token(&at, liquidity_token_id)
+            .map_err(runtime_error)?
+            .map_err(dex_rpc_error)
+    }
 }
 
 fn runtime_error(err: impl Debug) -> RpcError {
@@ -161,12 +300,32 @@ fn runtime_error(err: impl Debug) -> RpcError {
     .into()
 }
 
+fn unsupported_method_error(version: u32) -> RpcError {
+    CallError::Custom(ErrorObject::owned(
+        UNSUPPORTED_METHOD,
+        "Unsupported method",
+        Some(format!("Requires version {} of the runtime API", version)),
+    ))
+    .into()
+}
+
 fn dex_rpc_error(err: DexRpcError) -> RpcError {
     let (code, message, data) = match err {
         DexRpcError::ExchangeNotFound => (EXCHANGE_NOT_FOUND, "Exchange not found", None),
         DexRpcError::NotEnoughLiquidity => (NOT_ENOUGH_LIQUIDITY, "Not enough liquidity", None),
         DexRpcError::Overflow => (OVERFLOW, "Overflow", None),
         DexRpcError::Unexpected(msg) => (RUNTIME_ERROR, "Runtime error", Some(msg)),
+        // Data contains the pallet index followed by the encoded pallet error
+        DexRpcError::Module { index, error } => {
+            (MODULE_ERROR, "Module error", Some([&[index][..], &error[..]].concat()))
+        }
+        DexRpcError::Arithmetic(err) => {
+            (ARITHMETIC_ERROR, "Arithmetic error", Some(format!("{:?}", err).into_bytes()))
+        }
+        DexRpcError::Token(err) => {
+            (TOKEN_ERROR, "Token error", Some(format!("{:?}", err).into_bytes()))
+        }
+        DexRpcError::NoLiquidity => (NO_LIQUIDITY, "No liquidity", None),
     };
     CallError::Custom(ErrorObject::owned(code, message, data)).into()
 }
@@ -2,6 +2,7 @@ use super::*;
 use jsonrpsee::core::Error;
 use mock::*;
 use pallet_dex::rpc::RpcError;
+use sp_runtime::{ArithmeticError, FixedPointNumber, TokenError};
 use std::sync::Arc;
 
 type AssetId = u32;
@@ -16,6 +17,10 @@ const EXCHANGE_NOT_FOUND_MESSAGE: &str = "Exchange not found";
 const NOT_ENOUGH_LIQUIDITY_MESSAGE: &str = "Not enough liquidity";
 const OVERFLOW_MESSAGE: &str = "Overflow";
 const RUNTIME_ERROR_MESSAGE: &str = "Runtime error";
+const MODULE_ERROR_MESSAGE: &str = "Module error";
+const ARITHMETIC_ERROR_MESSAGE: &str = "Arithmetic error";
+const TOKEN_ERROR_MESSAGE: &str = "Token error";
+const NO_LIQUIDITY_MESSAGE: &str = "No liquidity";
 const DATA: [u8; 15] = [
     117, 110, 101, 120, 112, 101, 99, 116, 101, 100, 32, 100, 97, 116, 97,
 ];
@@ -60,6 +65,23 @@ async fn get_currency_to_asset_input_price_with_not_enough_liquidity() {
     assert(error, NOT_ENOUGH_LIQUIDITY, NOT_ENOUGH_LIQUIDITY_MESSAGE, None)
 }
 
+#[tokio::test]
+async fn get_currency_to_asset_input_price_with_no_liquidity() {
+    let expectation = Expectation::GetCurrencyToAssetInputPrice(
+        ASSET,
+        CURRENCY_AMOUNT,
+        Err(RpcError::NoLiquidity),
+    );
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let error = api
+        .get_currency_to_asset_input_price(ASSET, CURRENCY_AMOUNT, None)
+        .unwrap_err();
+
+    assert(error, NO_LIQUIDITY, NO_LIQUIDITY_MESSAGE, None)
+}
+
 #[tokio::test]
 async fn get_currency_to_asset_input_price_with_overflow() {
     let expectation =
@@ -91,6 +113,60 @@ async fn get_currency_to_asset_input_price_with_unexpected() {
     assert(error, RUNTIME_ERROR, RUNTIME_ERROR_MESSAGE, Some(&DATA))
 }
 
+#[tokio::test]
+async fn get_currency_to_asset_input_price_with_module_error() {
+    let expectation = Expectation::GetCurrencyToAssetInputPrice(
+        ASSET,
+        CURRENCY_AMOUNT,
+        Err(RpcError::Module {
+            index: 1,
+            error: [2, 0, 0, 0],
+        }),
+    );
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let error = api
+        .get_currency_to_asset_input_price(ASSET, CURRENCY_AMOUNT, None)
+        .unwrap_err();
+
+    assert(error, MODULE_ERROR, MODULE_ERROR_MESSAGE, Some(&[1, 2, 0, 0, 0]))
+}
+
+#[tokio::test]
+async fn get_currency_to_asset_input_price_with_arithmetic_error() {
+    let expectation = Expectation::GetCurrencyToAssetInputPrice(
+        ASSET,
+        CURRENCY_AMOUNT,
+        Err(RpcError::Arithmetic(ArithmeticError::Underflow)),
+    );
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let error = api
+        .get_currency_to_asset_input_price(ASSET, CURRENCY_AMOUNT, None)
+        .unwrap_err();
+
+    assert(error, ARITHMETIC_ERROR, ARITHMETIC_ERROR_MESSAGE, Some(b"Underflow"))
+}
+
+#[tokio::test]
+async fn get_currency_to_asset_input_price_with_token_error() {
+    let expectation = Expectation::GetCurrencyToAssetInputPrice(
+        ASSET,
+        CURRENCY_AMOUNT,
+        Err(RpcError::Token(TokenError::CannotCreate)),
+    );
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let error = api
+        .get_currency_to_asset_input_price(ASSET, CURRENCY_AMOUNT, None)
+        .unwrap_err();
+
+    assert(error, TOKEN_ERROR, TOKEN_ERROR_MESSAGE, Some(b"CannotCreate"))
+}
+
 #[tokio::test]
 async fn get_currency_to_asset_input_price_with_success() {
     let expectation = Expectation::GetCurrencyToAssetInputPrice(ASSET, CURRENCY_AMOUNT, Ok(100));
@@ -342,9 +418,83 @@ async fn get_asset_to_currency_output_price_with_success() {
     assert_eq!(100, result);
 }
 
+#[tokio::test]
+async fn get_spot_price_with_exchange_not_found() {
+    let expectation = Expectation::GetSpotPrice(ASSET, Err(RpcError::ExchangeNotFound));
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let error = api.get_spot_price(ASSET, None).unwrap_err();
+
+    assert(error, EXCHANGE_NOT_FOUND, EXCHANGE_NOT_FOUND_MESSAGE, None)
+}
+
+#[tokio::test]
+async fn get_spot_price_with_success() {
+    let price = Price::saturating_from_rational(3, 2);
+    let expectation = Expectation::GetSpotPrice(ASSET, Ok(price));
+
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let result = api.get_spot_price(ASSET, None).unwrap();
+
+    assert_eq!(price, result);
+}
+
+#[tokio::test]
+async fn get_exchange_by_liquidity_token_with_exchange_not_found() {
+    let expectation =
+        Expectation::GetExchangeByLiquidityToken(ASSET, Err(RpcError::ExchangeNotFound));
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let error = api
+        .get_exchange_by_liquidity_token(ASSET, None)
+        .unwrap_err();
+
+    assert(error, EXCHANGE_NOT_FOUND, EXCHANGE_NOT_FOUND_MESSAGE, None)
+}
+
+#[tokio::test]
+async fn get_exchange_by_liquidity_token_with_success() {
+    let expectation = Expectation::GetExchangeByLiquidityToken(ASSET, Ok(2));
+
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let result = api.get_exchange_by_liquidity_token(ASSET, None).unwrap();
+
+    assert_eq!(2, result);
+}
+
+#[tokio::test]
+async fn quote_with_success() {
+    let request = QuoteRequest::CurrencyToAssetInput {
+        asset_id: ASSET,
+        currency_amount: CURRENCY_AMOUNT,
+    };
+    let quote = Quote {
+        currency_amount: CURRENCY_AMOUNT,
+        token_amount: TOKEN_AMOUNT,
+        block_number: 1,
+    };
+    let expectation = Expectation::Quote(request.clone(), Ok(quote.clone()));
+
+    let client = Arc::new(TestApi::new(expectation));
+    let api = Dex::new(client);
+
+    let result = api.quote(request, None).unwrap();
+
+    assert_eq!(quote, result);
+}
+
 mod mock {
     use crate::tests::{AssetBalance, AssetId, Balance, RpcResult};
-    use pallet_dex_rpc_runtime_api::DexApi as DexRuntimeApi;
+    use crate::Price;
+    use pallet_dex_rpc_runtime_api::{
+        DexApi as DexRuntimeApi, DexParameters, ExchangeSnapshot, Quote, QuoteRequest, SourcedQuote,
+    };
     use sp_api::{ApiRef, ProvideRuntimeApi};
     use sp_blockchain::HeaderBackend;
     use sp_runtime::{
@@ -455,6 +605,55 @@ mod mock {
                     _ => panic!()
                 }
             }
+
+            fn get_spot_price(asset_id: AssetId) -> RpcResult<Price> {
+                match &self.call {
+                    Expectation::GetSpotPrice ( expected_asset, result)
+                        if asset_id == *expected_asset => result.clone(),
+                    _ => panic!()
+                }
+            }
+
+            fn get_exchange_by_liquidity_token(liquidity_token_id: AssetId) -> RpcResult<AssetId> {
+                match &self.call {
+                    Expectation::GetExchangeByLiquidityToken ( expected_token, result)
+                        if liquidity_token_id == *expected_token => result.clone(),
+                    _ => panic!()
+                }
+            }
+
+            // Not exposed by the node RPC (runtime API only)
+            fn get_reserves(_asset_id: AssetId) -> RpcResult<(Balance, AssetBalance, NumberFor<Block>)> {
+                unimplemented!()
+            }
+
+            fn get_reserves_batch(_asset_ids: Vec<AssetId>) -> Vec<RpcResult<(Balance, AssetBalance, NumberFor<Block>)>> {
+                unimplemented!()
+            }
+
+            fn get_exchanges_snapshot(_asset_ids: Vec<AssetId>) -> Vec<RpcResult<ExchangeSnapshot<AssetId, Balance, AssetBalance>>> {
+                unimplemented!()
+            }
+
+            fn quote(request: QuoteRequest<AssetId, Balance, AssetBalance>) -> RpcResult<Quote<Balance, AssetBalance, NumberFor<Block>>> {
+                match &self.call {
+                    Expectation::Quote ( expected_request, result)
+                        if request == *expected_request => result.clone(),
+                    _ => panic!()
+                }
+            }
+
+            fn dex_parameters() -> DexParameters<Balance, NumberFor<Block>> {
+                unimplemented!()
+            }
+
+            fn quote_with_reserves(_request: QuoteRequest<AssetId, Balance, AssetBalance>, _currency_reserve: Balance, _token_reserve: AssetBalance) -> RpcResult<Quote<Balance, AssetBalance, NumberFor<Block>>> {
+                unimplemented!()
+            }
+
+            fn quote_with_fallback(_request: QuoteRequest<AssetId, Balance, AssetBalance>) -> RpcResult<SourcedQuote<Balance, AssetBalance, NumberFor<Block>>> {
+                unimplemented!()
+            }
         }
     }
 
@@ -465,5 +664,11 @@ mod mock {
         GetCurrencyToAssetOutputPrice(AssetId, AssetBalance, RpcResult<Balance>),
         GetAssetToCurrencyInputPrice(AssetId, AssetBalance, RpcResult<Balance>),
         GetAssetToCurrencyOutputPrice(AssetId, Balance, RpcResult<AssetBalance>),
+        GetSpotPrice(AssetId, RpcResult<Price>),
+        GetExchangeByLiquidityToken(AssetId, RpcResult<AssetId>),
+        Quote(
+            QuoteRequest<AssetId, Balance, AssetBalance>,
+            RpcResult<Quote<Balance, AssetBalance, NumberFor<Block>>>,
+        ),
     }
 }
@@ -0,0 +1,112 @@
+//! Audited arithmetic of the swap and liquidity paths (`safe-math-audit` feature).
+//!
+//! The swap and liquidity paths saturate operations which are not expected to overflow (e.g.
+//! reserve updates and pro-rata shares), as a release build should rather round than fail a
+//! trade. The saturation is silent, though, so a precision bug would go unnoticed. With the
+//! `safe-math-audit` feature, every such operation is checked first, and an unexpected saturation
+//! is reported with `defensive!` (an error log, and a panic in debug builds) before falling back
+//! to the saturated result. Fuzzing and testnet builds surface these issues, while the results of
+//! release builds are unchanged.
+//!
+//! Saturations which are expected (e.g. clamping user input, or rounding a result to zero) keep
+//! using the plain saturating operations.
+
+use frame_support::sp_runtime::{
+    traits::{CheckedAdd, CheckedMul, CheckedSub, Saturating},
+    FixedPointNumber, FixedPointOperand, FixedU128,
+};
+
+/// Saturating operations audited with the `safe-math-audit` feature.
+pub(crate) trait AuditedSaturating: Sized {
+    /// Like `saturating_add`, reporting an overflow.
+    fn audited_add(self, other: Self) -> Self;
+    /// Like `saturating_sub`, reporting an underflow.
+    fn audited_sub(self, other: Self) -> Self;
+    /// Like `saturating_mul`, reporting an overflow.
+    fn audited_mul(self, other: Self) -> Self;
+    /// Like `saturating_accrue`, reporting an overflow.
+    fn audited_accrue(&mut self, other: Self);
+    /// Like `saturating_reduce`, reporting an underflow.
+    fn audited_reduce(&mut self, other: Self);
+}
+
+impl<N: Saturating + CheckedAdd + CheckedSub + CheckedMul + Copy> AuditedSaturating for N {
+    fn audited_add(self, other: Self) -> Self {
+        audit(self.checked_add(&other), || self.saturating_add(other), "Addition overflow")
+    }
+
+    fn audited_sub(self, other: Self) -> Self {
+        audit(self.checked_sub(&other), || self.saturating_sub(other), "Subtraction underflow")
+    }
+
+    fn audited_mul(self, other: Self) -> Self {
+        audit(self.checked_mul(&other), || self.saturating_mul(other), "Multiplication overflow")
+    }
+
+    fn audited_accrue(&mut self, other: Self) {
+        *self = self.audited_add(other);
+    }
+
+    fn audited_reduce(&mut self, other: Self) {
+        *self = self.audited_sub(other);
+    }
+}
+
+/// Get the pro-rata share `numerator / denominator` of `amount` (rounded down, like
+/// `FixedU128::saturating_from_rational(numerator, denominator).saturating_mul_int(amount)`),
+/// reporting an overflow of the share or of the result.
+pub(crate) fn audited_share<N: FixedPointOperand>(numerator: N, denominator: N, amount: N) -> N {
+    audit(
+        FixedU128::checked_from_rational(numerator, denominator)
+            .and_then(|share| share.checked_mul_int(amount)),
+        || FixedU128::saturating_from_rational(numerator, denominator).saturating_mul_int(amount),
+        "Share overflow",
+    )
+}
+
+/// Return the `checked` result, or the `saturated` one if the checked operation failed (reported
+/// with the `safe-math-audit` feature).
+fn audit<N>(checked: Option<N>, saturated: impl FnOnce() -> N, operation: &'static str) -> N {
+    match checked {
+        Some(result) => result,
+        None => {
+            #[cfg(feature = "safe-math-audit")]
+            frame_support::defensive!(operation);
+            #[cfg(not(feature = "safe-math-audit"))]
+            let _ = operation;
+            saturated()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audited_results() {
+        assert_eq!(1u128.audited_add(2), 3);
+        assert_eq!(3u128.audited_sub(2), 1);
+        assert_eq!(3u128.audited_mul(2), 6);
+        let mut reserve = 10u128;
+        reserve.audited_accrue(5);
+        reserve.audited_reduce(3);
+        assert_eq!(reserve, 12);
+        assert_eq!(audited_share(1u128, 3, 300), 99);
+    }
+
+    #[cfg(not(feature = "safe-math-audit"))]
+    #[test]
+    fn saturates_silently() {
+        assert_eq!(u128::MAX.audited_add(1), u128::MAX);
+        assert_eq!(1u128.audited_sub(2), 0);
+        assert_eq!(audited_share(2u128, 1, u128::MAX), u128::MAX);
+    }
+
+    #[cfg(feature = "safe-math-audit")]
+    #[test]
+    #[should_panic]
+    fn reports_saturation() {
+        1u128.audited_sub(2);
+    }
+}
@@ -1,11 +1,18 @@
-use crate::{AccountIdOf, Call, Config, Pallet, TradeAmount};
-use frame_benchmarking::{benchmarks, whitelisted_caller};
+use crate::{
+    curves::CurveInvariant, rpc::QuoteRequest, AccountIdOf, Bootstrap, Call, Config, CreationFee,
+    Curves, DelegatedSwap, Exchanges, FeeShare, LiquidityStreams, MarketMaker, MarketMakers,
+    OperatorApprovals, Pallet, PausedExchanges, PegReference, Price, RfqBenchmarkHelper, RfqQuote,
+    RfqSide, SeedingApprovals, TradeAmount, TradeSizeLimit, UsedRfqNonces,
+};
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
 use frame_support::pallet_prelude::DispatchResult;
+use frame_support::sp_runtime::{traits::Zero, FixedPointNumber, Permill};
 use frame_support::traits::{
     fungibles::{Create, Mutate},
-    Currency,
+    Currency, EnsureOrigin, Get,
 };
 use frame_system::RawOrigin;
+use sp_std::prelude::*;
 
 const INIT_BALANCE: u128 = 1_000_000_000_000_000;
 const INIT_LIQUIDITY: u128 = 1_000_000_000_000;
@@ -31,10 +38,26 @@ where
         liquidity_token_id,
         INIT_LIQUIDITY,
         INIT_LIQUIDITY,
+        None,
+        false,
     )?;
+    // Price the trades with the most expensive curve (worst case)
+    Curves::<T>::insert(asset_id, T::Curve::worst_case());
     Ok(())
 }
 
+// Sponsor the exchange, so trades pay rebates (worst case)
+fn prepare_sponsorship<T>(asset_id: u32) -> DispatchResult
+where
+    T: frame_system::Config<BlockNumber = u32>,
+    T: Config<AssetId = u32, AssetBalance = u128>,
+    T::Currency: Currency<AccountIdOf<T>, Balance = u128>,
+{
+    let sponsor: T::AccountId = account("sponsor", 0, 0);
+    T::Currency::make_free_balance_be(&sponsor, INIT_BALANCE);
+    Pallet::<T>::sponsor_exchange(RawOrigin::Signed(sponsor).into(), asset_id, INIT_LIQUIDITY, 1, 1)
+}
+
 benchmarks! {
     where_clause {
         where
@@ -44,21 +67,30 @@ benchmarks! {
             T::Assets: Create<AccountIdOf<T>> + Mutate<AccountIdOf<T>>,
     }
 
+    // With a full bootstrap whitelist (worst case)
     create_exchange {
         let caller: T::AccountId = whitelisted_caller();
         T::Assets::create(ASSET_B, caller.clone(), true, 1).unwrap();
         T::Assets::mint_into(ASSET_B, &caller, INIT_BALANCE).unwrap();
         T::Currency::make_free_balance_be(&caller, INIT_BALANCE);
-    }: _(RawOrigin::Signed(caller), ASSET_B, LIQ_TOKEN_B, INIT_LIQUIDITY, INIT_LIQUIDITY)
+        let whitelist = (0..T::MaxBootstrapAccounts::get())
+            .map(|i| account("whitelisted", i, 0))
+            .collect::<Vec<T::AccountId>>();
+        let bootstrap = Bootstrap { whitelist: whitelist.try_into().unwrap(), end: frame_system::Pallet::<T>::block_number() };
+        // Creation fee creates the fee destination account
+        CreationFee::<T>::put(T::Currency::minimum_balance());
+    }: _(RawOrigin::Signed(caller), ASSET_B, LIQ_TOKEN_B, INIT_LIQUIDITY, INIT_LIQUIDITY, Some(bootstrap), false)
     verify {
         assert!(Pallet::<T>::exchanges(ASSET_B).is_some());
+        assert!(Pallet::<T>::bootstraps(ASSET_B).is_some());
+        assert_eq!(T::Currency::free_balance(&T::FeeDestination::get()), T::Currency::minimum_balance());
     }
 
     add_liquidity {
         prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
         let caller: T::AccountId = whitelisted_caller();
         // Token amount is 2, not 1 because of the `+1` in liquidity added formula
-    }: _(RawOrigin::Signed(caller), ASSET_A, 1, 1, 2, 1)
+    }: _(RawOrigin::Signed(caller), ASSET_A, 1, 1, 2, Some(1), None, None)
     verify {
         let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
         assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + 1);
@@ -68,7 +100,27 @@ benchmarks! {
     remove_liquidity {
         prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
         let caller: T::AccountId = whitelisted_caller();
-    }: _(RawOrigin::Signed(caller), ASSET_A, 1, 1, 1, 1)
+    }: _(RawOrigin::Signed(caller), ASSET_A, 1, 1, 1, Some(1))
+    verify {
+        let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - 1);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - 1);
+    }
+
+    remove_liquidity_with_slippage {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+    }: _(RawOrigin::Signed(caller), ASSET_A, 1, 1, 1, Permill::from_percent(1), Some(1))
+    verify {
+        let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - 1);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - 1);
+    }
+
+    remove_liquidity_exact_assets {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+    }: _(RawOrigin::Signed(caller), ASSET_A, 1, 1, 1, Some(1))
     verify {
         let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
         assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - 1);
@@ -77,10 +129,11 @@ benchmarks! {
 
     currency_to_asset {
         prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        prepare_sponsorship::<T>(ASSET_A)?;
         let caller: T::AccountId = whitelisted_caller();
         let input_amount = 500;
         let min_output = 498; // sold amount (500) - provider fee (0.3%) should be ~498
-    }: _(RawOrigin::Signed(caller), ASSET_A, TradeAmount::FixedInput{input_amount, min_output}, 1, None)
+    }: _(RawOrigin::Signed(caller), ASSET_A, TradeAmount::FixedInput{input_amount, min_output}, Some(1), None, false, None)
     verify {
         let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
         assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + input_amount);
@@ -89,10 +142,11 @@ benchmarks! {
 
     asset_to_currency {
         prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        prepare_sponsorship::<T>(ASSET_A)?;
         let caller: T::AccountId = whitelisted_caller();
         let input_amount = 500;
         let min_output = 498; // sold amount (500) - provider fee (0.3%) should be ~498
-    }: _(RawOrigin::Signed(caller), ASSET_A, TradeAmount::FixedInput{input_amount, min_output}, 1, None)
+    }: _(RawOrigin::Signed(caller), ASSET_A, TradeAmount::FixedInput{input_amount, min_output}, Some(1), None, None)
     verify {
         let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
         assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - min_output);
@@ -102,11 +156,13 @@ benchmarks! {
     asset_to_asset {
         prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
         prepare_exchange::<T>(ASSET_B, LIQ_TOKEN_B)?;
+        prepare_sponsorship::<T>(ASSET_A)?;
+        prepare_sponsorship::<T>(ASSET_B)?;
         let caller: T::AccountId = whitelisted_caller();
         let input_amount = 500;
         let currency_amount = 498; // sold amount (500) - provider fee (0.3%) should be ~498
         let min_output = 496; // currency amount (498) - provider fee (0.3%) should be ~496
-    }: _(RawOrigin::Signed(caller), ASSET_A, ASSET_B, TradeAmount::FixedInput{input_amount, min_output}, 1, None)
+    }: _(RawOrigin::Signed(caller), ASSET_A, ASSET_B, TradeAmount::FixedInput{input_amount, min_output}, Some(1), None, false, None)
     verify {
         let exchange_a = Pallet::<T>::exchanges(ASSET_A).unwrap();
         assert_eq!(exchange_a.currency_reserve, INIT_LIQUIDITY - currency_amount);
@@ -117,5 +173,358 @@ benchmarks! {
         assert_eq!(exchange_b.token_reserve, INIT_LIQUIDITY - min_output);
     }
 
+    sponsor_exchange {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+    }: _(RawOrigin::Signed(caller), ASSET_A, INIT_LIQUIDITY, 1, 1)
+    verify {
+        assert!(Pallet::<T>::sponsorships(ASSET_A).is_some());
+    }
+
+    withdraw_sponsorship {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+        Pallet::<T>::sponsor_exchange(
+            RawOrigin::Signed(caller.clone()).into(),
+            ASSET_A,
+            INIT_LIQUIDITY,
+            1,
+            1,
+        )?;
+    }: _(RawOrigin::Signed(caller), ASSET_A)
+    verify {
+        assert!(Pallet::<T>::sponsorships(ASSET_A).is_none());
+    }
+
+    set_max_trade_size {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let origin = T::GovernanceOrigin::successful_origin();
+        let limit = TradeSizeLimit::ReserveShare(Permill::from_percent(10));
+    }: _<T::RuntimeOrigin>(origin, ASSET_A, Some(limit.clone()))
+    verify {
+        assert_eq!(Pallet::<T>::max_trade_sizes(ASSET_A), Some(limit));
+    }
+
+    settle_rfq {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+        let maker = T::BenchmarkHelper::signer();
+        T::Assets::mint_into(ASSET_A, &maker, INIT_BALANCE)?;
+        T::Currency::make_free_balance_be(&maker, INIT_BALANCE);
+        let parameters = MarketMaker {
+            fee_exempt: false,
+            max_currency: INIT_BALANCE,
+            max_tokens: INIT_BALANCE,
+        };
+        MarketMakers::<T>::insert(&maker, parameters);
+        // Hedging the quote (buying back 500 tokens for at most 600 currency) is the worst case
+        let quote = RfqQuote {
+            maker: maker.clone(),
+            taker: Some(caller.clone()),
+            asset_id: ASSET_A,
+            side: RfqSide::MakerSells,
+            currency_amount: 600,
+            token_amount: 500,
+            expiry: 1,
+            nonce: 0,
+            hedge: true,
+        };
+        let signature = T::BenchmarkHelper::sign(&maker, &Pallet::<T>::rfq_signing_payload(&quote));
+    }: _(RawOrigin::Signed(caller), quote, signature)
+    verify {
+        assert!(UsedRfqNonces::<T>::contains_key(&maker, 0));
+        let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - 500);
+    }
+
+    register_market_maker {
+        let origin = T::GovernanceOrigin::successful_origin();
+        let maker: T::AccountId = account("maker", 0, 0);
+        let parameters = MarketMaker {
+            fee_exempt: true,
+            max_currency: INIT_BALANCE,
+            max_tokens: INIT_BALANCE,
+        };
+    }: _<T::RuntimeOrigin>(origin, maker.clone(), parameters.clone())
+    verify {
+        assert_eq!(Pallet::<T>::market_makers(maker), Some(parameters));
+    }
+
+    deregister_market_maker {
+        let origin = T::GovernanceOrigin::successful_origin();
+        let maker: T::AccountId = account("maker", 0, 0);
+        let parameters = MarketMaker {
+            fee_exempt: true,
+            max_currency: INIT_BALANCE,
+            max_tokens: INIT_BALANCE,
+        };
+        MarketMakers::<T>::insert(&maker, parameters);
+    }: _<T::RuntimeOrigin>(origin, maker.clone())
+    verify {
+        assert!(Pallet::<T>::market_makers(maker).is_none());
+    }
+
+    stream_liquidity {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+    }: _(RawOrigin::Signed(caller.clone()), ASSET_A, 10, 20, 10)
+    verify {
+        assert!(Pallet::<T>::liquidity_streams(ASSET_A, caller).is_some());
+    }
+
+    cancel_liquidity_stream {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+        Pallet::<T>::stream_liquidity(RawOrigin::Signed(caller.clone()).into(), ASSET_A, 10, 20, 10)?;
+    }: _(RawOrigin::Signed(caller.clone()), ASSET_A)
+    verify {
+        assert!(Pallet::<T>::liquidity_streams(ASSET_A, caller).is_none());
+    }
+
+    // Last part of the stream (worst case), so the stream also ends
+    execute_liquidity_stream {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+        Pallet::<T>::stream_liquidity(RawOrigin::Signed(caller.clone()).into(), ASSET_A, 10, 20, 1)?;
+        let stream = LiquidityStreams::<T>::get(ASSET_A, &caller).unwrap();
+    }: {
+        Pallet::<T>::execute_liquidity_stream(ASSET_A, caller.clone(), stream, 2);
+    }
+    verify {
+        assert!(Pallet::<T>::liquidity_streams(ASSET_A, caller).is_none());
+        let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + 10);
+    }
+
+    approve_operator {
+        let caller: T::AccountId = whitelisted_caller();
+        // Storage deposit is reserved from the caller
+        T::Currency::make_free_balance_be(&caller, INIT_BALANCE);
+        let operator: T::AccountId = account("operator", 0, 0);
+    }: _(RawOrigin::Signed(caller.clone()), operator.clone(), ASSET_A, INIT_LIQUIDITY, None, Price::zero(), Some(10))
+    verify {
+        assert!(Pallet::<T>::operator_approvals(caller, (operator, ASSET_A)).is_some());
+    }
+
+    revoke_operator {
+        let caller: T::AccountId = whitelisted_caller();
+        T::Currency::make_free_balance_be(&caller, INIT_BALANCE);
+        let operator: T::AccountId = account("operator", 0, 0);
+        Pallet::<T>::approve_operator(
+            RawOrigin::Signed(caller.clone()).into(),
+            operator.clone(),
+            ASSET_A,
+            INIT_LIQUIDITY,
+            None,
+            Price::zero(),
+            None,
+        )?;
+    }: _(RawOrigin::Signed(caller.clone()), operator.clone(), ASSET_A)
+    verify {
+        assert!(Pallet::<T>::operator_approvals(caller, (operator, ASSET_A)).is_none());
+    }
+
+    // Asset to asset trade (worst case)
+    swap_from {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        prepare_exchange::<T>(ASSET_B, LIQ_TOKEN_B)?;
+        prepare_sponsorship::<T>(ASSET_A)?;
+        prepare_sponsorship::<T>(ASSET_B)?;
+        let caller: T::AccountId = whitelisted_caller();
+        let operator: T::AccountId = account("operator", 0, 0);
+        Pallet::<T>::approve_operator(
+            RawOrigin::Signed(caller.clone()).into(),
+            operator.clone(),
+            ASSET_A,
+            INIT_LIQUIDITY,
+            Some(ASSET_B),
+            Price::zero(),
+            Some(10),
+        )?;
+        let input_amount = 500;
+        let swap = DelegatedSwap::AssetToAsset {
+            sold_asset_id: ASSET_A,
+            bought_asset_id: ASSET_B,
+            amount: TradeAmount::FixedInput { input_amount, min_output: 496 },
+        };
+    }: _(RawOrigin::Signed(operator.clone()), caller.clone(), swap, Some(1))
+    verify {
+        let approval = OperatorApprovals::<T>::get(caller, (operator, ASSET_A)).unwrap();
+        assert_eq!(approval.allowance, INIT_LIQUIDITY - input_amount);
+    }
+
+    force_seed_exchange {
+        let n in 1 .. T::MaxSeedDeposits::get();
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let origin = T::GovernanceOrigin::successful_origin();
+        let source: T::AccountId = whitelisted_caller();
+        Pallet::<T>::approve_seeding(RawOrigin::Signed(source.clone()).into(), ASSET_A)?;
+        let deposits: Vec<_> = (0..n)
+            .map(|i| {
+                let depositor: T::AccountId = account("depositor", i, 0);
+                T::Currency::make_free_balance_be(&depositor, INIT_BALANCE);
+                (depositor, 1_000, 2_000)
+            })
+            .collect();
+        let deposits = deposits.try_into().unwrap();
+    }: _<T::RuntimeOrigin>(origin, ASSET_A, source, 0, deposits)
+    verify {
+        assert_eq!(Pallet::<T>::seeded_deposits(ASSET_A), n);
+        let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + 1_000 * n as u128);
+    }
+
+    set_creation_fee {
+        let origin = T::GovernanceOrigin::successful_origin();
+    }: _<T::RuntimeOrigin>(origin, 1_000)
+    verify {
+        assert_eq!(Pallet::<T>::creation_fee(), 1_000);
+    }
+
+    // With `e` exchanges, whose currency reserves are summed
+    redeem_underlying {
+        let e in 1 .. 1_000;
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        for i in 1..e {
+            prepare_exchange::<T>(1_000 + i, 2_000 + i)?;
+        }
+        let caller: T::AccountId = whitelisted_caller();
+        // Break the exchange invariants, so the exchange is in emergency mode
+        let mut exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
+        exchange.token_reserve += 1;
+        Exchanges::<T>::insert(ASSET_A, exchange);
+    }: _(RawOrigin::Signed(caller), ASSET_A, 1, e)
+    verify {
+        let exchange = Pallet::<T>::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - 1);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY);
+    }
+
+    set_retention_floor {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let origin = T::GovernanceOrigin::successful_origin();
+    }: _<T::RuntimeOrigin>(origin, ASSET_A, Some(INIT_LIQUIDITY))
+    verify {
+        assert_eq!(Pallet::<T>::retention_floors(ASSET_A), Some(INIT_LIQUIDITY));
+    }
+
+    set_trade_only_mode {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let origin = T::GovernanceOrigin::successful_origin();
+        let until = frame_system::Pallet::<T>::block_number() + T::MaxTradeOnlyPeriod::get();
+    }: _<T::RuntimeOrigin>(origin, ASSET_A, Some(until))
+    verify {
+        assert_eq!(Pallet::<T>::trade_only_until(ASSET_A), Some(until));
+    }
+
+    delist_exchange {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let origin = T::GovernanceOrigin::successful_origin();
+    }: _<T::RuntimeOrigin>(origin, ASSET_A)
+    verify {
+        assert!(Pallet::<T>::delistings(ASSET_A).is_some());
+    }
+
+    sweep_delisted_exchange {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let caller: T::AccountId = whitelisted_caller();
+        Pallet::<T>::delist_exchange(T::GovernanceOrigin::successful_origin(), ASSET_A)?;
+        frame_system::Pallet::<T>::set_block_number(T::DelistingGracePeriod::get() + 1);
+    }: _(RawOrigin::Signed(caller), ASSET_A)
+    verify {
+        assert!(Pallet::<T>::exchanges(ASSET_A).is_none());
+    }
+
+    set_peg_reference {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let origin = T::GovernanceOrigin::successful_origin();
+        let peg = PegReference {
+            price: Price::saturating_from_integer(1),
+            band: Permill::from_percent(1),
+            min_currency_reserve: INIT_LIQUIDITY,
+        };
+    }: _<T::RuntimeOrigin>(origin, ASSET_A, Some(peg))
+    verify {
+        assert!(Pallet::<T>::peg_references(ASSET_A).is_some());
+    }
+
+    // With `n` exchanges to pause, all of them existing
+    force_pause_many {
+        let n in 1 .. T::MaxPausedPerCall::get();
+        let asset_ids: Vec<_> = (0..n).map(|i| 100 + i).collect();
+        for asset_id in &asset_ids {
+            prepare_exchange::<T>(*asset_id, *asset_id + 1_000)?;
+        }
+        let origin = T::GovernanceOrigin::successful_origin();
+        let paused = asset_ids.clone().try_into().unwrap();
+    }: _<T::RuntimeOrigin>(origin, paused)
+    verify {
+        assert!(asset_ids.iter().all(PausedExchanges::<T>::contains_key));
+    }
+
+    force_unpause_many {
+        let n in 1 .. T::MaxPausedPerCall::get();
+        let asset_ids: Vec<_> = (0..n).map(|i| 100 + i).collect();
+        for asset_id in &asset_ids {
+            PausedExchanges::<T>::insert(asset_id, ());
+        }
+        let origin = T::GovernanceOrigin::successful_origin();
+        let unpaused = asset_ids.clone().try_into().unwrap();
+    }: _<T::RuntimeOrigin>(origin, unpaused)
+    verify {
+        assert!(!asset_ids.iter().any(PausedExchanges::<T>::contains_key));
+    }
+
+    set_fee_share {
+        let origin = T::GovernanceOrigin::successful_origin();
+        let origin_account: T::AccountId = account("origin", 0, 0);
+        let fee_share = FeeShare {
+            beneficiary: account("beneficiary", 0, 0),
+            share: Permill::from_percent(50),
+        };
+    }: _<T::RuntimeOrigin>(origin, origin_account.clone(), Some(fee_share))
+    verify {
+        assert!(Pallet::<T>::fee_shares(origin_account).is_some());
+    }
+
+    set_curve {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let origin = T::GovernanceOrigin::successful_origin();
+        // Compare the spot prices of the worst-case curve
+    }: _<T::RuntimeOrigin>(origin, ASSET_A, Some(T::Curve::worst_case()))
+    verify {
+        assert!(Curves::<T>::contains_key(ASSET_A));
+    }
+
+    approve_seeding {
+        let caller: T::AccountId = whitelisted_caller();
+        // Storage deposit is reserved from the caller
+        T::Currency::make_free_balance_be(&caller, INIT_BALANCE);
+    }: _(RawOrigin::Signed(caller.clone()), ASSET_A)
+    verify {
+        assert!(SeedingApprovals::<T>::contains_key(caller, ASSET_A));
+    }
+
+    revoke_seeding {
+        let caller: T::AccountId = whitelisted_caller();
+        T::Currency::make_free_balance_be(&caller, INIT_BALANCE);
+        Pallet::<T>::approve_seeding(RawOrigin::Signed(caller.clone()).into(), ASSET_A)?;
+    }: _(RawOrigin::Signed(caller.clone()), ASSET_A)
+    verify {
+        assert!(!SeedingApprovals::<T>::contains_key(caller, ASSET_A));
+    }
+
+    // Quote of the `quote` runtime API method (charged by the chain extension), with the
+    // worst-case curve
+    quote {
+        prepare_exchange::<T>(ASSET_A, LIQ_TOKEN_A)?;
+        let request = QuoteRequest::CurrencyToAssetOutput {
+            asset_id: ASSET_A,
+            token_amount: 1_000,
+        };
+    }: {
+        assert!(Pallet::<T>::quote(request).is_ok());
+    }
+
     impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
 }
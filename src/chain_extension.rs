@@ -0,0 +1,354 @@
+//! Chain extension exposing the exchanges to `pallet-contracts` smart contracts (`contracts`
+//! feature).
+//!
+//! Contracts (e.g. written in ink!) call the functions of [`DexExtension`] with the chain
+//! extension ID [`DEX_EXTENSION_ID`] and one of the [`DexFunction`] IDs. Inputs are the
+//! SCALE-encoded parameters listed for each function, and each function writes a SCALE-encoded
+//! `RpcResult` to the output buffer (the function itself always returns status code 0, unless its
+//! input can't be decoded or is longer than the maximum encoded length of its parameters). Trades
+//! are dispatched with the contract account as the signed origin: a contract only trades its own
+//! funds, never those of the account calling it. Like the `call_runtime` API of `pallet-contracts`,
+//! the origin is filtered by both the `BaseCallFilter` of the runtime and the `CallFilter` of
+//! `pallet-contracts`, so a runtime which disables trading from contracts (or at all) can't be
+//! bypassed through the extension. The weight of a trade is charged to the contract call before
+//! its dispatch, and the unused part of it is refunded after: all of it if the call is filtered,
+//! down to its actual weight if the dispatch reports one. Quotes are charged the benchmarked
+//! weight of the `quote` runtime API method, with the most expensive curve.
+
+use crate::{
+    rpc::QuoteRequestOf, AssetBalanceOf, AssetIdOf, BalanceOf, Call, Config, Pallet, TradeAmount,
+    WeightInfo,
+};
+use codec::Encode;
+use frame_support::{
+    dispatch::{DispatchErrorWithPostInfo, UnfilteredDispatchable},
+    sp_runtime::{DispatchError, RuntimeDebug},
+    traits::{Contains, OriginTrait},
+    weights::Weight,
+};
+use frame_system::{pallet_prelude::OriginFor, RawOrigin};
+use pallet_contracts::chain_extension::{
+    BufInBufOutState, ChainExtension, Environment, Ext, InitState, RegisteredChainExtension,
+    RetVal, SysConfig, UncheckedFrom,
+};
+use substrate_dex_primitives::RpcResult;
+
+/// ID of the chain extension, when registered in a tuple of chain extensions of the runtime.
+pub const DEX_EXTENSION_ID: u16 = 0x0DE0;
+
+/// Functions of the chain extension.
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+#[repr(u16)]
+pub enum DexFunction {
+    /// Quote a trade, like the `quote` runtime API method.
+    /// Input: `QuoteRequest`. Output: `RpcResult<Quote>`.
+    Quote = 1,
+    /// Sell currency for tokens (`currency_to_asset`).
+    /// Input: `(asset_id, TradeAmount, deadline)`. Output: `RpcResult<()>`.
+    CurrencyToAsset = 2,
+    /// Sell tokens for currency (`asset_to_currency`).
+    /// Input: `(asset_id, TradeAmount, deadline)`. Output: `RpcResult<()>`.
+    AssetToCurrency = 3,
+    /// Sell tokens for tokens of another asset (`asset_to_asset`).
+    /// Input: `(sold_asset_id, bought_asset_id, TradeAmount, deadline)`. Output: `RpcResult<()>`.
+    AssetToAsset = 4,
+}
+
+impl TryFrom<u16> for DexFunction {
+    type Error = DispatchError;
+
+    fn try_from(func_id: u16) -> Result<Self, Self::Error> {
+        match func_id {
+            1 => Ok(Self::Quote),
+            2 => Ok(Self::CurrencyToAsset),
+            3 => Ok(Self::AssetToCurrency),
+            4 => Ok(Self::AssetToAsset),
+            _ => Err(DispatchError::Other("Unknown DEX chain extension function")),
+        }
+    }
+}
+
+/// Input of [`DexFunction::CurrencyToAsset`].
+pub type CurrencyToAssetInput<T> = (
+    AssetIdOf<T>,
+    TradeAmount<BalanceOf<T>, AssetBalanceOf<T>>,
+    Option<<T as frame_system::Config>::BlockNumber>,
+);
+
+/// Input of [`DexFunction::AssetToCurrency`].
+pub type AssetToCurrencyInput<T> = (
+    AssetIdOf<T>,
+    TradeAmount<AssetBalanceOf<T>, BalanceOf<T>>,
+    Option<<T as frame_system::Config>::BlockNumber>,
+);
+
+/// Input of [`DexFunction::AssetToAsset`].
+pub type AssetToAssetInput<T> = (
+    AssetIdOf<T>,
+    AssetIdOf<T>,
+    TradeAmount<AssetBalanceOf<T>, AssetBalanceOf<T>>,
+    Option<<T as frame_system::Config>::BlockNumber>,
+);
+
+/// Get the trade call of `currency_to_asset`, with the contract account as the recipient.
+pub(crate) fn currency_to_asset_call<T: Config>(input: CurrencyToAssetInput<T>) -> Call<T> {
+    let (asset_id, amount, deadline) = input;
+    Call::<T>::currency_to_asset {
+        asset_id,
+        amount,
+        deadline,
+        recipient: None,
+        provision_recipient: false,
+        donation: None,
+    }
+}
+
+/// Get the trade call of `asset_to_currency`, with the contract account as the recipient.
+pub(crate) fn asset_to_currency_call<T: Config>(input: AssetToCurrencyInput<T>) -> Call<T> {
+    let (asset_id, amount, deadline) = input;
+    Call::<T>::asset_to_currency {
+        asset_id,
+        amount,
+        deadline,
+        recipient: None,
+        donation: None,
+    }
+}
+
+/// Get the trade call of `asset_to_asset`, with the contract account as the recipient.
+pub(crate) fn asset_to_asset_call<T: Config>(input: AssetToAssetInput<T>) -> Call<T> {
+    let (sold_asset_id, bought_asset_id, amount, deadline) = input;
+    Call::<T>::asset_to_asset {
+        sold_asset_id,
+        bought_asset_id,
+        amount,
+        deadline,
+        recipient: None,
+        provision_recipient: false,
+        donation: None,
+    }
+}
+
+/// Dispatch `call` with `origin`, unless the call filter of the origin (including the
+/// `BaseCallFilter` of the runtime) rejects it, like `RuntimeCall::dispatch`. Also returns the
+/// actual weight of the call, if known: zero when it's filtered, or the weight reported by its
+/// dispatch.
+pub(crate) fn dispatch_filtered<T>(
+    origin: OriginFor<T>,
+    call: Call<T>,
+) -> (RpcResult<()>, Option<Weight>)
+where
+    T: Config,
+    <T as frame_system::Config>::RuntimeCall: From<Call<T>>,
+{
+    if !origin.filter_call(&call.clone().into()) {
+        let error = Pallet::<T>::rpc_error(frame_system::Error::<T>::CallFiltered.into());
+        return (Err(error), Some(Weight::zero()));
+    }
+    match call.dispatch_bypass_filter(origin) {
+        Ok(post_info) => (Ok(()), post_info.actual_weight),
+        Err(DispatchErrorWithPostInfo { post_info, error }) => {
+            (Err(Pallet::<T>::rpc_error(error)), post_info.actual_weight)
+        }
+    }
+}
+
+/// Chain extension of the DEX pallet, to be used as (or in a tuple of) the `ChainExtension` of
+/// `pallet-contracts`.
+#[derive(Default)]
+pub struct DexExtension;
+
+impl DexExtension {
+    /// Dispatch `call` with the contract account as the signed origin, filtered by the
+    /// `CallFilter` of `pallet-contracts`, and write its result. `weight` (the pre-dispatch weight
+    /// of the call) is charged before the dispatch, and the unused part of it refunded after.
+    fn dispatch<T, E>(
+        env: &mut Environment<E, BufInBufOutState>,
+        call: Call<T>,
+        weight: Weight,
+    ) -> Result<(), DispatchError>
+    where
+        T: pallet_contracts::Config + Config,
+        <T as frame_system::Config>::RuntimeCall: From<Call<T>>,
+        E: Ext<T = T>,
+        <E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+    {
+        let charged = env.charge_weight(weight)?;
+        let mut origin: OriginFor<T> = RawOrigin::Signed(env.ext().address().clone()).into();
+        origin.add_filter(<T as pallet_contracts::Config>::CallFilter::contains);
+        let (result, actual_weight) = dispatch_filtered::<T>(origin, call);
+        if let Some(actual_weight) = actual_weight {
+            env.adjust_weight(charged, actual_weight.min(weight));
+        }
+        env.write(&result.encode(), false, None)
+    }
+}
+
+impl<T> ChainExtension<T> for DexExtension
+where
+    T: pallet_contracts::Config + Config,
+    <T as frame_system::Config>::RuntimeCall: From<Call<T>>,
+{
+    fn call<E>(&mut self, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+    where
+        E: Ext<T = T>,
+        <E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+    {
+        let function = DexFunction::try_from(env.func_id())?;
+        let mut env = env.buf_in_buf_out();
+        match function {
+            DexFunction::Quote => {
+                env.charge_weight(<T as Config>::WeightInfo::quote())?;
+                let request: QuoteRequestOf<T> = env.read_as()?;
+                env.write(&Pallet::<T>::quote(request).encode(), false, None)?;
+            }
+            DexFunction::CurrencyToAsset => {
+                let input: CurrencyToAssetInput<T> = env.read_as()?;
+                let weight = <T as Config>::WeightInfo::currency_to_asset();
+                Self::dispatch(&mut env, currency_to_asset_call::<T>(input), weight)?;
+            }
+            DexFunction::AssetToCurrency => {
+                let input: AssetToCurrencyInput<T> = env.read_as()?;
+                let weight = <T as Config>::WeightInfo::asset_to_currency();
+                Self::dispatch(&mut env, asset_to_currency_call::<T>(input), weight)?;
+            }
+            DexFunction::AssetToAsset => {
+                let input: AssetToAssetInput<T> = env.read_as()?;
+                let weight = <T as Config>::WeightInfo::asset_to_asset();
+                Self::dispatch(&mut env, asset_to_asset_call::<T>(input), weight)?;
+            }
+        }
+        Ok(RetVal::Converging(0))
+    }
+}
+
+impl<T> RegisteredChainExtension<T> for DexExtension
+where
+    T: pallet_contracts::Config + Config,
+    <T as frame_system::Config>::RuntimeCall: From<Call<T>>,
+{
+    const ID: u16 = DEX_EXTENSION_ID;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::*;
+    use crate::rpc::QuoteRequest;
+    use codec::{Decode, MaxEncodedLen};
+    use substrate_dex_primitives::RpcError;
+
+    // Like `Environment::read_as`, which rejects inputs longer than the maximum encoded length
+    fn read_as<V: Decode + MaxEncodedLen>(input: impl Encode) -> V {
+        let input = input.encode();
+        assert!(input.len() <= V::max_encoded_len());
+        V::decode(&mut &input[..]).unwrap()
+    }
+
+    fn signed(account: u64) -> RuntimeOrigin {
+        RawOrigin::Signed(account).into()
+    }
+
+    #[test]
+    fn function_ids() {
+        assert_eq!(DexFunction::try_from(1), Ok(DexFunction::Quote));
+        assert_eq!(DexFunction::try_from(2), Ok(DexFunction::CurrencyToAsset));
+        assert_eq!(DexFunction::try_from(3), Ok(DexFunction::AssetToCurrency));
+        assert_eq!(DexFunction::try_from(4), Ok(DexFunction::AssetToAsset));
+        assert!(DexFunction::try_from(0).is_err());
+        assert!(DexFunction::try_from(5).is_err());
+    }
+
+    #[test]
+    fn quote() {
+        new_test_ext().execute_with(|| {
+            let request: QuoteRequestOf<Test> = read_as(QuoteRequest::CurrencyToAssetInput {
+                asset_id: ASSET_A,
+                currency_amount: 1_000u128,
+            });
+            let quote = Dex::quote(request).unwrap();
+            assert_eq!(quote.currency_amount, 1_000);
+            assert!(quote.token_amount > 0);
+        })
+    }
+
+    #[test]
+    fn currency_to_asset() {
+        new_test_ext().execute_with(|| {
+            let input: CurrencyToAssetInput<Test> = read_as((
+                ASSET_A,
+                TradeAmount::<u128, u128>::FixedInput {
+                    input_amount: 1_000,
+                    min_output: 1,
+                },
+                Option::<u32>::None,
+            ));
+            let tokens = Assets::balance(ASSET_A, ACCOUNT_B);
+            let (result, _) = dispatch_filtered(signed(ACCOUNT_B), currency_to_asset_call(input));
+            assert_eq!(result, Ok(()));
+            assert!(Assets::balance(ASSET_A, ACCOUNT_B) > tokens);
+        })
+    }
+
+    #[test]
+    fn asset_to_currency() {
+        new_test_ext().execute_with(|| {
+            let input: AssetToCurrencyInput<Test> = read_as((
+                ASSET_A,
+                TradeAmount::<u128, u128>::FixedInput {
+                    input_amount: 1_000,
+                    min_output: 1,
+                },
+                Option::<u32>::None,
+            ));
+            let tokens = Assets::balance(ASSET_A, ACCOUNT_B);
+            let (result, _) = dispatch_filtered(signed(ACCOUNT_B), asset_to_currency_call(input));
+            assert_eq!(result, Ok(()));
+            assert_eq!(Assets::balance(ASSET_A, ACCOUNT_B), tokens - 1_000);
+        })
+    }
+
+    #[test]
+    fn asset_to_asset() {
+        new_test_ext().execute_with(|| {
+            let input: AssetToAssetInput<Test> = read_as((
+                ASSET_A,
+                ASSET_B,
+                TradeAmount::<u128, u128>::FixedInput {
+                    input_amount: 1_000,
+                    min_output: 1,
+                },
+                Option::<u32>::None,
+            ));
+            // Errors of the pallet are returned as they are
+            let (result, _) = dispatch_filtered(signed(ACCOUNT_B), asset_to_asset_call(input));
+            assert_eq!(result, Err(RpcError::ExchangeNotFound));
+        })
+    }
+
+    #[test]
+    fn filtered_trades() {
+        new_test_ext().execute_with(|| {
+            let input: CurrencyToAssetInput<Test> = read_as((
+                ASSET_A,
+                TradeAmount::<u128, u128>::FixedInput {
+                    input_amount: 1_000,
+                    min_output: 1,
+                },
+                Option::<u32>::None,
+            ));
+            let mut origin = signed(ACCOUNT_B);
+            origin.add_filter(|call| !matches!(call, RuntimeCall::Dex(_)));
+            let tokens = Assets::balance(ASSET_A, ACCOUNT_B);
+            // Nothing is dispatched, so all the weight is refunded
+            assert_eq!(
+                dispatch_filtered(origin, currency_to_asset_call(input)),
+                (
+                    Err(Dex::rpc_error(frame_system::Error::<Test>::CallFiltered.into())),
+                    Some(Weight::zero())
+                )
+            );
+            assert_eq!(Assets::balance(ASSET_A, ACCOUNT_B), tokens);
+        })
+    }
+}
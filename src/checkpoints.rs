@@ -0,0 +1,84 @@
+//! Per-block reserve checkpoints of exchanges, for off-chain audits (`reserve-trace` feature).
+//!
+//! Whenever the reserves of an exchange change, the state of the exchange at the end of the
+//! block is recorded, together with the amounts sold into the exchange by trades in that block.
+//! As every reserve change is recorded, consecutive checkpoints of an exchange form a complete
+//! trace: auditors can verify that the growth of the invariant per liquidity token
+//! (`currency_reserve * token_reserve / liquidity_supply²`) matches the provider fee charged on
+//! the traded amounts, without replaying the chain. Only the last `MaxReserveCheckpoints` blocks
+//! with reserve changes are kept per exchange (in a ring buffer).
+
+use crate::{
+    AssetBalanceOf, AssetIdOf, BalanceOf, Config, ExchangeOf, Pallet, ReserveCheckpoint,
+    ReserveCheckpointCursors, ReserveCheckpointOf, ReserveCheckpoints,
+};
+use frame_support::{
+    sp_runtime::traits::{Saturating, Zero},
+    traits::{fungibles::Inspect, Get},
+};
+use sp_std::vec::Vec;
+
+impl<T: Config> Pallet<T> {
+    /// Record the reserves of `exchange` (after an update) in the checkpoint of the current block,
+    /// adding `currency_in` and `tokens_in` to the amounts traded into the exchange in the block.
+    pub(crate) fn record_reserve_checkpoint(
+        exchange: &ExchangeOf<T>,
+        currency_in: BalanceOf<T>,
+        tokens_in: AssetBalanceOf<T>,
+    ) {
+        let slots = T::MaxReserveCheckpoints::get();
+        if slots.is_zero() {
+            return;
+        }
+        let asset_id = &exchange.asset_id;
+        let now = <frame_system::Pallet<T>>::block_number();
+        // The cursor is out of range if `MaxReserveCheckpoints` was lowered by a runtime upgrade
+        let next = <ReserveCheckpointCursors<T>>::get(asset_id) % slots;
+        let last = next.checked_sub(1).unwrap_or(slots - 1);
+        let (slot, mut checkpoint) = match <ReserveCheckpoints<T>>::get(asset_id, last) {
+            Some(checkpoint) if checkpoint.block_number == now => (last, checkpoint),
+            _ => {
+                <ReserveCheckpointCursors<T>>::insert(asset_id, next.saturating_add(1) % slots);
+                let checkpoint = ReserveCheckpoint {
+                    block_number: now,
+                    currency_reserve: Zero::zero(),
+                    token_reserve: Zero::zero(),
+                    liquidity_supply: Zero::zero(),
+                    currency_in: Zero::zero(),
+                    tokens_in: Zero::zero(),
+                };
+                (next, checkpoint)
+            }
+        };
+        checkpoint.currency_reserve = exchange.currency_reserve;
+        checkpoint.token_reserve = exchange.token_reserve;
+        checkpoint.liquidity_supply =
+            T::AssetRegistry::total_issuance(exchange.liquidity_token_id.clone());
+        checkpoint.currency_in.saturating_accrue(currency_in);
+        checkpoint.tokens_in.saturating_accrue(tokens_in);
+        <ReserveCheckpoints<T>>::insert(asset_id, slot, checkpoint);
+    }
+
+    /// Remove all checkpoints of an exchange (when it is removed).
+    pub(crate) fn clear_reserve_checkpoints(asset_id: &AssetIdOf<T>) {
+        let _ =
+            <ReserveCheckpoints<T>>::clear_prefix(asset_id, T::MaxReserveCheckpoints::get(), None);
+        <ReserveCheckpointCursors<T>>::remove(asset_id);
+    }
+
+    /// Export the trace of reserve transitions of an exchange between blocks `from` and `to`
+    /// (both inclusive), ordered by block number. Blocks without reserve changes have no
+    /// checkpoint, and blocks older than the last `MaxReserveCheckpoints` recorded ones are
+    /// not available anymore.
+    pub fn export_reserve_trace(
+        asset_id: AssetIdOf<T>,
+        from: T::BlockNumber,
+        to: T::BlockNumber,
+    ) -> Vec<ReserveCheckpointOf<T>> {
+        let mut trace: Vec<_> = <ReserveCheckpoints<T>>::iter_prefix_values(asset_id)
+            .filter(|checkpoint| checkpoint.block_number >= from && checkpoint.block_number <= to)
+            .collect();
+        trace.sort_by_key(|checkpoint| checkpoint.block_number);
+        trace
+    }
+}
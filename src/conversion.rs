@@ -0,0 +1,161 @@
+//! Conversion strategies between the currency balance and the asset balance types.
+//!
+//! Currency and asset amounts are mixed in liquidity and price computations, so the pallet
+//! converts them with the `BalanceConversion` type of its config. A conversion fails if the
+//! value can't be represented in the target type, and the error is propagated to the caller
+//! (as `Overflow`), instead of silently producing a wrong amount.
+
+use frame_support::{
+    sp_runtime::traits::{AtLeast32BitUnsigned, UniqueSaturatedInto},
+    traits::Get,
+};
+use sp_std::marker::PhantomData;
+
+/// Two-way conversion between currency and asset balances.
+pub trait BalanceConversion<Balance, AssetBalance> {
+    /// Convert a currency amount into the asset balance domain.
+    fn currency_to_asset(balance: Balance) -> Option<AssetBalance>;
+    /// Convert an asset amount into the currency balance domain.
+    fn asset_to_currency(asset_balance: AssetBalance) -> Option<Balance>;
+}
+
+/// 1:1 unit mapping, failing if the value doesn't fit into the target type.
+/// This is the recommended strategy for balance types of different widths.
+pub struct CheckedConversion;
+
+impl<Balance, AssetBalance> BalanceConversion<Balance, AssetBalance> for CheckedConversion
+where
+    Balance: AtLeast32BitUnsigned,
+    AssetBalance: AtLeast32BitUnsigned,
+{
+    fn currency_to_asset(balance: Balance) -> Option<AssetBalance> {
+        let balance: u128 = balance.try_into().ok()?;
+        balance.try_into().ok()
+    }
+
+    fn asset_to_currency(asset_balance: AssetBalance) -> Option<Balance> {
+        let asset_balance: u128 = asset_balance.try_into().ok()?;
+        asset_balance.try_into().ok()
+    }
+}
+
+/// 1:1 unit mapping, saturating at the maximum value of the target type. Never fails,
+/// so it should only be used if both balance types have the same width.
+pub struct SaturatingConversion;
+
+impl<Balance, AssetBalance> BalanceConversion<Balance, AssetBalance> for SaturatingConversion
+where
+    Balance: AtLeast32BitUnsigned,
+    AssetBalance: AtLeast32BitUnsigned,
+{
+    fn currency_to_asset(balance: Balance) -> Option<AssetBalance> {
+        let balance: u128 = balance.unique_saturated_into();
+        Some(balance.unique_saturated_into())
+    }
+
+    fn asset_to_currency(asset_balance: AssetBalance) -> Option<Balance> {
+        let asset_balance: u128 = asset_balance.unique_saturated_into();
+        Some(asset_balance.unique_saturated_into())
+    }
+}
+
+/// Unit mapping scaled by the difference of the currency and asset decimals, e.g. one unit
+/// of a currency with 12 decimals corresponds to 10^6 units of an asset with 18 decimals.
+/// Scaling down rounds down. Fails if the scaled value doesn't fit into the target type.
+pub struct ScaledConversion<CurrencyDecimals, AssetDecimals>(
+    PhantomData<(CurrencyDecimals, AssetDecimals)>,
+);
+
+impl<CurrencyDecimals: Get<u8>, AssetDecimals: Get<u8>>
+    ScaledConversion<CurrencyDecimals, AssetDecimals>
+{
+    fn scale(value: u128, from_decimals: u8, to_decimals: u8) -> Option<u128> {
+        if to_decimals >= from_decimals {
+            value.checked_mul(10u128.checked_pow((to_decimals - from_decimals).into())?)
+        } else {
+            // Dividing by more than 10^38 (u128::MAX < 10^39) always gives zero
+            Some(
+                10u128
+                    .checked_pow((from_decimals - to_decimals).into())
+                    .map_or(0, |divisor| value / divisor),
+            )
+        }
+    }
+}
+
+impl<Balance, AssetBalance, CurrencyDecimals, AssetDecimals>
+    BalanceConversion<Balance, AssetBalance> for ScaledConversion<CurrencyDecimals, AssetDecimals>
+where
+    Balance: AtLeast32BitUnsigned,
+    AssetBalance: AtLeast32BitUnsigned,
+    CurrencyDecimals: Get<u8>,
+    AssetDecimals: Get<u8>,
+{
+    fn currency_to_asset(balance: Balance) -> Option<AssetBalance> {
+        let balance: u128 = balance.try_into().ok()?;
+        Self::scale(balance, CurrencyDecimals::get(), AssetDecimals::get())?
+            .try_into()
+            .ok()
+    }
+
+    fn asset_to_currency(asset_balance: AssetBalance) -> Option<Balance> {
+        let asset_balance: u128 = asset_balance.try_into().ok()?;
+        Self::scale(asset_balance, AssetDecimals::get(), CurrencyDecimals::get())?
+            .try_into()
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_support::traits::ConstU8;
+
+    type Scaled = ScaledConversion<ConstU8<12>, ConstU8<18>>;
+
+    #[test]
+    fn checked_conversion() {
+        assert_eq!(
+            <CheckedConversion as BalanceConversion<u128, u64>>::currency_to_asset(1_000),
+            Some(1_000)
+        );
+        assert_eq!(
+            <CheckedConversion as BalanceConversion<u128, u64>>::currency_to_asset(u128::MAX),
+            None
+        );
+        assert_eq!(
+            <CheckedConversion as BalanceConversion<u128, u64>>::asset_to_currency(u64::MAX),
+            Some(u64::MAX as u128)
+        );
+    }
+
+    #[test]
+    fn saturating_conversion() {
+        assert_eq!(
+            <SaturatingConversion as BalanceConversion<u128, u64>>::currency_to_asset(u128::MAX),
+            Some(u64::MAX)
+        );
+        assert_eq!(
+            <SaturatingConversion as BalanceConversion<u128, u64>>::asset_to_currency(1_000),
+            Some(1_000)
+        );
+    }
+
+    #[test]
+    fn scaled_conversion() {
+        assert_eq!(
+            <Scaled as BalanceConversion<u128, u128>>::currency_to_asset(1_000),
+            Some(1_000_000_000)
+        );
+        assert_eq!(
+            <Scaled as BalanceConversion<u128, u128>>::asset_to_currency(1_999_999),
+            Some(1)
+        );
+        assert_eq!(<Scaled as BalanceConversion<u128, u128>>::currency_to_asset(u128::MAX), None);
+        type ScaledDown = ScaledConversion<ConstU8<0>, ConstU8<40>>;
+        assert_eq!(
+            <ScaledDown as BalanceConversion<u128, u128>>::asset_to_currency(u128::MAX),
+            Some(0)
+        );
+    }
+}
@@ -0,0 +1,828 @@
+//! Invariants (curves) pricing the trades of an exchange.
+//!
+//! The pallet prices all trades through the [`CurveInvariant`] trait, with the curve stored per
+//! exchange (see `Config::Curve` and `set_curve`). Exchanges without a curve use the default one,
+//! i.e. the constant product of [`crate::pricing`]. [`Curve`] bundles the curves of this crate;
+//! a runtime (or a downstream fork) can add experimental curves by implementing the trait for its
+//! own type, without patching the swap, liquidity or RPC code.
+//!
+//! Both reserves are passed in the currency balance domain (converted with `BalanceConversion`),
+//! and all results are rounded in favour of the exchange, like the constant product prices.
+//!
+//! The spot price of a curve (see [`CurveInvariant::spot_price`]) is used wherever the pallet needs
+//! the current price of an exchange (price limits, the spot price RPC), and the value of its
+//! invariant (see [`CurveInvariant::liquidity_value`]) measures the yield of the liquidity.
+
+use crate::{
+    arithmetic::AuditedSaturating,
+    pricing::{self, PriceError},
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::sp_runtime::{
+    helpers_128bit::multiply_by_rational_with_rounding,
+    traits::{AtLeast32BitUnsigned, Zero},
+    Rounding, RuntimeDebug,
+};
+use scale_info::TypeInfo;
+
+/// Maximum amplification coefficient of a [`StableSwap`] curve.
+pub const MAX_AMPLIFICATION: u32 = 1_000_000;
+
+/// Maximum weight of a reserve of a [`Weighted`] curve, bounding the multiplications of its
+/// powers and roots (and so the cost of its prices).
+pub const MAX_WEIGHT: u8 = 20;
+
+/// Maximum number of Newton iterations of the [`StableSwap`] invariant computations.
+const MAX_ITERATIONS: u32 = 255;
+
+/// Units by which the [`StableSwap`] invariant and reserves are rounded in favour of the
+/// exchange, covering the precision of the Newton iterations.
+const ROUNDING_MARGIN: u128 = 2;
+
+/// Fixed-point unit of the [`Weighted`] curve computations (18 decimals, like `FixedU128`).
+const ONE: u128 = 1_000_000_000_000_000_000;
+
+/// Which side of the exchange is sold.
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum TradeDirection {
+    /// Currency is sold for tokens
+    CurrencyToAsset,
+    /// Tokens are sold for currency
+    AssetToCurrency,
+}
+
+/// Invariant of an exchange, pricing its trades. The provider fee is a fraction
+/// `fee_numerator / fee_denominator` of the input amount.
+pub trait CurveInvariant<B> {
+    /// Check the parameters of the curve (e.g. when it is set by governance).
+    fn is_valid(&self) -> bool {
+        true
+    }
+
+    /// Get the amount received for selling exactly `input_amount` to a pool with the given reserves.
+    fn input_price(
+        &self,
+        direction: TradeDirection,
+        input_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError>;
+
+    /// Get the amount which must be sold to a pool with the given reserves to receive exactly
+    /// `output_amount`.
+    fn output_price(
+        &self,
+        direction: TradeDirection,
+        output_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError>;
+
+    /// Get the spot price of the asset (currency per token, without the provider fee) of a pool with
+    /// the given reserves, in 18-decimal fixed point (rounded down).
+    fn spot_price(&self, currency_reserve: B, token_reserve: B) -> Result<u128, PriceError>;
+
+    /// Get the value of the invariant of a pool with the given reserves, in the unit of the reserves.
+    /// The value is homogeneous of degree 1 (scaling both reserves scales it by the
+    /// same factor), and only grows with the provider fees, so its growth per liquidity token
+    /// measures the yield of the liquidity.
+    fn liquidity_value(
+        &self,
+        currency_reserve: u128,
+        token_reserve: u128,
+    ) -> Result<u128, PriceError>;
+
+    /// Get the curve with the most expensive price computations, for benchmarking the trades.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn worst_case() -> Self
+    where
+        Self: Sized + Default,
+    {
+        Self::default()
+    }
+}
+
+/// Constant product (`x * y = k`) curve of Uniswap V1, see [`crate::pricing`].
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ConstantProduct;
+
+impl<B: AtLeast32BitUnsigned + Copy> CurveInvariant<B> for ConstantProduct {
+    fn input_price(
+        &self,
+        _direction: TradeDirection,
+        input_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError> {
+        pricing::input_price(
+            input_amount,
+            input_reserve,
+            output_reserve,
+            fee_numerator,
+            fee_denominator,
+        )
+    }
+
+    fn output_price(
+        &self,
+        _direction: TradeDirection,
+        output_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError> {
+        pricing::output_price(
+            output_amount,
+            input_reserve,
+            output_reserve,
+            fee_numerator,
+            fee_denominator,
+        )
+    }
+
+    fn spot_price(&self, currency_reserve: B, token_reserve: B) -> Result<u128, PriceError> {
+        let (currency_reserve, token_reserve) = reserves(currency_reserve, token_reserve)?;
+        mul_div(currency_reserve, ONE, token_reserve, Rounding::Down)
+    }
+
+    fn liquidity_value(
+        &self,
+        currency_reserve: u128,
+        token_reserve: u128,
+    ) -> Result<u128, PriceError> {
+        // Geometric mean of the reserves
+        root_up(mul_div(currency_reserve, token_reserve, ONE, Rounding::Down)?, 2)
+    }
+}
+
+/// StableSwap (Curve) invariant of two assets, for assets pegged to the currency: the price stays
+/// close to 1 unless the pool is heavily imbalanced. The higher the amplification coefficient,
+/// the flatter the curve around the balanced point.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct StableSwap {
+    /// Amplification coefficient, between 1 and [`MAX_AMPLIFICATION`]
+    pub amplification: u32,
+}
+
+impl StableSwap {
+    /// Compute the invariant `D` of the reserves `x` and `y` (rounded down).
+    fn invariant(&self, x: u128, y: u128) -> Result<u128, PriceError> {
+        let sum = x.checked_add(y).ok_or(PriceError::Overflow)?;
+        let ann = u128::from(self.amplification) * 4;
+        let mut d = sum;
+        for _ in 0..MAX_ITERATIONS {
+            // d_p = d^3 / (4 * x * y)
+            let d_p = mul_div(
+                mul_div(d, d, x.audited_mul(2), Rounding::Down)?,
+                d,
+                y.audited_mul(2),
+                Rounding::Down,
+            )?;
+            let numerator = ann
+                .checked_mul(sum)
+                .and_then(|n| n.checked_add(d_p.checked_mul(2)?))
+                .ok_or(PriceError::Overflow)?;
+            let denominator = ann
+                .saturating_sub(1)
+                .checked_mul(d)
+                .and_then(|n| n.checked_add(d_p.checked_mul(3)?))
+                .ok_or(PriceError::Overflow)?;
+            let previous = d;
+            d = mul_div(numerator, d, denominator, Rounding::Down)?;
+            if d.max(previous) - d.min(previous) <= 1 {
+                return Ok(d);
+            }
+        }
+        Err(PriceError::Overflow)
+    }
+
+    /// Compute the reserve `y` matching the reserve `x` for the invariant `d` (rounded down).
+    fn other_reserve(&self, x: u128, d: u128) -> Result<u128, PriceError> {
+        let ann = u128::from(self.amplification) * 4;
+        // c = d^3 / (4 * x * ann), b = x + d / ann
+        let c =
+            mul_div(mul_div(d, d, x.audited_mul(2), Rounding::Down)?, d, ann * 2, Rounding::Down)?;
+        let b = d
+            .checked_div(ann)
+            .and_then(|n| x.checked_add(n))
+            .ok_or(PriceError::Overflow)?;
+        let mut y = d;
+        for _ in 0..MAX_ITERATIONS {
+            // y = (y^2 + c) / (2 * y + b - d)
+            let denominator = y
+                .checked_mul(2)
+                .and_then(|n| n.checked_add(b))
+                .and_then(|n| n.checked_sub(d))
+                .filter(|n| !n.is_zero())
+                .ok_or(PriceError::Overflow)?;
+            let previous = y;
+            y = mul_div(y, y, denominator, Rounding::Down)?
+                .checked_add(c / denominator)
+                .ok_or(PriceError::Overflow)?;
+            if y.max(previous) - y.min(previous) <= 1 {
+                return Ok(y);
+            }
+        }
+        Err(PriceError::Overflow)
+    }
+}
+
+impl<B: AtLeast32BitUnsigned + Copy> CurveInvariant<B> for StableSwap {
+    fn is_valid(&self) -> bool {
+        (1..=MAX_AMPLIFICATION).contains(&self.amplification)
+    }
+
+    fn input_price(
+        &self,
+        _direction: TradeDirection,
+        input_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError> {
+        let (input_reserve, output_reserve) = (to_u128(input_reserve)?, to_u128(output_reserve)?);
+        if input_reserve.is_zero() || output_reserve.is_zero() {
+            return Err(PriceError::NotEnoughLiquidity);
+        }
+        let input_amount = net_input(to_u128(input_amount)?, fee_numerator, fee_denominator)?;
+        let d = self
+            .invariant(input_reserve, output_reserve)?
+            .audited_add(1);
+        let new_input_reserve = input_reserve
+            .checked_add(input_amount)
+            .ok_or(PriceError::Overflow)?;
+        let new_output_reserve = self
+            .other_reserve(new_input_reserve, d)?
+            .audited_add(ROUNDING_MARGIN);
+        from_u128(output_reserve.saturating_sub(new_output_reserve))
+    }
+
+    fn output_price(
+        &self,
+        _direction: TradeDirection,
+        output_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError> {
+        let (input_reserve, output_reserve) = (to_u128(input_reserve)?, to_u128(output_reserve)?);
+        let output_amount = to_u128(output_amount)?;
+        if input_reserve.is_zero() || output_amount >= output_reserve {
+            return Err(PriceError::NotEnoughLiquidity);
+        }
+        let d = self
+            .invariant(input_reserve, output_reserve)?
+            .audited_add(1);
+        let new_input_reserve = self
+            .other_reserve(output_reserve - output_amount, d)?
+            .audited_add(ROUNDING_MARGIN);
+        let input_amount = new_input_reserve.saturating_sub(input_reserve);
+        gross_input(input_amount, fee_numerator, fee_denominator)
+    }
+
+    fn spot_price(&self, currency_reserve: B, token_reserve: B) -> Result<u128, PriceError> {
+        let (x, y) = reserves(currency_reserve, token_reserve)?;
+        let ann = u128::from(self.amplification) * 4;
+        let d = self.invariant(x, y)?;
+        // Ratio of the partial derivatives of the invariant: with k = d^3 / (4 * x * y),
+        // price = (ann + k / y) / (ann + k / x)
+        let k = mul_div(
+            mul_div(d, d, x.audited_mul(2), Rounding::Down)?,
+            d,
+            y.audited_mul(2),
+            Rounding::Down,
+        )?;
+        let ann = ann.checked_mul(ONE).ok_or(PriceError::Overflow)?;
+        let numerator = mul_div(k, ONE, y, Rounding::Down)?
+            .checked_add(ann)
+            .ok_or(PriceError::Overflow)?;
+        let denominator = mul_div(k, ONE, x, Rounding::Up)?
+            .checked_add(ann)
+            .ok_or(PriceError::Overflow)?;
+        mul_div(numerator, ONE, denominator, Rounding::Down)
+    }
+
+    fn liquidity_value(
+        &self,
+        currency_reserve: u128,
+        token_reserve: u128,
+    ) -> Result<u128, PriceError> {
+        let (x, y) = reserves(currency_reserve, token_reserve)?;
+        self.invariant(x, y)
+    }
+}
+
+/// Weighted (Balancer) invariant `x^wx * y^wy = k` of two assets, with the reserves weighted by
+/// the (integer) weights of the currency and the asset. At the balanced point, the currency
+/// reserve is worth `currency_weight / (currency_weight + token_weight)` of the pool. Prices are
+/// computed in 18-decimal fixed point.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct Weighted {
+    /// Weight of the currency reserve, between 1 and [`MAX_WEIGHT`]
+    pub currency_weight: u8,
+    /// Weight of the token reserve, between 1 and [`MAX_WEIGHT`]
+    pub token_weight: u8,
+}
+
+impl Weighted {
+    /// Weights of the input and output reserves of a trade.
+    fn weights(&self, direction: TradeDirection) -> (u32, u32) {
+        let (currency_weight, token_weight) =
+            (u32::from(self.currency_weight), u32::from(self.token_weight));
+        match direction {
+            TradeDirection::CurrencyToAsset => (currency_weight, token_weight),
+            TradeDirection::AssetToCurrency => (token_weight, currency_weight),
+        }
+    }
+}
+
+impl<B: AtLeast32BitUnsigned + Copy> CurveInvariant<B> for Weighted {
+    fn is_valid(&self) -> bool {
+        (1..=MAX_WEIGHT).contains(&self.currency_weight)
+            && (1..=MAX_WEIGHT).contains(&self.token_weight)
+    }
+
+    fn input_price(
+        &self,
+        direction: TradeDirection,
+        input_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError> {
+        let (input_reserve, output_reserve) = (to_u128(input_reserve)?, to_u128(output_reserve)?);
+        if input_reserve.is_zero() || output_reserve.is_zero() {
+            return Err(PriceError::NotEnoughLiquidity);
+        }
+        let input_amount = net_input(to_u128(input_amount)?, fee_numerator, fee_denominator)?;
+        let (input_weight, output_weight) = self.weights(direction);
+        // output = output_reserve * (1 - (input_reserve / new_input_reserve)^(wi / wo)),
+        // with the ratio rounded up
+        let new_input_reserve = input_reserve
+            .checked_add(input_amount)
+            .ok_or(PriceError::Overflow)?;
+        let base = mul_div(input_reserve, ONE, new_input_reserve, Rounding::Up)?;
+        let ratio = root_up(pow(base, input_weight, Rounding::Up)?, output_weight)?;
+        from_u128(mul_div(output_reserve, ONE.saturating_sub(ratio), ONE, Rounding::Down)?)
+    }
+
+    fn output_price(
+        &self,
+        direction: TradeDirection,
+        output_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError> {
+        let (input_reserve, output_reserve) = (to_u128(input_reserve)?, to_u128(output_reserve)?);
+        let output_amount = to_u128(output_amount)?;
+        if input_reserve.is_zero() || output_amount >= output_reserve {
+            return Err(PriceError::NotEnoughLiquidity);
+        }
+        let (input_weight, output_weight) = self.weights(direction);
+        // input = input_reserve * ((output_reserve / new_output_reserve)^(wo / wi) - 1),
+        // with the ratio rounded up
+        let base = mul_div(output_reserve, ONE, output_reserve - output_amount, Rounding::Up)?;
+        let ratio = root_up(pow(base, output_weight, Rounding::Up)?, input_weight)?;
+        let input_amount = mul_div(input_reserve, ratio.saturating_sub(ONE), ONE, Rounding::Up)?;
+        gross_input(input_amount, fee_numerator, fee_denominator)
+    }
+
+    fn spot_price(&self, currency_reserve: B, token_reserve: B) -> Result<u128, PriceError> {
+        let (currency_reserve, token_reserve) = reserves(currency_reserve, token_reserve)?;
+        // price = (currency_reserve / currency_weight) / (token_reserve / token_weight)
+        mul_div(
+            mul_div(currency_reserve, ONE, token_reserve, Rounding::Down)?,
+            u128::from(self.token_weight),
+            u128::from(self.currency_weight),
+            Rounding::Down,
+        )
+    }
+
+    fn liquidity_value(
+        &self,
+        currency_reserve: u128,
+        token_reserve: u128,
+    ) -> Result<u128, PriceError> {
+        let (currency_reserve, token_reserve) = reserves(currency_reserve, token_reserve)?;
+        // Weighted geometric mean of the reserves:
+        // currency_reserve * (token_reserve / currency_reserve)^(wt / (wc + wt))
+        let (currency_weight, token_weight) =
+            (u32::from(self.currency_weight), u32::from(self.token_weight));
+        let ratio = mul_div(token_reserve, ONE, currency_reserve, Rounding::Down)?;
+        let ratio =
+            root_up(pow(ratio, token_weight, Rounding::Down)?, currency_weight + token_weight)?;
+        mul_div(currency_reserve, ratio, ONE, Rounding::Down)
+    }
+}
+
+/// Curves of this crate, selectable per exchange (the default is the constant product).
+#[derive(Clone, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum Curve {
+    /// Constant product curve
+    #[default]
+    ConstantProduct,
+    /// StableSwap curve
+    StableSwap(StableSwap),
+    /// Weighted curve
+    Weighted(Weighted),
+}
+
+impl<B: AtLeast32BitUnsigned + Copy> CurveInvariant<B> for Curve {
+    fn is_valid(&self) -> bool {
+        match self {
+            Curve::ConstantProduct => true,
+            Curve::StableSwap(curve) => CurveInvariant::<B>::is_valid(curve),
+            Curve::Weighted(curve) => CurveInvariant::<B>::is_valid(curve),
+        }
+    }
+
+    fn input_price(
+        &self,
+        direction: TradeDirection,
+        input_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError> {
+        let curve: &dyn CurveInvariant<B> = match self {
+            Curve::ConstantProduct => &ConstantProduct,
+            Curve::StableSwap(curve) => curve,
+            Curve::Weighted(curve) => curve,
+        };
+        curve.input_price(
+            direction,
+            input_amount,
+            input_reserve,
+            output_reserve,
+            fee_numerator,
+            fee_denominator,
+        )
+    }
+
+    fn output_price(
+        &self,
+        direction: TradeDirection,
+        output_amount: B,
+        input_reserve: B,
+        output_reserve: B,
+        fee_numerator: B,
+        fee_denominator: B,
+    ) -> Result<B, PriceError> {
+        let curve: &dyn CurveInvariant<B> = match self {
+            Curve::ConstantProduct => &ConstantProduct,
+            Curve::StableSwap(curve) => curve,
+            Curve::Weighted(curve) => curve,
+        };
+        curve.output_price(
+            direction,
+            output_amount,
+            input_reserve,
+            output_reserve,
+            fee_numerator,
+            fee_denominator,
+        )
+    }
+
+    fn spot_price(&self, currency_reserve: B, token_reserve: B) -> Result<u128, PriceError> {
+        let curve: &dyn CurveInvariant<B> = match self {
+            Curve::ConstantProduct => &ConstantProduct,
+            Curve::StableSwap(curve) => curve,
+            Curve::Weighted(curve) => curve,
+        };
+        curve.spot_price(currency_reserve, token_reserve)
+    }
+
+    fn liquidity_value(
+        &self,
+        currency_reserve: u128,
+        token_reserve: u128,
+    ) -> Result<u128, PriceError> {
+        let curve: &dyn CurveInvariant<B> = match self {
+            Curve::ConstantProduct => &ConstantProduct,
+            Curve::StableSwap(curve) => curve,
+            Curve::Weighted(curve) => curve,
+        };
+        curve.liquidity_value(currency_reserve, token_reserve)
+    }
+
+    /// The weighted curve with the highest weights: both the powers and the roots of its prices
+    /// take the most multiplications (the roots for each bisection step), much more than the
+    /// Newton iterations of the StableSwap curve. Its spot price is the one of the constant
+    /// product, so it can be set on any exchange.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn worst_case() -> Self {
+        Curve::Weighted(Weighted {
+            currency_weight: MAX_WEIGHT,
+            token_weight: MAX_WEIGHT,
+        })
+    }
+}
+
+/// Both reserves as `u128`, if neither is zero.
+fn reserves<B: AtLeast32BitUnsigned>(
+    currency_reserve: B,
+    token_reserve: B,
+) -> Result<(u128, u128), PriceError> {
+    let (currency_reserve, token_reserve) = (to_u128(currency_reserve)?, to_u128(token_reserve)?);
+    if currency_reserve.is_zero() || token_reserve.is_zero() {
+        return Err(PriceError::NotEnoughLiquidity);
+    }
+    Ok((currency_reserve, token_reserve))
+}
+
+fn to_u128<B: AtLeast32BitUnsigned>(value: B) -> Result<u128, PriceError> {
+    value.try_into().map_err(|_| PriceError::Overflow)
+}
+
+fn from_u128<B: AtLeast32BitUnsigned>(value: u128) -> Result<B, PriceError> {
+    value.try_into().map_err(|_| PriceError::Overflow)
+}
+
+fn mul_div(a: u128, b: u128, c: u128, rounding: Rounding) -> Result<u128, PriceError> {
+    multiply_by_rational_with_rounding(a, b, c, rounding).ok_or(PriceError::Overflow)
+}
+
+/// Input amount without the provider fee (rounded down).
+fn net_input<B: AtLeast32BitUnsigned>(
+    input_amount: u128,
+    fee_numerator: B,
+    fee_denominator: B,
+) -> Result<u128, PriceError> {
+    let fee_denominator = to_u128(fee_denominator)?;
+    let net_numerator = fee_denominator
+        .checked_sub(to_u128(fee_numerator)?)
+        .ok_or(PriceError::Overflow)?;
+    mul_div(input_amount, net_numerator, fee_denominator, Rounding::Down)
+}
+
+/// Input amount including the provider fee (rounded up).
+fn gross_input<B: AtLeast32BitUnsigned>(
+    input_amount: u128,
+    fee_numerator: B,
+    fee_denominator: B,
+) -> Result<B, PriceError> {
+    let fee_denominator = to_u128(fee_denominator)?;
+    let net_numerator = fee_denominator
+        .checked_sub(to_u128(fee_numerator)?)
+        .filter(|n| !n.is_zero())
+        .ok_or(PriceError::Overflow)?;
+    from_u128(mul_div(input_amount, fee_denominator, net_numerator, Rounding::Up)?)
+}
+
+/// `base^exponent` in fixed point, each multiplication rounded as given. Computed by squaring,
+/// so it takes at most 64 multiplications whatever the exponent.
+pub(crate) fn pow(
+    mut base: u128,
+    mut exponent: u32,
+    rounding: Rounding,
+) -> Result<u128, PriceError> {
+    let mut result = ONE;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_div(result, base, ONE, rounding)?;
+        }
+        exponent >>= 1;
+        // Not squared past the highest bit, so a power which fits doesn't overflow
+        if exponent > 0 {
+            base = mul_div(base, base, ONE, rounding)?;
+        }
+    }
+    Ok(result)
+}
+
+/// Smallest fixed-point `y` with `y^exponent >= value` (the root rounded up), by bisection.
+pub(crate) fn root_up(value: u128, exponent: u32) -> Result<u128, PriceError> {
+    if exponent == 1 {
+        return Ok(value);
+    }
+    // The root lies between 1 and `value` if `value >= 1`, between `value` and 1 otherwise
+    let (mut low, mut high) = if value >= ONE {
+        (ONE, value)
+    } else {
+        (value, ONE)
+    };
+    while low < high {
+        let middle = low + (high - low) / 2;
+        // Rounded down, so a root found from it is not lower than the exact root
+        match pow(middle, exponent, Rounding::Down) {
+            Ok(power) if power < value => low = middle + 1,
+            _ => high = middle,
+        }
+    }
+    Ok(high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESERVE: u128 = 1_000_000_000_000;
+
+    #[test]
+    fn constant_product_matches_pricing() {
+        let curve = Curve::default();
+        assert_eq!(
+            curve.input_price(
+                TradeDirection::CurrencyToAsset,
+                1_000_000u128,
+                RESERVE,
+                RESERVE,
+                3,
+                1000
+            ),
+            pricing::input_price(1_000_000u128, RESERVE, RESERVE, 3, 1000)
+        );
+        assert_eq!(
+            curve.output_price(
+                TradeDirection::AssetToCurrency,
+                1_000_000u128,
+                RESERVE,
+                RESERVE,
+                3,
+                1000
+            ),
+            pricing::output_price(1_000_000u128, RESERVE, RESERVE, 3, 1000)
+        );
+    }
+
+    #[test]
+    fn stable_swap() {
+        let curve = Curve::StableSwap(StableSwap { amplification: 100 });
+        let direction = TradeDirection::CurrencyToAsset;
+        // Balanced pool: almost 1:1, much less price impact than the constant product
+        let output = curve
+            .input_price(direction, RESERVE / 10, RESERVE, RESERVE, 0, 1)
+            .unwrap();
+        let constant_product = pricing::input_price(RESERVE / 10, RESERVE, RESERVE, 0, 1).unwrap();
+        assert!(output < RESERVE / 10);
+        assert!(output > RESERVE / 10 - RESERVE / 10_000);
+        assert!(output > constant_product);
+        // Output and input prices are consistent
+        let input = curve
+            .output_price(direction, output, RESERVE, RESERVE, 0, 1)
+            .unwrap();
+        assert!(input.abs_diff(RESERVE / 10) <= 4);
+        // The fee is charged on the input
+        assert!(
+            curve
+                .input_price(direction, RESERVE / 10, RESERVE, RESERVE, 3, 1000)
+                .unwrap()
+                < output
+        );
+    }
+
+    #[test]
+    fn weighted() {
+        let even = Curve::Weighted(Weighted {
+            currency_weight: 1,
+            token_weight: 1,
+        });
+        // Equal weights match the constant product (up to rounding)
+        let output = even.input_price(
+            TradeDirection::CurrencyToAsset,
+            1_000_000u128,
+            RESERVE,
+            RESERVE,
+            3,
+            1000,
+        );
+        let constant_product =
+            pricing::input_price(1_000_000u128, RESERVE, RESERVE, 3, 1000).unwrap();
+        assert!(output.unwrap().abs_diff(constant_product) <= 1);
+
+        // 80/20 pool, with the currency reserve worth 4 times the token reserve at the same price
+        let curve = Curve::Weighted(Weighted {
+            currency_weight: 4,
+            token_weight: 1,
+        });
+        let output = curve
+            .input_price(TradeDirection::CurrencyToAsset, 1_000u128, 4 * RESERVE, RESERVE, 0, 1)
+            .unwrap();
+        assert!((998..=1_000).contains(&output));
+        let input =
+            curve.output_price(TradeDirection::CurrencyToAsset, output, 4 * RESERVE, RESERVE, 0, 1);
+        assert!((999..=1_000).contains(&input.unwrap()));
+    }
+
+    #[test]
+    fn spot_prices() {
+        let stable_swap = Curve::StableSwap(StableSwap { amplification: 100 });
+        let weighted = Curve::Weighted(Weighted {
+            currency_weight: 4,
+            token_weight: 1,
+        });
+        // Constant product: ratio of the reserves
+        assert_eq!(Curve::default().spot_price(2 * RESERVE, RESERVE), Ok(2 * ONE));
+        // StableSwap: 1 at the balanced point, close to 1 around it
+        assert_eq!(stable_swap.spot_price(RESERVE, RESERVE), Ok(ONE));
+        let price = stable_swap.spot_price(RESERVE, 2 * RESERVE).unwrap();
+        assert!(price < ONE && price > ONE * 99 / 100);
+        // The spot price is the marginal price of a small trade
+        let output = stable_swap
+            .input_price(TradeDirection::CurrencyToAsset, 1_000_000u128, RESERVE, 2 * RESERVE, 0, 1)
+            .unwrap();
+        let marginal = mul_div(1_000_000, ONE, output, Rounding::Down).unwrap();
+        assert!(marginal.abs_diff(price) < ONE / 100_000);
+        // Weighted: reserves divided by their weights
+        assert_eq!(weighted.spot_price(4 * RESERVE, RESERVE), Ok(ONE));
+        for curve in [Curve::default(), stable_swap, weighted] {
+            assert_eq!(curve.spot_price(0u128, RESERVE), Err(PriceError::NotEnoughLiquidity));
+        }
+    }
+
+    #[test]
+    fn powers_and_roots() {
+        assert_eq!(pow(2 * ONE, 10, Rounding::Down), Ok(1_024 * ONE));
+        assert_eq!(pow(ONE / 2, 3, Rounding::Down), Ok(ONE / 8));
+        assert_eq!(pow(2 * ONE, 0, Rounding::Down), Ok(ONE));
+        assert_eq!(pow(2 * ONE, 200, Rounding::Down), Err(PriceError::Overflow));
+        assert_eq!(root_up(1_024 * ONE, 10), Ok(2 * ONE));
+        assert_eq!(root_up(ONE / 8, 3), Ok(ONE / 2));
+        // Large exponents are cheap
+        assert_eq!(pow(ONE, u32::MAX, Rounding::Down), Ok(ONE));
+        let root = root_up(2 * ONE, u32::MAX).unwrap();
+        assert!(root > ONE && root - ONE < ONE / 1_000_000_000);
+    }
+
+    #[test]
+    fn liquidity_values() {
+        let even = Curve::Weighted(Weighted {
+            currency_weight: 1,
+            token_weight: 1,
+        });
+        let value = |curve: &Curve, x, y| CurveInvariant::<u128>::liquidity_value(curve, x, y);
+        // Constant product and even weights: geometric mean of the reserves
+        assert_eq!(value(&Curve::default(), 4 * RESERVE, RESERVE), Ok(2 * RESERVE));
+        assert!(
+            value(&even, 4 * RESERVE, RESERVE)
+                .unwrap()
+                .abs_diff(2 * RESERVE)
+                <= 1
+        );
+        // StableSwap: sum of the reserves at the balanced point
+        let stable_swap = Curve::StableSwap(StableSwap { amplification: 100 });
+        assert!(
+            value(&stable_swap, RESERVE, RESERVE)
+                .unwrap()
+                .abs_diff(2 * RESERVE)
+                <= 1
+        );
+        // Trading (without fees) doesn't change the value
+        let output = stable_swap
+            .input_price(TradeDirection::CurrencyToAsset, RESERVE / 10, RESERVE, RESERVE, 0, 1)
+            .unwrap();
+        let traded = value(&stable_swap, RESERVE + RESERVE / 10, RESERVE - output).unwrap();
+        assert!(traded.abs_diff(2 * RESERVE) <= 4);
+    }
+
+    #[test]
+    fn curve_errors() {
+        let curves = [
+            Curve::StableSwap(StableSwap { amplification: 100 }),
+            Curve::Weighted(Weighted {
+                currency_weight: 1,
+                token_weight: 1,
+            }),
+        ];
+        let direction = TradeDirection::CurrencyToAsset;
+        for curve in curves {
+            assert_eq!(
+                curve.input_price(direction, 1u128, 0, 1, 3, 1000),
+                Err(PriceError::NotEnoughLiquidity)
+            );
+            assert_eq!(
+                curve.output_price(direction, 1u128, 1, 1, 3, 1000),
+                Err(PriceError::NotEnoughLiquidity)
+            );
+            assert_eq!(curve.input_price(direction, 1u128, 1, 1, 2, 1), Err(PriceError::Overflow));
+        }
+        assert!(!CurveInvariant::<u128>::is_valid(&Curve::StableSwap(StableSwap {
+            amplification: 0
+        })));
+        assert!(!CurveInvariant::<u128>::is_valid(&Curve::Weighted(Weighted {
+            currency_weight: 0,
+            token_weight: 1
+        })));
+        assert!(!CurveInvariant::<u128>::is_valid(&Curve::Weighted(Weighted {
+            currency_weight: 1,
+            token_weight: MAX_WEIGHT + 1
+        })));
+        assert!(CurveInvariant::<u128>::is_valid(&Curve::Weighted(Weighted {
+            currency_weight: MAX_WEIGHT,
+            token_weight: MAX_WEIGHT
+        })));
+    }
+}
@@ -0,0 +1,154 @@
+//! Bounded, resumable iteration over exchanges.
+//!
+//! Any feature which needs to visit all exchanges (e.g. statistics, listing or maintenance)
+//! should use [`Pallet::iter_exchanges_bounded`] instead of iterating `Exchanges` directly,
+//! so that a single call never exceeds its item or weight (PoV) budget. Iteration stops when
+//! either budget is exhausted, and can be resumed in a later call (e.g. in the next block)
+//! from the returned cursor.
+
+use crate::{AssetIdOf, Config, ExchangeOf, Exchanges, Pallet};
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::vec::Vec;
+
+/// Raw storage key of the last visited exchange.
+pub type ExchangeCursor = Vec<u8>;
+
+/// Outcome of a bounded iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IterationOutcome {
+    /// Cursor to resume the iteration from, `None` if all exchanges have been visited
+    pub cursor: Option<ExchangeCursor>,
+    /// Number of visited exchanges
+    pub processed: u32,
+    /// Weight consumed by the iteration (storage reads and per-item weight)
+    pub weight: Weight,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Visit at most `max_items` exchanges, starting after `cursor` (or from the start if `None`),
+    /// calling `f` on each of them. Every visited exchange is charged one storage read and
+    /// `weight_per_item` (the weight of `f`). The iteration stops before exceeding `weight_limit`.
+    pub fn iter_exchanges_bounded<F>(
+        cursor: Option<ExchangeCursor>,
+        max_items: u32,
+        weight_limit: Weight,
+        weight_per_item: Weight,
+        mut f: F,
+    ) -> IterationOutcome
+    where
+        F: FnMut(&AssetIdOf<T>, &ExchangeOf<T>),
+    {
+        let item_weight = T::DbWeight::get().reads(1).saturating_add(weight_per_item);
+        let mut iter = match cursor {
+            Some(cursor) => <Exchanges<T>>::iter_from(cursor),
+            None => <Exchanges<T>>::iter(),
+        };
+        let mut processed = 0;
+        let mut weight = Weight::zero();
+        loop {
+            if processed >= max_items || weight.saturating_add(item_weight) > weight_limit {
+                // Budget exhausted, resume from the last visited exchange
+                let cursor = iter.last_raw_key().to_vec();
+                return IterationOutcome {
+                    cursor: Some(cursor),
+                    processed,
+                    weight,
+                };
+            }
+            match iter.next() {
+                Some((asset_id, exchange)) => {
+                    f(&asset_id, &exchange);
+                    processed += 1;
+                    weight = weight.saturating_add(item_weight);
+                }
+                None => {
+                    return IterationOutcome {
+                        cursor: None,
+                        processed,
+                        weight: weight.saturating_add(T::DbWeight::get().reads(1)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::*;
+    use frame_support::{assert_ok, traits::Get, weights::Weight};
+
+    fn create_exchanges() {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false
+        ));
+    }
+
+    #[test]
+    fn iter_exchanges_bounded_all() {
+        new_test_ext().execute_with(|| {
+            create_exchanges();
+            let mut visited = vec![];
+            let outcome = Dex::iter_exchanges_bounded(
+                None,
+                u32::MAX,
+                Weight::MAX,
+                Weight::zero(),
+                |asset_id, _| visited.push(*asset_id),
+            );
+            visited.sort();
+            assert_eq!(visited, vec![ASSET_A, ASSET_B]);
+            assert_eq!(outcome.cursor, None);
+            assert_eq!(outcome.processed, 2);
+        })
+    }
+
+    #[test]
+    fn iter_exchanges_bounded_resume() {
+        new_test_ext().execute_with(|| {
+            create_exchanges();
+            let mut visited = vec![];
+            let mut cursor = None;
+            loop {
+                let outcome =
+                    Dex::iter_exchanges_bounded(cursor, 1, Weight::MAX, Weight::zero(), |id, _| {
+                        visited.push(*id)
+                    });
+                assert!(outcome.processed <= 1);
+                cursor = outcome.cursor;
+                if cursor.is_none() {
+                    break;
+                }
+            }
+            visited.sort();
+            assert_eq!(visited, vec![ASSET_A, ASSET_B]);
+        })
+    }
+
+    #[test]
+    fn iter_exchanges_bounded_weight_limit() {
+        new_test_ext().execute_with(|| {
+            create_exchanges();
+            let weight_per_item = Weight::from_ref_time(1_000);
+            let item_weight = <Test as frame_system::Config>::DbWeight::get()
+                .reads(1)
+                .saturating_add(weight_per_item);
+            let outcome = Dex::iter_exchanges_bounded(
+                None,
+                u32::MAX,
+                item_weight,
+                weight_per_item,
+                |_, _| {},
+            );
+            assert_eq!(outcome.processed, 1);
+            assert_eq!(outcome.weight, item_weight);
+            assert!(outcome.cursor.is_some());
+        })
+    }
+}
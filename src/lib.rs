@@ -9,22 +9,47 @@
 //! (i.e. currency & assets), and perform trades (currency-to-asset, asset-to-currency, asset-to-asset).
 //! DEX pallet also allows querying asset prices by custom RPC methods.
 //!
+//! ## Liquidity tokens as collateral
+//!
+//! Liquidity tokens can be frozen (e.g. by a lending pallet holding them as collateral).
+//! The pallet never burns frozen liquidity tokens – liquidity can only be removed from the part
+//! of a provider's balance which is not frozen (see [`Pallet::get_withdrawable_liquidity`]).
+//! Frozen liquidity keeps accruing fees, as these are added to the exchange reserves.
+//!
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+mod arithmetic;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+#[cfg(feature = "contracts")]
+pub mod chain_extension;
+#[cfg(feature = "reserve-trace")]
+pub mod checkpoints;
+pub mod conversion;
+pub mod curves;
+pub mod iteration;
+#[cfg(test)]
+mod liquidity_spec;
+pub mod migrations;
 #[cfg(test)]
 mod mock;
+pub mod pricing;
 pub mod rpc;
 #[cfg(test)]
 mod tests;
+pub mod traits;
+mod try_state;
 pub mod weights;
+pub mod yields;
 
 use frame_support::traits::Currency;
 use sp_std::prelude::*;
 
 pub use pallet::*;
+#[cfg(feature = "runtime-benchmarks")]
+pub use traits::RfqBenchmarkHelper;
+pub use traits::{InspectDex, OnPoolCreated};
 pub use weights::WeightInfo;
 
 type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
@@ -32,31 +57,45 @@ type BalanceOf<T> = <<T as Config>::Currency as Currency<AccountIdOf<T>>>::Balan
 type AssetIdOf<T> = <T as Config>::AssetId;
 type AssetBalanceOf<T> = <T as Config>::AssetBalance;
 
+pub use substrate_dex_primitives::Price;
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
+    use crate::arithmetic::{audited_share, AuditedSaturating};
+    use crate::conversion::BalanceConversion;
+    use crate::curves::{CurveInvariant, TradeDirection};
     use codec::EncodeLike;
     use frame_support::{
         pallet_prelude::*,
         sp_runtime::{
             traits::{
-                AccountIdConversion, CheckedAdd, CheckedMul, CheckedSub, Convert, One, Saturating,
-                Zero,
+                AccountIdConversion, CheckedAdd, CheckedSub, IdentifyAccount, One, Saturating,
+                Verify, Zero,
             },
-            FixedPointNumber, FixedPointOperand, FixedU128,
+            FixedPointNumber, FixedPointOperand, FixedU128, PerThing, Permill,
         },
         traits::{
             fungibles::{Create, Destroy, Inspect, Mutate, Transfer},
             tokens::{Balance, WithdrawConsequence},
-            ExistenceRequirement,
+            Contains, ExistenceRequirement, ReservableCurrency, WithdrawReasons,
         },
-        transactional, PalletId,
+        transactional,
+        weights::WeightToFee,
+        PalletId,
     };
     use frame_system::pallet_prelude::*;
     use sp_std::fmt::Debug;
 
+    /// The current storage version (see [`crate::migrations`]).
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+    /// The domain separation context of RFQ quote signatures.
+    const RFQ_SIGNING_CONTEXT: &[u8; 7] = b"dex/rfq";
+
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::config]
@@ -69,7 +108,7 @@ pub mod pallet {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
         /// The currency trait.
-        type Currency: Currency<Self::AccountId>;
+        type Currency: ReservableCurrency<Self::AccountId>;
 
         /// The balance type for assets (i.e. tokens).
         type AssetBalance: Balance
@@ -78,9 +117,8 @@ pub mod pallet {
             + MaybeSerializeDeserialize
             + TypeInfo;
 
-        // Two-way conversion between asset and currency balances
-        type AssetToCurrencyBalance: Convert<Self::AssetBalance, BalanceOf<Self>>;
-        type CurrencyToAssetBalance: Convert<BalanceOf<Self>, Self::AssetBalance>;
+        /// Two-way conversion between currency and asset balances (see [`crate::conversion`]).
+        type BalanceConversion: BalanceConversion<BalanceOf<Self>, Self::AssetBalance>;
 
         /// The asset ID type.
         type AssetId: MaybeSerializeDeserialize
@@ -105,6 +143,10 @@ pub mod pallet {
         /// Information on runtime weights.
         type WeightInfo: WeightInfo;
 
+        /// Conversion of the weight of a trade to its transaction fee (e.g. the `WeightToFee` of
+        /// `pallet-transaction-payment`). Sponsorship rebates are capped at the fee of the trade.
+        type WeightToFee: WeightToFee<Balance = BalanceOf<Self>>;
+
         /// Provider fee numerator.
         #[pallet::constant]
         type ProviderFeeNumerator: Get<BalanceOf<Self>>;
@@ -116,12 +158,129 @@ pub mod pallet {
         /// Minimum currency deposit for a new exchange.
         #[pallet::constant]
         type MinDeposit: Get<BalanceOf<Self>>;
+
+        /// Deadline (number of blocks after the current block) of operations submitted without one.
+        /// Must not be greater than `MaxDeadline`.
+        #[pallet::constant]
+        type DefaultDeadline: Get<Self::BlockNumber>;
+
+        /// Maximum number of blocks after the current block the deadline of an operation can be.
+        #[pallet::constant]
+        type MaxDeadline: Get<Self::BlockNumber>;
+
+        /// Maximum number of accounts whitelisted for the bootstrap phase of an exchange.
+        #[pallet::constant]
+        type MaxBootstrapAccounts: Get<u32>;
+
+        /// Maximum number of blocks after the current block the bootstrap phase of an exchange can end.
+        #[pallet::constant]
+        type MaxBootstrapPeriod: Get<Self::BlockNumber>;
+
+        /// Share of the token reserve by which the pallet account balance may diverge from
+        /// the reserve before a `ReserveMismatch` event is emitted.
+        #[pallet::constant]
+        type ReserveMismatchTolerance: Get<Permill>;
+
+        /// Maximum number of snapshot deposits seeded by a single `force_seed_exchange` call.
+        #[pallet::constant]
+        type MaxSeedDeposits: Get<u32>;
+
+        /// Account receiving protocol fees (e.g. the exchange creation fee).
+        #[pallet::constant]
+        type FeeDestination: Get<Self::AccountId>;
+
+        /// Maximum number of operations in a single `batch_liquidity` call.
+        #[pallet::constant]
+        type MaxLiquidityOps: Get<u32>;
+
+        /// Maximum number of blocks after the current block an exchange can be in trade-only mode.
+        #[pallet::constant]
+        type MaxTradeOnlyPeriod: Get<Self::BlockNumber>;
+
+        /// The amount of currency reserved from an account for each long-lived storage item
+        /// it creates (an operator approval or a liquidity stream), released when the item is removed.
+        #[pallet::constant]
+        type StorageDeposit: Get<BalanceOf<Self>>;
+
+        /// Number of blocks liquidity providers have to withdraw from a delisted exchange before
+        /// its remaining reserves can be swept to `RecoveryAccount`.
+        #[pallet::constant]
+        type DelistingGracePeriod: Get<Self::BlockNumber>;
+
+        /// Account receiving the remaining reserves of delisted exchanges.
+        #[pallet::constant]
+        type RecoveryAccount: Get<Self::AccountId>;
+
+        /// Account receiving the donated tokens which are burned (i.e. without beneficiary), as
+        /// the pallet can't burn tradable assets (e.g. an account nobody controls).
+        #[pallet::constant]
+        type BurnAccount: Get<Self::AccountId>;
+
+        /// Maximum number of reserve checkpoints kept per exchange. Only used with the
+        /// `reserve-trace` feature (see [`Pallet::export_reserve_trace`]).
+        #[pallet::constant]
+        type MaxReserveCheckpoints: Get<u32>;
+
+        /// Maximum number of exchanges paused (or unpaused) by governance in a single call.
+        #[pallet::constant]
+        type MaxPausedPerCall: Get<u32>;
+
+        /// Invariant pricing the trades of an exchange, selected per exchange by governance.
+        /// Exchanges without a curve use the default one (see [`curves::Curve`]).
+        type Curve: Member + Parameter + MaxEncodedLen + Default + CurveInvariant<BalanceOf<Self>>;
+
+        /// Minimum number of blocks between two yield checkpoints of an exchange (e.g. the number
+        /// of blocks per day), see [`Pallet::lp_apy`].
+        #[pallet::constant]
+        type YieldCheckpointPeriod: Get<Self::BlockNumber>;
+
+        /// Number of `YieldCheckpointPeriod`s per year, used to annualize yields.
+        #[pallet::constant]
+        type YieldPeriodsPerYear: Get<u32>;
+
+        /// Maximum number of yield checkpoints kept per exchange.
+        #[pallet::constant]
+        type MaxYieldCheckpoints: Get<u32>;
+
+        /// Handler called when a new exchange is created.
+        type OnPoolCreated: OnPoolCreated<Self::AccountId, Self::AssetId>;
+
+        /// External safety oracle. Trades on exchanges for the assets it contains are rejected.
+        type SafetyOracle: Contains<Self::AssetId>;
+
+        /// Origin allowed to manage exchange parameters (e.g. maximum trade size).
+        type GovernanceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Origin allowed to create exchanges in migration mode, i.e. with a liquidity token which
+        /// already has holders. Its success value is the account depositing the reserves.
+        type MigrationOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
+
+        /// Former liquidity tokens which may be reused in migration mode, i.e. liquidity tokens owned
+        /// by the pallet account whose exchange was removed (e.g. by a runtime fix).
+        type MigratableLiquidityTokens: Contains<Self::AssetId>;
+
+        /// Off-chain signature type, used by market makers to sign RFQ quotes.
+        type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+
+        /// Public key type of off-chain signatures, identifying the signing account.
+        type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
+
+        /// Helper for signing RFQ quotes in benchmarks.
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: RfqBenchmarkHelper<Self::AccountId, Self::OffchainSignature>;
     }
 
     pub trait ConfigHelper: Config {
         fn pallet_account() -> AccountIdOf<Self>;
-        fn currency_to_asset(curr_balance: BalanceOf<Self>) -> AssetBalanceOf<Self>;
-        fn asset_to_currency(asset_balance: AssetBalanceOf<Self>) -> BalanceOf<Self>;
+        fn sponsor_account(asset_id: &AssetIdOf<Self>) -> AccountIdOf<Self>;
+        fn stream_escrow_account(asset_id: &AssetIdOf<Self>) -> AccountIdOf<Self>;
+        fn pool_account(asset_id: &AssetIdOf<Self>, subsystem: PoolSubsystem) -> AccountIdOf<Self>;
+        fn currency_to_asset(
+            curr_balance: BalanceOf<Self>,
+        ) -> Result<AssetBalanceOf<Self>, Error<Self>>;
+        fn asset_to_currency(
+            asset_balance: AssetBalanceOf<Self>,
+        ) -> Result<BalanceOf<Self>, Error<Self>>;
         fn net_amount_numerator() -> BalanceOf<Self>;
     }
 
@@ -132,13 +291,32 @@ pub mod pallet {
         }
 
         #[inline(always)]
-        fn currency_to_asset(curr_balance: BalanceOf<Self>) -> AssetBalanceOf<Self> {
-            Self::CurrencyToAssetBalance::convert(curr_balance)
+        fn sponsor_account(asset_id: &AssetIdOf<Self>) -> AccountIdOf<Self> {
+            Self::pool_account(asset_id, PoolSubsystem::Sponsorship)
+        }
+
+        #[inline(always)]
+        fn stream_escrow_account(asset_id: &AssetIdOf<Self>) -> AccountIdOf<Self> {
+            Self::pool_account(asset_id, PoolSubsystem::StreamEscrow)
         }
 
         #[inline(always)]
-        fn asset_to_currency(asset_balance: AssetBalanceOf<Self>) -> BalanceOf<Self> {
-            Self::AssetToCurrencyBalance::convert(asset_balance)
+        fn pool_account(asset_id: &AssetIdOf<Self>, subsystem: PoolSubsystem) -> AccountIdOf<Self> {
+            Self::PalletId::get().into_sub_account_truncating((subsystem.tag(), asset_id))
+        }
+
+        #[inline(always)]
+        fn currency_to_asset(
+            curr_balance: BalanceOf<Self>,
+        ) -> Result<AssetBalanceOf<Self>, Error<Self>> {
+            Self::BalanceConversion::currency_to_asset(curr_balance).ok_or(Error::<Self>::Overflow)
+        }
+
+        #[inline(always)]
+        fn asset_to_currency(
+            asset_balance: AssetBalanceOf<Self>,
+        ) -> Result<BalanceOf<Self>, Error<Self>> {
+            Self::BalanceConversion::asset_to_currency(asset_balance).ok_or(Error::<Self>::Overflow)
         }
 
         #[inline(always)]
@@ -151,16 +329,22 @@ pub mod pallet {
 
     type GenesisExchangeInfo<T> =
         (AccountIdOf<T>, AssetIdOf<T>, AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>);
+    // (market_maker, fee_exempt, max_currency, max_tokens)
+    type GenesisMarketMakerInfo<T> = (AccountIdOf<T>, bool, BalanceOf<T>, AssetBalanceOf<T>);
 
     #[pallet::genesis_config]
     pub struct GenesisConfig<T: Config> {
         pub exchanges: Vec<GenesisExchangeInfo<T>>,
+        pub market_makers: Vec<GenesisMarketMakerInfo<T>>,
     }
 
     #[cfg(feature = "std")]
     impl<T: Config> Default for GenesisConfig<T> {
         fn default() -> GenesisConfig<T> {
-            GenesisConfig { exchanges: vec![] }
+            GenesisConfig {
+                exchanges: vec![],
+                market_makers: vec![],
+            }
         }
     }
 
@@ -192,7 +376,8 @@ pub mod pallet {
                     liquidity_token_id: liquidity_token_id.clone(),
                 };
 
-                let liquidity_minted = T::currency_to_asset(*currency_amount);
+                let liquidity_minted = T::currency_to_asset(*currency_amount)
+                    .expect("Currency amount should be convertible to asset balance");
 
                 // --------------------- Currency & token transfer ---------------------
                 assert!(
@@ -229,20 +414,40 @@ pub mod pallet {
                 );
 
                 // -------------------------- Balances update --------------------------
-                exchange
-                    .currency_reserve
-                    .saturating_accrue(*currency_amount);
-                exchange.token_reserve.saturating_accrue(*token_amount);
+                exchange.currency_reserve.audited_accrue(*currency_amount);
+                exchange.token_reserve.audited_accrue(*token_amount);
+                #[cfg(feature = "reserve-trace")]
+                Pallet::<T>::record_reserve_checkpoint(&exchange, Zero::zero(), Zero::zero());
                 <Exchanges<T>>::insert(asset_id.clone(), exchange);
+                <ReservesUpdatedAt<T>>::insert(
+                    asset_id.clone(),
+                    <frame_system::Pallet<T>>::block_number(),
+                );
+                <LiquidityTokens<T>>::insert(liquidity_token_id.clone(), asset_id.clone());
+            }
+            for (market_maker, fee_exempt, max_currency, max_tokens) in &self.market_makers {
+                let info = MarketMaker {
+                    fee_exempt: *fee_exempt,
+                    max_currency: *max_currency,
+                    max_tokens: *max_tokens,
+                };
+                <MarketMakers<T>>::insert(market_maker, info);
             }
         }
     }
 
+    /// Events are kept small, as they take up block space. Do not put data derivable from other
+    /// fields into events, and prefer a single fixed-point number (e.g. `FixedU128`) over pairs of
+    /// balances for prices. Maximum encoded sizes of all events are asserted in tests.
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
-        /// A new exchange was created [asset_id, liquidity_token_id]
-        ExchangeCreated(AssetIdOf<T>, AssetIdOf<T>),
+        /// A new exchange was created [asset_id, liquidity_token_id, creation_fee]
+        ExchangeCreated(AssetIdOf<T>, AssetIdOf<T>, BalanceOf<T>),
+        /// An exchange to be created already exists [asset_id, liquidity_token_id]
+        ExchangeAlreadyExists(AssetIdOf<T>, AssetIdOf<T>),
+        /// An exchange was removed, as all its liquidity was withdrawn [asset_id, liquidity_token_id]
+        ExchangeRemoved(AssetIdOf<T>, AssetIdOf<T>),
         /// Liquidity was added to an exchange [provider_id, asset_id, currency_amount, token_amount, liquidity_minted]
         LiquidityAdded(
             T::AccountId,
@@ -275,6 +480,94 @@ pub mod pallet {
             BalanceOf<T>,
             AssetBalanceOf<T>,
         ),
+        /// An exchange was sponsored [asset_id, sponsor_id, currency_amount, rebate, block_budget]
+        ExchangeSponsored(AssetIdOf<T>, T::AccountId, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+        /// Sponsorship of an exchange was withdrawn [asset_id, sponsor_id, currency_amount]
+        SponsorshipWithdrawn(AssetIdOf<T>, T::AccountId, BalanceOf<T>),
+        /// A trader received a rebate from the exchange sponsor [asset_id, trader_id, currency_amount]
+        RebatePaid(AssetIdOf<T>, T::AccountId, BalanceOf<T>),
+        /// Maximum trade size of an exchange was set or removed [asset_id, limit]
+        MaxTradeSizeSet(AssetIdOf<T>, Option<TradeSizeLimitOf<T>>),
+        /// Exchange creation fee was set [creation_fee]
+        CreationFeeSet(BalanceOf<T>),
+        /// A swap recipient account was funded out of the traded currency [asset_id, recipient_id, currency_amount]
+        RecipientProvisioned(AssetIdOf<T>, T::AccountId, BalanceOf<T>),
+        /// An RFQ quote was settled [asset_id, maker_id, taker_id, currency_amount, token_amount]
+        RfqSettled(AssetIdOf<T>, T::AccountId, T::AccountId, BalanceOf<T>, AssetBalanceOf<T>),
+        /// A market maker was registered or its parameters were updated [maker_id, parameters]
+        MarketMakerRegistered(T::AccountId, MarketMakerOf<T>),
+        /// A market maker was deregistered [maker_id]
+        MarketMakerDeregistered(T::AccountId),
+        /// Liquidity streaming into an exchange was started [asset_id, provider_id, currency_amount, max_tokens]
+        LiquidityStreamStarted(AssetIdOf<T>, T::AccountId, BalanceOf<T>, AssetBalanceOf<T>),
+        /// Liquidity streaming ended, remaining funds were returned [asset_id, provider_id, currency_amount, token_amount]
+        LiquidityStreamEnded(AssetIdOf<T>, T::AccountId, BalanceOf<T>, AssetBalanceOf<T>),
+        /// An operator was approved to sell an asset on behalf of its owner [owner_id, operator_id, asset_id, allowance, expiry]
+        OperatorApproved(
+            T::AccountId,
+            T::AccountId,
+            AssetIdOf<T>,
+            AssetBalanceOf<T>,
+            Option<T::BlockNumber>,
+        ),
+        /// An operator approval was revoked [owner_id, operator_id, asset_id]
+        OperatorRevoked(T::AccountId, T::AccountId, AssetIdOf<T>),
+        /// Asset balance of the pallet account diverges from the token reserve of the exchange
+        /// more than `ReserveMismatchTolerance` [asset_id, token_reserve, token_balance]
+        ReserveMismatch(AssetIdOf<T>, AssetBalanceOf<T>, AssetBalanceOf<T>),
+        /// Snapshot deposits were seeded into an exchange [asset_id, seeded_deposits]
+        ExchangeSeeded(AssetIdOf<T>, u32),
+        /// Liquidity was redeemed for the underlying balances in emergency mode [provider_id, asset_id, currency_amount, token_amount, liquidity_amount]
+        UnderlyingRedeemed(
+            T::AccountId,
+            AssetIdOf<T>,
+            BalanceOf<T>,
+            AssetBalanceOf<T>,
+            AssetBalanceOf<T>,
+        ),
+        /// Liquidity retention floor of an exchange was set or removed [asset_id, floor]
+        RetentionFloorSet(AssetIdOf<T>, Option<AssetBalanceOf<T>>),
+        /// Trade-only mode of an exchange was set or removed [asset_id, until]
+        TradeOnlyModeSet(AssetIdOf<T>, Option<T::BlockNumber>),
+        /// Part of a swap output was donated (or burned if there is no beneficiary) [asset_id, beneficiary_id, currency_amount, token_amount]
+        OutputDonated(AssetIdOf<T>, Option<T::AccountId>, BalanceOf<T>, AssetBalanceOf<T>),
+        /// An asset was traded for another asset, emitted before the events of its legs [sold_asset_id, bought_asset_id, buyer_id, recipient_id, sold_token_amount, bought_token_amount]
+        AssetTradedForAsset(
+            AssetIdOf<T>,
+            AssetIdOf<T>,
+            T::AccountId,
+            T::AccountId,
+            AssetBalanceOf<T>,
+            AssetBalanceOf<T>,
+        ),
+        /// A batch of liquidity operations is executed, emitted before the events of its legs [provider_id, ops]
+        LiquidityBatchExecuted(T::AccountId, u32),
+        /// A leg of a multi-leg operation starts, its events follow until the next leg [leg_index]
+        LegStarted(u32),
+        /// An exchange was delisted, liquidity can be withdrawn until the end of the grace period [asset_id, grace_end]
+        DelistingStarted(AssetIdOf<T>, T::BlockNumber),
+        /// Remaining reserves of a delisted exchange were swept to the recovery account [asset_id, recovery_id, currency_amount, token_amount]
+        DelistedExchangeSwept(AssetIdOf<T>, T::AccountId, BalanceOf<T>, AssetBalanceOf<T>),
+        /// Peg reference of an exchange was set or removed [asset_id, peg]
+        PegReferenceSet(AssetIdOf<T>, Option<PegReferenceOf<T>>),
+        /// Trading on an exchange was paused by governance [asset_id]
+        ExchangePaused(AssetIdOf<T>),
+        /// Trading on an exchange was unpaused by governance [asset_id]
+        ExchangeUnpaused(AssetIdOf<T>),
+        /// Fee share of an origin account was set or removed [origin_id, fee_share]
+        FeeShareSet(T::AccountId, Option<FeeShareOf<T>>),
+        /// Part of the provider fee of a trade was credited to the beneficiary of the trader's fee share [asset_id, origin_id, beneficiary_id, currency_amount, token_amount]
+        FeeSharePaid(AssetIdOf<T>, T::AccountId, T::AccountId, BalanceOf<T>, AssetBalanceOf<T>),
+        /// Curve of an exchange was set or reset to the default curve [asset_id, curve]
+        CurveSet(AssetIdOf<T>, Option<T::Curve>),
+        /// An account approved seeding an exchange with its funds [source_id, asset_id]
+        SeedingApproved(T::AccountId, AssetIdOf<T>),
+        /// An account revoked its approval of seeding an exchange with its funds [source_id, asset_id]
+        SeedingRevoked(T::AccountId, AssetIdOf<T>),
+        /// Pausing an exchange was skipped, as there is no exchange for the asset [asset_id]
+        PauseSkipped(AssetIdOf<T>),
+        /// Unpausing an exchange was skipped, as it isn't paused [asset_id]
+        UnpauseSkipped(AssetIdOf<T>),
     }
 
     #[pallet::error]
@@ -335,6 +628,94 @@ pub mod pallet {
         Underflow,
         /// Deadline specified for the operation has passed
         DeadlinePassed,
+        /// Deadline specified for the operation is too far in the future
+        DeadlineTooFar,
+        /// Trading on the exchange is suspended by the safety oracle or paused by governance
+        ExchangeSuspended,
+        /// Liquidity tokens of the specified account are frozen
+        LiquidityFrozen,
+        /// Asset price deviates from the expected price more than the specified tolerance
+        PriceMoved,
+        /// Zero value provided for `rebate` parameter
+        RebateIsZero,
+        /// Exchange is not sponsored
+        SponsorshipNotFound,
+        /// Caller is not the sponsor of the exchange
+        NotSponsor,
+        /// Trade input exceeds the maximum trade size of the exchange
+        TradeTooLarge,
+        /// Quote maker is not a registered market maker
+        NotMarketMaker,
+        /// Quote signature is invalid
+        InvalidSignature,
+        /// Quote can only be settled by another account
+        NotQuoteTaker,
+        /// Quote with the given nonce has already been settled
+        QuoteAlreadySettled,
+        /// Quoted amounts exceed the quote size limits of the market maker
+        QuoteTooLarge,
+        /// Zero value provided for `blocks` parameter
+        BlocksIsZero,
+        /// Caller already streams liquidity into the exchange
+        StreamAlreadyExists,
+        /// Caller doesn't stream liquidity into the exchange
+        StreamNotFound,
+        /// Specified bootstrap phase ends before the current block
+        BootstrapEndPassed,
+        /// Exchange is in the bootstrap phase and the account is not whitelisted
+        NotWhitelisted,
+        /// Operator is not approved to sell the asset on behalf of the owner
+        NotApproved,
+        /// Operator approval has expired
+        ApprovalExpired,
+        /// Trade exceeds the remaining allowance of the operator
+        AllowanceExceeded,
+        /// First snapshot deposit index doesn't match the number of deposits already seeded
+        SeedIndexMismatch,
+        /// Exchange is neither suspended nor are its invariants broken
+        NotInEmergency,
+        /// Withdrawal would reduce the liquidity supply of the exchange below its retention floor
+        RetentionFloorReached,
+        /// Liquidity of the exchange is frozen until the trade-only mode expires
+        TradeOnlyMode,
+        /// Trade-only mode expiry is in the past or more than `MaxTradeOnlyPeriod` blocks ahead
+        InvalidTradeOnlyExpiry,
+        /// The exchange is delisted, liquidity can only be withdrawn
+        ExchangeDelisted,
+        /// The exchange is not delisted
+        NotDelisted,
+        /// The grace period of the delisted exchange is not over yet
+        GracePeriodNotOver,
+        /// Peg reference price is zero or its band is not lower than 100%
+        InvalidPegReference,
+        /// The liquidity token already has holders, it can only be used in migration mode
+        LiquidityTokenHasHolders,
+        /// The liquidity token is not a former liquidity token, or has no holders to migrate
+        LiquidityTokenNotMigratable,
+        /// Deposited currency doesn't match the existing liquidity token supply
+        MigrationReservesMismatch,
+        /// Zero value provided for the share of a fee share
+        FeeShareIsZero,
+        /// The expected price was quoted more than the specified maximum age ago
+        QuoteTooOld,
+        /// The parameters of the curve are out of range
+        InvalidCurve,
+        /// The amount of liquidity tokens to burn is higher than the specified maximum
+        MaxLiquidityTooLow,
+        /// Specified bootstrap phase ends more than `MaxBootstrapPeriod` blocks after the current block
+        BootstrapTooLong,
+        /// Operator is not approved to buy the asset (or the currency) with the sold asset
+        BoughtAssetNotApproved,
+        /// Trade price is lower than the minimum price of the operator approval
+        PriceBelowApproved,
+        /// The source account didn't approve seeding the exchange with its funds
+        SeedingNotApproved,
+        /// The liquidity token is an asset traded on an exchange
+        LiquidityTokenIsTraded,
+        /// Changing the curve would move the spot price of the exchange
+        CurveMovesPrice,
+        /// Specified number of exchanges is lower than the actual number
+        WrongExchangeCount,
     }
 
     #[derive(
@@ -359,37 +740,549 @@ pub mod pallet {
         },
     }
 
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct Sponsorship<AccountId, Balance, BlockNumber> {
+        /// Account which deposited the rebate funds
+        pub sponsor: AccountId,
+        /// Amount of currency rebated to a trader per trade
+        pub rebate: Balance,
+        /// Maximum amount of currency rebated per block
+        pub block_budget: Balance,
+        /// Amount of currency rebated in `last_block`
+        pub spent: Balance,
+        /// Number of the last block in which a rebate was paid
+        pub last_block: BlockNumber,
+    }
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct PriceLimit<BlockNumber> {
+        /// Expected asset price (currency per token), e.g. as quoted by the UI
+        pub expected_price: Price,
+        /// Maximum accepted deviation of the actual price from `expected_price`
+        pub tolerance: Permill,
+        /// Number of the block the expected price was quoted at (e.g. `Quote::block_number`),
+        /// only checked if `max_age` is set
+        pub quoted_at: BlockNumber,
+        /// (Optional) maximum number of blocks the chain may advance after `quoted_at`
+        pub max_age: Option<BlockNumber>,
+    }
+
+    /// Maximum input of a single trade on an exchange.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub enum TradeSizeLimit<Balance, AssetBalance> {
+        /// Maximum amounts of currency and tokens sold to the exchange in a single trade
+        Absolute {
+            max_currency: Balance,
+            max_tokens: AssetBalance,
+        },
+        /// Maximum share of the exchange reserve (of the sold currency/asset) sold in a single trade
+        ReserveShare(Permill),
+    }
+
+    /// Liquidity streamed into an exchange in equal parts over a number of blocks.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct LiquidityStream<Balance, AssetBalance, BlockNumber> {
+        /// Amount of escrowed currency not streamed yet
+        pub currency_remaining: Balance,
+        /// Amount of escrowed tokens not streamed yet
+        pub tokens_remaining: AssetBalance,
+        /// Number of parts not streamed yet
+        pub blocks_remaining: u32,
+        /// Number of the last block in which a part was streamed (or the stream was started)
+        pub last_block: BlockNumber,
+    }
+
+    /// Trading authority over an asset delegated by its owner to an operator account.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct OperatorApproval<AssetId, AssetBalance, BlockNumber> {
+        /// Remaining amount of the asset the operator may sell
+        pub allowance: AssetBalance,
+        /// Asset the operator may buy with the sold asset (`None` meaning the currency)
+        pub bought_asset_id: Option<AssetId>,
+        /// Minimum price the operator may sell at: amount of the bought currency or asset per sold token
+        pub min_price: Price,
+        /// Number of the last block in which the approval can be used (`None` meaning no expiry)
+        pub expiry: Option<BlockNumber>,
+    }
+
+    /// Part of a swap output routed away from the swap recipient, e.g. by apps implementing
+    /// round-ups or deflationary mechanics.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct Donation<AccountId> {
+        /// Share of the bought amount which is donated
+        pub share: Permill,
+        /// Account receiving the donation (`None` meaning the donation is burned, donated tokens
+        /// being sent to `BurnAccount`)
+        pub beneficiary: Option<AccountId>,
+    }
+
+    /// Reference price of an asset pegged to the currency (e.g. the canonical stable asset of the
+    /// chain), quoted instead of the AMM price if the exchange liquidity is too thin.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct PegReference<Balance> {
+        /// Pegged price of the asset, in the same unit as the spot price of the exchange
+        pub price: Price,
+        /// Deviation from the pegged price applied to fallback quotes (in the quoted trade's
+        /// disadvantage), must be lower than 100%
+        pub band: Permill,
+        /// Currency reserve below which the exchange liquidity is considered too thin
+        pub min_currency_reserve: Balance,
+    }
+
+    /// Share of the provider fees of trades dispatched by a registered origin (e.g. the account
+    /// of another pallet routing its order flow through the exchanges).
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct FeeShare<AccountId> {
+        /// Account credited with the fee share
+        pub beneficiary: AccountId,
+        /// Share of the provider fee credited to the beneficiary
+        pub share: Permill,
+    }
+
+    /// State of an exchange at the end of a block in which its reserves changed, recorded with the
+    /// `reserve-trace` feature.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct ReserveCheckpoint<Balance, AssetBalance, BlockNumber> {
+        pub block_number: BlockNumber,
+        pub currency_reserve: Balance,
+        pub token_reserve: AssetBalance,
+        /// Total issuance of the liquidity token
+        pub liquidity_supply: AssetBalance,
+        /// Amount of currency sold into the exchange by trades in the block
+        pub currency_in: Balance,
+        /// Amount of tokens sold into the exchange by trades in the block
+        pub tokens_in: AssetBalance,
+    }
+
+    /// Redemption value of one liquidity token of an exchange, recorded once per
+    /// `YieldCheckpointPeriod`.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct YieldCheckpoint<BlockNumber> {
+        pub block_number: BlockNumber,
+        /// Amount of currency redeemed per liquidity token
+        pub currency_per_liquidity: FixedU128,
+        /// Amount of tokens redeemed per liquidity token
+        pub tokens_per_liquidity: FixedU128,
+    }
+
+    /// User-created storage item, for which a storage deposit is held from its creator.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub enum DepositItem<AccountId, AssetId> {
+        /// Approval of an operator to sell an asset [operator_id, asset_id]
+        OperatorApproval(AccountId, AssetId),
+        /// Liquidity stream into the exchange of an asset [asset_id]
+        LiquidityStream(AssetId),
+        /// Approval of seeding the exchange of an asset with the account's funds [asset_id]
+        SeedingApproval(AssetId),
+    }
+
+    /// Trade performed by an operator on behalf of the owner of the sold asset.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub enum DelegatedSwap<AssetId, Balance, AssetBalance> {
+        /// Sell the asset for currency
+        AssetToCurrency {
+            asset_id: AssetId,
+            amount: TradeAmount<AssetBalance, Balance>,
+        },
+        /// Sell the asset for another asset
+        AssetToAsset {
+            sold_asset_id: AssetId,
+            bought_asset_id: AssetId,
+            amount: TradeAmount<AssetBalance, AssetBalance>,
+        },
+    }
+
+    /// Liquidity operation of a `batch_liquidity` call.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub enum LiquidityOp<AssetId, Balance, AssetBalance> {
+        /// Add liquidity, as in `add_liquidity`
+        Add {
+            asset_id: AssetId,
+            currency_amount: Balance,
+            min_liquidity: AssetBalance,
+            max_tokens: AssetBalance,
+        },
+        /// Remove liquidity, as in `remove_liquidity`
+        Remove {
+            asset_id: AssetId,
+            liquidity_amount: AssetBalance,
+            min_currency: Balance,
+            min_tokens: AssetBalance,
+        },
+    }
+
+    /// Bootstrap phase of an exchange, during which only whitelisted accounts may add liquidity
+    /// or trade on it.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct Bootstrap<Whitelist, BlockNumber> {
+        /// Accounts allowed to add liquidity and trade during the bootstrap phase
+        pub whitelist: Whitelist,
+        /// Number of the last block of the bootstrap phase
+        pub end: BlockNumber,
+    }
+
+    /// Parameters of a registered market maker.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct MarketMaker<Balance, AssetBalance> {
+        /// Whether the market maker is exempt from fees (see [`Pallet::is_fee_exempt`])
+        pub fee_exempt: bool,
+        /// Maximum amount of currency traded in a single quote
+        pub max_currency: Balance,
+        /// Maximum amount of tokens traded in a single quote
+        pub max_tokens: AssetBalance,
+    }
+
+    /// Side of an RFQ quote, from the market maker's perspective.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub enum RfqSide {
+        /// The market maker sells the asset for currency
+        MakerSells,
+        /// The market maker buys the asset for currency
+        MakerBuys,
+    }
+
+    /// Quote signed off-chain by a market maker, settled on-chain with `settle_rfq`.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct RfqQuote<AccountId, AssetId, Balance, AssetBalance, BlockNumber> {
+        /// Market maker account, which signed the quote
+        pub maker: AccountId,
+        /// (Optional) the only account allowed to settle the quote
+        pub taker: Option<AccountId>,
+        /// ID of the asset traded for currency
+        pub asset_id: AssetId,
+        /// Whether the market maker sells or buys the asset
+        pub side: RfqSide,
+        /// Amount of currency traded
+        pub currency_amount: Balance,
+        /// Amount of tokens traded
+        pub token_amount: AssetBalance,
+        /// Number of the last block in which the quote can be settled
+        pub expiry: BlockNumber,
+        /// Quote number, unique per market maker
+        pub nonce: u64,
+        /// Offset the market maker's position against the exchange after settlement
+        pub hedge: bool,
+    }
+
+    /// Subsystem holding the funds of an exchange in a sub-account of its own, derived from the
+    /// `PalletId` (see [`Pallet::get_pool_accounts`]).
+    #[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub enum PoolSubsystem {
+        /// Rebates of a sponsorship (see `sponsor_exchange`)
+        Sponsorship,
+        /// Currency and tokens escrowed by the liquidity streams into the exchange (see
+        /// `stream_liquidity`)
+        StreamEscrow,
+    }
+
+    impl PoolSubsystem {
+        /// All subsystems, in the order of [`Pallet::get_pool_accounts`].
+        pub const ALL: [PoolSubsystem; 2] = [Self::Sponsorship, Self::StreamEscrow];
+
+        /// Tag of the subsystem in the seed of its sub-accounts.
+        pub fn tag(&self) -> [u8; 7] {
+            match self {
+                Self::Sponsorship => *b"sponsor",
+                Self::StreamEscrow => *b"streams",
+            }
+        }
+    }
+
     // (sold_token_amount, currency_amount, bought_token_amount)
     type AssetToAssetPrice<T> = (AssetBalanceOf<T>, BalanceOf<T>, AssetBalanceOf<T>);
 
-    // Type alias for convenience
-    type ExchangeOf<T> = Exchange<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>>;
+    // Type aliases for convenience
+    pub(crate) type ExchangeOf<T> = Exchange<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>>;
+    type SponsorshipOf<T> =
+        Sponsorship<AccountIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+    type TradeSizeLimitOf<T> = TradeSizeLimit<BalanceOf<T>, AssetBalanceOf<T>>;
+    type MarketMakerOf<T> = MarketMaker<BalanceOf<T>, AssetBalanceOf<T>>;
+    type OperatorApprovalOf<T> =
+        OperatorApproval<AssetIdOf<T>, AssetBalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+    type SeedDepositOf<T> = (AccountIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>);
+    type SeedDepositsOf<T> = BoundedVec<SeedDepositOf<T>, <T as Config>::MaxSeedDeposits>;
+    type PausedAssetsOf<T> = BoundedVec<AssetIdOf<T>, <T as Config>::MaxPausedPerCall>;
+    type DelegatedSwapOf<T> = DelegatedSwap<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>>;
+    type DonationOf<T> = Donation<AccountIdOf<T>>;
+    type DepositItemOf<T> = DepositItem<AccountIdOf<T>, AssetIdOf<T>>;
+    type LiquidityOpOf<T> = LiquidityOp<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>>;
+    type LiquidityOpsOf<T> = BoundedVec<LiquidityOpOf<T>, <T as Config>::MaxLiquidityOps>;
+    type BootstrapOf<T> = Bootstrap<
+        BoundedVec<AccountIdOf<T>, <T as Config>::MaxBootstrapAccounts>,
+        <T as frame_system::Config>::BlockNumber,
+    >;
+    type LiquidityStreamOf<T> =
+        LiquidityStream<BalanceOf<T>, AssetBalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+    type PegReferenceOf<T> = PegReference<BalanceOf<T>>;
+    type PriceLimitOf<T> = PriceLimit<<T as frame_system::Config>::BlockNumber>;
+    type FeeShareOf<T> = FeeShare<AccountIdOf<T>>;
+    pub type ReserveCheckpointOf<T> = ReserveCheckpoint<
+        BalanceOf<T>,
+        AssetBalanceOf<T>,
+        <T as frame_system::Config>::BlockNumber,
+    >;
+    pub type YieldCheckpointOf<T> = YieldCheckpoint<<T as frame_system::Config>::BlockNumber>;
+    type YieldCheckpointsOf<T> =
+        BoundedVec<YieldCheckpointOf<T>, <T as Config>::MaxYieldCheckpoints>;
+    type RfqQuoteOf<T> = RfqQuote<
+        AccountIdOf<T>,
+        AssetIdOf<T>,
+        BalanceOf<T>,
+        AssetBalanceOf<T>,
+        <T as frame_system::Config>::BlockNumber,
+    >;
 
     #[pallet::storage]
     #[pallet::getter(fn exchanges)]
     pub(super) type Exchanges<T: Config> =
         StorageMap<_, Twox64Concat, AssetIdOf<T>, ExchangeOf<T>, OptionQuery>;
 
+    /// Reverse index of exchanges by their liquidity token ID.
+    #[pallet::storage]
+    #[pallet::getter(fn liquidity_tokens)]
+    pub(super) type LiquidityTokens<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, AssetIdOf<T>, OptionQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn sponsorships)]
+    pub(super) type Sponsorships<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, SponsorshipOf<T>, OptionQuery>;
+
+    /// Non-refundable fee charged for creating an exchange, paid to `FeeDestination`.
+    #[pallet::storage]
+    #[pallet::getter(fn creation_fee)]
+    pub(super) type CreationFee<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+    /// Liquidity token supply below which liquidity can't be withdrawn from an exchange,
+    /// set by governance during incident containment.
+    #[pallet::storage]
+    #[pallet::getter(fn retention_floors)]
+    pub(super) type RetentionFloors<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, AssetBalanceOf<T>, OptionQuery>;
+
+    /// Number of the last block in which liquidity of an exchange is frozen (trade-only mode).
+    #[pallet::storage]
+    #[pallet::getter(fn trade_only_until)]
+    pub(super) type TradeOnlyUntil<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, T::BlockNumber, OptionQuery>;
+
+    /// Number of the last block of the grace period of a delisted exchange.
+    #[pallet::storage]
+    #[pallet::getter(fn delistings)]
+    pub(super) type Delistings<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, T::BlockNumber, OptionQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn peg_references)]
+    pub(super) type PegReferences<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, PegReferenceOf<T>, OptionQuery>;
+
+    /// Fee shares of registered origin accounts, credited on each of their trades.
+    #[pallet::storage]
+    #[pallet::getter(fn fee_shares)]
+    pub(super) type FeeShares<T: Config> =
+        StorageMap<_, Blake2_128Concat, AccountIdOf<T>, FeeShareOf<T>, OptionQuery>;
+
+    /// Curves of the exchanges, set by governance. Exchanges without one use the default curve.
+    #[pallet::storage]
+    #[pallet::getter(fn curves)]
+    pub(super) type Curves<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, T::Curve, ValueQuery>;
+
+    /// Exchanges on which trading is paused by governance.
+    #[pallet::storage]
+    pub(super) type PausedExchanges<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, (), OptionQuery>;
+
+    /// Ring buffer of the last `MaxReserveCheckpoints` reserve checkpoints of each exchange,
+    /// indexed by slot (`reserve-trace` feature).
+    #[pallet::storage]
+    pub(super) type ReserveCheckpoints<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        AssetIdOf<T>,
+        Twox64Concat,
+        u32,
+        ReserveCheckpointOf<T>,
+        OptionQuery,
+    >;
+
+    /// Slot of the next reserve checkpoint of each exchange.
+    #[pallet::storage]
+    pub(super) type ReserveCheckpointCursors<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, u32, ValueQuery>;
+
+    /// Last `MaxYieldCheckpoints` yield checkpoints of each exchange, oldest first.
+    #[pallet::storage]
+    #[pallet::getter(fn yield_checkpoints)]
+    pub(super) type YieldCheckpoints<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, YieldCheckpointsOf<T>, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn max_trade_sizes)]
+    pub(super) type MaxTradeSizes<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, TradeSizeLimitOf<T>, OptionQuery>;
+
+    /// Trading authority delegated by asset owners, per owner and (operator, asset).
+    #[pallet::storage]
+    #[pallet::getter(fn operator_approvals)]
+    pub(super) type OperatorApprovals<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        AccountIdOf<T>,
+        Blake2_128Concat,
+        (AccountIdOf<T>, AssetIdOf<T>),
+        OperatorApprovalOf<T>,
+        OptionQuery,
+    >;
+
+    /// Storage deposits held from accounts, per account and created storage item.
+    #[pallet::storage]
+    #[pallet::getter(fn storage_deposits)]
+    pub(super) type StorageDeposits<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        AccountIdOf<T>,
+        Blake2_128Concat,
+        DepositItemOf<T>,
+        BalanceOf<T>,
+        OptionQuery,
+    >;
+
+    /// Number of the block in which the reserves of an exchange were last updated.
+    #[pallet::storage]
+    #[pallet::getter(fn reserves_updated_at)]
+    pub(super) type ReservesUpdatedAt<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, T::BlockNumber, OptionQuery>;
+
+    /// Bootstrap phases of exchanges (kept after the phase ends, until the exchange is removed).
+    #[pallet::storage]
+    #[pallet::getter(fn bootstraps)]
+    pub(super) type Bootstraps<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, BootstrapOf<T>, OptionQuery>;
+
+    /// Total amount of tokens escrowed by liquidity streams (held by the stream escrow sub-account
+    /// of the exchange), per exchange.
+    #[pallet::storage]
+    #[pallet::getter(fn escrowed_tokens)]
+    pub(super) type EscrowedTokens<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, AssetBalanceOf<T>, ValueQuery>;
+
+    /// Accounts which approved seeding an exchange with their funds (see `approve_seeding`), per
+    /// account and exchange.
+    #[pallet::storage]
+    #[pallet::getter(fn seeding_approvals)]
+    pub(super) type SeedingApprovals<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        AccountIdOf<T>,
+        Twox64Concat,
+        AssetIdOf<T>,
+        (),
+        OptionQuery,
+    >;
+
+    /// Number of snapshot deposits seeded into exchanges by `force_seed_exchange`.
+    #[pallet::storage]
+    #[pallet::getter(fn seeded_deposits)]
+    pub(super) type SeededDeposits<T: Config> =
+        StorageMap<_, Twox64Concat, AssetIdOf<T>, u32, ValueQuery>;
+
+    /// Liquidity being streamed into exchanges, per exchange and provider.
+    #[pallet::storage]
+    #[pallet::getter(fn liquidity_streams)]
+    pub(super) type LiquidityStreams<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        AssetIdOf<T>,
+        Blake2_128Concat,
+        AccountIdOf<T>,
+        LiquidityStreamOf<T>,
+        OptionQuery,
+    >;
+
+    /// Last liquidity stream visited by `on_idle`, after which the execution of due streams resumes
+    /// in the next block (`None` meaning from the first stream).
+    #[pallet::storage]
+    pub(super) type LiquidityStreamsCursor<T: Config> =
+        StorageValue<_, (AssetIdOf<T>, AccountIdOf<T>), OptionQuery>;
+
+    /// Registered market makers (allowed to sign RFQ quotes) and their parameters.
+    #[pallet::storage]
+    #[pallet::getter(fn market_makers)]
+    pub(super) type MarketMakers<T: Config> =
+        StorageMap<_, Blake2_128Concat, AccountIdOf<T>, MarketMakerOf<T>, OptionQuery>;
+
+    /// Nonces of settled RFQ quotes, per market maker.
+    #[pallet::storage]
+    pub(super) type UsedRfqNonces<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, AccountIdOf<T>, Twox64Concat, u64, (), OptionQuery>;
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn integrity_test() {
+            assert!(
+                T::DefaultDeadline::get() <= T::MaxDeadline::get(),
+                "`DefaultDeadline` must not be greater than `MaxDeadline`"
+            );
+        }
+
+        fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Self::execute_liquidity_streams(now, remaining_weight)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+            Self::do_try_state()
+        }
+    }
+
     #[pallet::call]
     impl<T: Config> Pallet<T> {
         /// Create a new exchange. Deposit initial liquidity (currency & assets).
         /// Create a new liquidity token. Mint & transfer to the caller account an amount
-        /// of the liquidity token equal to `currency_amount`.
+        /// of the liquidity token equal to `currency_amount`. Charge the creation fee (if any, see
+        /// `set_creation_fee`), paid to `FeeDestination`.
         /// Emit two events on success: `ExchangeCreated` and `LiquidityAdded`.
+        /// Notify `OnPoolCreated` handler.
+        ///
+        /// In migration mode (e.g. re-deploying a pool after a runtime fix), the liquidity token
+        /// must be a former liquidity token (see `MigratableLiquidityTokens`) with holders, not
+        /// traded on any exchange: no liquidity token is created nor minted, and the deposited
+        /// reserves back the existing supply, which they must match (one unit of currency per
+        /// liquidity token, as for a new exchange).
         ///
         /// **Parameters:**
-        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `origin` – Origin for the call. Must be signed, or `MigrationOrigin` in migration mode.
         ///   * `asset_id` – ID of the asset traded on the created exchange. Asset with this ID must exist.
-        ///   * `liquidity_token_id` – ID of the liquidity token to be created. Asset with this ID must *not* exist.
+        ///   * `liquidity_token_id` – ID of the liquidity token to be created. Asset with this ID must *not* exist,
+        ///     unless in migration mode.
         ///   * `currency_amount` – Initial amount of the currency to deposit in the pool. Must be at least equal `MinDeposit`.
         ///   * `token_amount` – Initial amount of tokens to deposit in the pool. Must be greater than 0.
+        ///   * `bootstrap` – (Optional) bootstrap phase of the exchange: until the `end` block (inclusive),
+        ///     only accounts in the `whitelist` may add liquidity or trade on the exchange.
+        ///   * `migration` – Whether to create the exchange in migration mode, with the existing
+        ///     liquidity token `liquidity_token_id`.
         ///
         /// **Errors:**
         ///   * `AssetNotFound` – Asset with the given `asset_id` does not exist or has total supply equal 0.
         ///   * `ExchangeAlreadyExists` – An exchange fot the specified asset already exists.
         ///   * `TokenIdTaken` – Specified `liquidity_token_id` is already taken by another liquidity token.
+        ///   * `LiquidityTokenHasHolders` – Specified `liquidity_token_id` already has holders (and
+        ///     the migration mode is not used).
+        ///   * `LiquidityTokenNotMigratable` – In migration mode, specified `liquidity_token_id` is not
+        ///     in `MigratableLiquidityTokens` or has no holders.
+        ///   * `LiquidityTokenIsTraded` – In migration mode, specified `liquidity_token_id` is the asset
+        ///     of an exchange (or `asset_id`).
+        ///   * `MigrationReservesMismatch` – In migration mode, `currency_amount` doesn't equal the
+        ///     liquidity token supply.
         ///   * `CurrencyAmountTooLow` – Specified `currency_amount` is lower than `MinDeposit`.
         ///   * `TokenAmountIsZero` – Specified `token_amount` equals 0.
+        ///   * `BalanceTooLow` – The caller doesn't have enough currency for `currency_amount`
+        ///     and the creation fee.
+        ///   * `BootstrapEndPassed` – Specified bootstrap phase `end` is lower than the current block number.
+        ///   * `BootstrapTooLong` – Specified bootstrap phase `end` is more than `MaxBootstrapPeriod` blocks after the
+        ///     current block.
         #[pallet::weight(<T as Config>::WeightInfo::create_exchange())]
         #[transactional]
         pub fn create_exchange(
@@ -398,72 +1291,114 @@ pub mod pallet {
             liquidity_token_id: AssetIdOf<T>,
             currency_amount: BalanceOf<T>,
             token_amount: AssetBalanceOf<T>,
+            bootstrap: Option<BootstrapOf<T>>,
+            migration: bool,
         ) -> DispatchResult {
-            // -------------------------- Validation part --------------------------
-            let caller = ensure_signed(origin)?;
-            ensure!(currency_amount >= T::MinDeposit::get(), Error::<T>::CurrencyAmountTooLow);
-            ensure!(token_amount > Zero::zero(), Error::<T>::TokenAmountIsZero);
-            if T::Assets::total_issuance(asset_id.clone()).is_zero() {
-                Err(Error::<T>::AssetNotFound)?
-            }
-            if <Exchanges<T>>::contains_key(asset_id.clone()) {
-                Err(Error::<T>::ExchangeAlreadyExists)?
-            }
-
-            // ----------------------- Create liquidity token ----------------------
-            T::AssetRegistry::create(
-                liquidity_token_id.clone(),
-                T::pallet_account(),
-                false,
-                <AssetBalanceOf<T>>::one(),
-            )
-            .map_err(|_| Error::<T>::TokenIdTaken)?;
-
-            // -------------------------- Update storage ---------------------------
-            let exchange = Exchange {
-                asset_id: asset_id.clone(),
-                currency_reserve: <BalanceOf<T>>::zero(),
-                token_reserve: <AssetBalanceOf<T>>::zero(),
-                liquidity_token_id: liquidity_token_id.clone(),
+            let caller = if migration {
+                T::MigrationOrigin::ensure_origin(origin)?
+            } else {
+                ensure_signed(origin)?
             };
-            let liquidity_minted = T::currency_to_asset(currency_amount);
-            Self::do_add_liquidity(
-                exchange,
+            if let Some(bootstrap) = &bootstrap {
+                let now = <frame_system::Pallet<T>>::block_number();
+                ensure!(bootstrap.end >= now, Error::<T>::BootstrapEndPassed);
+                ensure!(
+                    bootstrap.end <= now.saturating_add(T::MaxBootstrapPeriod::get()),
+                    Error::<T>::BootstrapTooLong
+                );
+            }
+            Self::do_create_exchange(
+                caller,
+                asset_id.clone(),
+                liquidity_token_id,
                 currency_amount,
                 token_amount,
-                liquidity_minted,
-                caller,
+                migration,
             )?;
-
-            // ---------------------------- Emit event -----------------------------
-            Self::deposit_event(Event::ExchangeCreated(asset_id, liquidity_token_id));
+            if let Some(bootstrap) = bootstrap {
+                <Bootstraps<T>>::insert(asset_id, bootstrap);
+            }
             Ok(())
         }
 
-        /// Add liquidity to an existing exchange. The caller specifies an exact amount of currency
-        /// to be deposited, a maximum amount of tokens to be deposited, and a minimum amount
-        /// of liquidity tokens to receive. Emit `LiquidityAdded` event on success.
+        /// Make sure an exchange for the given asset exists. If there is no such exchange,
+        /// create it exactly like `create_exchange` does (without a bootstrap phase). Otherwise, do nothing except emitting
+        /// `ExchangeAlreadyExists` event with the ID of the existing exchange's liquidity token.
+        /// Useful for router-style callers which only need the exchange to exist.
         ///
         /// **Parameters:**
         ///   * `origin` – Origin for the call. Must be signed.
-        ///   * `asset_id` – ID of the deposited asset. An exchange for this asset must exist.
-        ///   * `currency_amount` – The amount of the currency to deposit in the pool. Must be greater than 0.
-        ///   * `min_liquidity` – The minimum amount of liquidity tokens to receive. Must be greater than 0.
-        ///   * `max_tokens` – The maximum amount of tokens to be deposited. Must be greater than 0.
-        ///   * `deadline` – Number of the last block in which the transaction can be included.
+        ///   * `asset_id` – ID of the asset traded on the exchange. Asset with this ID must exist.
+        ///   * `liquidity_token_id` – ID of the liquidity token to be created. Ignored if the exchange exists.
+        ///   * `currency_amount` – Initial amount of the currency to deposit in the pool. Ignored if the exchange exists.
+        ///   * `token_amount` – Initial amount of tokens to deposit in the pool. Ignored if the exchange exists.
         ///
         /// **Errors:**
-        ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
-        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
-        ///   * `CurrencyAmountIsZero` – Specified `currency_amount` equals 0.
-        ///   * `MinLiquidityIsZero` – Specified `min_liquidity` equals 0.
-        ///   * `MaxTokensIsZero` – Specified `max_tokens` equals 0.
-        ///   * `BalanceTooLow` – Specified `currency_amount` is greater than the available currency balance of the caller account.
+        ///   * Same as `create_exchange`, except `ExchangeAlreadyExists`.
+        #[pallet::weight(<T as Config>::WeightInfo::create_exchange())]
+        #[transactional]
+        pub fn get_or_create_exchange(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            liquidity_token_id: AssetIdOf<T>,
+            currency_amount: BalanceOf<T>,
+            token_amount: AssetBalanceOf<T>,
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            match <Exchanges<T>>::get(asset_id.clone()) {
+                Some(exchange) => {
+                    Self::deposit_event(Event::ExchangeAlreadyExists(
+                        asset_id,
+                        exchange.liquidity_token_id,
+                    ));
+                    Ok(())
+                }
+                None => Self::do_create_exchange(
+                    caller,
+                    asset_id,
+                    liquidity_token_id,
+                    currency_amount,
+                    token_amount,
+                    false,
+                ),
+            }
+        }
+
+        /// Add liquidity to an existing exchange. The caller specifies an exact amount of currency
+        /// to be deposited, a maximum amount of tokens to be deposited, and a minimum amount
+        /// of liquidity tokens to receive. Emit `LiquidityAdded` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `asset_id` – ID of the deposited asset. An exchange for this asset must exist.
+        ///   * `currency_amount` – The amount of the currency to deposit in the pool. Must be greater than 0.
+        ///   * `min_liquidity` – The minimum amount of liquidity tokens to receive. Must be greater than 0.
+        ///   * `max_tokens` – The maximum amount of tokens to be deposited. Must be greater than 0.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
+        ///   * `price_limit` – (Optional) expected asset price and maximum accepted deviation from it,
+        ///     optionally with the block it was quoted at and the maximum age of the quote.
+        ///
+        /// **Errors:**
+        ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
+        ///   * `DeadlineTooFar` – Specified `deadline` is more than `MaxDeadline` blocks after the current block.
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `TradeOnlyMode` – The exchange is in trade-only mode, liquidity changes are frozen.
+        ///   * `ExchangeDelisted` – The exchange is delisted.
+        ///   * `CurrencyAmountIsZero` – Specified `currency_amount` equals 0.
+        ///   * `MinLiquidityIsZero` – Specified `min_liquidity` equals 0.
+        ///   * `MaxTokensIsZero` – Specified `max_tokens` equals 0.
+        ///   * `BalanceTooLow` – Specified `currency_amount` is greater than the available currency balance of the caller account.
         ///   * `NotEnoughTokens` – Specified `max_tokens` is greater than the available asset balance of the caller account.
         ///   * `MaxTokensTooLow` – Specified `max_tokens` is too low to match the `currency_amount`.
         ///     Currency and tokens need to be added proportionally.
         ///   * `MinLiquidityTooHigh` – The amount of liquidity tokes which would be minted by depositing the specified
         ///     `currency_amount` is lower than the specified `min_liquidity`.
+        ///   * `PriceMoved` – The current spot price of the asset (on the curve of the exchange) deviates from
+        ///     the expected price more than the specified tolerance (`price_limit`).
+        ///   * `QuoteTooOld` – The current block is more than `max_age` blocks after the block the price
+        ///     was quoted at (`price_limit`).
+        ///   * `NotWhitelisted` – The exchange is in the bootstrap phase and the caller is not whitelisted.
         #[pallet::weight(<T as Config>::WeightInfo::add_liquidity())]
         pub fn add_liquidity(
             origin: OriginFor<T>,
@@ -471,46 +1406,25 @@ pub mod pallet {
             currency_amount: BalanceOf<T>,
             min_liquidity: AssetBalanceOf<T>,
             max_tokens: AssetBalanceOf<T>,
-            deadline: T::BlockNumber,
+            deadline: Option<T::BlockNumber>,
+            price_limit: Option<PriceLimitOf<T>>,
         ) -> DispatchResult {
-            // -------------------------- Validation part --------------------------
             let caller = ensure_signed(origin)?;
             Self::check_deadline(&deadline)?;
-            ensure!(currency_amount > Zero::zero(), Error::<T>::CurrencyAmountIsZero);
-            ensure!(max_tokens > Zero::zero(), Error::<T>::MaxTokensIsZero);
-            ensure!(min_liquidity > Zero::zero(), Error::<T>::MinLiquidityIsZero);
-            Self::check_enough_currency(&caller, &currency_amount)?;
-            Self::check_enough_tokens(&asset_id, &caller, &max_tokens)?;
-            let exchange = Self::get_exchange(&asset_id)?;
-
-            // -------------------- Token/liquidity computation --------------------
-            let total_liquidity = T::Assets::total_issuance(exchange.liquidity_token_id.clone());
-            debug_assert!(total_liquidity > Zero::zero());
-            let currency_amount = T::currency_to_asset(currency_amount);
-            let currency_reserve = T::currency_to_asset(exchange.currency_reserve);
-            let token_amount =
-                FixedU128::saturating_from_rational(currency_amount, currency_reserve)
-                    .saturating_mul_int(exchange.token_reserve)
-                    .saturating_add(One::one());
-            let liquidity_minted =
-                FixedU128::saturating_from_rational(currency_amount, currency_reserve)
-                    .saturating_mul_int(total_liquidity);
-            ensure!(token_amount <= max_tokens, Error::<T>::MaxTokensTooLow);
-            ensure!(liquidity_minted >= min_liquidity, Error::<T>::MinLiquidityTooHigh);
-
-            // ----------------------------- State update ----------------------------
-            Self::do_add_liquidity(
-                exchange,
-                T::asset_to_currency(currency_amount),
-                token_amount,
-                liquidity_minted,
+            Self::do_provide_liquidity(
                 caller,
+                asset_id,
+                currency_amount,
+                min_liquidity,
+                max_tokens,
+                price_limit,
             )
         }
 
         /// Remove liquidity from an exchange. The caller specifies the amount of liquidity tokens
         /// to burn, and minimum amounts of currency and asset to receive.
-        /// Emit `LiquidityRemoved` event on success.
+        /// Emit `LiquidityRemoved` event on success. If all the liquidity is removed, remove
+        /// the exchange (see `ExchangeRemoved` event).
         ///
         /// **Parameters:**
         ///   * `origin` – Origin for the call. Must be signed.
@@ -518,16 +1432,22 @@ pub mod pallet {
         ///   * `liquidity_amount` – The amount of liquidity tokens to be burned. Must be greater than 0.
         ///   * `min_currency` – The minimum amount of currency to receive. Must be greater than 0.
         ///   * `min_tokens` – The minimum amount of tokens to receive. Must be greater than 0.
-        ///   * `deadline` – Number of the last block in which the transaction can be included.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
         ///
         /// **Errors:**
         ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
+        ///   * `DeadlineTooFar` – Specified `deadline` is more than `MaxDeadline` blocks after the current block.
         ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `TradeOnlyMode` – The exchange is in trade-only mode, liquidity changes are frozen.
         ///   * `LiquidityAmountIsZero` – Specified `liquidity_amount` equals 0.
         ///   * `MinCurrencyIsZero` – Specified `min_currency` equals 0.
         ///   * `MinTokensIsZero` – Specified `min_tokens` equals 0.
         ///   * `ProviderLiquidityTooLow` – Specified `liquidity_amount` is greater than the liquidity
         ///     token balance of the caller account.
+        ///   * `LiquidityFrozen` – Liquidity tokens of the caller account are frozen.
+        ///   * `RetentionFloorReached` – Burning `liquidity_amount` would reduce the liquidity token supply
+        ///     below the retention floor of the exchange.
         ///   * `MinCurrencyTooHigh` – The amount of currency which could be received in exchange for the specified
         ///     `liquidity_amount` is lower than the specified `min_currency`.
         ///   * `MinTokensTooHigh` – The amount of tokens which could be received in exchange for the specified
@@ -539,29 +1459,158 @@ pub mod pallet {
             liquidity_amount: AssetBalanceOf<T>,
             min_currency: BalanceOf<T>,
             min_tokens: AssetBalanceOf<T>,
-            deadline: T::BlockNumber,
+            deadline: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            Self::check_deadline(&deadline)?;
+            Self::do_withdraw_liquidity(
+                caller,
+                asset_id,
+                liquidity_amount,
+                min_currency,
+                min_tokens,
+            )
+        }
+
+        /// Remove liquidity from an exchange, bounding the slippage instead of specifying exact minimum
+        /// amounts. The caller specifies the amount of liquidity tokens to burn, the reference amounts
+        /// of currency and asset it expects (e.g. its pro-rata entitlement quoted when submitting
+        /// the call) and the maximum accepted slippage. The received amounts (the pro-rata
+        /// entitlement at execution time) must not be lower than the reference amounts reduced by
+        /// `max_slippage`, so small reserve shifts are tolerated while larger ones are rejected.
+        /// Emit `LiquidityRemoved` event on success. If all the liquidity is removed, remove
+        /// the exchange (see `ExchangeRemoved` event).
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `asset_id` – ID of the withdrawn asset. An exchange for this asset must exist.
+        ///   * `liquidity_amount` – The amount of liquidity tokens to be burned. Must be greater than 0.
+        ///   * `expected_currency` – The reference amount of currency to receive.
+        ///   * `expected_tokens` – The reference amount of tokens to receive.
+        ///   * `max_slippage` – The maximum accepted difference between the reference and the
+        ///     received amounts, as a fraction of the reference amounts.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
+        ///
+        /// **Errors:**
+        ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
+        ///   * `DeadlineTooFar` – Specified `deadline` is more than `MaxDeadline` blocks after the current block.
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `TradeOnlyMode` – The exchange is in trade-only mode, liquidity changes are frozen.
+        ///   * `LiquidityAmountIsZero` – Specified `liquidity_amount` equals 0.
+        ///   * `ProviderLiquidityTooLow` – Specified `liquidity_amount` is greater than the liquidity
+        ///     token balance of the caller account.
+        ///   * `LiquidityFrozen` – Liquidity tokens of the caller account are frozen.
+        ///   * `RetentionFloorReached` – Burning `liquidity_amount` would reduce the liquidity token supply
+        ///     below the retention floor of the exchange.
+        ///   * `MinCurrencyTooHigh` – The amount of currency which could be received in exchange for the specified
+        ///     `liquidity_amount` is zero or lower than `expected_currency` reduced by `max_slippage`.
+        ///   * `MinTokensTooHigh` – The amount of tokens which could be received in exchange for the specified
+        ///     `liquidity_amount` is zero or lower than `expected_tokens` reduced by `max_slippage`.
+        #[pallet::weight(<T as Config>::WeightInfo::remove_liquidity_with_slippage())]
+        pub fn remove_liquidity_with_slippage(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            liquidity_amount: AssetBalanceOf<T>,
+            expected_currency: BalanceOf<T>,
+            expected_tokens: AssetBalanceOf<T>,
+            max_slippage: Permill,
+            deadline: Option<T::BlockNumber>,
         ) -> DispatchResult {
             // -------------------------- Validation part --------------------------
             let caller = ensure_signed(origin)?;
             Self::check_deadline(&deadline)?;
             ensure!(liquidity_amount > Zero::zero(), Error::<T>::LiquidityAmountIsZero);
-            ensure!(min_currency > Zero::zero(), Error::<T>::MinCurrencyIsZero);
-            ensure!(min_tokens > Zero::zero(), Error::<T>::MinTokensIsZero);
             let exchange = Self::get_exchange(&asset_id)?;
             Self::check_enough_liquidity_owned(&exchange, &caller, &liquidity_amount)?;
+            Self::check_retention_floor(&exchange, &liquidity_amount)?;
+            Self::check_not_trade_only(&asset_id)?;
 
             // --------------- Withdrawn currency/tokens computation ---------------
-            let currency_reserve = T::currency_to_asset(exchange.currency_reserve);
+            let (currency_amount, token_amount) =
+                Self::get_withdrawn_amounts(&exchange, &liquidity_amount)?;
+            let min_currency =
+                expected_currency.saturating_sub(max_slippage.mul_ceil(expected_currency));
+            let min_tokens = expected_tokens.saturating_sub(max_slippage.mul_ceil(expected_tokens));
+            ensure!(
+                currency_amount > Zero::zero() && currency_amount >= min_currency,
+                Error::<T>::MinCurrencyTooHigh
+            );
+            ensure!(
+                token_amount > Zero::zero() && token_amount >= min_tokens,
+                Error::<T>::MinTokensTooHigh
+            );
+
+            // ----------------------------- State update ----------------------------
+            Self::do_remove_liquidity(
+                exchange,
+                currency_amount,
+                token_amount,
+                liquidity_amount,
+                caller,
+            )
+        }
+
+        /// Remove liquidity from an exchange by target amounts: the caller specifies the exact amounts
+        /// of currency and asset to receive, and the pallet burns the liquidity tokens required to
+        /// withdraw them (i.e. the maximum of the pro-rata shares of both amounts). If the ratio of
+        /// the amounts differs from the ratio of the reserves, the surplus of the burned pro-rata
+        /// entitlement stays in the exchange. Emit `LiquidityRemoved` event on success. If all the
+        /// liquidity is removed, the whole reserves are withdrawn and the exchange is removed (see
+        /// `ExchangeRemoved` event).
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `asset_id` – ID of the withdrawn asset. An exchange for this asset must exist.
+        ///   * `currency_out` – The amount of currency to receive. Must be greater than 0.
+        ///   * `token_out` – The amount of tokens to receive. Must be greater than 0.
+        ///   * `max_lp_burn` – The maximum amount of liquidity tokens to burn.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
+        ///
+        /// **Errors:**
+        ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
+        ///   * `DeadlineTooFar` – Specified `deadline` is more than `MaxDeadline` blocks after the current block.
+        ///   * `CurrencyAmountIsZero` – Specified `currency_out` equals 0.
+        ///   * `TokenAmountIsZero` – Specified `token_out` equals 0.
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `TradeOnlyMode` – The exchange is in trade-only mode, liquidity changes are frozen.
+        ///   * `MaxLiquidityTooLow` – The amount of liquidity tokens required to withdraw the specified
+        ///     amounts is higher than the specified `max_lp_burn`.
+        ///   * `ProviderLiquidityTooLow` – The amount of liquidity tokens required to withdraw the specified
+        ///     amounts is greater than the liquidity token balance of the caller account.
+        ///   * `LiquidityFrozen` – Liquidity tokens of the caller account are frozen.
+        ///   * `RetentionFloorReached` – Burning the required liquidity tokens would reduce the liquidity
+        ///     token supply below the retention floor of the exchange.
+        #[pallet::weight(<T as Config>::WeightInfo::remove_liquidity_exact_assets())]
+        pub fn remove_liquidity_exact_assets(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            currency_out: BalanceOf<T>,
+            token_out: AssetBalanceOf<T>,
+            max_lp_burn: AssetBalanceOf<T>,
+            deadline: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            let caller = ensure_signed(origin)?;
+            Self::check_deadline(&deadline)?;
+            ensure!(currency_out > Zero::zero(), Error::<T>::CurrencyAmountIsZero);
+            ensure!(token_out > Zero::zero(), Error::<T>::TokenAmountIsZero);
+            let exchange = Self::get_exchange(&asset_id)?;
+            Self::check_not_trade_only(&asset_id)?;
+
+            // ----------------------- Burned liquidity computation ----------------------
+            let liquidity_amount = Self::get_burned_liquidity(&exchange, currency_out, token_out)?;
+            ensure!(liquidity_amount <= max_lp_burn, Error::<T>::MaxLiquidityTooLow);
+            Self::check_enough_liquidity_owned(&exchange, &caller, &liquidity_amount)?;
+            Self::check_retention_floor(&exchange, &liquidity_amount)?;
             let total_liquidity = T::Assets::total_issuance(exchange.liquidity_token_id.clone());
-            let currency_amount =
-                FixedU128::saturating_from_rational(liquidity_amount, total_liquidity)
-                    .saturating_mul_int(currency_reserve);
-            let currency_amount = T::asset_to_currency(currency_amount);
-            let token_amount =
-                FixedU128::saturating_from_rational(liquidity_amount, total_liquidity)
-                    .saturating_mul_int(exchange.token_reserve);
-            ensure!(currency_amount >= min_currency, Error::<T>::MinCurrencyTooHigh);
-            ensure!(token_amount >= min_tokens, Error::<T>::MinTokensTooHigh);
+            let (currency_amount, token_amount) = if liquidity_amount == total_liquidity {
+                // Don't leave reserves in an exchange without liquidity
+                Self::get_withdrawn_amounts(&exchange, &liquidity_amount)?
+            } else {
+                (currency_out, token_out)
+            };
 
             // ----------------------------- State update ----------------------------
             Self::do_remove_liquidity(
@@ -577,25 +1626,38 @@ pub mod pallet {
         ///   * exact amount of currency to sell (`input_amount`) and minimum amount of tokens to buy (`min_output`), or
         ///   * exact amount of tokens to buy (`output_amount`) and maximum amount of currency to sell (`max_input`).
         ///
-        /// Emit `CurrencyTradedForAsset` event on success.
+        /// Emit `CurrencyTradedForAsset` event on success (and `RecipientProvisioned` if the recipient
+        /// account has been funded, `OutputDonated` if part of the output has been donated).
         ///
         /// **Parameters:**
         ///   * `origin` – Origin for the call. Must be signed.
         ///   * `asset_id` – ID of the bought asset. An exchange for this asset must exist and have sufficient liquidity.
         ///   * `amount` – Amount of the currency and asset to trade.
-        ///   * `deadline` – Number of the last block in which the transaction can be included.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
         ///   * `recipient` – (Optional) account to transfer the bought tokens to.
+        ///   * `provision_recipient` – If the recipient account doesn't exist (and so might be unable to hold
+        ///     the bought asset), fund its existential deposit out of the traded currency.
+        ///   * `donation` – (Optional) share of the bought tokens to transfer to a beneficiary
+        ///     (or to burn) instead of the recipient. The minimum output applies to the amount
+        ///     before the donation.
         ///
         /// **Errors:**
         ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
+        ///   * `DeadlineTooFar` – Specified `deadline` is more than `MaxDeadline` blocks after the current block.
         ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
         ///   * `TradeAmountIsZero` – Specified currency or token amount equals 0.
+        ///   * `ExchangeSuspended` – Trading the asset is suspended by the safety oracle or paused by
+        ///     governance.
+        ///   * `ExchangeDelisted` – The exchange is delisted.
+        ///   * `NotWhitelisted` – The exchange is in the bootstrap phase and the caller is not whitelisted.
         ///   * `MinTokensTooHigh` – The amount of tokens which could be received in exchange for the specified
         ///     currency amount (`input_amount`) is lower than the specified minimum (`min_output`).
         ///   * `MaxCurrencyTooLow` – The amount of currency which must be spent to receive the specified
         ///     asset amount (`output_amount`) is higher than the specified maximum (`max_input`).
         ///   * `NotEnoughLiquidity` – There is not enough liquidity in the pool to buy the specified
         ///     amount of tokens (`output_amount`).
+        ///   * `TradeTooLarge` – The amount of currency to be sold exceeds the maximum trade size of the exchange.
         ///   * `BalanceTooLow` – The available currency balance of the caller account is not enough to perform the trade.
         ///   * `Overflow` – An overflow occurred during price computation.
         #[pallet::weight(<T as Config>::WeightInfo::currency_to_asset())]
@@ -603,54 +1665,81 @@ pub mod pallet {
             origin: OriginFor<T>,
             asset_id: AssetIdOf<T>,
             amount: TradeAmount<BalanceOf<T>, AssetBalanceOf<T>>,
-            deadline: T::BlockNumber,
+            deadline: Option<T::BlockNumber>,
             recipient: Option<AccountIdOf<T>>,
+            provision_recipient: bool,
+            donation: Option<DonationOf<T>>,
         ) -> DispatchResult {
             // -------------------------- Validation part --------------------------
             let caller = ensure_signed(origin)?;
             let recipient = recipient.unwrap_or_else(|| caller.clone());
             Self::check_deadline(&deadline)?;
             Self::check_trade_amount(&amount)?;
+            Self::check_not_suspended(&asset_id)?;
+            Self::check_not_delisted(&asset_id)?;
+            Self::check_whitelisted(&asset_id, &caller)?;
             let exchange = Self::get_exchange(&asset_id)?;
+            let deposit = Self::get_recipient_deposit(&recipient, provision_recipient);
 
             // --------------------------- Compute price ---------------------------
             let (currency_amount, token_amount) =
-                Self::get_currency_to_asset_price(&exchange, amount)?;
-            Self::check_enough_currency(&caller, &currency_amount)?;
+                Self::get_currency_to_asset_price(&exchange, amount, deposit)?;
+            Self::check_currency_trade_size(&exchange, &currency_amount)?;
+            Self::check_enough_currency(&caller, &currency_amount.saturating_add(deposit))?;
 
             // --------------------------- Perform trade ---------------------------
+            Self::provision_recipient(&asset_id, &caller, &recipient, deposit)?;
             Self::swap_currency_for_asset(
                 exchange,
                 currency_amount,
                 token_amount,
-                caller,
+                caller.clone(),
                 recipient,
-            )
+                donation,
+            )?;
+            Self::pay_rebate(
+                &asset_id,
+                &caller,
+                T::WeightToFee::weight_to_fee(&<T as Config>::WeightInfo::currency_to_asset()),
+            );
+            Self::pay_fee_share(&asset_id, &caller, currency_amount, Zero::zero());
+            Ok(())
         }
 
         /// Exchange asset for currency. Optionally, transfer bought currency to `recipient`. The caller can specify either:
         ///   * exact amount of tokes to sell (`input_amount`) and minimum amount of currency to buy (`min_output`), or
         ///   * exact amount of currency to buy (`output_amount`) and maximum amount of tokens to sell (`max_input`).
         ///
-        /// Emit `AssetTradedForCurrency` event on success.
+        /// Emit `AssetTradedForCurrency` event on success (and `OutputDonated` if part of the output
+        /// has been donated).
         ///
         /// **Parameters:**
         ///   * `origin` – Origin for the call. Must be signed.
         ///   * `asset_id` – ID of the sold asset. An exchange for this asset must exist and have sufficient liquidity.
         ///   * `amount` – Amount of the currency and asset to trade.
-        ///   * `deadline` – Number of the last block in which the transaction can be included.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
         ///   * `recipient` – (Optional) account to transfer the currency tokens to.
+        ///   * `donation` – (Optional) share of the bought currency to transfer to a beneficiary
+        ///     (or to burn) instead of the recipient. The minimum output applies to the amount
+        ///     before the donation.
         ///
         /// **Errors:**
         ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
+        ///   * `DeadlineTooFar` – Specified `deadline` is more than `MaxDeadline` blocks after the current block.
         ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
         ///   * `TradeAmountIsZero` – Specified currency or token amount equals 0.
+        ///   * `ExchangeSuspended` – Trading the asset is suspended by the safety oracle or paused by
+        ///     governance.
+        ///   * `ExchangeDelisted` – The exchange is delisted.
+        ///   * `NotWhitelisted` – The exchange is in the bootstrap phase and the caller is not whitelisted.
         ///   * `MinCurrencyTooHigh` – The amount of currency which could be received in exchange for the specified
         ///     asset amount (`input_amount`) is lower than the specified minimum (`min_output`).
         ///   * `MaxTokensTooLow` – The amount of asset which must be spent to receive the specified
         ///     currency amount (`output_amount`) is higher than the specified maximum (`max_input`).
         ///   * `NotEnoughLiquidity` – There is not enough liquidity in the pool to buy the specified
         ///     amount of currency (`output_amount`).
+        ///   * `TradeTooLarge` – The amount of asset to be sold exceeds the maximum trade size of the exchange.
         ///   * `NotEnoughTokens` – The available asset balance of the caller account is not enough to perform the trade.
         ///   * `Overflow` – An overflow occurred during price computation.
         #[pallet::weight(<T as Config>::WeightInfo::asset_to_currency())]
@@ -658,220 +1747,1950 @@ pub mod pallet {
             origin: OriginFor<T>,
             asset_id: AssetIdOf<T>,
             amount: TradeAmount<AssetBalanceOf<T>, BalanceOf<T>>,
-            deadline: T::BlockNumber,
+            deadline: Option<T::BlockNumber>,
             recipient: Option<AccountIdOf<T>>,
+            donation: Option<DonationOf<T>>,
         ) -> DispatchResult {
-            // -------------------------- Validation part --------------------------
             let caller = ensure_signed(origin)?;
             let recipient = recipient.unwrap_or_else(|| caller.clone());
-            Self::check_deadline(&deadline)?;
-            Self::check_trade_amount(&amount)?;
-            let exchange = Self::get_exchange(&asset_id)?;
-
-            // --------------------------- Compute price ---------------------------
-            let (currency_amount, token_amount) =
-                Self::get_asset_to_currency_price(&exchange, amount)?;
-            Self::check_enough_tokens(&asset_id, &caller, &token_amount)?;
-
-            // --------------------------- Perform trade ---------------------------
-            Self::swap_asset_for_currency(
-                exchange,
-                currency_amount,
-                token_amount,
-                caller,
-                recipient,
-            )
+            Self::do_asset_to_currency(caller, recipient, asset_id, amount, deadline, donation)?;
+            Ok(())
         }
 
         /// Exchange asset for another asset. Optionally, transfer bought asset to `recipient`. The caller can specify either:
         ///   * exact amount of tokes to sell (`input_amount`) and minimum amount of tokens to buy (`min_output`), or
         ///   * exact amount of tokens to buy (`output_amount`) and maximum amount of tokens to sell (`max_input`).
         ///
+        /// Emit `AssetTradedForAsset` event, followed by `LegStarted(0)` and `AssetTradedForCurrency` events
+        /// of the sold asset leg and `LegStarted(1)` and `CurrencyTradedForAsset` events of the bought asset leg.
+        /// Emit `RecipientProvisioned` event if the recipient account has been funded (and `OutputDonated`
+        /// if part of the output has been donated).
+        ///
         /// **Parameters:**
         ///   * `origin` – Origin for the call. Must be signed.
         ///   * `sold_asset_id` – ID of the sold asset. An exchange for this asset must exist and have sufficient liquidity.
         ///   * `bought_asset_id` – ID of the bought asset. An exchange for this asset must exist and have sufficient liquidity.
         ///   * `amount` – Amount of the assets to trade.
-        ///   * `deadline` – Number of the last block in which the transaction can be included.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
         ///   * `recipient` – (Optional) account to transfer the bought tokens to.
+        ///   * `provision_recipient` – If the recipient account doesn't exist (and so might be unable to hold
+        ///     the bought asset), fund its existential deposit out of the traded currency.
+        ///   * `donation` – (Optional) share of the bought tokens to transfer to a beneficiary
+        ///     (or to burn) instead of the recipient. The minimum output applies to the amount
+        ///     before the donation.
         ///
         /// **Errors:**
         ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
+        ///   * `DeadlineTooFar` – Specified `deadline` is more than `MaxDeadline` blocks after the current block.
         ///   * `ExchangeNotFound` – There is no exchange for the given `sold_asset_id` or `bought_asset_id`.
         ///   * `TradeAmountIsZero` – Specified bought or sold token amount equals 0.
+        ///   * `ExchangeSuspended` – Trading one of the assets is suspended by the safety oracle or
+        ///     paused by governance.
+        ///   * `ExchangeDelisted` – One of the exchanges is delisted.
+        ///   * `NotWhitelisted` – One of the exchanges is in the bootstrap phase and the caller is not whitelisted.
         ///   * `MinBoughtTokensTooHigh` – The amount of asset which could be bought in exchange for the specified
         ///     sold asset amount (`input_amount`) is lower than the specified minimum (`min_output`).
         ///   * `MaxSoldTokensTooLow` – The amount of asset which must be sold to receive the specified
         ///     bought asset amount (`output_amount`) is higher than the specified maximum (`max_input`).
         ///   * `NotEnoughLiquidity` – There is not enough liquidity in one of the pools to buy the specified amount of asset
         ///     (`output_amount`).
+        ///   * `TradeTooLarge` – The trade exceeds the maximum trade size of one of the exchanges.
         ///   * `NotEnoughTokens` – The available sold asset balance of the caller account is not enough to perform the trade.
         ///   * `Overflow` – An overflow occurred during price computation.
         #[pallet::weight(<T as Config>::WeightInfo::asset_to_asset())]
+        #[allow(clippy::too_many_arguments)]
         pub fn asset_to_asset(
             origin: OriginFor<T>,
             sold_asset_id: AssetIdOf<T>,
             bought_asset_id: AssetIdOf<T>,
             amount: TradeAmount<AssetBalanceOf<T>, AssetBalanceOf<T>>,
-            deadline: T::BlockNumber,
+            deadline: Option<T::BlockNumber>,
             recipient: Option<AccountIdOf<T>>,
+            provision_recipient: bool,
+            donation: Option<DonationOf<T>>,
         ) -> DispatchResult {
-            // -------------------------- Validation part --------------------------
             let caller = ensure_signed(origin)?;
             let recipient = recipient.unwrap_or_else(|| caller.clone());
-            Self::check_deadline(&deadline)?;
-            Self::check_trade_amount(&amount)?;
-            let sold_asset_exchange = Self::get_exchange(&sold_asset_id)?;
-            let bought_asset_exchange = Self::get_exchange(&bought_asset_id)?;
-
-            // --------------------------- Compute price ---------------------------
-            let (sold_token_amount, currency_amount, bought_token_amount) =
-                Self::get_asset_to_asset_price(
-                    &sold_asset_exchange,
-                    &bought_asset_exchange,
-                    amount,
-                )?;
-            Self::check_enough_tokens(&sold_asset_id, &caller, &sold_token_amount)?;
-
-            // --------------------------- Perform trade ---------------------------
-            Self::swap_asset_for_asset(
-                sold_asset_exchange,
-                bought_asset_exchange,
-                currency_amount,
-                sold_token_amount,
-                bought_token_amount,
+            Self::do_asset_to_asset(
                 caller,
                 recipient,
-            )
+                sold_asset_id,
+                bought_asset_id,
+                amount,
+                deadline,
+                provision_recipient,
+                donation,
+            )?;
+            Ok(())
         }
-    }
 
-    impl<T: Config> Pallet<T> {
-        pub(crate) fn get_exchange(asset_id: &AssetIdOf<T>) -> Result<ExchangeOf<T>, Error<T>> {
-            <Exchanges<T>>::get(asset_id.clone()).ok_or(Error::<T>::ExchangeNotFound)
-        }
+        /// Sponsor an exchange. Deposit currency from which traders receive a fixed rebate
+        /// (e.g. compensating their transaction fees) after each trade on the exchange,
+        /// up to a per-block budget. Calling it again (by the same sponsor) tops the deposit up
+        /// and updates the rebate parameters. Emit `ExchangeSponsored` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `asset_id` – ID of the asset traded on the sponsored exchange. An exchange for this asset must exist.
+        ///   * `currency_amount` – The amount of currency to deposit for rebates.
+        ///   * `rebate` – The amount of currency rebated to a trader per trade. Must be greater than 0.
+        ///     It is capped at the transaction fee of the trade (see `Config::WeightToFee`).
+        ///   * `block_budget` – The maximum amount of currency rebated per block.
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `RebateIsZero` – Specified `rebate` equals 0.
+        ///   * `NotSponsor` – The exchange is already sponsored by another account.
+        ///   * `BalanceTooLow` – Specified `currency_amount` is greater than the available currency balance
+        ///     of the caller account.
+        #[pallet::weight(<T as Config>::WeightInfo::sponsor_exchange())]
+        pub fn sponsor_exchange(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            currency_amount: BalanceOf<T>,
+            rebate: BalanceOf<T>,
+            block_budget: BalanceOf<T>,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            let caller = ensure_signed(origin)?;
+            ensure!(!rebate.is_zero(), Error::<T>::RebateIsZero);
+            Self::get_exchange(&asset_id)?;
+            Self::check_enough_currency(&caller, &currency_amount)?;
+            let (spent, last_block) = match <Sponsorships<T>>::get(asset_id.clone()) {
+                Some(sponsorship) => {
+                    ensure!(sponsorship.sponsor == caller, Error::<T>::NotSponsor);
+                    (sponsorship.spent, sponsorship.last_block)
+                }
+                None => (Zero::zero(), Zero::zero()),
+            };
+
+            // --------------------------- Deposit funds ---------------------------
+            <T as pallet::Config>::Currency::transfer(
+                &caller,
+                &T::sponsor_account(&asset_id),
+                currency_amount,
+                ExistenceRequirement::KeepAlive,
+            )?;
+
+            // -------------------------- Update storage ---------------------------
+            let sponsorship = Sponsorship {
+                sponsor: caller.clone(),
+                rebate,
+                block_budget,
+                spent,
+                last_block,
+            };
+            <Sponsorships<T>>::insert(asset_id.clone(), sponsorship);
 
-        fn check_deadline(deadline: &T::BlockNumber) -> Result<(), Error<T>> {
-            ensure!(deadline >= &<frame_system::Pallet<T>>::block_number(), Error::DeadlinePassed);
+            // ---------------------------- Emit event -----------------------------
+            Self::deposit_event(Event::ExchangeSponsored(
+                asset_id,
+                caller,
+                currency_amount,
+                rebate,
+                block_budget,
+            ));
             Ok(())
         }
 
-        fn check_trade_amount<A: Zero, B: Zero>(
-            amount: &TradeAmount<A, B>,
-        ) -> Result<(), Error<T>> {
-            match amount {
-                TradeAmount::FixedInput {
-                    input_amount,
-                    min_output,
-                } => {
-                    ensure!(!input_amount.is_zero(), Error::TradeAmountIsZero);
-                    ensure!(!min_output.is_zero(), Error::TradeAmountIsZero);
-                }
-                TradeAmount::FixedOutput {
-                    output_amount,
-                    max_input,
-                } => {
-                    ensure!(!output_amount.is_zero(), Error::TradeAmountIsZero);
-                    ensure!(!max_input.is_zero(), Error::TradeAmountIsZero);
-                }
-            };
+        /// End sponsorship of an exchange. Return all not yet rebated currency to the sponsor.
+        /// Emit `SponsorshipWithdrawn` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed by the sponsor.
+        ///   * `asset_id` – ID of the asset traded on the sponsored exchange.
+        ///
+        /// **Errors:**
+        ///   * `SponsorshipNotFound` – The exchange for the given `asset_id` is not sponsored.
+        ///   * `NotSponsor` – The caller is not the sponsor of the exchange.
+        #[pallet::weight(<T as Config>::WeightInfo::withdraw_sponsorship())]
+        pub fn withdraw_sponsorship(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let sponsorship =
+                <Sponsorships<T>>::get(asset_id.clone()).ok_or(Error::<T>::SponsorshipNotFound)?;
+            ensure!(sponsorship.sponsor == caller, Error::<T>::NotSponsor);
+            Self::do_withdraw_sponsorship(asset_id, sponsorship)
+        }
+
+        /// Set (or remove) the maximum size of a single trade on an exchange. Forces large trades
+        /// to be split (e.g. executed by TWAMM or batch mechanisms) instead of being executed
+        /// as a single swap moving the price drastically. Emit `MaxTradeSizeSet` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist.
+        ///   * `limit` – The maximum trade size, either absolute or as a share of the exchange reserves.
+        ///     `None` removes the limit.
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        #[pallet::weight(<T as Config>::WeightInfo::set_max_trade_size())]
+        pub fn set_max_trade_size(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            limit: Option<TradeSizeLimitOf<T>>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            Self::get_exchange(&asset_id)?;
+            <MaxTradeSizes<T>>::set(asset_id.clone(), limit.clone());
+            Self::deposit_event(Event::MaxTradeSizeSet(asset_id, limit));
             Ok(())
         }
 
-        fn check_enough_currency(
-            account_id: &AccountIdOf<T>,
-            amount: &BalanceOf<T>,
-        ) -> Result<(), Error<T>> {
+        /// Settle a quote signed off-chain by a registered market maker (RFQ). Currency and asset
+        /// are transferred directly between the market maker and the caller (taker) at the quoted
+        /// amounts. If the quote requests hedging, the market maker's position is then offset
+        /// against the exchange, without a loss to the market maker:
+        ///   * if the maker sold the asset, the same amount of the asset is bought back from the exchange
+        ///     for at most the received currency,
+        ///   * if the maker bought the asset, it is sold to the exchange for at least the paid currency.
+        ///
+        /// Each quote can be settled once. Emit `RfqSettled` event on success (preceded by the trade event
+        /// of the hedge, if any). The hedge pays the fee share of the market maker, if any.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `quote` – The quote to settle.
+        ///   * `signature` – Signature of the SCALE-encoded `(b"dex/rfq", genesis_hash, quote)` by the market
+        ///     maker account (see `rfq_signing_payload`).
+        ///
+        /// **Errors:**
+        ///   * `NotMarketMaker` – The quote maker is not a registered market maker.
+        ///   * `InvalidSignature` – The signature doesn't match the quote and its maker.
+        ///   * `DeadlinePassed` – Quote `expiry` is lower than the current block number. The expiry is chosen by the
+        ///     market maker, so it isn't limited by `MaxDeadline`.
+        ///   * `NotQuoteTaker` – The quote can only be settled by another account.
+        ///   * `QuoteAlreadySettled` – A quote with the same `nonce` has already been settled.
+        ///   * `TradeAmountIsZero` – Quoted currency or token amount equals 0.
+        ///   * `QuoteTooLarge` – Quoted currency or token amount exceeds the quote size limits of the market maker.
+        ///   * `ExchangeSuspended` – Trading the asset is suspended by the safety oracle or paused by
+        ///     governance.
+        ///   * `ExchangeDelisted` – The exchange is delisted.
+        ///   * `BalanceTooLow` – The available currency balance of the paying account is not enough to settle the quote.
+        ///   * `NotEnoughTokens` – The available asset balance of the paying account is not enough to settle the quote.
+        ///   * `ExchangeNotFound` – Hedging is requested and there is no exchange for the asset.
+        ///   * `NotWhitelisted` – Hedging is requested, the exchange is in the bootstrap phase and the market maker
+        ///     is not whitelisted.
+        ///   * `MaxCurrencyTooLow` – The hedge would cost the market maker more currency than received.
+        ///   * `MinCurrencyTooHigh` – The hedge would return the market maker less currency than paid.
+        ///   * `TradeTooLarge` – The hedge exceeds the maximum trade size of the exchange.
+        #[pallet::weight(<T as Config>::WeightInfo::settle_rfq())]
+        pub fn settle_rfq(
+            origin: OriginFor<T>,
+            quote: RfqQuoteOf<T>,
+            signature: T::OffchainSignature,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            let taker = ensure_signed(origin)?;
+            let market_maker =
+                <MarketMakers<T>>::get(&quote.maker).ok_or(Error::<T>::NotMarketMaker)?;
             ensure!(
-                &<T as Config>::Currency::free_balance(account_id) >= amount,
-                Error::<T>::BalanceTooLow
+                signature.verify(&Self::rfq_signing_payload(&quote)[..], &quote.maker),
+                Error::<T>::InvalidSignature
+            );
+            ensure!(
+                quote.expiry >= <frame_system::Pallet<T>>::block_number(),
+                Error::<T>::DeadlinePassed
+            );
+            if let Some(quote_taker) = &quote.taker {
+                ensure!(quote_taker == &taker, Error::<T>::NotQuoteTaker);
+            }
+            ensure!(
+                !<UsedRfqNonces<T>>::contains_key(&quote.maker, quote.nonce),
+                Error::<T>::QuoteAlreadySettled
+            );
+            ensure!(
+                !quote.currency_amount.is_zero() && !quote.token_amount.is_zero(),
+                Error::<T>::TradeAmountIsZero
+            );
+            ensure!(
+                quote.currency_amount <= market_maker.max_currency
+                    && quote.token_amount <= market_maker.max_tokens,
+                Error::<T>::QuoteTooLarge
+            );
+            Self::check_not_suspended(&quote.asset_id)?;
+            Self::check_not_delisted(&quote.asset_id)?;
+            let (currency_payer, token_payer) = match quote.side {
+                RfqSide::MakerSells => (&taker, &quote.maker),
+                RfqSide::MakerBuys => (&quote.maker, &taker),
+            };
+            Self::check_enough_currency(currency_payer, &quote.currency_amount)?;
+            Self::check_enough_tokens(&quote.asset_id, token_payer, &quote.token_amount)?;
+
+            // ---------------------------- Settle quote ---------------------------
+            <UsedRfqNonces<T>>::insert(&quote.maker, quote.nonce, ());
+            <T as pallet::Config>::Currency::transfer(
+                currency_payer,
+                token_payer,
+                quote.currency_amount,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            T::Assets::transfer(
+                quote.asset_id.clone(),
+                token_payer,
+                currency_payer,
+                quote.token_amount,
+                false,
+            )?;
+            if quote.hedge {
+                Self::hedge_rfq(&quote)?;
+            }
+
+            // ---------------------------- Emit event -----------------------------
+            Self::deposit_event(Event::RfqSettled(
+                quote.asset_id,
+                quote.maker,
+                taker,
+                quote.currency_amount,
+                quote.token_amount,
+            ));
+            Ok(())
+        }
+
+        /// Register a market maker (allowed to sign RFQ quotes), or update the parameters
+        /// of a registered one. Emit `MarketMakerRegistered` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `market_maker` – The market maker account.
+        ///   * `parameters` – Market maker parameters: fee exemption and quote size limits.
+        #[pallet::weight(<T as Config>::WeightInfo::register_market_maker())]
+        pub fn register_market_maker(
+            origin: OriginFor<T>,
+            market_maker: AccountIdOf<T>,
+            parameters: MarketMakerOf<T>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            <MarketMakers<T>>::insert(&market_maker, parameters.clone());
+            Self::deposit_event(Event::MarketMakerRegistered(market_maker, parameters));
+            Ok(())
+        }
+
+        /// Deregister a market maker. Quotes it signed can no longer be settled.
+        /// Emit `MarketMakerDeregistered` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `market_maker` – The market maker account.
+        ///
+        /// **Errors:**
+        ///   * `NotMarketMaker` – The account is not a registered market maker.
+        #[pallet::weight(<T as Config>::WeightInfo::deregister_market_maker())]
+        pub fn deregister_market_maker(
+            origin: OriginFor<T>,
+            market_maker: AccountIdOf<T>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            <MarketMakers<T>>::take(&market_maker).ok_or(Error::<T>::NotMarketMaker)?;
+            Self::deposit_event(Event::MarketMakerDeregistered(market_maker));
+            Ok(())
+        }
+
+        /// Stream liquidity into an exchange over a number of blocks, avoiding a single large deposit.
+        /// Currency and tokens are escrowed, then in each of the following `blocks` blocks (as long as
+        /// there is spare block weight) an equal part of the currency is added to the exchange together
+        /// with the proportional amount of tokens. Unused tokens are returned when the stream ends.
+        /// The stream ends early if a part can't be added (e.g. the escrowed tokens are not enough
+        /// to match the currency at the current price). `StorageDeposit` is reserved from the caller
+        /// until the stream ends. The escrowed funds are held by the stream escrow sub-account of the
+        /// exchange (see `PoolSubsystem`): if it doesn't exist yet, the caller also funds it with the
+        /// existential deposit of the currency, which stays there for the later streams.
+        /// Emit `LiquidityStreamStarted` event on success (and `RecipientProvisioned` if the escrow
+        /// sub-account has been funded), then `LiquidityAdded` event for each part and
+        /// `LiquidityStreamEnded` event in the end.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist.
+        ///   * `currency_amount` – The amount of the currency to stream. Must be greater than 0.
+        ///   * `max_tokens` – The maximum amount of tokens to stream. Must be greater than 0.
+        ///   * `blocks` – The number of blocks (parts) to stream the liquidity over. Must be greater than 0.
+        ///
+        /// **Errors:**
+        ///   * `CurrencyAmountIsZero` – Specified `currency_amount` equals 0.
+        ///   * `MaxTokensIsZero` – Specified `max_tokens` equals 0.
+        ///   * `BlocksIsZero` – Specified `blocks` equals 0.
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `StreamAlreadyExists` – The caller already streams liquidity into the exchange.
+        ///   * `NotWhitelisted` – The exchange is in the bootstrap phase and the caller is not whitelisted.
+        ///   * `ExchangeDelisted` – The exchange is delisted.
+        ///   * `BalanceTooLow` – Specified `currency_amount` is greater than the available currency balance of the caller account,
+        ///     or the caller doesn't have enough currency left to reserve the storage deposit.
+        ///   * `NotEnoughTokens` – Specified `max_tokens` is greater than the available asset balance of the caller account.
+        #[pallet::weight(<T as Config>::WeightInfo::stream_liquidity())]
+        pub fn stream_liquidity(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            currency_amount: BalanceOf<T>,
+            max_tokens: AssetBalanceOf<T>,
+            blocks: u32,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            let caller = ensure_signed(origin)?;
+            ensure!(!currency_amount.is_zero(), Error::<T>::CurrencyAmountIsZero);
+            ensure!(!max_tokens.is_zero(), Error::<T>::MaxTokensIsZero);
+            ensure!(blocks > 0, Error::<T>::BlocksIsZero);
+            Self::get_exchange(&asset_id)?;
+            Self::check_whitelisted(&asset_id, &caller)?;
+            Self::check_not_delisted(&asset_id)?;
+            ensure!(
+                !<LiquidityStreams<T>>::contains_key(asset_id.clone(), &caller),
+                Error::<T>::StreamAlreadyExists
+            );
+            Self::check_enough_currency(&caller, &currency_amount)?;
+            Self::check_enough_tokens(&asset_id, &caller, &max_tokens)?;
+
+            // ---------------------------- Escrow funds ---------------------------
+            Self::hold_storage_deposit(&caller, DepositItem::LiquidityStream(asset_id.clone()))?;
+            let escrow_account = T::stream_escrow_account(&asset_id);
+            // Keep the escrow sub-account alive, so it can hold any asset and its currency is never
+            // reaped as dust
+            let deposit = Self::get_recipient_deposit(&escrow_account, true);
+            Self::provision_recipient(&asset_id, &caller, &escrow_account, deposit)?;
+            <T as pallet::Config>::Currency::transfer(
+                &caller,
+                &escrow_account,
+                currency_amount,
+                ExistenceRequirement::KeepAlive,
+            )?;
+            T::Assets::transfer(asset_id.clone(), &caller, &escrow_account, max_tokens, true)?;
+            <EscrowedTokens<T>>::mutate(asset_id.clone(), |escrowed| {
+                escrowed.audited_accrue(max_tokens)
+            });
+            let stream = LiquidityStream {
+                currency_remaining: currency_amount,
+                tokens_remaining: max_tokens,
+                blocks_remaining: blocks,
+                last_block: <frame_system::Pallet<T>>::block_number(),
+            };
+            <LiquidityStreams<T>>::insert(asset_id.clone(), &caller, stream);
+
+            // ---------------------------- Emit event -----------------------------
+            Self::deposit_event(Event::LiquidityStreamStarted(
+                asset_id,
+                caller,
+                currency_amount,
+                max_tokens,
+            ));
+            Ok(())
+        }
+
+        /// Cancel streaming liquidity into an exchange. Return the currency and tokens not streamed yet.
+        /// Emit `LiquidityStreamEnded` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed by the liquidity provider.
+        ///   * `asset_id` – ID of the asset traded on the exchange.
+        ///
+        /// **Errors:**
+        ///   * `StreamNotFound` – The caller doesn't stream liquidity into the exchange.
+        #[pallet::weight(<T as Config>::WeightInfo::cancel_liquidity_stream())]
+        pub fn cancel_liquidity_stream(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let stream = <LiquidityStreams<T>>::get(asset_id.clone(), &caller)
+                .ok_or(Error::<T>::StreamNotFound)?;
+            Self::do_end_liquidity_stream(asset_id, caller, stream)
+        }
+
+        /// Approve an operator (e.g. a bot or vault account) to sell the caller's asset
+        /// with `swap_from`, up to `allowance` in total, only for the `bought_asset_id` asset
+        /// (or the currency) and at `min_price` or higher. Overwrites the previous approval
+        /// of the operator for the asset. `StorageDeposit` is reserved from the caller for a new
+        /// approval, until it is revoked. Emit `OperatorApproved` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed by the owner of the asset.
+        ///   * `operator` – The approved operator account.
+        ///   * `asset_id` – ID of the asset the operator may sell.
+        ///   * `allowance` – The maximum total amount of the asset the operator may sell.
+        ///   * `bought_asset_id` – ID of the asset the operator may buy with the sold asset (`None` meaning the
+        ///     currency).
+        ///   * `min_price` – The minimum price the operator may sell at, as amount of the bought currency or asset
+        ///     per sold token (including fees).
+        ///   * `expiry` – (Optional) number of the last block in which the approval can be used.
+        ///
+        /// **Errors:**
+        ///   * `ApprovalExpired` – Specified `expiry` is lower than the current block number.
+        ///   * `BalanceTooLow` – The caller doesn't have enough currency to reserve the storage deposit.
+        #[pallet::weight(<T as Config>::WeightInfo::approve_operator())]
+        pub fn approve_operator(
+            origin: OriginFor<T>,
+            operator: AccountIdOf<T>,
+            asset_id: AssetIdOf<T>,
+            allowance: AssetBalanceOf<T>,
+            bought_asset_id: Option<AssetIdOf<T>>,
+            min_price: Price,
+            expiry: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            let owner = ensure_signed(origin)?;
+            if let Some(expiry) = expiry {
+                let now = <frame_system::Pallet<T>>::block_number();
+                ensure!(expiry >= now, Error::<T>::ApprovalExpired);
+            }
+            Self::hold_storage_deposit(
+                &owner,
+                DepositItem::OperatorApproval(operator.clone(), asset_id.clone()),
+            )?;
+            let approval = OperatorApproval {
+                allowance,
+                bought_asset_id,
+                min_price,
+                expiry,
+            };
+            <OperatorApprovals<T>>::insert(&owner, (operator.clone(), asset_id.clone()), approval);
+            Self::deposit_event(Event::OperatorApproved(
+                owner, operator, asset_id, allowance, expiry,
+            ));
+            Ok(())
+        }
+
+        /// Revoke the approval of an operator to sell the caller's asset, releasing its storage deposit.
+        /// Emit `OperatorRevoked` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed by the owner of the asset.
+        ///   * `operator` – The approved operator account.
+        ///   * `asset_id` – ID of the asset the operator may sell.
+        ///
+        /// **Errors:**
+        ///   * `NotApproved` – The operator is not approved to sell the asset.
+        #[pallet::weight(<T as Config>::WeightInfo::revoke_operator())]
+        pub fn revoke_operator(
+            origin: OriginFor<T>,
+            operator: AccountIdOf<T>,
+            asset_id: AssetIdOf<T>,
+        ) -> DispatchResult {
+            let owner = ensure_signed(origin)?;
+            let key = (operator.clone(), asset_id.clone());
+            ensure!(<OperatorApprovals<T>>::contains_key(&owner, &key), Error::<T>::NotApproved);
+            <OperatorApprovals<T>>::remove(&owner, &key);
+            Self::release_storage_deposit(
+                &owner,
+                DepositItem::OperatorApproval(operator.clone(), asset_id.clone()),
+            );
+            Self::deposit_event(Event::OperatorRevoked(owner, operator, asset_id));
+            Ok(())
+        }
+
+        /// Sell the owner's asset as an approved operator, like `asset_to_currency` or
+        /// `asset_to_asset` would if called by the owner. Bought currency/asset is always
+        /// transferred to the owner. The bought currency/asset and the trade price must match the
+        /// approval. The sold amount is deducted from the operator's allowance.
+        /// Emit `AssetTradedForCurrency` event (or `AssetTradedForCurrency` and `CurrencyTradedForAsset`
+        /// events) on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed by the operator.
+        ///   * `owner` – The owner of the sold asset, who approved the operator.
+        ///   * `swap` – The trade: sold (and bought) asset and amounts, as in `asset_to_currency`
+        ///     and `asset_to_asset`.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
+        ///
+        /// **Errors:**
+        ///   * `NotApproved` – The operator is not approved to sell the asset on behalf of the owner.
+        ///   * `ApprovalExpired` – The approval has expired.
+        ///   * `BoughtAssetNotApproved` – The operator is not approved to buy the currency or asset.
+        ///   * `AllowanceExceeded` – The sold amount exceeds the remaining allowance of the operator.
+        ///   * `PriceBelowApproved` – The bought amount per sold token is lower than the minimum price of the
+        ///     approval.
+        ///   * Same as `asset_to_currency` or `asset_to_asset` (referring to the owner account).
+        #[pallet::weight(<T as Config>::WeightInfo::swap_from())]
+        pub fn swap_from(
+            origin: OriginFor<T>,
+            owner: AccountIdOf<T>,
+            swap: DelegatedSwapOf<T>,
+            deadline: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            let operator = ensure_signed(origin)?;
+            let (sold_asset_id, bought_asset_id) = match &swap {
+                DelegatedSwap::AssetToCurrency { asset_id, .. } => (asset_id.clone(), None),
+                DelegatedSwap::AssetToAsset {
+                    sold_asset_id,
+                    bought_asset_id,
+                    ..
+                } => (sold_asset_id.clone(), Some(bought_asset_id.clone())),
+            };
+            let key = (operator, sold_asset_id);
+            let approval = Self::get_approval(&owner, &key)?;
+            ensure!(
+                approval.bought_asset_id == bought_asset_id,
+                Error::<T>::BoughtAssetNotApproved
+            );
+            let (sold_amount, bought_amount) = match swap {
+                DelegatedSwap::AssetToCurrency { asset_id, amount } => {
+                    let (sold_amount, bought_amount) = Self::do_asset_to_currency(
+                        owner.clone(),
+                        owner.clone(),
+                        asset_id,
+                        amount,
+                        deadline,
+                        None,
+                    )?;
+                    (sold_amount, T::currency_to_asset(bought_amount)?)
+                }
+                DelegatedSwap::AssetToAsset {
+                    sold_asset_id,
+                    bought_asset_id,
+                    amount,
+                } => Self::do_asset_to_asset(
+                    owner.clone(),
+                    owner.clone(),
+                    sold_asset_id,
+                    bought_asset_id,
+                    amount,
+                    deadline,
+                    false,
+                    None,
+                )?,
+            };
+            ensure!(
+                bought_amount >= approval.min_price.saturating_mul_int(sold_amount),
+                Error::<T>::PriceBelowApproved
+            );
+            // The extrinsic is transactional, so the trade is reverted if the allowance is exceeded
+            Self::use_allowance(&owner, &key, approval, sold_amount)
+        }
+
+        /// Seed an existing exchange with liquidity positions from an off-chain snapshot, e.g. when
+        /// migrating liquidity from a previous deployment. The `source` account holding the migrated
+        /// funds must have approved seeding the exchange (see `approve_seeding`). For each deposit,
+        /// currency and tokens are transferred from `source` to the exchange and liquidity is minted
+        /// to the depositor (proportionally to the deposited currency, as in `add_liquidity`). As in
+        /// `add_liquidity`, the tokens are deposited at the current ratio of the reserves, so seeding
+        /// doesn't move the price of the exchange, and the token amount of a deposit is only
+        /// the maximum transferred. A snapshot is seeded
+        /// in batches: `start_index` must equal the number of deposits already seeded into the exchange,
+        /// so a batch can't be seeded twice and the migration can be resumed from the next batch.
+        /// Emit `LiquidityAdded` event for each deposit and `ExchangeSeeded` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist.
+        ///   * `source` – The account holding the migrated currency and tokens.
+        ///   * `start_index` – Index of the first deposit of the batch in the snapshot.
+        ///   * `deposits` – The batch of deposits (depositor, currency amount, maximum token amount),
+        ///     at most `MaxSeedDeposits`.
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `ExchangeDelisted` – The exchange is delisted.
+        ///   * `SeedingNotApproved` – The `source` account didn't approve seeding the exchange.
+        ///   * `SeedIndexMismatch` – Specified `start_index` doesn't match the number of deposits
+        ///     already seeded into the exchange.
+        ///   * `CurrencyAmountIsZero` – Currency amount of a deposit equals 0.
+        ///   * `TokenAmountIsZero` – Token amount of a deposit equals 0.
+        ///   * `MaxTokensTooLow` – Token amount of a deposit is lower than the amount required at the current
+        ///     ratio of the reserves.
+        ///   * `BalanceTooLow` – The `source` account doesn't hold enough currency.
+        ///   * `NotEnoughTokens` – The `source` account doesn't hold enough tokens.
+        #[pallet::weight((
+            <T as Config>::WeightInfo::force_seed_exchange(deposits.len() as u32),
+            DispatchClass::Operational,
+        ))]
+        pub fn force_seed_exchange(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            source: AccountIdOf<T>,
+            start_index: u32,
+            deposits: SeedDepositsOf<T>,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            Self::get_exchange(&asset_id)?;
+            Self::check_not_delisted(&asset_id)?;
+            ensure!(
+                <SeedingApprovals<T>>::contains_key(&source, &asset_id),
+                Error::<T>::SeedingNotApproved
+            );
+            let seeded = <SeededDeposits<T>>::get(asset_id.clone());
+            ensure!(start_index == seeded, Error::<T>::SeedIndexMismatch);
+
+            // ---------------------------- Seed deposits ----------------------------
+            for (depositor, currency_amount, max_tokens) in deposits.iter().cloned() {
+                ensure!(currency_amount > Zero::zero(), Error::<T>::CurrencyAmountIsZero);
+                ensure!(max_tokens > Zero::zero(), Error::<T>::TokenAmountIsZero);
+                let exchange = Self::get_exchange(&asset_id)?;
+                let (token_amount, liquidity_minted) =
+                    Self::get_added_liquidity(&exchange, currency_amount)?;
+                ensure!(token_amount <= max_tokens, Error::<T>::MaxTokensTooLow);
+                Self::check_enough_currency(&source, &currency_amount)?;
+                Self::check_enough_tokens(&asset_id, &source, &token_amount)?;
+                Self::do_add_liquidity(
+                    exchange,
+                    currency_amount,
+                    token_amount,
+                    liquidity_minted,
+                    source.clone(),
+                    depositor,
+                )?;
+            }
+            let seeded = seeded.saturating_add(deposits.len() as u32);
+            <SeededDeposits<T>>::insert(asset_id.clone(), seeded);
+            Self::deposit_event(Event::ExchangeSeeded(asset_id, seeded));
+            Ok(())
+        }
+
+        /// Emergency exit: burn liquidity tokens for a pro-rata share of the balances actually held
+        /// for the exchange, ignoring the stored token reserve. Only allowed if the exchange is
+        /// suspended (see `SafetyOracle` and `force_pause_many`) or its invariants are broken (see
+        /// `do_try_state_per_exchange`), so LPs can exit even if reserve bookkeeping is corrupted.
+        /// The tokens are paid from the asset balance of the pallet account (tokens escrowed by
+        /// liquidity streams are held by the stream escrow sub-account). Currency of all exchanges is
+        /// held by the same pallet account, so the currency is paid from the stored currency reserve,
+        /// capped by the pallet account balance left after the currency reserves of the other
+        /// exchanges (an inflated reserve is never paid with the currency of other exchanges).
+        /// Neither the trade-only mode nor the retention floor of the exchange apply, so they can't
+        /// trap liquidity providers in an emergency.
+        /// Emit `UnderlyingRedeemed` event on success. If all the liquidity is redeemed, remove
+        /// the exchange (see `ExchangeRemoved` event).
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist.
+        ///   * `liquidity_amount` – The amount of liquidity tokens to be burned. Must be greater than 0.
+        ///   * `exchanges` – Upper bound of the number of exchanges (determines the call weight).
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `NotInEmergency` – The exchange is neither suspended nor are its invariants broken.
+        ///   * `LiquidityAmountIsZero` – Specified `liquidity_amount` equals 0.
+        ///   * `ProviderLiquidityTooLow` – Specified `liquidity_amount` is greater than the liquidity
+        ///     owned by the caller.
+        ///   * `LiquidityFrozen` – Specified `liquidity_amount` is greater than the liquidity owned
+        ///     by the caller which is not frozen.
+        ///   * `WrongExchangeCount` – Specified `exchanges` is lower than the number of exchanges.
+        #[pallet::weight(<T as Config>::WeightInfo::redeem_underlying(*exchanges))]
+        pub fn redeem_underlying(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            liquidity_amount: AssetBalanceOf<T>,
+            exchanges: u32,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            let caller = ensure_signed(origin)?;
+            let mut exchange = Self::get_exchange(&asset_id)?;
+            ensure!(
+                Self::is_suspended(&asset_id)
+                    || Self::do_try_state_per_exchange(&asset_id).is_err(),
+                Error::<T>::NotInEmergency
+            );
+            ensure!(liquidity_amount > Zero::zero(), Error::<T>::LiquidityAmountIsZero);
+            Self::check_enough_liquidity_owned(&exchange, &caller, &liquidity_amount)?;
+            let other_reserves = Self::get_other_currency_reserves(&asset_id, exchanges)?;
+
+            // ------------------ Pro-rata share of the balances -------------------
+            let pallet_account = T::pallet_account();
+            let token_balance = T::Assets::balance(asset_id.clone(), &pallet_account);
+            let currency_balance = exchange.currency_reserve.min(
+                <T as Config>::Currency::free_balance(&pallet_account)
+                    .saturating_sub(other_reserves),
+            );
+            let total_liquidity =
+                T::AssetRegistry::total_issuance(exchange.liquidity_token_id.clone());
+            let share = FixedU128::saturating_from_rational(liquidity_amount, total_liquidity);
+            let currency_amount = T::asset_to_currency(
+                share.saturating_mul_int(T::currency_to_asset(currency_balance)?),
+            )?;
+            let token_amount = share.saturating_mul_int(token_balance);
+
+            // --------------------- Currency & token transfer ---------------------
+            T::AssetRegistry::burn_from(
+                exchange.liquidity_token_id.clone(),
+                &caller,
+                liquidity_amount,
+            )?;
+            <T as pallet::Config>::Currency::transfer(
+                &pallet_account,
+                &caller,
+                currency_amount,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            T::Assets::transfer(asset_id.clone(), &pallet_account, &caller, token_amount, false)?;
+
+            // -------------------------- Balances update --------------------------
+            let liquidity_token_id = exchange.liquidity_token_id.clone();
+            exchange.currency_reserve.saturating_reduce(currency_amount);
+            exchange.token_reserve.saturating_reduce(token_amount);
+            #[cfg(feature = "reserve-trace")]
+            Self::record_reserve_checkpoint(&exchange, Zero::zero(), Zero::zero());
+            <Exchanges<T>>::insert(asset_id.clone(), exchange);
+            <ReservesUpdatedAt<T>>::insert(
+                asset_id.clone(),
+                <frame_system::Pallet<T>>::block_number(),
+            );
+            Self::deposit_event(Event::UnderlyingRedeemed(
+                caller,
+                asset_id.clone(),
+                currency_amount,
+                token_amount,
+                liquidity_amount,
+            ));
+
+            // ------------------- Remove exchange without liquidity ---------------
+            if T::AssetRegistry::total_issuance(liquidity_token_id.clone()).is_zero() {
+                Self::do_remove_exchange(asset_id, liquidity_token_id)?;
+            }
+            Ok(())
+        }
+
+        /// Set the non-refundable fee charged for creating an exchange, deterring pool spam
+        /// and squatting. The fee is paid to `FeeDestination`, in addition to the initial
+        /// liquidity. Emit `CreationFeeSet` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `creation_fee` – The fee charged for creating an exchange. 0 disables the fee.
+        #[pallet::weight(<T as Config>::WeightInfo::set_creation_fee())]
+        pub fn set_creation_fee(
+            origin: OriginFor<T>,
+            creation_fee: BalanceOf<T>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            <CreationFee<T>>::put(creation_fee);
+            Self::deposit_event(Event::CreationFeeSet(creation_fee));
+            Ok(())
+        }
+
+        /// Add and remove liquidity across multiple exchanges atomically, e.g. for rebalancing
+        /// a treasury position across many pools in a single block. Operations are executed
+        /// in order, each on the reserves left by the previous ones, and the whole batch fails
+        /// if any of the operations fails. Emit `LiquidityBatchExecuted` event, followed by
+        /// `LegStarted` event and `LiquidityAdded` or `LiquidityRemoved` event for each operation
+        /// (and `ExchangeRemoved` event for each exchange left without liquidity).
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `ops` – The operations, at most `MaxLiquidityOps`, with the same parameters as
+        ///     `add_liquidity` (without a price limit) or `remove_liquidity`.
+        ///   * `deadline` – (Optional) number of the last block in which the transaction can be included.
+        ///     Defaults to `DefaultDeadline` blocks after the current block.
+        ///
+        /// **Errors:**
+        ///   * `DeadlinePassed` – Specified `deadline` is lower than the current block number.
+        ///   * `DeadlineTooFar` – Specified `deadline` is more than `MaxDeadline` blocks after the current block.
+        ///   * Same as `add_liquidity` or `remove_liquidity`, for the first failed operation.
+        #[pallet::weight(Pallet::<T>::batch_liquidity_weight(ops))]
+        pub fn batch_liquidity(
+            origin: OriginFor<T>,
+            ops: LiquidityOpsOf<T>,
+            deadline: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            Self::check_deadline(&deadline)?;
+            Self::deposit_event(Event::LiquidityBatchExecuted(caller.clone(), ops.len() as u32));
+            for (index, op) in ops.into_iter().enumerate() {
+                Self::deposit_event(Event::LegStarted(index as u32));
+                match op {
+                    LiquidityOp::Add {
+                        asset_id,
+                        currency_amount,
+                        min_liquidity,
+                        max_tokens,
+                    } => Self::do_provide_liquidity(
+                        caller.clone(),
+                        asset_id,
+                        currency_amount,
+                        min_liquidity,
+                        max_tokens,
+                        None,
+                    )?,
+                    LiquidityOp::Remove {
+                        asset_id,
+                        liquidity_amount,
+                        min_currency,
+                        min_tokens,
+                    } => Self::do_withdraw_liquidity(
+                        caller.clone(),
+                        asset_id,
+                        liquidity_amount,
+                        min_currency,
+                        min_tokens,
+                    )?,
+                }
+            }
+            Ok(())
+        }
+
+        /// Set (or remove) the liquidity retention floor of an exchange: the liquidity token supply
+        /// below which liquidity can't be withdrawn (by `remove_liquidity` or `batch_liquidity`;
+        /// the `redeem_underlying` emergency exit is exempt). Used during incident containment to prevent a bank run from
+        /// draining the pool mid-investigation. Emit `RetentionFloorSet` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist,
+        ///     unless the floor is removed.
+        ///   * `floor` – The minimum liquidity token supply. `None` removes the floor.
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        #[pallet::weight((<T as Config>::WeightInfo::set_retention_floor(), DispatchClass::Operational))]
+        pub fn set_retention_floor(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            floor: Option<AssetBalanceOf<T>>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            // Removing the floor must never fail
+            if floor.is_some() {
+                Self::get_exchange(&asset_id)?;
+            }
+            <RetentionFloors<T>>::set(asset_id.clone(), floor);
+            Self::deposit_event(Event::RetentionFloorSet(asset_id, floor));
+            Ok(())
+        }
+
+        /// Set (or remove) the trade-only mode of an exchange: liquidity can't be added nor removed
+        /// (and liquidity streams are paused) until the given block, while trading continues,
+        /// e.g. during liquidity token migration snapshots. The `redeem_underlying` emergency exit
+        /// is exempt. The mode expires automatically,
+        /// preventing indefinite lockups. Emit `TradeOnlyModeSet` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist,
+        ///     unless the mode is removed.
+        ///   * `until` – Number of the last block in which liquidity is frozen, at most `MaxTradeOnlyPeriod`
+        ///     blocks after the current block. `None` removes the mode.
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `InvalidTradeOnlyExpiry` – Specified `until` is lower than the current block number or more than
+        ///     `MaxTradeOnlyPeriod` blocks after the current block.
+        #[pallet::weight((<T as Config>::WeightInfo::set_trade_only_mode(), DispatchClass::Operational))]
+        pub fn set_trade_only_mode(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            until: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            // Removing the mode must never fail
+            if let Some(until) = until {
+                Self::get_exchange(&asset_id)?;
+                let now = <frame_system::Pallet<T>>::block_number();
+                ensure!(
+                    until >= now && until <= now.saturating_add(T::MaxTradeOnlyPeriod::get()),
+                    Error::<T>::InvalidTradeOnlyExpiry
+                );
+            }
+            <TradeOnlyUntil<T>>::set(asset_id.clone(), until);
+            Self::deposit_event(Event::TradeOnlyModeSet(asset_id, until));
+            Ok(())
+        }
+
+        /// Delist an exchange: trading and adding liquidity are blocked immediately, and liquidity
+        /// providers have `DelistingGracePeriod` blocks to withdraw their liquidity. After the grace
+        /// period, remaining reserves can be swept to `RecoveryAccount` with `sweep_delisted_exchange`.
+        /// Emit `DelistingStarted` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist.
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `ExchangeDelisted` – The exchange is already delisted.
+        #[pallet::weight((<T as Config>::WeightInfo::delist_exchange(), DispatchClass::Operational))]
+        pub fn delist_exchange(origin: OriginFor<T>, asset_id: AssetIdOf<T>) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            Self::get_exchange(&asset_id)?;
+            Self::check_not_delisted(&asset_id)?;
+            let grace_end = <frame_system::Pallet<T>>::block_number()
+                .saturating_add(T::DelistingGracePeriod::get());
+            <Delistings<T>>::insert(asset_id.clone(), grace_end);
+            Self::deposit_event(Event::DelistingStarted(asset_id, grace_end));
+            Ok(())
+        }
+
+        /// Sweep the remaining reserves of a delisted exchange to `RecoveryAccount` after its grace
+        /// period, and remove the exchange. The liquidity token is not destroyed, so the liquidity
+        /// tokens not withdrawn (e.g. frozen as collateral) stay with their holders.
+        /// Can be called by anyone. Emit `DelistedExchangeSwept` and `ExchangeRemoved` events on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed.
+        ///   * `asset_id` – ID of the asset traded on the delisted exchange.
+        ///
+        /// **Errors:**
+        ///   * `NotDelisted` – The exchange is not delisted.
+        ///   * `GracePeriodNotOver` – The current block is not after the end of the grace period.
+        #[pallet::weight(<T as Config>::WeightInfo::sweep_delisted_exchange())]
+        pub fn sweep_delisted_exchange(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            ensure_signed(origin)?;
+            let grace_end =
+                <Delistings<T>>::get(asset_id.clone()).ok_or(Error::<T>::NotDelisted)?;
+            ensure!(
+                <frame_system::Pallet<T>>::block_number() > grace_end,
+                Error::<T>::GracePeriodNotOver
+            );
+            let exchange = Self::get_exchange(&asset_id)?;
+
+            // ------------------------------ Sweep --------------------------------
+            let pallet_account = T::pallet_account();
+            let recovery_account = T::RecoveryAccount::get();
+            <T as pallet::Config>::Currency::transfer(
+                &pallet_account,
+                &recovery_account,
+                exchange.currency_reserve,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            T::Assets::transfer(
+                asset_id.clone(),
+                &pallet_account,
+                &recovery_account,
+                exchange.token_reserve,
+                false,
+            )?;
+            Self::deposit_event(Event::DelistedExchangeSwept(
+                asset_id.clone(),
+                recovery_account,
+                exchange.currency_reserve,
+                exchange.token_reserve,
+            ));
+            Self::purge_exchange(asset_id, exchange.liquidity_token_id)
+        }
+
+        /// Set (or remove) the peg reference of an exchange for an asset pegged to the currency
+        /// (e.g. the canonical stable asset of the chain). If the exchange liquidity is too thin,
+        /// `quote_with_fallback` quotes the pegged price (plus or minus the band) instead of
+        /// extrapolating the AMM price. Trades are not affected. Emit `PegReferenceSet` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist,
+        ///     unless the peg reference is removed.
+        ///   * `peg` – The pegged price, its band and the currency reserve below which it is quoted.
+        ///     `None` removes the peg reference.
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `InvalidPegReference` – The pegged price is zero or the band is not lower than 100%.
+        #[pallet::weight(<T as Config>::WeightInfo::set_peg_reference())]
+        pub fn set_peg_reference(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            peg: Option<PegReferenceOf<T>>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            // Removing the peg reference must never fail
+            if let Some(peg) = &peg {
+                Self::get_exchange(&asset_id)?;
+                ensure!(
+                    peg.price.into_inner() > 0 && peg.band < Permill::from_percent(100),
+                    Error::<T>::InvalidPegReference
+                );
+            }
+            <PegReferences<T>>::set(asset_id.clone(), peg.clone());
+            Self::deposit_event(Event::PegReferenceSet(asset_id, peg));
+            Ok(())
+        }
+
+        /// Pause trading on multiple exchanges at once, e.g. during an incident affecting several
+        /// assets (like a compromised bridge minting them). Paused exchanges reject trades with
+        /// `ExchangeSuspended`, like exchanges suspended by the safety oracle, and their liquidity
+        /// can be redeemed with `redeem_underlying`. Emit `ExchangePaused` event for each exchange.
+        /// Assets without an exchange (e.g. removed since the call was submitted) are skipped, so the
+        /// other exchanges are still paused in an emergency: emit `PauseSkipped` event for each.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_ids` – IDs of the assets traded on the exchanges to pause (at most
+        ///     `MaxPausedPerCall`).
+        #[pallet::weight((
+            <T as Config>::WeightInfo::force_pause_many(asset_ids.len() as u32),
+            DispatchClass::Operational,
+        ))]
+        pub fn force_pause_many(
+            origin: OriginFor<T>,
+            asset_ids: PausedAssetsOf<T>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            for asset_id in asset_ids {
+                if !<Exchanges<T>>::contains_key(asset_id.clone()) {
+                    Self::deposit_event(Event::PauseSkipped(asset_id));
+                    continue;
+                }
+                <PausedExchanges<T>>::insert(asset_id.clone(), ());
+                Self::deposit_event(Event::ExchangePaused(asset_id));
+            }
+            Ok(())
+        }
+
+        /// Unpause trading on multiple exchanges paused by `force_pause_many`.
+        /// Emit `ExchangeUnpaused` event for each exchange. Exchanges which aren't paused (e.g.
+        /// already unpaused, or never paused) are skipped: emit `UnpauseSkipped` event for each.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_ids` – IDs of the assets traded on the exchanges to unpause (at most
+        ///     `MaxPausedPerCall`).
+        #[pallet::weight((
+            <T as Config>::WeightInfo::force_unpause_many(asset_ids.len() as u32),
+            DispatchClass::Operational,
+        ))]
+        pub fn force_unpause_many(
+            origin: OriginFor<T>,
+            asset_ids: PausedAssetsOf<T>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            // Unpausing must never fail
+            for asset_id in asset_ids {
+                if <PausedExchanges<T>>::take(asset_id.clone()).is_none() {
+                    Self::deposit_event(Event::UnpauseSkipped(asset_id));
+                    continue;
+                }
+                Self::deposit_event(Event::ExchangeUnpaused(asset_id));
+            }
+            Ok(())
+        }
+
+        /// Register (or remove) the fee share of an origin account, e.g. the account of another
+        /// pallet hedging through the exchanges, under a revenue-sharing agreement. After each trade
+        /// of the origin account, the share of the provider fee it paid is credited to the
+        /// beneficiary, out of the reserves of the exchange. Emit `FeeShareSet` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `origin_account` – The account dispatching the trades (e.g. derived from the `PalletId`
+        ///     of the origin pallet).
+        ///   * `fee_share` – The beneficiary and the share of the provider fee credited to it.
+        ///     `None` removes the fee share.
+        ///
+        /// **Errors:**
+        ///   * `FeeShareIsZero` – The share of the fee share equals 0.
+        #[pallet::weight(<T as Config>::WeightInfo::set_fee_share())]
+        pub fn set_fee_share(
+            origin: OriginFor<T>,
+            origin_account: AccountIdOf<T>,
+            fee_share: Option<FeeShareOf<T>>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            // Removing the fee share must never fail
+            if let Some(fee_share) = &fee_share {
+                ensure!(!fee_share.share.is_zero(), Error::<T>::FeeShareIsZero);
+            }
+            <FeeShares<T>>::set(&origin_account, fee_share.clone());
+            Self::deposit_event(Event::FeeShareSet(origin_account, fee_share));
+            Ok(())
+        }
+
+        /// Set the curve pricing the trades of an exchange (e.g. a StableSwap curve for an asset
+        /// pegged to the currency), or reset it to the default curve. Liquidity is added and
+        /// removed in proportion to the reserves whatever the curve. Changing the curve must not
+        /// move the spot price of the exchange, else arbitrageurs would trade the reserves back to
+        /// the new price at the expense of the liquidity providers: e.g. a StableSwap curve can only
+        /// be set on a balanced exchange. The yield checkpoints of the exchange are cleared, as the
+        /// liquidity values of different curves can't be compared (see `lp_apy`).
+        /// Emit `CurveSet` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be `GovernanceOrigin`.
+        ///   * `asset_id` – ID of the asset traded on the exchange. An exchange for this asset must exist,
+        ///     unless the curve is reset.
+        ///   * `curve` – The curve and its parameters. `None` resets the curve to the default one.
+        ///
+        /// **Errors:**
+        ///   * `ExchangeNotFound` – There is no exchange for the given `asset_id`.
+        ///   * `InvalidCurve` – The parameters of the curve are out of range.
+        ///   * `CurveMovesPrice` – The spot price of the exchange on the curve differs from the current one.
+        #[pallet::weight(<T as Config>::WeightInfo::set_curve())]
+        pub fn set_curve(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            curve: Option<T::Curve>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            if let Some(curve) = &curve {
+                Self::get_exchange(&asset_id)?;
+                ensure!(curve.is_valid(), Error::<T>::InvalidCurve);
+            }
+            // Resetting the curve of a removed exchange must never fail
+            if let Some(exchange) = <Exchanges<T>>::get(&asset_id) {
+                // An empty exchange has no price to move
+                if !exchange.currency_reserve.is_zero() && !exchange.token_reserve.is_zero() {
+                    let spot_price = curve.clone().unwrap_or_default().spot_price(
+                        exchange.currency_reserve,
+                        T::asset_to_currency(exchange.token_reserve)?,
+                    )?;
+                    ensure!(
+                        Price::from_inner(spot_price) == Self::get_curve_spot_price(&exchange)?,
+                        Error::<T>::CurveMovesPrice
+                    );
+                }
+                <YieldCheckpoints<T>>::remove(&asset_id);
+            }
+            match &curve {
+                Some(curve) => <Curves<T>>::insert(asset_id.clone(), curve.clone()),
+                None => <Curves<T>>::remove(asset_id.clone()),
+            }
+            Self::deposit_event(Event::CurveSet(asset_id, curve));
+            Ok(())
+        }
+
+        /// Approve seeding the exchange of `asset_id` with the caller's currency and tokens by
+        /// `force_seed_exchange`, e.g. as the holder of the funds migrated from a previous deployment.
+        /// `StorageDeposit` is reserved from the caller for a new approval, until it is revoked.
+        /// Emit `SeedingApproved` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed by the account holding the migrated funds.
+        ///   * `asset_id` – ID of the asset traded on the exchange.
+        ///
+        /// **Errors:**
+        ///   * `BalanceTooLow` – The caller doesn't have enough currency to reserve the storage deposit.
+        #[pallet::weight(<T as Config>::WeightInfo::approve_seeding())]
+        pub fn approve_seeding(origin: OriginFor<T>, asset_id: AssetIdOf<T>) -> DispatchResult {
+            let source = ensure_signed(origin)?;
+            Self::hold_storage_deposit(&source, DepositItem::SeedingApproval(asset_id.clone()))?;
+            <SeedingApprovals<T>>::insert(&source, asset_id.clone(), ());
+            Self::deposit_event(Event::SeedingApproved(source, asset_id));
+            Ok(())
+        }
+
+        /// Revoke the approval of seeding the exchange of `asset_id` with the caller's funds, releasing
+        /// its storage deposit. Emit `SeedingRevoked` event on success.
+        ///
+        /// **Parameters:**
+        ///   * `origin` – Origin for the call. Must be signed by the account which approved seeding.
+        ///   * `asset_id` – ID of the asset traded on the exchange.
+        ///
+        /// **Errors:**
+        ///   * `SeedingNotApproved` – The caller didn't approve seeding the exchange.
+        #[pallet::weight(<T as Config>::WeightInfo::revoke_seeding())]
+        pub fn revoke_seeding(origin: OriginFor<T>, asset_id: AssetIdOf<T>) -> DispatchResult {
+            let source = ensure_signed(origin)?;
+            ensure!(
+                <SeedingApprovals<T>>::contains_key(&source, &asset_id),
+                Error::<T>::SeedingNotApproved
+            );
+            <SeedingApprovals<T>>::remove(&source, &asset_id);
+            Self::release_storage_deposit(&source, DepositItem::SeedingApproval(asset_id.clone()));
+            Self::deposit_event(Event::SeedingRevoked(source, asset_id));
+            Ok(())
+        }
+    }
+
+    impl<T> From<pricing::PriceError> for Error<T> {
+        fn from(err: pricing::PriceError) -> Self {
+            match err {
+                pricing::PriceError::NotEnoughLiquidity => Error::NotEnoughLiquidity,
+                pricing::PriceError::Overflow => Error::Overflow,
+            }
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        pub(crate) fn get_exchange(asset_id: &AssetIdOf<T>) -> Result<ExchangeOf<T>, Error<T>> {
+            <Exchanges<T>>::get(asset_id.clone()).ok_or(Error::<T>::ExchangeNotFound)
+        }
+
+        /// Get the ID of the liquidity token of the exchange for `asset_id` (if it exists).
+        pub fn get_liquidity_token_id(asset_id: &AssetIdOf<T>) -> Option<AssetIdOf<T>> {
+            <Exchanges<T>>::get(asset_id.clone()).map(|exchange| exchange.liquidity_token_id)
+        }
+
+        /// Get the ID of the asset traded on the exchange with `liquidity_token_id` liquidity token
+        /// (if such exchange exists).
+        pub fn get_asset_id_by_liquidity_token(
+            liquidity_token_id: &AssetIdOf<T>,
+        ) -> Option<AssetIdOf<T>> {
+            <LiquidityTokens<T>>::get(liquidity_token_id.clone())
+        }
+
+        /// Get the sub-accounts of the exchange for `asset_id` (one per [`PoolSubsystem`]), e.g. for
+        /// explorers to label them. The accounts are derived whether or not the exchange exists.
+        pub fn get_pool_accounts(asset_id: &AssetIdOf<T>) -> Vec<(PoolSubsystem, AccountIdOf<T>)> {
+            PoolSubsystem::ALL
+                .into_iter()
+                .map(|subsystem| (subsystem, T::pool_account(asset_id, subsystem)))
+                .collect()
+        }
+
+        /// Get all registered market makers and their parameters.
+        pub fn get_market_makers() -> Vec<(AccountIdOf<T>, MarketMakerOf<T>)> {
+            <MarketMakers<T>>::iter().collect()
+        }
+
+        /// Get the payload a market maker signs for `quote`: the SCALE-encoded
+        /// `(b"dex/rfq", genesis_hash, quote)`, so that a signed quote can't be replayed on another
+        /// chain, or by another pallet verifying signatures of the same keys.
+        pub fn rfq_signing_payload(quote: &RfqQuoteOf<T>) -> Vec<u8> {
+            let genesis_hash = <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero());
+            (RFQ_SIGNING_CONTEXT, genesis_hash, quote).encode()
+        }
+
+        /// Check if `account_id` is a registered market maker exempt from fees. Meant for runtime
+        /// components charging fees (e.g. a transaction payment adapter).
+        pub fn is_fee_exempt(account_id: &AccountIdOf<T>) -> bool {
+            <MarketMakers<T>>::get(account_id).map_or(false, |market_maker| market_maker.fee_exempt)
+        }
+
+        /// Validate parameters and add `provider`'s liquidity to the exchange
+        fn do_provide_liquidity(
+            provider: AccountIdOf<T>,
+            asset_id: AssetIdOf<T>,
+            currency_amount: BalanceOf<T>,
+            min_liquidity: AssetBalanceOf<T>,
+            max_tokens: AssetBalanceOf<T>,
+            price_limit: Option<PriceLimitOf<T>>,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            ensure!(currency_amount > Zero::zero(), Error::<T>::CurrencyAmountIsZero);
+            ensure!(max_tokens > Zero::zero(), Error::<T>::MaxTokensIsZero);
+            ensure!(min_liquidity > Zero::zero(), Error::<T>::MinLiquidityIsZero);
+            Self::check_enough_currency(&provider, &currency_amount)?;
+            Self::check_enough_tokens(&asset_id, &provider, &max_tokens)?;
+            let exchange = Self::get_exchange(&asset_id)?;
+            Self::check_whitelisted(&asset_id, &provider)?;
+            Self::check_not_trade_only(&asset_id)?;
+            Self::check_not_delisted(&asset_id)?;
+
+            // -------------------- Token/liquidity computation --------------------
+            if let Some(price_limit) = price_limit {
+                Self::check_price_limit(&price_limit, &exchange)?;
+            }
+            let (token_amount, liquidity_minted) =
+                Self::get_added_liquidity(&exchange, currency_amount)?;
+            ensure!(token_amount <= max_tokens, Error::<T>::MaxTokensTooLow);
+            ensure!(liquidity_minted >= min_liquidity, Error::<T>::MinLiquidityTooHigh);
+
+            // ----------------------------- State update ----------------------------
+            Self::do_add_liquidity(
+                exchange,
+                currency_amount,
+                token_amount,
+                liquidity_minted,
+                provider.clone(),
+                provider,
+            )
+        }
+
+        /// Validate parameters and remove `provider`'s liquidity from the exchange
+        fn do_withdraw_liquidity(
+            provider: AccountIdOf<T>,
+            asset_id: AssetIdOf<T>,
+            liquidity_amount: AssetBalanceOf<T>,
+            min_currency: BalanceOf<T>,
+            min_tokens: AssetBalanceOf<T>,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            ensure!(liquidity_amount > Zero::zero(), Error::<T>::LiquidityAmountIsZero);
+            ensure!(min_currency > Zero::zero(), Error::<T>::MinCurrencyIsZero);
+            ensure!(min_tokens > Zero::zero(), Error::<T>::MinTokensIsZero);
+            let exchange = Self::get_exchange(&asset_id)?;
+            Self::check_enough_liquidity_owned(&exchange, &provider, &liquidity_amount)?;
+            Self::check_retention_floor(&exchange, &liquidity_amount)?;
+            Self::check_not_trade_only(&asset_id)?;
+
+            // --------------- Withdrawn currency/tokens computation ---------------
+            let (currency_amount, token_amount) =
+                Self::get_withdrawn_amounts(&exchange, &liquidity_amount)?;
+            ensure!(currency_amount >= min_currency, Error::<T>::MinCurrencyTooHigh);
+            ensure!(token_amount >= min_tokens, Error::<T>::MinTokensTooHigh);
+
+            // ----------------------------- State update ----------------------------
+            Self::do_remove_liquidity(
+                exchange,
+                currency_amount,
+                token_amount,
+                liquidity_amount,
+                provider,
+            )
+        }
+
+        /// Weight of a `batch_liquidity` call, i.e. the sum of the weights of its operations
+        fn batch_liquidity_weight(ops: &[LiquidityOpOf<T>]) -> Weight {
+            ops.iter().fold(Weight::zero(), |weight, op| {
+                weight.saturating_add(match op {
+                    LiquidityOp::Add { .. } => <T as Config>::WeightInfo::add_liquidity(),
+                    LiquidityOp::Remove { .. } => <T as Config>::WeightInfo::remove_liquidity(),
+                })
+            })
+        }
+
+        /// Validate parameters, create liquidity token, deposit initial liquidity,
+        /// emit event, notify `OnPoolCreated` handler
+        fn do_create_exchange(
+            caller: AccountIdOf<T>,
+            asset_id: AssetIdOf<T>,
+            liquidity_token_id: AssetIdOf<T>,
+            currency_amount: BalanceOf<T>,
+            token_amount: AssetBalanceOf<T>,
+            migration: bool,
+        ) -> DispatchResult {
+            // -------------------------- Validation part --------------------------
+            ensure!(currency_amount >= T::MinDeposit::get(), Error::<T>::CurrencyAmountTooLow);
+            ensure!(token_amount > Zero::zero(), Error::<T>::TokenAmountIsZero);
+            if T::Assets::total_issuance(asset_id.clone()).is_zero() {
+                Err(Error::<T>::AssetNotFound)?
+            }
+            if <Exchanges<T>>::contains_key(asset_id.clone()) {
+                Err(Error::<T>::ExchangeAlreadyExists)?
+            }
+            ensure!(
+                !<LiquidityTokens<T>>::contains_key(liquidity_token_id.clone()),
+                Error::<T>::TokenIdTaken
+            );
+            let liquidity_supply = T::AssetRegistry::total_issuance(liquidity_token_id.clone());
+            let mut liquidity_minted = T::currency_to_asset(currency_amount)?;
+            if migration {
+                ensure!(
+                    T::MigratableLiquidityTokens::contains(&liquidity_token_id)
+                        && !liquidity_supply.is_zero(),
+                    Error::<T>::LiquidityTokenNotMigratable
+                );
+                ensure!(
+                    liquidity_token_id != asset_id
+                        && !<Exchanges<T>>::contains_key(liquidity_token_id.clone()),
+                    Error::<T>::LiquidityTokenIsTraded
+                );
+                // Any excess would be gifted to the existing holders, any shortfall diluting them
+                ensure!(
+                    liquidity_minted == liquidity_supply,
+                    Error::<T>::MigrationReservesMismatch
+                );
+                // Existing holders own the deposited reserves
+                liquidity_minted = Zero::zero();
+            } else {
+                ensure!(liquidity_supply.is_zero(), Error::<T>::LiquidityTokenHasHolders);
+            }
+            let creation_fee = <CreationFee<T>>::get();
+            Self::check_enough_currency(&caller, &currency_amount.saturating_add(creation_fee))?;
+
+            // ------------------------ Pay the creation fee -----------------------
+            if !creation_fee.is_zero() {
+                <T as pallet::Config>::Currency::transfer(
+                    &caller,
+                    &T::FeeDestination::get(),
+                    creation_fee,
+                    ExistenceRequirement::KeepAlive,
+                )?;
+            }
+
+            // ----------------------- Create liquidity token ----------------------
+            if !migration {
+                T::AssetRegistry::create(
+                    liquidity_token_id.clone(),
+                    T::pallet_account(),
+                    false,
+                    <AssetBalanceOf<T>>::one(),
+                )
+                .map_err(|_| Error::<T>::TokenIdTaken)?;
+            }
+
+            // -------------------------- Update storage ---------------------------
+            let exchange = Exchange {
+                asset_id: asset_id.clone(),
+                currency_reserve: <BalanceOf<T>>::zero(),
+                token_reserve: <AssetBalanceOf<T>>::zero(),
+                liquidity_token_id: liquidity_token_id.clone(),
+            };
+            Self::do_add_liquidity(
+                exchange,
+                currency_amount,
+                token_amount,
+                liquidity_minted,
+                caller.clone(),
+                caller.clone(),
+            )?;
+            <LiquidityTokens<T>>::insert(liquidity_token_id.clone(), asset_id.clone());
+
+            // ------------------------ Emit event & notify ------------------------
+            Self::deposit_event(Event::ExchangeCreated(
+                asset_id.clone(),
+                liquidity_token_id.clone(),
+                creation_fee,
+            ));
+            T::OnPoolCreated::on_pool_created(&asset_id, &liquidity_token_id, &caller);
+            Ok(())
+        }
+
+        /// Sell `seller`'s asset for currency transferred to `recipient`, return the sold token amount
+        /// and the bought currency amount
+        fn do_asset_to_currency(
+            seller: AccountIdOf<T>,
+            recipient: AccountIdOf<T>,
+            asset_id: AssetIdOf<T>,
+            amount: TradeAmount<AssetBalanceOf<T>, BalanceOf<T>>,
+            deadline: Option<T::BlockNumber>,
+            donation: Option<DonationOf<T>>,
+        ) -> Result<(AssetBalanceOf<T>, BalanceOf<T>), DispatchError> {
+            // -------------------------- Validation part --------------------------
+            Self::check_deadline(&deadline)?;
+            Self::check_trade_amount(&amount)?;
+            Self::check_not_suspended(&asset_id)?;
+            Self::check_not_delisted(&asset_id)?;
+            Self::check_whitelisted(&asset_id, &seller)?;
+            let exchange = Self::get_exchange(&asset_id)?;
+
+            // --------------------------- Compute price ---------------------------
+            let (currency_amount, token_amount) =
+                Self::get_asset_to_currency_price(&exchange, amount)?;
+            Self::check_token_trade_size(&exchange, &token_amount)?;
+            Self::check_enough_tokens(&asset_id, &seller, &token_amount)?;
+
+            // --------------------------- Perform trade ---------------------------
+            Self::swap_asset_for_currency(
+                exchange,
+                currency_amount,
+                token_amount,
+                seller.clone(),
+                recipient,
+                donation,
+            )?;
+            Self::pay_rebate(
+                &asset_id,
+                &seller,
+                T::WeightToFee::weight_to_fee(&<T as Config>::WeightInfo::asset_to_currency()),
+            );
+            Self::pay_fee_share(&asset_id, &seller, Zero::zero(), token_amount);
+            Ok((token_amount, currency_amount))
+        }
+
+        /// Sell `seller`'s asset for another asset transferred to `recipient`, return the sold token amount
+        /// and the bought token amount
+        #[allow(clippy::too_many_arguments)]
+        fn do_asset_to_asset(
+            seller: AccountIdOf<T>,
+            recipient: AccountIdOf<T>,
+            sold_asset_id: AssetIdOf<T>,
+            bought_asset_id: AssetIdOf<T>,
+            amount: TradeAmount<AssetBalanceOf<T>, AssetBalanceOf<T>>,
+            deadline: Option<T::BlockNumber>,
+            provision_recipient: bool,
+            donation: Option<DonationOf<T>>,
+        ) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+            // -------------------------- Validation part --------------------------
+            Self::check_deadline(&deadline)?;
+            Self::check_trade_amount(&amount)?;
+            Self::check_not_suspended(&sold_asset_id)?;
+            Self::check_not_suspended(&bought_asset_id)?;
+            Self::check_not_delisted(&sold_asset_id)?;
+            Self::check_not_delisted(&bought_asset_id)?;
+            Self::check_whitelisted(&sold_asset_id, &seller)?;
+            Self::check_whitelisted(&bought_asset_id, &seller)?;
+            let sold_asset_exchange = Self::get_exchange(&sold_asset_id)?;
+            let bought_asset_exchange = Self::get_exchange(&bought_asset_id)?;
+            let deposit = Self::get_recipient_deposit(&recipient, provision_recipient);
+
+            // --------------------------- Compute price ---------------------------
+            let (sold_token_amount, currency_amount, bought_token_amount) =
+                Self::get_asset_to_asset_price(
+                    &sold_asset_exchange,
+                    &bought_asset_exchange,
+                    amount,
+                    deposit,
+                )?;
+            let traded_currency_amount = currency_amount.saturating_sub(deposit);
+            Self::check_token_trade_size(&sold_asset_exchange, &sold_token_amount)?;
+            Self::check_currency_trade_size(&bought_asset_exchange, &traded_currency_amount)?;
+            Self::check_enough_tokens(&sold_asset_id, &seller, &sold_token_amount)?;
+
+            // --------------------------- Perform trade ---------------------------
+            Self::swap_asset_for_asset(
+                sold_asset_exchange,
+                bought_asset_exchange,
+                currency_amount,
+                deposit,
+                sold_token_amount,
+                bought_token_amount,
+                seller.clone(),
+                recipient,
+                donation,
+            )?;
+            // Both rebates together are capped at the fee of the trade
+            let fee = T::WeightToFee::weight_to_fee(&<T as Config>::WeightInfo::asset_to_asset());
+            let rebate = Self::pay_rebate(&sold_asset_id, &seller, fee);
+            Self::pay_rebate(&bought_asset_id, &seller, fee.saturating_sub(rebate));
+            Self::pay_fee_share(&sold_asset_id, &seller, Zero::zero(), sold_token_amount);
+            Self::pay_fee_share(&bought_asset_id, &seller, traded_currency_amount, Zero::zero());
+            Ok((sold_token_amount, bought_token_amount))
+        }
+
+        /// Get the unexpired approval of the (operator, asset) `key` to sell the owner's asset
+        fn get_approval(
+            owner: &AccountIdOf<T>,
+            key: &(AccountIdOf<T>, AssetIdOf<T>),
+        ) -> Result<OperatorApprovalOf<T>, Error<T>> {
+            let approval =
+                <OperatorApprovals<T>>::get(owner, key).ok_or(Error::<T>::NotApproved)?;
+            if let Some(expiry) = approval.expiry {
+                let now = <frame_system::Pallet<T>>::block_number();
+                ensure!(now <= expiry, Error::<T>::ApprovalExpired);
+            }
+            Ok(approval)
+        }
+
+        /// Use up `amount` of the operator's allowance to sell the owner's asset
+        fn use_allowance(
+            owner: &AccountIdOf<T>,
+            key: &(AccountIdOf<T>, AssetIdOf<T>),
+            mut approval: OperatorApprovalOf<T>,
+            amount: AssetBalanceOf<T>,
+        ) -> DispatchResult {
+            approval.allowance = approval
+                .allowance
+                .checked_sub(&amount)
+                .ok_or(Error::<T>::AllowanceExceeded)?;
+            <OperatorApprovals<T>>::insert(owner, key, approval);
+            Ok(())
+        }
+
+        /// Check the deadline of an operation (`None` meaning the default deadline)
+        fn check_deadline(deadline: &Option<T::BlockNumber>) -> Result<(), Error<T>> {
+            let now = <frame_system::Pallet<T>>::block_number();
+            let deadline =
+                deadline.unwrap_or_else(|| now.saturating_add(T::DefaultDeadline::get()));
+            ensure!(deadline >= now, Error::DeadlinePassed);
+            ensure!(deadline <= now.saturating_add(T::MaxDeadline::get()), Error::DeadlineTooFar);
+            Ok(())
+        }
+
+        fn check_currency_trade_size(
+            exchange: &ExchangeOf<T>,
+            currency_amount: &BalanceOf<T>,
+        ) -> Result<(), Error<T>> {
+            let max_currency = match <MaxTradeSizes<T>>::get(exchange.asset_id.clone()) {
+                Some(TradeSizeLimit::Absolute { max_currency, .. }) => max_currency,
+                Some(TradeSizeLimit::ReserveShare(share)) => share * exchange.currency_reserve,
+                None => return Ok(()),
+            };
+            ensure!(currency_amount <= &max_currency, Error::<T>::TradeTooLarge);
+            Ok(())
+        }
+
+        fn check_token_trade_size(
+            exchange: &ExchangeOf<T>,
+            token_amount: &AssetBalanceOf<T>,
+        ) -> Result<(), Error<T>> {
+            let max_tokens = match <MaxTradeSizes<T>>::get(exchange.asset_id.clone()) {
+                Some(TradeSizeLimit::Absolute { max_tokens, .. }) => max_tokens,
+                Some(TradeSizeLimit::ReserveShare(share)) => share * exchange.token_reserve,
+                None => return Ok(()),
+            };
+            ensure!(token_amount <= &max_tokens, Error::<T>::TradeTooLarge);
+            Ok(())
+        }
+
+        /// Check if trading the asset is suspended by the safety oracle or paused by governance
+        fn is_suspended(asset_id: &AssetIdOf<T>) -> bool {
+            T::SafetyOracle::contains(asset_id) || <PausedExchanges<T>>::contains_key(asset_id)
+        }
+
+        fn check_not_suspended(asset_id: &AssetIdOf<T>) -> Result<(), Error<T>> {
+            ensure!(!Self::is_suspended(asset_id), Error::ExchangeSuspended);
+            Ok(())
+        }
+
+        /// Check if the account may add liquidity and trade on the exchange (only whitelisted
+        /// accounts may, while the exchange is in the bootstrap phase)
+        fn check_whitelisted(
+            asset_id: &AssetIdOf<T>,
+            account_id: &AccountIdOf<T>,
+        ) -> Result<(), Error<T>> {
+            if let Some(bootstrap) = <Bootstraps<T>>::get(asset_id) {
+                let now = <frame_system::Pallet<T>>::block_number();
+                ensure!(
+                    now > bootstrap.end || bootstrap.whitelist.contains(account_id),
+                    Error::NotWhitelisted
+                );
+            }
+            Ok(())
+        }
+
+        fn check_trade_amount<A: Zero, B: Zero>(
+            amount: &TradeAmount<A, B>,
+        ) -> Result<(), Error<T>> {
+            match amount {
+                TradeAmount::FixedInput {
+                    input_amount,
+                    min_output,
+                } => {
+                    ensure!(!input_amount.is_zero(), Error::TradeAmountIsZero);
+                    ensure!(!min_output.is_zero(), Error::TradeAmountIsZero);
+                }
+                TradeAmount::FixedOutput {
+                    output_amount,
+                    max_input,
+                } => {
+                    ensure!(!output_amount.is_zero(), Error::TradeAmountIsZero);
+                    ensure!(!max_input.is_zero(), Error::TradeAmountIsZero);
+                }
+            };
+            Ok(())
+        }
+
+        /// Get the amounts of tokens to deposit and liquidity to mint when adding `currency_amount`
+        /// to the exchange (proportionally to the exchange reserves).
+        fn get_added_liquidity(
+            exchange: &ExchangeOf<T>,
+            currency_amount: BalanceOf<T>,
+        ) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), Error<T>> {
+            let total_liquidity = T::Assets::total_issuance(exchange.liquidity_token_id.clone());
+            debug_assert!(total_liquidity > Zero::zero());
+            let currency_amount = T::currency_to_asset(currency_amount)?;
+            let currency_reserve = T::currency_to_asset(exchange.currency_reserve)?;
+            let token_amount =
+                audited_share(currency_amount, currency_reserve, exchange.token_reserve)
+                    .audited_add(One::one());
+            let liquidity_minted =
+                audited_share(currency_amount, currency_reserve, total_liquidity);
+            Ok((token_amount, liquidity_minted))
+        }
+
+        /// Get the amounts of currency and tokens corresponding to `liquidity_amount`
+        /// (pro-rata share of the exchange reserves).
+        fn get_withdrawn_amounts(
+            exchange: &ExchangeOf<T>,
+            liquidity_amount: &AssetBalanceOf<T>,
+        ) -> Result<(BalanceOf<T>, AssetBalanceOf<T>), Error<T>> {
+            let currency_reserve = T::currency_to_asset(exchange.currency_reserve)?;
+            let total_liquidity = T::Assets::total_issuance(exchange.liquidity_token_id.clone());
+            let currency_amount =
+                audited_share(*liquidity_amount, total_liquidity, currency_reserve);
+            let token_amount =
+                audited_share(*liquidity_amount, total_liquidity, exchange.token_reserve);
+            Ok((T::asset_to_currency(currency_amount)?, token_amount))
+        }
+
+        /// Get the amount of liquidity tokens to burn to withdraw (at least) `currency_amount` and
+        /// `token_amount` from the exchange, i.e. the liquidity whose pro-rata share of the reserves
+        /// covers both amounts.
+        fn get_burned_liquidity(
+            exchange: &ExchangeOf<T>,
+            currency_amount: BalanceOf<T>,
+            token_amount: AssetBalanceOf<T>,
+        ) -> Result<AssetBalanceOf<T>, Error<T>> {
+            let total_liquidity = T::Assets::total_issuance(exchange.liquidity_token_id.clone());
+            let currency_liquidity = audited_share(
+                T::currency_to_asset(currency_amount)?,
+                T::currency_to_asset(exchange.currency_reserve)?,
+                total_liquidity,
+            );
+            let token_liquidity =
+                audited_share(token_amount, exchange.token_reserve, total_liquidity);
+            let liquidity_amount = currency_liquidity.max(token_liquidity);
+            // The shares are rounded down, so the pro-rata amounts might be slightly lower
+            let (withdrawn_currency, withdrawn_tokens) =
+                Self::get_withdrawn_amounts(exchange, &liquidity_amount)?;
+            if withdrawn_currency < currency_amount || withdrawn_tokens < token_amount {
+                Ok(liquidity_amount.audited_add(One::one()))
+            } else {
+                Ok(liquidity_amount)
+            }
+        }
+
+        fn check_price_limit(
+            price_limit: &PriceLimitOf<T>,
+            exchange: &ExchangeOf<T>,
+        ) -> Result<(), Error<T>> {
+            if let Some(max_age) = price_limit.max_age {
+                let now = <frame_system::Pallet<T>>::block_number();
+                ensure!(
+                    now <= price_limit.quoted_at.saturating_add(max_age),
+                    Error::<T>::QuoteTooOld
+                );
+            }
+            let price = Self::get_curve_spot_price(exchange)?;
+            let expected_price = price_limit.expected_price;
+            let deviation = if price > expected_price {
+                price.saturating_sub(expected_price)
+            } else {
+                expected_price.saturating_sub(price)
+            };
+            let max_deviation = expected_price.saturating_mul(Price::from(price_limit.tolerance));
+            ensure!(deviation <= max_deviation, Error::<T>::PriceMoved);
+            Ok(())
+        }
+
+        fn check_enough_currency(
+            account_id: &AccountIdOf<T>,
+            amount: &BalanceOf<T>,
+        ) -> Result<(), Error<T>> {
+            ensure!(
+                &<T as Config>::Currency::free_balance(account_id) >= amount,
+                Error::<T>::BalanceTooLow
+            );
+            Ok(())
+        }
+
+        fn check_enough_tokens(
+            asset_id: &AssetIdOf<T>,
+            account_id: &AccountIdOf<T>,
+            amount: &AssetBalanceOf<T>,
+        ) -> Result<(), Error<T>> {
+            match T::Assets::can_withdraw(asset_id.clone(), account_id, *amount) {
+                WithdrawConsequence::Success => Ok(()),
+                WithdrawConsequence::ReducedToZero(_) => Ok(()),
+                WithdrawConsequence::UnknownAsset => Err(Error::<T>::AssetNotFound),
+                _ => Err(Error::<T>::NotEnoughTokens),
+            }
+        }
+
+        fn check_enough_liquidity_owned(
+            exchange: &ExchangeOf<T>,
+            account_id: &AccountIdOf<T>,
+            amount: &AssetBalanceOf<T>,
+        ) -> Result<(), Error<T>> {
+            let asset_id = exchange.liquidity_token_id.clone();
+            match T::AssetRegistry::can_withdraw(asset_id, account_id, *amount) {
+                WithdrawConsequence::Success => Ok(()),
+                WithdrawConsequence::ReducedToZero(_) => Ok(()),
+                WithdrawConsequence::UnknownAsset => Err(Error::<T>::AssetNotFound),
+                WithdrawConsequence::Frozen => Err(Error::<T>::LiquidityFrozen),
+                _ => Err(Error::<T>::ProviderLiquidityTooLow),
+            }
+        }
+
+        /// Check that the exchange is not delisted (trading and adding liquidity are blocked)
+        fn check_not_delisted(asset_id: &AssetIdOf<T>) -> Result<(), Error<T>> {
+            ensure!(!<Delistings<T>>::contains_key(asset_id), Error::<T>::ExchangeDelisted);
+            Ok(())
+        }
+
+        /// Check that liquidity of the exchange is not frozen by the trade-only mode
+        fn check_not_trade_only(asset_id: &AssetIdOf<T>) -> Result<(), Error<T>> {
+            ensure!(!Self::is_trade_only(asset_id), Error::<T>::TradeOnlyMode);
+            Ok(())
+        }
+
+        /// Whether the exchange is in trade-only mode, i.e. the mode has not expired yet
+        fn is_trade_only(asset_id: &AssetIdOf<T>) -> bool {
+            <TradeOnlyUntil<T>>::get(asset_id)
+                .map_or(false, |until| <frame_system::Pallet<T>>::block_number() <= until)
+        }
+
+        /// Check that burning `amount` of liquidity tokens keeps the liquidity token supply
+        /// at or above the retention floor of the exchange (if any)
+        fn check_retention_floor(
+            exchange: &ExchangeOf<T>,
+            amount: &AssetBalanceOf<T>,
+        ) -> Result<(), Error<T>> {
+            if let Some(floor) = <RetentionFloors<T>>::get(exchange.asset_id.clone()) {
+                let total_liquidity =
+                    T::AssetRegistry::total_issuance(exchange.liquidity_token_id.clone());
+                ensure!(
+                    total_liquidity.saturating_sub(*amount) >= floor,
+                    Error::<T>::RetentionFloorReached
+                );
+            }
+            Ok(())
+        }
+
+        /// Get the sum of the currency reserves of the exchanges other than the one for `asset_id`,
+        /// checking that there are at most `exchanges` exchanges.
+        fn get_other_currency_reserves(
+            asset_id: &AssetIdOf<T>,
+            exchanges: u32,
+        ) -> Result<BalanceOf<T>, Error<T>> {
+            let mut reserves = <BalanceOf<T>>::zero();
+            // One more exchange than the bound is visited, to detect a bound which is too low
+            let outcome = Self::iter_exchanges_bounded(
+                None,
+                exchanges.saturating_add(1),
+                Weight::from_ref_time(u64::MAX),
+                Weight::zero(),
+                |other_asset_id, exchange| {
+                    if other_asset_id != asset_id {
+                        reserves.saturating_accrue(exchange.currency_reserve);
+                    }
+                },
             );
-            Ok(())
+            ensure!(outcome.processed <= exchanges, Error::<T>::WrongExchangeCount);
+            Ok(reserves)
         }
 
-        fn check_enough_tokens(
+        /// Get the amount of liquidity tokens `account_id` can burn to remove liquidity from
+        /// the exchange for `asset_id`, i.e. the liquidity token balance which is not frozen.
+        pub fn get_withdrawable_liquidity(
             asset_id: &AssetIdOf<T>,
             account_id: &AccountIdOf<T>,
-            amount: &AssetBalanceOf<T>,
-        ) -> Result<(), Error<T>> {
-            match T::Assets::can_withdraw(asset_id.clone(), account_id, *amount) {
-                WithdrawConsequence::Success => Ok(()),
-                WithdrawConsequence::ReducedToZero(_) => Ok(()),
-                WithdrawConsequence::UnknownAsset => Err(Error::<T>::AssetNotFound),
-                _ => Err(Error::<T>::NotEnoughTokens),
-            }
+        ) -> Result<AssetBalanceOf<T>, Error<T>> {
+            let exchange = Self::get_exchange(asset_id)?;
+            Ok(T::AssetRegistry::reducible_balance(exchange.liquidity_token_id, account_id, false))
         }
 
-        fn check_enough_liquidity_owned(
-            exchange: &ExchangeOf<T>,
-            account_id: &AccountIdOf<T>,
-            amount: &AssetBalanceOf<T>,
-        ) -> Result<(), Error<T>> {
-            let asset_id = exchange.liquidity_token_id.clone();
-            match T::AssetRegistry::can_withdraw(asset_id, account_id, *amount) {
-                WithdrawConsequence::Success => Ok(()),
-                WithdrawConsequence::ReducedToZero(_) => Ok(()),
-                WithdrawConsequence::UnknownAsset => Err(Error::<T>::AssetNotFound),
-                _ => Err(Error::<T>::ProviderLiquidityTooLow),
-            }
+        /// Get the spot price of the asset of `exchange` (currency per token, without fees) on the
+        /// curve of the exchange.
+        pub(crate) fn get_curve_spot_price(exchange: &ExchangeOf<T>) -> Result<Price, Error<T>> {
+            let spot_price = <Curves<T>>::get(&exchange.asset_id).spot_price(
+                exchange.currency_reserve,
+                T::asset_to_currency(exchange.token_reserve)?,
+            )?;
+            Ok(Price::from_inner(spot_price))
         }
 
         pub(crate) fn get_input_price(
+            curve: &T::Curve,
+            direction: TradeDirection,
             input_amount: &BalanceOf<T>,
             input_reserve: &BalanceOf<T>,
             output_reserve: &BalanceOf<T>,
         ) -> Result<BalanceOf<T>, Error<T>> {
             debug_assert!(!input_reserve.is_zero());
             debug_assert!(!output_reserve.is_zero());
-            let input_amount_with_fee = input_amount
-                .checked_mul(&T::net_amount_numerator())
-                .ok_or(Error::Overflow)?;
-            let numerator = input_amount_with_fee
-                .checked_mul(output_reserve)
-                .ok_or(Error::Overflow)?;
-            let denominator = input_reserve
-                .checked_mul(&T::ProviderFeeDenominator::get())
-                .ok_or(Error::Overflow)?
-                .checked_add(&input_amount_with_fee)
-                .ok_or(Error::Overflow)?;
-            Ok(numerator / denominator)
+            Ok(curve.input_price(
+                direction,
+                *input_amount,
+                *input_reserve,
+                *output_reserve,
+                T::ProviderFeeNumerator::get(),
+                T::ProviderFeeDenominator::get(),
+            )?)
         }
 
         pub(crate) fn get_output_price(
+            curve: &T::Curve,
+            direction: TradeDirection,
             output_amount: &BalanceOf<T>,
             input_reserve: &BalanceOf<T>,
             output_reserve: &BalanceOf<T>,
         ) -> Result<BalanceOf<T>, Error<T>> {
             debug_assert!(!input_reserve.is_zero());
             debug_assert!(!output_reserve.is_zero());
-            ensure!(output_amount < output_reserve, Error::<T>::NotEnoughLiquidity);
-            let numerator = input_reserve
-                .checked_mul(output_amount)
-                .ok_or(Error::Overflow)?
-                .checked_mul(&T::ProviderFeeDenominator::get())
-                .ok_or(Error::Overflow)?;
-            let denominator = output_reserve
-                .saturating_sub(*output_amount)
-                .checked_mul(&T::net_amount_numerator())
-                .ok_or(Error::Overflow)?;
-            Ok((numerator / denominator).saturating_add(<BalanceOf<T>>::one()))
+            Ok(curve.output_price(
+                direction,
+                *output_amount,
+                *input_reserve,
+                *output_reserve,
+                T::ProviderFeeNumerator::get(),
+                T::ProviderFeeDenominator::get(),
+            )?)
         }
 
+        /// Compute the traded amounts. `deposit` is the part of the currency input which
+        /// is not traded but used to fund the recipient account.
         fn get_currency_to_asset_price(
             exchange: &ExchangeOf<T>,
             amount: TradeAmount<BalanceOf<T>, AssetBalanceOf<T>>,
+            deposit: BalanceOf<T>,
         ) -> Result<(BalanceOf<T>, AssetBalanceOf<T>), Error<T>> {
             match amount {
                 TradeAmount::FixedInput {
                     input_amount: currency_amount,
                     min_output: min_tokens,
                 } => {
+                    let currency_amount = currency_amount.saturating_sub(deposit);
                     let token_amount = Self::get_input_price(
+                        &<Curves<T>>::get(&exchange.asset_id),
+                        TradeDirection::CurrencyToAsset,
                         &currency_amount,
                         &exchange.currency_reserve,
-                        &T::asset_to_currency(exchange.token_reserve),
+                        &T::asset_to_currency(exchange.token_reserve)?,
                     )?;
-                    let token_amount = T::currency_to_asset(token_amount);
+                    let token_amount = T::currency_to_asset(token_amount)?;
                     ensure!(token_amount >= min_tokens, Error::MinTokensTooHigh);
                     Ok((currency_amount, token_amount))
                 }
@@ -880,11 +3699,16 @@ pub mod pallet {
                     output_amount: token_amount,
                 } => {
                     let currency_amount = Self::get_output_price(
-                        &T::asset_to_currency(token_amount),
+                        &<Curves<T>>::get(&exchange.asset_id),
+                        TradeDirection::CurrencyToAsset,
+                        &T::asset_to_currency(token_amount)?,
                         &exchange.currency_reserve,
-                        &T::asset_to_currency(exchange.token_reserve),
+                        &T::asset_to_currency(exchange.token_reserve)?,
                     )?;
-                    ensure!(currency_amount <= max_currency, Error::MaxCurrencyTooLow);
+                    ensure!(
+                        currency_amount.saturating_add(deposit) <= max_currency,
+                        Error::MaxCurrencyTooLow
+                    );
                     Ok((currency_amount, token_amount))
                 }
             }
@@ -900,8 +3724,10 @@ pub mod pallet {
                     min_output: min_currency,
                 } => {
                     let currency_amount = Self::get_input_price(
-                        &T::asset_to_currency(token_amount),
-                        &T::asset_to_currency(exchange.token_reserve),
+                        &<Curves<T>>::get(&exchange.asset_id),
+                        TradeDirection::AssetToCurrency,
+                        &T::asset_to_currency(token_amount)?,
+                        &T::asset_to_currency(exchange.token_reserve)?,
                         &exchange.currency_reserve,
                     )?;
                     ensure!(currency_amount >= min_currency, Error::MinCurrencyTooHigh);
@@ -912,21 +3738,26 @@ pub mod pallet {
                     output_amount: currency_amount,
                 } => {
                     let token_amount = Self::get_output_price(
+                        &<Curves<T>>::get(&exchange.asset_id),
+                        TradeDirection::AssetToCurrency,
                         &currency_amount,
-                        &T::asset_to_currency(exchange.token_reserve),
+                        &T::asset_to_currency(exchange.token_reserve)?,
                         &exchange.currency_reserve,
                     )?;
-                    let token_amount = T::currency_to_asset(token_amount);
+                    let token_amount = T::currency_to_asset(token_amount)?;
                     ensure!(token_amount <= max_tokens, Error::MaxTokensTooLow);
                     Ok((currency_amount, token_amount))
                 }
             }
         }
 
+        /// Compute the traded amounts. `deposit` is the part of the intermediate currency which
+        /// is not traded but used to fund the recipient account.
         fn get_asset_to_asset_price(
             sold_asset_exchange: &ExchangeOf<T>,
             bought_asset_exchange: &ExchangeOf<T>,
             amount: TradeAmount<AssetBalanceOf<T>, AssetBalanceOf<T>>,
+            deposit: BalanceOf<T>,
         ) -> Result<AssetToAssetPrice<T>, Error<T>> {
             match amount {
                 TradeAmount::FixedInput {
@@ -934,16 +3765,20 @@ pub mod pallet {
                     min_output: min_bought_tokens,
                 } => {
                     let currency_amount = Self::get_input_price(
-                        &T::asset_to_currency(sold_token_amount),
-                        &T::asset_to_currency(sold_asset_exchange.token_reserve),
+                        &<Curves<T>>::get(&sold_asset_exchange.asset_id),
+                        TradeDirection::AssetToCurrency,
+                        &T::asset_to_currency(sold_token_amount)?,
+                        &T::asset_to_currency(sold_asset_exchange.token_reserve)?,
                         &sold_asset_exchange.currency_reserve,
                     )?;
                     let bought_token_amount = Self::get_input_price(
-                        &currency_amount,
+                        &<Curves<T>>::get(&bought_asset_exchange.asset_id),
+                        TradeDirection::CurrencyToAsset,
+                        &currency_amount.saturating_sub(deposit),
                         &bought_asset_exchange.currency_reserve,
-                        &T::asset_to_currency(bought_asset_exchange.token_reserve),
+                        &T::asset_to_currency(bought_asset_exchange.token_reserve)?,
                     )?;
-                    let bought_token_amount = T::currency_to_asset(bought_token_amount);
+                    let bought_token_amount = T::currency_to_asset(bought_token_amount)?;
                     ensure!(
                         bought_token_amount >= min_bought_tokens,
                         Error::<T>::MinBoughtTokensTooHigh
@@ -955,16 +3790,22 @@ pub mod pallet {
                     output_amount: bought_token_amount,
                 } => {
                     let currency_amount = Self::get_output_price(
-                        &T::asset_to_currency(bought_token_amount),
+                        &<Curves<T>>::get(&bought_asset_exchange.asset_id),
+                        TradeDirection::CurrencyToAsset,
+                        &T::asset_to_currency(bought_token_amount)?,
                         &bought_asset_exchange.currency_reserve,
-                        &T::asset_to_currency(bought_asset_exchange.token_reserve),
-                    )?;
+                        &T::asset_to_currency(bought_asset_exchange.token_reserve)?,
+                    )?
+                    .checked_add(&deposit)
+                    .ok_or(Error::<T>::Overflow)?;
                     let sold_token_amount = Self::get_output_price(
+                        &<Curves<T>>::get(&sold_asset_exchange.asset_id),
+                        TradeDirection::AssetToCurrency,
                         &currency_amount,
-                        &T::asset_to_currency(sold_asset_exchange.token_reserve),
+                        &T::asset_to_currency(sold_asset_exchange.token_reserve)?,
                         &sold_asset_exchange.currency_reserve,
                     )?;
-                    let sold_token_amount = T::currency_to_asset(sold_token_amount);
+                    let sold_token_amount = T::currency_to_asset(sold_token_amount)?;
                     ensure!(sold_token_amount <= max_sold_tokens, Error::<T>::MaxSoldTokensTooLow);
                     Ok((sold_token_amount, currency_amount, bought_token_amount))
                 }
@@ -979,28 +3820,40 @@ pub mod pallet {
             currency_amount: BalanceOf<T>,
             token_amount: AssetBalanceOf<T>,
             liquidity_minted: AssetBalanceOf<T>,
+            payer: AccountIdOf<T>,
             provider: AccountIdOf<T>,
         ) -> DispatchResult {
             // --------------------- Currency & token transfer ---------------------
             let asset_id = exchange.asset_id.clone();
             let pallet_account = T::pallet_account();
-            <T as pallet::Config>::Currency::transfer(
-                &provider,
-                &pallet_account,
-                currency_amount,
-                ExistenceRequirement::KeepAlive,
-            )?;
-            T::Assets::transfer(asset_id.clone(), &provider, &pallet_account, token_amount, true)?;
-            T::AssetRegistry::mint_into(
-                exchange.liquidity_token_id.clone(),
-                &provider,
-                liquidity_minted,
-            )?;
+            // Streamed liquidity is moved from its escrow to the pallet account beforehand
+            if payer != pallet_account {
+                <T as pallet::Config>::Currency::transfer(
+                    &payer,
+                    &pallet_account,
+                    currency_amount,
+                    ExistenceRequirement::KeepAlive,
+                )?;
+                T::Assets::transfer(asset_id.clone(), &payer, &pallet_account, token_amount, true)?;
+            }
+            if !liquidity_minted.is_zero() {
+                T::AssetRegistry::mint_into(
+                    exchange.liquidity_token_id.clone(),
+                    &provider,
+                    liquidity_minted,
+                )?;
+            }
 
             // -------------------------- Balances update --------------------------
-            exchange.currency_reserve.saturating_accrue(currency_amount);
-            exchange.token_reserve.saturating_accrue(token_amount);
+            exchange.currency_reserve.audited_accrue(currency_amount);
+            exchange.token_reserve.audited_accrue(token_amount);
+            #[cfg(feature = "reserve-trace")]
+            Self::record_reserve_checkpoint(&exchange, Zero::zero(), Zero::zero());
             <Exchanges<T>>::insert(asset_id.clone(), exchange);
+            <ReservesUpdatedAt<T>>::insert(
+                asset_id.clone(),
+                <frame_system::Pallet<T>>::block_number(),
+            );
 
             // ---------------------------- Emit event -----------------------------
             Self::deposit_event(Event::LiquidityAdded(
@@ -1040,18 +3893,69 @@ pub mod pallet {
             T::Assets::transfer(asset_id.clone(), &pallet_account, &provider, token_amount, false)?;
 
             // -------------------------- Balances update --------------------------
-            exchange.currency_reserve.saturating_reduce(currency_amount);
-            exchange.token_reserve.saturating_reduce(token_amount);
+            let liquidity_token_id = exchange.liquidity_token_id.clone();
+            exchange.currency_reserve.audited_reduce(currency_amount);
+            exchange.token_reserve.audited_reduce(token_amount);
+            #[cfg(feature = "reserve-trace")]
+            Self::record_reserve_checkpoint(&exchange, Zero::zero(), Zero::zero());
             <Exchanges<T>>::insert(asset_id.clone(), exchange);
+            <ReservesUpdatedAt<T>>::insert(
+                asset_id.clone(),
+                <frame_system::Pallet<T>>::block_number(),
+            );
 
             // ---------------------------- Emit event -----------------------------
             Self::deposit_event(Event::LiquidityRemoved(
                 provider,
-                asset_id,
+                asset_id.clone(),
                 currency_amount,
                 token_amount,
                 liquidity_amount,
             ));
+
+            // ------------------- Remove exchange without liquidity ---------------
+            if T::AssetRegistry::total_issuance(liquidity_token_id.clone()).is_zero() {
+                Self::do_remove_exchange(asset_id, liquidity_token_id)?;
+            }
+            Ok(())
+        }
+
+        /// Remove an exchange without liquidity: destroy its liquidity token and purge the exchange
+        fn do_remove_exchange(
+            asset_id: AssetIdOf<T>,
+            liquidity_token_id: AssetIdOf<T>,
+        ) -> DispatchResult {
+            let witness = T::AssetRegistry::get_destroy_witness(&liquidity_token_id)
+                .ok_or(Error::<T>::AssetNotFound)?;
+            T::AssetRegistry::destroy(liquidity_token_id.clone(), witness, None)?;
+            Self::purge_exchange(asset_id, liquidity_token_id)
+        }
+
+        /// Return the remaining rebate funds of an exchange to the sponsor, purge the exchange
+        /// parameters, emit event
+        fn purge_exchange(
+            asset_id: AssetIdOf<T>,
+            liquidity_token_id: AssetIdOf<T>,
+        ) -> DispatchResult {
+            if let Some(sponsorship) = <Sponsorships<T>>::get(asset_id.clone()) {
+                Self::do_withdraw_sponsorship(asset_id.clone(), sponsorship)?;
+            }
+            <Exchanges<T>>::remove(asset_id.clone());
+            <ReservesUpdatedAt<T>>::remove(asset_id.clone());
+            <LiquidityTokens<T>>::remove(liquidity_token_id.clone());
+            <MaxTradeSizes<T>>::remove(asset_id.clone());
+            <RetentionFloors<T>>::remove(asset_id.clone());
+            <TradeOnlyUntil<T>>::remove(asset_id.clone());
+            <Delistings<T>>::remove(asset_id.clone());
+            <PegReferences<T>>::remove(asset_id.clone());
+            <PausedExchanges<T>>::remove(asset_id.clone());
+            <Curves<T>>::remove(asset_id.clone());
+            <YieldCheckpoints<T>>::remove(asset_id.clone());
+            <Bootstraps<T>>::remove(asset_id.clone());
+            #[cfg(feature = "reserve-trace")]
+            Self::clear_reserve_checkpoints(&asset_id);
+            <SeededDeposits<T>>::remove(asset_id.clone());
+            Self::deposit_event(Event::ExchangeRemoved(asset_id, liquidity_token_id));
             Ok(())
         }
 
@@ -1063,6 +3967,7 @@ pub mod pallet {
             token_amount: AssetBalanceOf<T>,
             buyer: AccountIdOf<T>,
             recipient: AccountIdOf<T>,
+            donation: Option<DonationOf<T>>,
         ) -> DispatchResult {
             // --------------------- Currency & token transfer ---------------------
             let asset_id = exchange.asset_id.clone();
@@ -1075,18 +3980,33 @@ pub mod pallet {
                     ExistenceRequirement::AllowDeath,
                 )?;
             }
-            T::Assets::transfer(
-                asset_id.clone(),
-                &pallet_account,
-                &recipient,
-                token_amount,
-                false,
-            )?;
+            let donated = donation
+                .as_ref()
+                .map_or_else(Zero::zero, |d| d.share * token_amount);
+            let received = token_amount.audited_sub(donated);
+            if !received.is_zero() {
+                T::Assets::transfer(
+                    asset_id.clone(),
+                    &pallet_account,
+                    &recipient,
+                    received,
+                    false,
+                )?;
+            }
+            Self::donate_tokens(&asset_id, &pallet_account, donation, donated)?;
 
             // -------------------------- Balances update --------------------------
-            exchange.currency_reserve.saturating_accrue(currency_amount);
-            exchange.token_reserve.saturating_reduce(token_amount);
+            exchange.currency_reserve.audited_accrue(currency_amount);
+            exchange.token_reserve.audited_reduce(token_amount);
+            Self::check_reserve_mismatch(&exchange, &pallet_account);
+            #[cfg(feature = "reserve-trace")]
+            Self::record_reserve_checkpoint(&exchange, currency_amount, Zero::zero());
+            Self::record_yield_checkpoint(&exchange);
             <Exchanges<T>>::insert(asset_id.clone(), exchange);
+            <ReservesUpdatedAt<T>>::insert(
+                asset_id.clone(),
+                <frame_system::Pallet<T>>::block_number(),
+            );
 
             // ---------------------------- Emit event -----------------------------
             Self::deposit_event(Event::CurrencyTradedForAsset(
@@ -1107,24 +4027,37 @@ pub mod pallet {
             token_amount: AssetBalanceOf<T>,
             buyer: AccountIdOf<T>,
             recipient: AccountIdOf<T>,
+            donation: Option<DonationOf<T>>,
         ) -> DispatchResult {
             // --------------------- Currency & token transfer ---------------------
             let asset_id = exchange.asset_id.clone();
             let pallet_account = T::pallet_account();
             T::Assets::transfer(asset_id.clone(), &buyer, &pallet_account, token_amount, false)?;
+            let donated = donation
+                .as_ref()
+                .map_or_else(Zero::zero, |d| d.share * currency_amount);
             if recipient != pallet_account {
                 <T as pallet::Config>::Currency::transfer(
                     &pallet_account,
                     &recipient,
-                    currency_amount,
+                    currency_amount.audited_sub(donated),
                     ExistenceRequirement::AllowDeath,
                 )?;
             }
+            Self::donate_currency(&asset_id, &pallet_account, donation, donated)?;
 
             // -------------------------- Balances update --------------------------
-            exchange.token_reserve.saturating_accrue(token_amount);
-            exchange.currency_reserve.saturating_reduce(currency_amount);
+            exchange.token_reserve.audited_accrue(token_amount);
+            exchange.currency_reserve.audited_reduce(currency_amount);
+            Self::check_reserve_mismatch(&exchange, &pallet_account);
+            #[cfg(feature = "reserve-trace")]
+            Self::record_reserve_checkpoint(&exchange, Zero::zero(), token_amount);
+            Self::record_yield_checkpoint(&exchange);
             <Exchanges<T>>::insert(asset_id.clone(), exchange);
+            <ReservesUpdatedAt<T>>::insert(
+                asset_id.clone(),
+                <frame_system::Pallet<T>>::block_number(),
+            );
 
             // ---------------------------- Emit event -----------------------------
             Self::deposit_event(Event::AssetTradedForCurrency(
@@ -1137,32 +4070,550 @@ pub mod pallet {
             Ok(())
         }
 
-        /// Swap one asset to currency, then currency to another asset
+        /// Transfer `amount` of the bought tokens from the pallet account to the donation
+        /// beneficiary, or to `BurnAccount` if there is none. Emit `OutputDonated` event.
+        fn donate_tokens(
+            asset_id: &AssetIdOf<T>,
+            pallet_account: &AccountIdOf<T>,
+            donation: Option<DonationOf<T>>,
+            amount: AssetBalanceOf<T>,
+        ) -> DispatchResult {
+            let donation = match donation {
+                Some(donation) if !amount.is_zero() => donation,
+                _ => return Ok(()),
+            };
+            let beneficiary = donation
+                .beneficiary
+                .clone()
+                .unwrap_or_else(T::BurnAccount::get);
+            T::Assets::transfer(asset_id.clone(), pallet_account, &beneficiary, amount, false)?;
+            Self::deposit_event(Event::OutputDonated(
+                asset_id.clone(),
+                donation.beneficiary,
+                Zero::zero(),
+                amount,
+            ));
+            Ok(())
+        }
+
+        /// Transfer `amount` of the bought currency from the pallet account to the donation
+        /// beneficiary, or burn it if there is none. Emit `OutputDonated` event.
+        fn donate_currency(
+            asset_id: &AssetIdOf<T>,
+            pallet_account: &AccountIdOf<T>,
+            donation: Option<DonationOf<T>>,
+            amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let donation = match donation {
+                Some(donation) if !amount.is_zero() => donation,
+                _ => return Ok(()),
+            };
+            match &donation.beneficiary {
+                Some(beneficiary) => {
+                    <T as pallet::Config>::Currency::transfer(
+                        pallet_account,
+                        beneficiary,
+                        amount,
+                        ExistenceRequirement::AllowDeath,
+                    )?;
+                }
+                None => {
+                    // Dropping the imbalance reduces the total issuance
+                    let _ = <T as pallet::Config>::Currency::withdraw(
+                        pallet_account,
+                        amount,
+                        WithdrawReasons::TRANSFER,
+                        ExistenceRequirement::AllowDeath,
+                    )?;
+                }
+            }
+            Self::deposit_event(Event::OutputDonated(
+                asset_id.clone(),
+                donation.beneficiary,
+                amount,
+                Zero::zero(),
+            ));
+            Ok(())
+        }
+
+        /// Emit `ReserveMismatch` event if the asset balance of the pallet account (already read
+        /// by the trade transfer) diverges from the token reserve beyond the tolerance. Never fails.
+        fn check_reserve_mismatch(exchange: &ExchangeOf<T>, pallet_account: &AccountIdOf<T>) {
+            let balance = T::Assets::balance(exchange.asset_id.clone(), pallet_account);
+            let tolerance = T::ReserveMismatchTolerance::get() * exchange.token_reserve;
+            let mismatch = if balance < exchange.token_reserve {
+                exchange.token_reserve - balance > tolerance
+            } else {
+                balance - exchange.token_reserve > tolerance
+            };
+            if mismatch {
+                Self::deposit_event(Event::ReserveMismatch(
+                    exchange.asset_id.clone(),
+                    exchange.token_reserve,
+                    balance,
+                ));
+            }
+        }
+
+        /// Return remaining rebate funds to the sponsor, remove sponsorship, emit event
+        fn do_withdraw_sponsorship(
+            asset_id: AssetIdOf<T>,
+            sponsorship: SponsorshipOf<T>,
+        ) -> DispatchResult {
+            let sponsor_account = T::sponsor_account(&asset_id);
+            let currency_amount = <T as Config>::Currency::free_balance(&sponsor_account);
+            <T as pallet::Config>::Currency::transfer(
+                &sponsor_account,
+                &sponsorship.sponsor,
+                currency_amount,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            <Sponsorships<T>>::remove(asset_id.clone());
+            Self::deposit_event(Event::SponsorshipWithdrawn(
+                asset_id,
+                sponsorship.sponsor,
+                currency_amount,
+            ));
+            Ok(())
+        }
+
+        /// Pay a rebate to the trader if the exchange is sponsored and the block budget
+        /// is not exhausted, at most `fee` (the transaction fee paid for the trade). Return the paid
+        /// rebate. Never fails – the trade is not affected if a rebate can't be paid.
+        fn pay_rebate(
+            asset_id: &AssetIdOf<T>,
+            trader: &AccountIdOf<T>,
+            fee: BalanceOf<T>,
+        ) -> BalanceOf<T> {
+            let mut sponsorship = match <Sponsorships<T>>::get(asset_id.clone()) {
+                Some(sponsorship) => sponsorship,
+                None => return Zero::zero(),
+            };
+            let now = <frame_system::Pallet<T>>::block_number();
+            if sponsorship.last_block != now {
+                sponsorship.spent = Zero::zero();
+                sponsorship.last_block = now;
+            }
+            let sponsor_account = T::sponsor_account(asset_id);
+            let rebate = sponsorship
+                .rebate
+                .min(sponsorship.block_budget.saturating_sub(sponsorship.spent))
+                .min(<T as Config>::Currency::free_balance(&sponsor_account))
+                .min(fee);
+            if rebate.is_zero() {
+                return Zero::zero();
+            }
+            let transferred = <T as pallet::Config>::Currency::transfer(
+                &sponsor_account,
+                trader,
+                rebate,
+                ExistenceRequirement::AllowDeath,
+            );
+            if transferred.is_err() {
+                return Zero::zero();
+            }
+            sponsorship.spent.saturating_accrue(rebate);
+            <Sponsorships<T>>::insert(asset_id.clone(), sponsorship);
+            Self::deposit_event(Event::RebatePaid(asset_id.clone(), trader.clone(), rebate));
+            rebate
+        }
+
+        /// Credit the share of the provider fee paid by a trade of `trader` to the beneficiary of its
+        /// fee share (if registered), out of the reserves of the exchange the fee was paid to.
+        /// Never fails – the trade is not affected if the share can't be credited.
+        fn pay_fee_share(
+            asset_id: &AssetIdOf<T>,
+            trader: &AccountIdOf<T>,
+            currency_in: BalanceOf<T>,
+            tokens_in: AssetBalanceOf<T>,
+        ) {
+            let fee_share = match <FeeShares<T>>::get(trader) {
+                Some(fee_share) => fee_share,
+                None => return,
+            };
+            let mut exchange = match <Exchanges<T>>::get(asset_id.clone()) {
+                Some(exchange) => exchange,
+                None => return,
+            };
+            // Rounded down, in favour of the liquidity providers
+            let fee = Permill::from_rational(
+                T::ProviderFeeNumerator::get(),
+                T::ProviderFeeDenominator::get(),
+            );
+            let mut currency_amount = fee_share.share.mul_floor(fee.mul_floor(currency_in));
+            let mut token_amount = fee_share.share.mul_floor(fee.mul_floor(tokens_in));
+            let pallet_account = T::pallet_account();
+            if !currency_amount.is_zero()
+                && <T as pallet::Config>::Currency::transfer(
+                    &pallet_account,
+                    &fee_share.beneficiary,
+                    currency_amount,
+                    ExistenceRequirement::AllowDeath,
+                )
+                .is_err()
+            {
+                currency_amount = Zero::zero();
+            }
+            if !token_amount.is_zero()
+                && T::Assets::transfer(
+                    asset_id.clone(),
+                    &pallet_account,
+                    &fee_share.beneficiary,
+                    token_amount,
+                    false,
+                )
+                .is_err()
+            {
+                token_amount = Zero::zero();
+            }
+            if currency_amount.is_zero() && token_amount.is_zero() {
+                return;
+            }
+            exchange.currency_reserve.audited_reduce(currency_amount);
+            exchange.token_reserve.audited_reduce(token_amount);
+            #[cfg(feature = "reserve-trace")]
+            Self::record_reserve_checkpoint(&exchange, Zero::zero(), Zero::zero());
+            <Exchanges<T>>::insert(asset_id.clone(), exchange);
+            <ReservesUpdatedAt<T>>::insert(
+                asset_id.clone(),
+                <frame_system::Pallet<T>>::block_number(),
+            );
+            Self::deposit_event(Event::FeeSharePaid(
+                asset_id.clone(),
+                trader.clone(),
+                fee_share.beneficiary,
+                currency_amount,
+                token_amount,
+            ));
+        }
+
+        /// Perform both legs of an asset-to-asset trade. The intermediate currency is only credited
+        /// from one exchange to the other within the pallet account, so the trader doesn't need to
+        /// hold (nor receive) any currency, and no transfer fees or deposits apply to the hop.
+        /// `deposit` is the part of `currency_amount` used to fund the recipient account.
         #[transactional]
+        #[allow(clippy::too_many_arguments)]
         fn swap_asset_for_asset(
             sold_asset_exchange: ExchangeOf<T>,
             bought_asset_exchange: ExchangeOf<T>,
             currency_amount: BalanceOf<T>,
+            deposit: BalanceOf<T>,
             sold_token_amount: AssetBalanceOf<T>,
             bought_token_amount: AssetBalanceOf<T>,
             buyer: AccountIdOf<T>,
             recipient: AccountIdOf<T>,
+            donation: Option<DonationOf<T>>,
         ) -> DispatchResult {
             let pallet_account: AccountIdOf<T> = T::pallet_account();
+            let bought_asset_id = bought_asset_exchange.asset_id.clone();
+            // Summary first, then the events of each leg, so indexers can tell them from
+            // single-leg trades
+            Self::deposit_event(Event::AssetTradedForAsset(
+                sold_asset_exchange.asset_id.clone(),
+                bought_asset_id.clone(),
+                buyer.clone(),
+                recipient.clone(),
+                sold_token_amount,
+                bought_token_amount,
+            ));
+            Self::deposit_event(Event::LegStarted(0));
             Self::swap_asset_for_currency(
                 sold_asset_exchange,
                 currency_amount,
                 sold_token_amount,
                 buyer,
                 pallet_account.clone(),
+                None,
             )?;
+            Self::deposit_event(Event::LegStarted(1));
+            Self::provision_recipient(&bought_asset_id, &pallet_account, &recipient, deposit)?;
             Self::swap_currency_for_asset(
                 bought_asset_exchange,
-                currency_amount,
+                currency_amount.saturating_sub(deposit),
                 bought_token_amount,
                 pallet_account,
                 recipient,
+                donation,
             )
         }
+
+        /// Offset the market maker's position resulting from an RFQ settlement against the exchange,
+        /// without a loss to the market maker. The hedge pays the fee share of the market maker
+        /// (if any), like any other trade.
+        fn hedge_rfq(quote: &RfqQuoteOf<T>) -> DispatchResult {
+            let exchange = Self::get_exchange(&quote.asset_id)?;
+            Self::check_whitelisted(&quote.asset_id, &quote.maker)?;
+            let (currency_in, tokens_in) = match quote.side {
+                RfqSide::MakerSells => {
+                    let amount = TradeAmount::FixedOutput {
+                        max_input: quote.currency_amount,
+                        output_amount: quote.token_amount,
+                    };
+                    let (currency_amount, token_amount) =
+                        Self::get_currency_to_asset_price(&exchange, amount, Zero::zero())?;
+                    Self::check_currency_trade_size(&exchange, &currency_amount)?;
+                    Self::swap_currency_for_asset(
+                        exchange,
+                        currency_amount,
+                        token_amount,
+                        quote.maker.clone(),
+                        quote.maker.clone(),
+                        None,
+                    )?;
+                    (currency_amount, Zero::zero())
+                }
+                RfqSide::MakerBuys => {
+                    let amount = TradeAmount::FixedInput {
+                        input_amount: quote.token_amount,
+                        min_output: quote.currency_amount,
+                    };
+                    let (currency_amount, token_amount) =
+                        Self::get_asset_to_currency_price(&exchange, amount)?;
+                    Self::check_token_trade_size(&exchange, &token_amount)?;
+                    Self::swap_asset_for_currency(
+                        exchange,
+                        currency_amount,
+                        token_amount,
+                        quote.maker.clone(),
+                        quote.maker.clone(),
+                        None,
+                    )?;
+                    (Zero::zero(), token_amount)
+                }
+            };
+            Self::pay_fee_share(&quote.asset_id, &quote.maker, currency_in, tokens_in);
+            Ok(())
+        }
+
+        /// Stream the next part of due liquidity streams, within `weight_limit`. Every visited
+        /// stream is charged two storage reads (the stream and the trade-only mode of the exchange),
+        /// every streamed part `execute_liquidity_stream` weight.
+        fn execute_liquidity_streams(now: T::BlockNumber, weight_limit: Weight) -> Weight {
+            let cursor_weight = T::DbWeight::get().reads_writes(1, 1);
+            let read_weight = T::DbWeight::get().reads(2);
+            let execute_weight = <T as Config>::WeightInfo::execute_liquidity_stream();
+            if cursor_weight
+                .saturating_add(read_weight)
+                .saturating_add(execute_weight)
+                > weight_limit
+            {
+                return Weight::zero();
+            }
+            let mut weight = cursor_weight;
+            // Resume after the last stream visited in the previous block, so that the streams at the
+            // end of the map are executed too when the spare weight doesn't allow visiting all of them
+            let mut cursor = <LiquidityStreamsCursor<T>>::get();
+            let mut iter = match &cursor {
+                Some((asset_id, provider)) => <LiquidityStreams<T>>::iter_from(
+                    <LiquidityStreams<T>>::hashed_key_for(asset_id, provider),
+                ),
+                None => <LiquidityStreams<T>>::iter(),
+            };
+            let mut due_streams = Vec::new();
+            while weight
+                .saturating_add(read_weight)
+                .saturating_add(execute_weight)
+                <= weight_limit
+            {
+                match iter.next() {
+                    Some((asset_id, provider, stream)) => {
+                        weight.saturating_accrue(read_weight);
+                        cursor = Some((asset_id.clone(), provider.clone()));
+                        // Streams are paused while the exchange is in trade-only mode
+                        if stream.last_block < now && !Self::is_trade_only(&asset_id) {
+                            weight.saturating_accrue(execute_weight);
+                            due_streams.push((asset_id, provider, stream));
+                        }
+                    }
+                    None => {
+                        // All streams visited, start from the first one in the next block
+                        cursor = None;
+                        break;
+                    }
+                }
+            }
+            match cursor {
+                Some(cursor) => <LiquidityStreamsCursor<T>>::put(cursor),
+                None => <LiquidityStreamsCursor<T>>::kill(),
+            }
+            // Streams are updated after iterating, as altering a map while iterating it is undefined
+            for (asset_id, provider, stream) in due_streams {
+                Self::execute_liquidity_stream(asset_id, provider, stream, now);
+            }
+            weight
+        }
+
+        /// Add the next part of a liquidity stream to the exchange. End the stream (returning
+        /// the remaining funds) after its last part, or if the part can't be added.
+        pub(crate) fn execute_liquidity_stream(
+            asset_id: AssetIdOf<T>,
+            provider: AccountIdOf<T>,
+            mut stream: LiquidityStreamOf<T>,
+            now: T::BlockNumber,
+        ) {
+            let currency_amount = stream.currency_remaining / stream.blocks_remaining.into();
+            let added = Self::add_streamed_liquidity(
+                &asset_id,
+                &provider,
+                currency_amount,
+                stream.tokens_remaining,
+            );
+            if let Ok(token_amount) = added {
+                Self::release_escrowed_tokens(&asset_id, token_amount);
+                stream.currency_remaining.audited_reduce(currency_amount);
+                stream.tokens_remaining.audited_reduce(token_amount);
+                stream.blocks_remaining.saturating_dec();
+                stream.last_block = now;
+                if stream.blocks_remaining > 0 {
+                    <LiquidityStreams<T>>::insert(asset_id, &provider, stream);
+                    return;
+                }
+            }
+            // Returning funds held by the escrow sub-account can't fail, unless the state is corrupted
+            let ended = Self::do_end_liquidity_stream(asset_id, provider, stream);
+            debug_assert!(ended.is_ok(), "Unexpected error while ending a liquidity stream");
+        }
+
+        /// Add escrowed currency (and proportional tokens) to the exchange, return the added tokens
+        #[transactional]
+        fn add_streamed_liquidity(
+            asset_id: &AssetIdOf<T>,
+            provider: &AccountIdOf<T>,
+            currency_amount: BalanceOf<T>,
+            max_tokens: AssetBalanceOf<T>,
+        ) -> Result<AssetBalanceOf<T>, DispatchError> {
+            let exchange = Self::get_exchange(asset_id)?;
+            Self::check_not_delisted(asset_id)?;
+            Self::check_whitelisted(asset_id, provider)?;
+            let (token_amount, liquidity_minted) =
+                Self::get_added_liquidity(&exchange, currency_amount)?;
+            ensure!(token_amount <= max_tokens, Error::<T>::MaxTokensTooLow);
+            let (escrow_account, pallet_account) =
+                (T::stream_escrow_account(asset_id), T::pallet_account());
+            <T as pallet::Config>::Currency::transfer(
+                &escrow_account,
+                &pallet_account,
+                currency_amount,
+                ExistenceRequirement::KeepAlive,
+            )?;
+            T::Assets::transfer(
+                asset_id.clone(),
+                &escrow_account,
+                &pallet_account,
+                token_amount,
+                false,
+            )?;
+            Self::do_add_liquidity(
+                exchange,
+                currency_amount,
+                token_amount,
+                liquidity_minted,
+                pallet_account,
+                provider.clone(),
+            )?;
+            Ok(token_amount)
+        }
+
+        /// Return the funds of a liquidity stream not streamed yet, remove the stream, emit event
+        #[transactional]
+        fn do_end_liquidity_stream(
+            asset_id: AssetIdOf<T>,
+            provider: AccountIdOf<T>,
+            stream: LiquidityStreamOf<T>,
+        ) -> DispatchResult {
+            let escrow_account = T::stream_escrow_account(&asset_id);
+            <T as pallet::Config>::Currency::transfer(
+                &escrow_account,
+                &provider,
+                stream.currency_remaining,
+                ExistenceRequirement::KeepAlive,
+            )?;
+            T::Assets::transfer(
+                asset_id.clone(),
+                &escrow_account,
+                &provider,
+                stream.tokens_remaining,
+                false,
+            )?;
+            <LiquidityStreams<T>>::remove(asset_id.clone(), &provider);
+            Self::release_storage_deposit(
+                &provider,
+                DepositItem::LiquidityStream(asset_id.clone()),
+            );
+            Self::release_escrowed_tokens(&asset_id, stream.tokens_remaining);
+            Self::deposit_event(Event::LiquidityStreamEnded(
+                asset_id,
+                provider,
+                stream.currency_remaining,
+                stream.tokens_remaining,
+            ));
+            Ok(())
+        }
+
+        /// Reserve `StorageDeposit` from the account for a storage item it creates (nothing if
+        /// a deposit is already held for the item)
+        fn hold_storage_deposit(who: &AccountIdOf<T>, item: DepositItemOf<T>) -> DispatchResult {
+            let deposit = T::StorageDeposit::get();
+            if deposit.is_zero() || <StorageDeposits<T>>::contains_key(who, &item) {
+                return Ok(());
+            }
+            <T as Config>::Currency::reserve(who, deposit)
+                .map_err(|_| Error::<T>::BalanceTooLow)?;
+            <StorageDeposits<T>>::insert(who, item, deposit);
+            Ok(())
+        }
+
+        /// Release the storage deposit held from the account for a removed storage item
+        fn release_storage_deposit(who: &AccountIdOf<T>, item: DepositItemOf<T>) {
+            if let Some(deposit) = <StorageDeposits<T>>::take(who, item) {
+                <T as Config>::Currency::unreserve(who, deposit);
+            }
+        }
+
+        /// Decrease the amount of tokens escrowed by liquidity streams for the exchange
+        fn release_escrowed_tokens(asset_id: &AssetIdOf<T>, amount: AssetBalanceOf<T>) {
+            <EscrowedTokens<T>>::mutate_exists(asset_id.clone(), |escrowed| {
+                if let Some(remaining) = escrowed {
+                    remaining.audited_reduce(amount);
+                    if remaining.is_zero() {
+                        *escrowed = None;
+                    }
+                }
+            });
+        }
+
+        /// Get the deposit needed to fund the recipient account (if requested and the account
+        /// doesn't exist), i.e. the existential deposit of the currency.
+        fn get_recipient_deposit(recipient: &AccountIdOf<T>, provision: bool) -> BalanceOf<T> {
+            if provision && <T as Config>::Currency::total_balance(recipient).is_zero() {
+                <T as Config>::Currency::minimum_balance()
+            } else {
+                Zero::zero()
+            }
+        }
+
+        /// Transfer `deposit` from `payer` to `recipient` (so it can hold the bought asset), emit event
+        fn provision_recipient(
+            asset_id: &AssetIdOf<T>,
+            payer: &AccountIdOf<T>,
+            recipient: &AccountIdOf<T>,
+            deposit: BalanceOf<T>,
+        ) -> DispatchResult {
+            if deposit.is_zero() {
+                return Ok(());
+            }
+            <T as pallet::Config>::Currency::transfer(
+                payer,
+                recipient,
+                deposit,
+                ExistenceRequirement::AllowDeath,
+            )?;
+            Self::deposit_event(Event::RecipientProvisioned(
+                asset_id.clone(),
+                recipient.clone(),
+                deposit,
+            ));
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,140 @@
+//! Executable specification of the liquidity share math, for integrators to diff their
+//! implementations against. With `C`/`T` the currency/token reserves and `L` the liquidity
+//! token supply of an exchange, and `ratio(a, b)` the 18-decimal fixed-point number `a / b`
+//! rounded down:
+//!   * creating an exchange mints `c` liquidity for `c` deposited currency,
+//!   * adding `c` currency deposits `floor(ratio(c, C) * T) + 1` tokens
+//!     and mints `floor(ratio(c, C) * L)` liquidity,
+//!   * burning `l` liquidity withdraws `floor(ratio(l, L) * C)` currency
+//!     and `floor(ratio(l, L) * T)` tokens.
+//!
+//! Each row creates a fresh exchange with reserves `C`/`T` (so `L = C`) and performs one
+//! operation through the extrinsics.
+
+use crate::mock::*;
+use crate::Error;
+use frame_support::{assert_ok, dispatch::DispatchError, traits::Currency};
+
+const PROVIDER: u64 = 1000;
+const SPEC_ASSET: u32 = 300;
+const SPEC_LIQ_TOKEN: u32 = 301;
+/// Largest reserves (and liquidity supply) used by the rows, leaving room for the deposits.
+const MAX_RESERVE: u128 = u128::MAX / 4;
+
+/// Create an exchange with the given reserves, owned entirely by `PROVIDER`.
+fn create_spec_exchange(currency_reserve: u128, token_reserve: u128) {
+    let balance = MAX_RESERVE * 2 + 1_000;
+    Balances::make_free_balance_be(&PROVIDER, balance);
+    assert_ok!(Assets::force_create(RuntimeOrigin::root(), SPEC_ASSET, PROVIDER, true, 1));
+    assert_ok!(Assets::mint(RuntimeOrigin::signed(PROVIDER), SPEC_ASSET, PROVIDER, balance));
+    assert_ok!(Dex::create_exchange(
+        RuntimeOrigin::signed(PROVIDER),
+        SPEC_ASSET,
+        SPEC_LIQ_TOKEN,
+        currency_reserve,
+        token_reserve,
+        None,
+        false
+    ));
+}
+
+#[test]
+fn initial_mint_equals_deposited_currency() {
+    // (currency deposited, tokens deposited)
+    let rows: Vec<(u128, u128)> = vec![(1, 1), (1, 1_000), (1_000, 1), (MAX_RESERVE, MAX_RESERVE)];
+    for (currency_amount, token_amount) in rows {
+        new_test_ext().execute_with(|| {
+            create_spec_exchange(currency_amount, token_amount);
+            assert_eq!(Assets::total_supply(SPEC_LIQ_TOKEN), currency_amount);
+            assert_eq!(Assets::balance(SPEC_LIQ_TOKEN, PROVIDER), currency_amount);
+        })
+    }
+}
+
+#[test]
+fn subsequent_mint_is_proportional_to_currency() {
+    // (C, T, currency added) => (tokens deposited, liquidity minted)
+    type Row = ((u128, u128, u128), Result<(u128, u128), DispatchError>);
+    let rows: Vec<Row> = vec![
+        ((1, 1, 1), Ok((2, 1))),
+        ((1_000, 1_000, 1), Ok((2, 1))),
+        ((1_000, 2_000, 500), Ok((1_001, 500))),
+        // ratio(1, 3) * 3 rounds down to 0 liquidity
+        ((3, 7, 1), Err(Error::<Test>::MinLiquidityTooHigh.into())),
+        ((MAX_RESERVE, MAX_RESERVE, 1), Err(Error::<Test>::MinLiquidityTooHigh.into())),
+        ((MAX_RESERVE, MAX_RESERVE, MAX_RESERVE), Ok((MAX_RESERVE + 1, MAX_RESERVE))),
+    ];
+    for ((currency_reserve, token_reserve, currency_amount), expected) in rows {
+        new_test_ext().execute_with(|| {
+            create_spec_exchange(currency_reserve, token_reserve);
+            let result = Dex::add_liquidity(
+                RuntimeOrigin::signed(PROVIDER),
+                SPEC_ASSET,
+                currency_amount,
+                1,
+                MAX_RESERVE + 1,
+                None,
+                None,
+            )
+            .map(|_| {
+                let exchange = Dex::exchanges(SPEC_ASSET).unwrap();
+                (
+                    exchange.token_reserve - token_reserve,
+                    Assets::total_supply(SPEC_LIQ_TOKEN) - currency_reserve,
+                )
+            });
+            assert_eq!(
+                result, expected,
+                "C = {}, T = {}, c = {}",
+                currency_reserve, token_reserve, currency_amount
+            );
+        })
+    }
+}
+
+#[test]
+fn burn_is_pro_rata_rounded_down() {
+    // (C, T, liquidity burned) => (currency withdrawn, tokens withdrawn)
+    type Row = ((u128, u128, u128), Result<(u128, u128), DispatchError>);
+    let rows: Vec<Row> = vec![
+        ((1, 1, 1), Ok((1, 1))),
+        ((1_000, 2_000, 1), Ok((1, 2))),
+        ((1_000, 999, 999), Ok((999, 998))),
+        ((1_000, 2_000, 1_000), Ok((1_000, 2_000))),
+        // ratio(1, 3) * 3 rounds down to 0 currency
+        ((3, 7, 1), Err(Error::<Test>::MinCurrencyTooHigh.into())),
+        (
+            (MAX_RESERVE, MAX_RESERVE, MAX_RESERVE / 2),
+            Ok((
+                42_535_295_865_117_307_847_851_234_198_736_410_565,
+                42_535_295_865_117_307_847_851_234_198_736_410_565,
+            )),
+        ),
+    ];
+    for ((currency_reserve, token_reserve, liquidity_amount), expected) in rows {
+        new_test_ext().execute_with(|| {
+            create_spec_exchange(currency_reserve, token_reserve);
+            let currency_before = Balances::free_balance(PROVIDER);
+            let tokens_before = Assets::balance(SPEC_ASSET, PROVIDER);
+            let result = Dex::remove_liquidity(
+                RuntimeOrigin::signed(PROVIDER),
+                SPEC_ASSET,
+                liquidity_amount,
+                1,
+                1,
+                None,
+            )
+            .map(|_| {
+                (
+                    Balances::free_balance(PROVIDER) - currency_before,
+                    Assets::balance(SPEC_ASSET, PROVIDER) - tokens_before,
+                )
+            });
+            assert_eq!(
+                result, expected,
+                "C = {}, T = {}, l = {}",
+                currency_reserve, token_reserve, liquidity_amount
+            );
+        })
+    }
+}
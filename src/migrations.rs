@@ -0,0 +1,38 @@
+//! Storage migrations of the pallet, to be added to the `Executive` migrations of a runtime.
+
+use crate::{Config, Exchanges, LiquidityTokens, Pallet, ReservesUpdatedAt};
+use frame_support::{
+    traits::{Get, GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+    weights::Weight,
+};
+use sp_std::marker::PhantomData;
+
+/// Migration to storage version 1: backfill the `LiquidityTokens` reverse index and the
+/// `ReservesUpdatedAt` blocks of the exchanges created before they were introduced.
+///
+/// The block in which the reserves of an existing exchange were last updated is unknown, so it is
+/// set to the block of the upgrade. Exchanges which already have an entry keep it.
+pub struct MigrateToV1<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() >= 1 {
+            return T::DbWeight::get().reads(1);
+        }
+        let now = <frame_system::Pallet<T>>::block_number();
+        let (mut reads, mut writes) = (1, 1);
+        for (asset_id, exchange) in <Exchanges<T>>::iter() {
+            reads += 3;
+            if !<LiquidityTokens<T>>::contains_key(&exchange.liquidity_token_id) {
+                <LiquidityTokens<T>>::insert(&exchange.liquidity_token_id, &asset_id);
+                writes += 1;
+            }
+            if !<ReservesUpdatedAt<T>>::contains_key(&asset_id) {
+                <ReservesUpdatedAt<T>>::insert(&asset_id, now);
+                writes += 1;
+            }
+        }
+        StorageVersion::new(1).put::<Pallet<T>>();
+        T::DbWeight::get().reads_writes(reads, writes)
+    }
+}
@@ -1,9 +1,14 @@
 use crate as dex;
-use frame_support::traits::{ConstU128, ConstU16, ConstU32, Everything, GenesisBuild};
-use frame_support::{parameter_types, PalletId};
-use frame_system::EnsureRoot;
+use frame_support::traits::{
+    ConstU128, ConstU16, ConstU32, Contains, Everything, GenesisBuild, IsInVec,
+};
+use frame_support::{ord_parameter_types, parameter_types, weights::IdentityFee, PalletId};
+use frame_system::{EnsureRoot, EnsureSignedBy};
 use sp_core::H256;
-use sp_runtime::traits::{BlakeTwo256, Identity, IdentityLookup};
+use sp_runtime::testing::{TestSignature, UintAuthorityId};
+use sp_runtime::traits::{BlakeTwo256, IdentityLookup};
+use sp_runtime::Permill;
+use std::cell::RefCell;
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -80,6 +85,16 @@ impl pallet_assets::Config for Test {
 
 parameter_types! {
     pub const DexPalletId: PalletId = PalletId(*b"dex_mock");
+    pub const ReserveMismatchTolerance: Permill = Permill::from_percent(1);
+    pub const FeeDestination: u64 = FEE_ACCOUNT;
+    // Includes a traded asset, so tests can check it is rejected anyway
+    pub MigratableLiquidityTokens: Vec<u32> = vec![LIQ_TOKEN_B, LIQ_TOKEN_B + 1, ASSET_A];
+    pub const RecoveryAccount: u64 = RECOVERY_ACCOUNT;
+    pub const BurnAccount: u64 = BURN_ACCOUNT;
+}
+
+ord_parameter_types! {
+    pub const Migrator: u64 = MIGRATOR;
 }
 
 impl dex::Config for Test {
@@ -87,24 +102,106 @@ impl dex::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type Currency = Balances;
     type AssetBalance = u128;
-    type AssetToCurrencyBalance = Identity;
-    type CurrencyToAssetBalance = Identity;
+    type BalanceConversion = dex::conversion::CheckedConversion;
     type AssetId = u32;
     type Assets = Assets;
     type AssetRegistry = Assets;
     type WeightInfo = ();
+    type WeightToFee = IdentityFee<u128>;
     // Provider fee is 0.3%
     type ProviderFeeNumerator = ConstU128<3>;
     type ProviderFeeDenominator = ConstU128<1000>;
     type MinDeposit = ConstU128<MIN_DEPOSIT>;
+    type DefaultDeadline = ConstU32<20>;
+    type MaxDeadline = ConstU32<MAX_DEADLINE>;
+    type MaxBootstrapAccounts = ConstU32<3>;
+    type MaxBootstrapPeriod = ConstU32<MAX_BOOTSTRAP_PERIOD>;
+    type ReserveMismatchTolerance = ReserveMismatchTolerance;
+    type MaxSeedDeposits = ConstU32<3>;
+    type FeeDestination = FeeDestination;
+    type MaxLiquidityOps = ConstU32<3>;
+    type MaxTradeOnlyPeriod = ConstU32<100>;
+    type StorageDeposit = ConstU128<STORAGE_DEPOSIT>;
+    type DelistingGracePeriod = ConstU32<10>;
+    type RecoveryAccount = RecoveryAccount;
+    type BurnAccount = BurnAccount;
+    type MaxReserveCheckpoints = ConstU32<3>;
+    type MaxPausedPerCall = ConstU32<3>;
+    type Curve = dex::curves::Curve;
+    type YieldCheckpointPeriod = ConstU32<10>;
+    type YieldPeriodsPerYear = ConstU32<365>;
+    type MaxYieldCheckpoints = ConstU32<3>;
+    type OnPoolCreated = PoolCreationRecorder;
+    type SafetyOracle = FlaggedAssets;
+    type GovernanceOrigin = EnsureRoot<u64>;
+    type MigrationOrigin = EnsureSignedBy<Migrator, u64>;
+    type MigratableLiquidityTokens = IsInVec<MigratableLiquidityTokens>;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = RfqSigner;
+}
+
+thread_local! {
+    pub(crate) static CREATED_POOLS: RefCell<Vec<(u32, u32, u64)>> = RefCell::new(vec![]);
+    pub(crate) static FLAGGED_ASSETS: RefCell<Vec<u32>> = RefCell::new(vec![]);
+}
+
+// Safety oracle mock, assets are flagged by tests
+pub struct FlaggedAssets;
+
+impl Contains<u32> for FlaggedAssets {
+    fn contains(asset_id: &u32) -> bool {
+        FLAGGED_ASSETS.with(|assets| assets.borrow().contains(asset_id))
+    }
+}
+
+pub(crate) fn flag_asset(asset_id: u32) {
+    FLAGGED_ASSETS.with(|assets| assets.borrow_mut().push(asset_id));
+}
+
+// Records all created pools, so tests can check the `OnPoolCreated` hook was called
+pub struct PoolCreationRecorder;
+
+impl dex::OnPoolCreated<u64, u32> for PoolCreationRecorder {
+    fn on_pool_created(asset_id: &u32, liquidity_token_id: &u32, creator: &u64) {
+        CREATED_POOLS.with(|pools| {
+            pools
+                .borrow_mut()
+                .push((*asset_id, *liquidity_token_id, *creator))
+        });
+    }
+}
+
+// Signs RFQ quotes in benchmarks (`TestSignature` is valid for the signer and payload it holds)
+#[cfg(feature = "runtime-benchmarks")]
+pub struct RfqSigner;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl dex::RfqBenchmarkHelper<u64, TestSignature> for RfqSigner {
+    fn signer() -> u64 {
+        MARKET_MAKER
+    }
+
+    fn sign(signer: &u64, payload: &[u8]) -> TestSignature {
+        TestSignature(*signer, payload.to_vec())
+    }
 }
 
 pub(crate) const ACCOUNT_A: u64 = 0;
 pub(crate) const ACCOUNT_B: u64 = 1;
 pub(crate) const ACCOUNT_C: u64 = 2;
+pub(crate) const MARKET_MAKER: u64 = ACCOUNT_C;
+pub(crate) const MIGRATOR: u64 = ACCOUNT_C;
+pub(crate) const FEE_ACCOUNT: u64 = 99;
+pub(crate) const RECOVERY_ACCOUNT: u64 = 98;
+pub(crate) const BURN_ACCOUNT: u64 = 97;
 pub(crate) const INIT_BALANCE: u128 = 1_000_000_000_000_000;
 pub(crate) const INIT_LIQUIDITY: u128 = 1_000_000_000_000;
 pub(crate) const MIN_DEPOSIT: u128 = 1;
+pub(crate) const MAX_DEADLINE: u32 = 100;
+pub(crate) const MAX_BOOTSTRAP_PERIOD: u32 = 50;
+pub(crate) const STORAGE_DEPOSIT: u128 = 10;
 pub(crate) const ASSET_A: u32 = 100;
 pub(crate) const ASSET_B: u32 = 101;
 pub(crate) const LIQ_TOKEN_A: u32 = 200;
@@ -142,6 +239,7 @@ pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
 
     dex::GenesisConfig::<Test> {
         exchanges: vec![(ACCOUNT_A, ASSET_A, LIQ_TOKEN_A, INIT_LIQUIDITY, INIT_LIQUIDITY)],
+        market_makers: vec![(MARKET_MAKER, false, INIT_BALANCE, INIT_BALANCE)],
     }
     .assimilate_storage(&mut storage)
     .unwrap();
@@ -0,0 +1,123 @@
+//! Pure pricing functions of the constant product formula, independent of the exchange storage.
+//! They can be used by other pallets and tests to compute expected trade amounts.
+//!
+//! The provider fee is a fraction `fee_numerator / fee_denominator` of the input amount, which
+//! must not exceed 100%. All results are rounded in favour of the exchange: input prices are
+//! rounded down, while output prices are rounded down and increased by one (i.e. rounded up,
+//! possibly charging a single unit more).
+
+use crate::arithmetic::AuditedSaturating;
+use frame_support::sp_runtime::traits::AtLeast32BitUnsigned;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceError {
+    /// One of the reserves is zero, or the output amount is not lower than the output reserve
+    NotEnoughLiquidity,
+    /// An overflow occurred during price computation
+    Overflow,
+}
+
+/// Get the amount received for selling exactly `input_amount` to a pool with the given reserves.
+pub fn input_price<B: AtLeast32BitUnsigned + Copy>(
+    input_amount: B,
+    input_reserve: B,
+    output_reserve: B,
+    fee_numerator: B,
+    fee_denominator: B,
+) -> Result<B, PriceError> {
+    if input_reserve.is_zero() || output_reserve.is_zero() {
+        return Err(PriceError::NotEnoughLiquidity);
+    }
+    let net_numerator = fee_denominator
+        .checked_sub(&fee_numerator)
+        .ok_or(PriceError::Overflow)?;
+    let input_amount_with_fee = input_amount
+        .checked_mul(&net_numerator)
+        .ok_or(PriceError::Overflow)?;
+    let numerator = input_amount_with_fee
+        .checked_mul(&output_reserve)
+        .ok_or(PriceError::Overflow)?;
+    let denominator = input_reserve
+        .checked_mul(&fee_denominator)
+        .ok_or(PriceError::Overflow)?
+        .checked_add(&input_amount_with_fee)
+        .ok_or(PriceError::Overflow)?;
+    Ok(numerator / denominator)
+}
+
+/// Get the amount which must be sold to a pool with the given reserves to receive exactly `output_amount`.
+pub fn output_price<B: AtLeast32BitUnsigned + Copy>(
+    output_amount: B,
+    input_reserve: B,
+    output_reserve: B,
+    fee_numerator: B,
+    fee_denominator: B,
+) -> Result<B, PriceError> {
+    if input_reserve.is_zero() || output_amount >= output_reserve {
+        return Err(PriceError::NotEnoughLiquidity);
+    }
+    let net_numerator = fee_denominator
+        .checked_sub(&fee_numerator)
+        .ok_or(PriceError::Overflow)?;
+    let numerator = input_reserve
+        .checked_mul(&output_amount)
+        .ok_or(PriceError::Overflow)?
+        .checked_mul(&fee_denominator)
+        .ok_or(PriceError::Overflow)?;
+    let denominator = output_reserve
+        .audited_sub(output_amount)
+        .checked_mul(&net_numerator)
+        .ok_or(PriceError::Overflow)?;
+    if denominator.is_zero() {
+        return Err(PriceError::Overflow);
+    }
+    Ok((numerator / denominator).audited_add(B::one()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_price_with_fee() {
+        // 1_000_000 - 0.3% fee, slightly decreased by the price impact
+        assert_eq!(
+            input_price(1_000_000u128, 1_000_000_000_000, 1_000_000_000_000, 3, 1000),
+            Ok(996_999)
+        );
+    }
+
+    #[test]
+    fn input_price_rounds_down() {
+        assert_eq!(input_price(1u128, 1_000, 1_000, 0, 1), Ok(0));
+        assert_eq!(input_price(2u128, 2, 3, 0, 1), Ok(1));
+    }
+
+    #[test]
+    fn input_price_errors() {
+        assert_eq!(input_price(1u128, 0, 1, 3, 1000), Err(PriceError::NotEnoughLiquidity));
+        assert_eq!(input_price(u128::MAX, 1, 1, 3, 1000), Err(PriceError::Overflow));
+        assert_eq!(input_price(1u128, 1, 1, 2, 1), Err(PriceError::Overflow));
+    }
+
+    #[test]
+    fn output_price_with_fee() {
+        assert_eq!(
+            output_price(1_000_000u128, 1_000_000_000_000, 1_000_000_000_000, 3, 1000),
+            Ok(1_003_011)
+        );
+    }
+
+    #[test]
+    fn output_price_rounds_up() {
+        assert_eq!(output_price(1u128, 1_000, 1_000, 0, 1), Ok(2));
+    }
+
+    #[test]
+    fn output_price_errors() {
+        assert_eq!(output_price(1u128, 1, 1, 3, 1000), Err(PriceError::NotEnoughLiquidity));
+        assert_eq!(output_price(1u128, 0, 2, 3, 1000), Err(PriceError::NotEnoughLiquidity));
+        assert_eq!(output_price(1u128, u128::MAX, 2, 3, 1000), Err(PriceError::Overflow));
+        assert_eq!(output_price(1u128, 1, 2, 1, 1), Err(PriceError::Overflow));
+    }
+}
@@ -1,18 +1,46 @@
-use crate::{AssetBalanceOf, AssetIdOf, BalanceOf, Config, ConfigHelper, Error, Pallet};
-use codec::{Decode, Encode};
-use scale_info::prelude::format;
-use sp_std::fmt::Debug;
+use crate::{
+    curves::TradeDirection, AccountIdOf, AssetBalanceOf, AssetIdOf, BalanceOf, Call, Config,
+    ConfigHelper, CreationFee, Curves, Error, ExchangeOf, Exchanges, LiquidityStreams, Pallet,
+    PegReference, PegReferences, Price, ReservesUpdatedAt, Sponsorships,
+};
+use codec::Decode;
+use frame_support::dispatch::UnfilteredDispatchable;
+use frame_support::sp_runtime::{
+    traits::{One, Saturating, Zero},
+    DispatchError, FixedPointNumber, ModuleError,
+};
+use frame_support::storage::{with_transaction, TransactionOutcome};
+use frame_support::traits::{fungibles::Inspect, Currency, Get, PalletInfoAccess};
+use frame_support::weights::Weight;
 use sp_std::vec::Vec;
+pub use substrate_dex_primitives::{
+    AccountPosition, AccountPositions, AssetStatus, DexParameters, ExchangeSnapshot, Quote,
+    QuoteRequest, QuoteSource, RpcError, RpcResult, SimulatedCall, SourcedQuote,
+};
 
-#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
-pub enum RpcError {
-    ExchangeNotFound,
-    NotEnoughLiquidity,
-    Overflow,
-    Unexpected(Vec<u8>),
-}
+pub type AssetStatusOf<T> = AssetStatus<BalanceOf<T>, AssetBalanceOf<T>>;
+
+/// Currency reserve, token reserve and the number of the block the reserves were last updated in.
+pub type ReservesOf<T> =
+    (BalanceOf<T>, AssetBalanceOf<T>, <T as frame_system::Config>::BlockNumber);
+
+pub type QuoteRequestOf<T> = QuoteRequest<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>>;
+pub type QuoteOf<T> =
+    Quote<BalanceOf<T>, AssetBalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+pub type SourcedQuoteOf<T> =
+    SourcedQuote<BalanceOf<T>, AssetBalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+
+pub type ExchangeSnapshotOf<T> = ExchangeSnapshot<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>>;
+
+pub type AccountPositionOf<T> = AccountPosition<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>>;
+pub type AccountPositionsOf<T> = AccountPositions<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>>;
+
+/// Maximum number of exchanges visited by a single `get_account_positions` query.
+pub const MAX_POSITIONS_PAGE: u32 = 100;
+
+pub type SimulatedCallOf<T> = SimulatedCall<<T as frame_system::Config>::RuntimeEvent>;
 
-pub type RpcResult<T> = Result<T, RpcError>;
+pub type DexParametersOf<T> = DexParameters<BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
 
 impl<T: Config> From<Error<T>> for RpcError {
     fn from(err: Error<T>) -> Self {
@@ -20,25 +48,191 @@ impl<T: Config> From<Error<T>> for RpcError {
             Error::ExchangeNotFound => Self::ExchangeNotFound,
             Error::NotEnoughLiquidity => Self::NotEnoughLiquidity,
             Error::Overflow => Self::Overflow,
-            err => Self::Unexpected(format!("{:?}", err).into_bytes()),
+            // Other errors keep their pallet and error index
+            err => DispatchError::from(err).into(),
         }
     }
 }
 
 impl<T: Config> Pallet<T> {
+    /// Map a dispatch error (e.g. from a simulated call) into [`RpcError`].
+    /// Errors of this pallet are mapped the same way as [`Error`], errors of other pallets
+    /// keep their pallet and error index too.
+    pub fn rpc_error(err: DispatchError) -> RpcError {
+        if let DispatchError::Module(ModuleError { index, error, .. }) = err {
+            if index as usize == <Self as PalletInfoAccess>::index() {
+                if let Ok(err) = Error::<T>::decode(&mut &error[..]) {
+                    return err.into();
+                }
+            }
+        }
+        err.into()
+    }
+
+    /// Get the exchange for the asset, if it has liquidity to quote prices from.
+    fn get_quoted_exchange(asset_id: &AssetIdOf<T>) -> RpcResult<ExchangeOf<T>> {
+        let exchange = Self::get_exchange(asset_id)?;
+        if exchange.currency_reserve.is_zero() || exchange.token_reserve.is_zero() {
+            return Err(RpcError::NoLiquidity);
+        }
+        Ok(exchange)
+    }
+
+    /// Get the currency and token amounts of the requested trade.
+    pub fn quote(request: QuoteRequestOf<T>) -> RpcResult<QuoteOf<T>> {
+        let exchange = Self::get_quoted_exchange(request.asset_id())?;
+        Self::quote_with_reserves(request, exchange.currency_reserve, exchange.token_reserve)
+    }
+
+    /// Get the currency and token amounts of the requested trade if the exchange had the given
+    /// reserves, e.g. after a pending transaction. Pure computation with the fee of the pallet and
+    /// the curve of the exchange: the exchange of the requested asset doesn't need to exist (the
+    /// default curve is used then), and its reserves are not used.
+    pub fn quote_with_reserves(
+        request: QuoteRequestOf<T>,
+        currency_reserve: BalanceOf<T>,
+        token_reserve: AssetBalanceOf<T>,
+    ) -> RpcResult<QuoteOf<T>> {
+        if currency_reserve.is_zero() || token_reserve.is_zero() {
+            return Err(RpcError::NoLiquidity);
+        }
+        let curve = <Curves<T>>::get(request.asset_id());
+        let (currency_amount, token_amount) = match request {
+            QuoteRequest::CurrencyToAssetInput {
+                currency_amount, ..
+            } => {
+                let price = Self::get_input_price(
+                    &curve,
+                    TradeDirection::CurrencyToAsset,
+                    &currency_amount,
+                    &currency_reserve,
+                    &T::asset_to_currency(token_reserve)?,
+                )?;
+                (currency_amount, T::currency_to_asset(price)?)
+            }
+            QuoteRequest::CurrencyToAssetOutput { token_amount, .. } => {
+                let price = Self::get_output_price(
+                    &curve,
+                    TradeDirection::CurrencyToAsset,
+                    &T::asset_to_currency(token_amount)?,
+                    &currency_reserve,
+                    &T::asset_to_currency(token_reserve)?,
+                )?;
+                (price, token_amount)
+            }
+            QuoteRequest::AssetToCurrencyInput { token_amount, .. } => {
+                let price = Self::get_input_price(
+                    &curve,
+                    TradeDirection::AssetToCurrency,
+                    &T::asset_to_currency(token_amount)?,
+                    &T::asset_to_currency(token_reserve)?,
+                    &currency_reserve,
+                )?;
+                (price, token_amount)
+            }
+            QuoteRequest::AssetToCurrencyOutput {
+                currency_amount, ..
+            } => {
+                let price = Self::get_output_price(
+                    &curve,
+                    TradeDirection::AssetToCurrency,
+                    &currency_amount,
+                    &T::asset_to_currency(token_reserve)?,
+                    &currency_reserve,
+                )?;
+                (currency_amount, T::currency_to_asset(price)?)
+            }
+        };
+        Ok(Quote {
+            currency_amount,
+            token_amount,
+            block_number: <frame_system::Pallet<T>>::block_number(),
+        })
+    }
+
+    /// Get the currency and token amounts of the requested trade like `quote`, unless the exchange
+    /// has a peg reference and its liquidity is too thin (its currency reserve is below the
+    /// reference's minimum, or the AMM can't quote the trade): the trade is then quoted at the pegged
+    /// price plus (buying the asset) or minus (selling the asset) the band of the reference.
+    pub fn quote_with_fallback(request: QuoteRequestOf<T>) -> RpcResult<SourcedQuoteOf<T>> {
+        let exchange = Self::get_exchange(request.asset_id())?;
+        let peg = match <PegReferences<T>>::get(request.asset_id()) {
+            Some(peg) => peg,
+            None => {
+                return Self::quote(request).map(|quote| SourcedQuote {
+                    quote,
+                    source: QuoteSource::Amm,
+                })
+            }
+        };
+        if exchange.currency_reserve >= peg.min_currency_reserve {
+            match Self::quote(request.clone()) {
+                Ok(quote) => {
+                    return Ok(SourcedQuote {
+                        quote,
+                        source: QuoteSource::Amm,
+                    })
+                }
+                Err(RpcError::NoLiquidity | RpcError::NotEnoughLiquidity) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(SourcedQuote {
+            quote: Self::quote_pegged(request, &peg)?,
+            source: QuoteSource::PegFallback,
+        })
+    }
+
+    /// Quote the trade at the pegged price, moved by the band in the trade's disadvantage.
+    /// Amounts are rounded down.
+    fn quote_pegged(
+        request: QuoteRequestOf<T>,
+        peg: &PegReference<BalanceOf<T>>,
+    ) -> RpcResult<QuoteOf<T>> {
+        let band = Price::from(peg.band);
+        let ask = peg.price.saturating_mul(Price::one().saturating_add(band));
+        let bid = peg.price.saturating_mul(Price::one().saturating_sub(band));
+        let divide = |price: Price, amount: AssetBalanceOf<T>| {
+            price
+                .reciprocal()
+                .and_then(|reciprocal| reciprocal.checked_mul_int(amount))
+                .ok_or(RpcError::Overflow)
+        };
+        let multiply = |price: Price, amount: AssetBalanceOf<T>| {
+            price.checked_mul_int(amount).ok_or(RpcError::Overflow)
+        };
+        let (currency_amount, token_amount) = match request {
+            QuoteRequest::CurrencyToAssetInput {
+                currency_amount, ..
+            } => (currency_amount, divide(ask, T::currency_to_asset(currency_amount)?)?),
+            QuoteRequest::CurrencyToAssetOutput { token_amount, .. } => {
+                (T::asset_to_currency(multiply(ask, token_amount)?)?, token_amount)
+            }
+            QuoteRequest::AssetToCurrencyInput { token_amount, .. } => {
+                (T::asset_to_currency(multiply(bid, token_amount)?)?, token_amount)
+            }
+            QuoteRequest::AssetToCurrencyOutput {
+                currency_amount, ..
+            } => (currency_amount, divide(bid, T::currency_to_asset(currency_amount)?)?),
+        };
+        Ok(Quote {
+            currency_amount,
+            token_amount,
+            block_number: <frame_system::Pallet<T>>::block_number(),
+        })
+    }
+
     /// Get the price for a fixed-input currency-to-asset trade,
     /// i.e. 'How much asset would I get if I paid this much currency'?
     pub fn get_currency_to_asset_input_price(
         asset_id: AssetIdOf<T>,
         currency_amount: BalanceOf<T>,
     ) -> RpcResult<AssetBalanceOf<T>> {
-        let exchange = Self::get_exchange(&asset_id)?;
-        let price = Self::get_input_price(
-            &currency_amount,
-            &exchange.currency_reserve,
-            &T::asset_to_currency(exchange.token_reserve),
-        )?;
-        Ok(T::currency_to_asset(price))
+        let request = QuoteRequest::CurrencyToAssetInput {
+            asset_id,
+            currency_amount,
+        };
+        Ok(Self::quote(request)?.token_amount)
     }
 
     /// Get the price for a fixed-output currency-to-asset trade,
@@ -47,13 +241,11 @@ impl<T: Config> Pallet<T> {
         asset_id: AssetIdOf<T>,
         token_amount: AssetBalanceOf<T>,
     ) -> RpcResult<BalanceOf<T>> {
-        let exchange = Self::get_exchange(&asset_id)?;
-        let price = Self::get_output_price(
-            &T::asset_to_currency(token_amount),
-            &exchange.currency_reserve,
-            &T::asset_to_currency(exchange.token_reserve),
-        )?;
-        Ok(price)
+        let request = QuoteRequest::CurrencyToAssetOutput {
+            asset_id,
+            token_amount,
+        };
+        Ok(Self::quote(request)?.currency_amount)
     }
 
     /// Get the price for a fixed-input asset-to-currency trade,
@@ -62,13 +254,11 @@ impl<T: Config> Pallet<T> {
         asset_id: AssetIdOf<T>,
         token_amount: AssetBalanceOf<T>,
     ) -> RpcResult<BalanceOf<T>> {
-        let exchange = Self::get_exchange(&asset_id)?;
-        let price = Self::get_input_price(
-            &T::asset_to_currency(token_amount),
-            &T::asset_to_currency(exchange.token_reserve),
-            &exchange.currency_reserve,
-        )?;
-        Ok(price)
+        let request = QuoteRequest::AssetToCurrencyInput {
+            asset_id,
+            token_amount,
+        };
+        Ok(Self::quote(request)?.currency_amount)
     }
 
     /// Get the price for a fixed-output currency-to-asset trade,
@@ -77,22 +267,259 @@ impl<T: Config> Pallet<T> {
         asset_id: AssetIdOf<T>,
         currency_amount: BalanceOf<T>,
     ) -> RpcResult<AssetBalanceOf<T>> {
+        let request = QuoteRequest::AssetToCurrencyOutput {
+            asset_id,
+            currency_amount,
+        };
+        Ok(Self::quote(request)?.token_amount)
+    }
+
+    /// Get the ID of the asset traded on the exchange with the given liquidity token,
+    /// i.e. 'Which pool does this liquidity token belong to'?
+    pub fn get_exchange_by_liquidity_token(
+        liquidity_token_id: AssetIdOf<T>,
+    ) -> RpcResult<AssetIdOf<T>> {
+        Self::get_asset_id_by_liquidity_token(&liquidity_token_id).ok_or(RpcError::ExchangeNotFound)
+    }
+
+    /// Get the current spot price of the asset (currency per one unit of the asset) on the curve
+    /// of the exchange, rounded down: the ratio of the exchange reserves for the default curve.
+    /// Fees are not included.
+    pub fn get_spot_price(asset_id: AssetIdOf<T>) -> RpcResult<Price> {
+        let exchange = Self::get_quoted_exchange(&asset_id)?;
+        Ok(Self::get_curve_spot_price(&exchange)?)
+    }
+
+    /// Get the reserves of the exchange together with the status of its asset for the recipient,
+    /// i.e. 'Can this account actually receive this much asset bought on the exchange'?
+    /// Status depends on the assets implementation (e.g. asset freezing, sufficiency, minimum balance).
+    pub fn get_asset_status(
+        asset_id: AssetIdOf<T>,
+        recipient: AccountIdOf<T>,
+        token_amount: AssetBalanceOf<T>,
+    ) -> RpcResult<AssetStatusOf<T>> {
         let exchange = Self::get_exchange(&asset_id)?;
-        let price = Self::get_output_price(
-            &currency_amount,
-            &T::asset_to_currency(exchange.token_reserve),
-            &exchange.currency_reserve,
-        )?;
-        Ok(T::currency_to_asset(price))
+        let can_receive = T::Assets::can_deposit(asset_id.clone(), &recipient, token_amount, false)
+            .into_result()
+            .map_err(RpcError::from);
+        Ok(AssetStatus {
+            currency_reserve: exchange.currency_reserve,
+            token_reserve: exchange.token_reserve,
+            min_balance: T::Assets::minimum_balance(asset_id),
+            can_receive,
+        })
+    }
+
+    /// Get the reserves of the exchange and the number of the block they were last updated in,
+    /// i.e. 'Has this pool changed since I last looked'? Zero reserves are returned as they are.
+    pub fn get_reserves(asset_id: AssetIdOf<T>) -> RpcResult<ReservesOf<T>> {
+        let exchange = Self::get_exchange(&asset_id)?;
+        let updated_at = <ReservesUpdatedAt<T>>::get(&asset_id).unwrap_or_else(Zero::zero);
+        Ok((exchange.currency_reserve, exchange.token_reserve, updated_at))
+    }
+
+    /// Get the reserves of the given exchanges (in the given order), see [`Self::get_reserves`].
+    pub fn get_reserves_batch(asset_ids: Vec<AssetIdOf<T>>) -> Vec<RpcResult<ReservesOf<T>>> {
+        asset_ids.into_iter().map(Self::get_reserves).collect()
+    }
+
+    /// Get the reserves and spot prices of the given exchanges (in the given order), all read
+    /// from the same state, i.e. 'What do these pools look like at this block'?
+    /// Each exchange is a separate result, so a missing or empty one doesn't fail the others.
+    pub fn get_exchanges_snapshot(
+        asset_ids: Vec<AssetIdOf<T>>,
+    ) -> Vec<RpcResult<ExchangeSnapshotOf<T>>> {
+        asset_ids
+            .into_iter()
+            .map(|asset_id| {
+                let spot_price = Self::get_spot_price(asset_id.clone())?;
+                let exchange = Self::get_exchange(&asset_id)?;
+                Ok(ExchangeSnapshot {
+                    asset_id,
+                    currency_reserve: exchange.currency_reserve,
+                    token_reserve: exchange.token_reserve,
+                    spot_price,
+                })
+            })
+            .collect()
+    }
+
+    /// Get the positions of the account (liquidity, liquidity streams and sponsorships) in at most
+    /// `limit` (capped by [`MAX_POSITIONS_PAGE`]) exchanges, starting after the `start_after` exchange.
+    /// Exchanges without a position of the account are visited but not returned, so a page may
+    /// contain fewer positions than `limit` even if `next` is set.
+    pub fn get_account_positions(
+        account: AccountIdOf<T>,
+        start_after: Option<AssetIdOf<T>>,
+        limit: u32,
+    ) -> AccountPositionsOf<T> {
+        let cursor = start_after.as_ref().map(<Exchanges<T>>::hashed_key_for);
+        let mut positions = Vec::new();
+        let mut last_visited = None;
+        let outcome = Self::iter_exchanges_bounded(
+            cursor,
+            limit.min(MAX_POSITIONS_PAGE),
+            Weight::MAX,
+            Weight::zero(),
+            |asset_id, exchange| {
+                last_visited = Some(asset_id.clone());
+                let liquidity =
+                    T::AssetRegistry::balance(exchange.liquidity_token_id.clone(), &account);
+                let streamed = <LiquidityStreams<T>>::get(asset_id, &account)
+                    .map(|stream| (stream.currency_remaining, stream.tokens_remaining));
+                let sponsorship = <Sponsorships<T>>::get(asset_id)
+                    .filter(|sponsorship| sponsorship.sponsor == account)
+                    .map(|_| <T as Config>::Currency::free_balance(&T::sponsor_account(asset_id)));
+                if liquidity.is_zero() && streamed.is_none() && sponsorship.is_none() {
+                    return;
+                }
+                positions.push(AccountPosition {
+                    asset_id: asset_id.clone(),
+                    liquidity,
+                    withdrawable_liquidity: T::AssetRegistry::reducible_balance(
+                        exchange.liquidity_token_id.clone(),
+                        &account,
+                        false,
+                    ),
+                    streamed,
+                    sponsorship,
+                });
+            },
+        );
+        AccountPositions {
+            positions,
+            next: outcome.cursor.and(last_visited.or(start_after)),
+        }
+    }
+
+    /// Dry-run the calls in order, as if dispatched by `caller` (without charging fees or weight),
+    /// each on the state left by the previous ones (a failed call leaves the state unchanged).
+    /// Return the result and emitted events of each call. All changes are discarded in the end.
+    pub fn simulate_calls(caller: AccountIdOf<T>, calls: Vec<Call<T>>) -> Vec<SimulatedCallOf<T>> {
+        let outcomes = with_transaction(|| {
+            let outcomes = calls
+                .into_iter()
+                .map(|call| {
+                    let first_event = <frame_system::Pallet<T>>::event_count() as usize;
+                    let origin = frame_system::RawOrigin::Signed(caller.clone()).into();
+                    let result = call
+                        .dispatch_bypass_filter(origin)
+                        .map(|_| ())
+                        .map_err(|error| Self::rpc_error(error.error));
+                    let events = <frame_system::Pallet<T>>::events()
+                        .into_iter()
+                        .skip(first_event)
+                        .map(|record| record.event)
+                        .collect();
+                    SimulatedCall { result, events }
+                })
+                .collect();
+            TransactionOutcome::Rollback(Ok::<_, DispatchError>(outcomes))
+        });
+        // Fails only if the transactional layer limit is reached
+        outcomes.unwrap_or_default()
+    }
+
+    /// Get the `Config` constants and governance-set parameters of the pallet.
+    pub fn dex_parameters() -> DexParametersOf<T> {
+        DexParameters {
+            provider_fee_numerator: T::ProviderFeeNumerator::get(),
+            provider_fee_denominator: T::ProviderFeeDenominator::get(),
+            min_deposit: T::MinDeposit::get(),
+            creation_fee: <CreationFee<T>>::get(),
+            default_deadline: T::DefaultDeadline::get(),
+            max_deadline: T::MaxDeadline::get(),
+            max_trade_only_period: T::MaxTradeOnlyPeriod::get(),
+            max_bootstrap_accounts: T::MaxBootstrapAccounts::get(),
+            max_seed_deposits: T::MaxSeedDeposits::get(),
+            max_liquidity_ops: T::MaxLiquidityOps::get(),
+            reserve_mismatch_tolerance: T::ReserveMismatchTolerance::get(),
+            storage_deposit: T::StorageDeposit::get(),
+            delisting_grace_period: T::DelistingGracePeriod::get(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::curves::{Curve, StableSwap};
     use crate::mock::*;
-    use crate::rpc::RpcError;
-    use crate::{AssetBalanceOf, AssetIdOf, BalanceOf, Exchange, Exchanges};
-    use frame_support::assert_noop;
+    use crate::rpc::{
+        AccountPosition, AccountPositions, AssetStatus, DexParameters, ExchangeSnapshot, Quote,
+        QuoteOf, QuoteRequest, QuoteSource, RpcError, RpcResult, SimulatedCall, SourcedQuote,
+    };
+    use crate::{
+        AssetBalanceOf, AssetIdOf, BalanceOf, Curves, Error, Exchange, Exchanges, PegReference,
+        Price,
+    };
+    use frame_support::sp_runtime::{
+        traits::One, ArithmeticError, DispatchError, FixedPointNumber, Permill, TokenError,
+    };
+    use frame_support::{assert_noop, assert_ok};
+
+    #[test]
+    fn rpc_error_from_dex_error() {
+        assert_eq!(Dex::rpc_error(Error::<Test>::Overflow.into()), RpcError::Overflow);
+        assert_eq!(
+            Dex::rpc_error(Error::<Test>::ExchangeNotFound.into()),
+            RpcError::ExchangeNotFound
+        );
+        // Dex pallet has index 3, `DeadlinePassed` error has index 27
+        assert_eq!(
+            Dex::rpc_error(Error::<Test>::DeadlinePassed.into()),
+            RpcError::Module {
+                index: 3,
+                error: [27, 0, 0, 0]
+            }
+        );
+        assert_eq!(
+            RpcError::from(Error::<Test>::DeadlinePassed),
+            RpcError::Module {
+                index: 3,
+                error: [27, 0, 0, 0]
+            }
+        );
+    }
+
+    #[test]
+    fn rpc_error_from_other_module_error() {
+        // Balances pallet has index 1, `InsufficientBalance` error has index 2
+        assert_eq!(
+            Dex::rpc_error(pallet_balances::Error::<Test>::InsufficientBalance.into()),
+            RpcError::Module {
+                index: 1,
+                error: [2, 0, 0, 0]
+            }
+        );
+    }
+
+    #[test]
+    fn rpc_error_from_arithmetic_and_token_errors() {
+        assert_eq!(
+            Dex::rpc_error(DispatchError::Arithmetic(ArithmeticError::Overflow)),
+            RpcError::Arithmetic(ArithmeticError::Overflow)
+        );
+        assert_eq!(
+            Dex::rpc_error(DispatchError::Token(TokenError::NoFunds)),
+            RpcError::Token(TokenError::NoFunds)
+        );
+        assert!(matches!(Dex::rpc_error(DispatchError::BadOrigin), RpcError::Unexpected(_)));
+    }
+
+    #[test]
+    fn rpc_error_retryable() {
+        assert!(RpcError::NotEnoughLiquidity.is_retryable());
+        assert!(RpcError::NoLiquidity.is_retryable());
+        assert!(RpcError::Token(TokenError::Frozen).is_retryable());
+        assert!(RpcError::Token(TokenError::CannotCreate).is_retryable());
+        assert!(!RpcError::Token(TokenError::UnknownAsset).is_retryable());
+        assert!(!RpcError::ExchangeNotFound.is_retryable());
+        assert!(!RpcError::Overflow.is_retryable());
+        assert!(!Dex::rpc_error(DispatchError::BadOrigin).is_retryable());
+        assert!(!Dex::rpc_error(Error::<Test>::ExchangeNotFound.into()).is_retryable());
+        assert!(Dex::rpc_error(pallet_balances::Error::<Test>::InsufficientBalance.into())
+            .is_retryable());
+    }
 
     #[test]
     fn get_currency_to_asset_input_price_exchange_not_found() {
@@ -246,4 +673,498 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn get_spot_price_exchange_not_found() {
+        new_test_ext().execute_with(|| {
+            assert_noop!(Dex::get_spot_price(u32::MAX), RpcError::ExchangeNotFound);
+        })
+    }
+
+    #[test]
+    fn get_spot_price() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(Dex::get_spot_price(ASSET_A).unwrap(), Price::one());
+            Exchanges::<Test>::mutate(ASSET_A, |exchange| {
+                let exchange = exchange.as_mut().unwrap();
+                exchange.currency_reserve = 2;
+                exchange.token_reserve = 3;
+            });
+            // 2/3 rounded down
+            assert_eq!(
+                Dex::get_spot_price(ASSET_A).unwrap(),
+                Price::from_inner(666_666_666_666_666_666)
+            );
+            // The StableSwap curve keeps the price closer to parity
+            Curves::<Test>::insert(ASSET_A, Curve::StableSwap(StableSwap { amplification: 100 }));
+            let price = Dex::get_spot_price(ASSET_A).unwrap();
+            assert!(price > Price::from_inner(666_666_666_666_666_666) && price < Price::one());
+        })
+    }
+
+    #[test]
+    fn get_exchange_by_liquidity_token() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(Dex::get_exchange_by_liquidity_token(LIQ_TOKEN_A), Ok(ASSET_A));
+            assert_noop!(Dex::get_exchange_by_liquidity_token(ASSET_A), RpcError::ExchangeNotFound);
+        })
+    }
+
+    #[test]
+    fn quotes_no_liquidity() {
+        new_test_ext().execute_with(|| {
+            Exchanges::<Test>::mutate(ASSET_A, |exchange| {
+                let exchange = exchange.as_mut().unwrap();
+                exchange.currency_reserve = 0;
+                exchange.token_reserve = 0;
+            });
+            assert_noop!(Dex::get_currency_to_asset_input_price(ASSET_A, 1), RpcError::NoLiquidity);
+            assert_noop!(
+                Dex::get_currency_to_asset_output_price(ASSET_A, 1),
+                RpcError::NoLiquidity
+            );
+            assert_noop!(Dex::get_asset_to_currency_input_price(ASSET_A, 1), RpcError::NoLiquidity);
+            assert_noop!(
+                Dex::get_asset_to_currency_output_price(ASSET_A, 1),
+                RpcError::NoLiquidity
+            );
+            assert_noop!(Dex::get_spot_price(ASSET_A), RpcError::NoLiquidity);
+        })
+    }
+
+    #[test]
+    fn get_exchanges_snapshot() {
+        new_test_ext().execute_with(|| {
+            Exchanges::<Test>::mutate(ASSET_A, |exchange| {
+                let exchange = exchange.as_mut().unwrap();
+                exchange.currency_reserve = 2;
+                exchange.token_reserve = 1;
+            });
+            assert_eq!(
+                Dex::get_exchanges_snapshot(vec![ASSET_B, ASSET_A]),
+                vec![
+                    Err(RpcError::ExchangeNotFound),
+                    Ok(ExchangeSnapshot {
+                        asset_id: ASSET_A,
+                        currency_reserve: 2,
+                        token_reserve: 1,
+                        spot_price: Price::saturating_from_integer(2),
+                    }),
+                ]
+            );
+            assert_eq!(Dex::get_exchanges_snapshot(vec![]), vec![]);
+        })
+    }
+
+    #[test]
+    fn get_reserves() {
+        new_test_ext().execute_with(|| {
+            // Set at genesis
+            assert_eq!(Dex::get_reserves(ASSET_A), Ok((INIT_LIQUIDITY, INIT_LIQUIDITY, 0)));
+            assert_noop!(Dex::get_reserves(ASSET_B), RpcError::ExchangeNotFound);
+        })
+    }
+
+    #[test]
+    fn get_reserves_batch() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(
+                Dex::get_reserves_batch(vec![ASSET_B, ASSET_A]),
+                vec![
+                    Err(RpcError::ExchangeNotFound),
+                    Ok((INIT_LIQUIDITY, INIT_LIQUIDITY, 0))
+                ]
+            );
+        })
+    }
+
+    #[test]
+    fn get_asset_status() {
+        new_test_ext().execute_with(|| {
+            let status = AssetStatus {
+                currency_reserve: INIT_LIQUIDITY,
+                token_reserve: INIT_LIQUIDITY,
+                min_balance: 1,
+                can_receive: Ok(()),
+            };
+            assert_eq!(Dex::get_asset_status(ASSET_A, ACCOUNT_B, 1), Ok(status.clone()));
+            // Amount lower than the minimum balance can't create a new asset account
+            let asset_id = 300;
+            assert_ok!(Assets::force_create(RuntimeOrigin::root(), asset_id, ACCOUNT_A, true, 10));
+            assert_ok!(Assets::mint(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                asset_id,
+                ACCOUNT_A,
+                INIT_BALANCE
+            ));
+            assert_ok!(Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                asset_id,
+                301,
+                INIT_LIQUIDITY,
+                INIT_LIQUIDITY,
+                None,
+                false
+            ));
+            assert_eq!(
+                Dex::get_asset_status(asset_id, 1337, 5),
+                Ok(AssetStatus {
+                    min_balance: 10,
+                    can_receive: Err(RpcError::Token(TokenError::BelowMinimum)),
+                    ..status
+                })
+            );
+            assert_noop!(Dex::get_asset_status(ASSET_B, ACCOUNT_B, 1), RpcError::ExchangeNotFound);
+        })
+    }
+
+    #[test]
+    fn quote_matches_price_methods() {
+        new_test_ext().execute_with(|| {
+            let asset_id = ASSET_A;
+            // Both reserves equal INIT_LIQUIDITY, so the prices are symmetric. Expected amounts:
+            //   * input price: 1_000 * 997 * 10^12 / (10^12 * 1_000 + 1_000 * 997), rounded down
+            //   * output price: 10^12 * 1_000 * 1_000 / ((10^12 - 1_000) * 997) + 1, rounded up
+            let (input_price, output_price) = (996, 1_004);
+            let quote = |currency_amount, token_amount| Quote {
+                currency_amount,
+                token_amount,
+                block_number: 1,
+            };
+            assert_eq!(
+                Dex::quote(QuoteRequest::CurrencyToAssetInput {
+                    asset_id,
+                    currency_amount: 1_000
+                }),
+                Ok(quote(1_000, input_price))
+            );
+            assert_eq!(Dex::get_currency_to_asset_input_price(asset_id, 1_000), Ok(input_price));
+            assert_eq!(
+                Dex::quote(QuoteRequest::CurrencyToAssetOutput {
+                    asset_id,
+                    token_amount: 1_000
+                }),
+                Ok(quote(output_price, 1_000))
+            );
+            assert_eq!(Dex::get_currency_to_asset_output_price(asset_id, 1_000), Ok(output_price));
+            assert_eq!(
+                Dex::quote(QuoteRequest::AssetToCurrencyInput {
+                    asset_id,
+                    token_amount: 1_000
+                }),
+                Ok(quote(input_price, 1_000))
+            );
+            assert_eq!(Dex::get_asset_to_currency_input_price(asset_id, 1_000), Ok(input_price));
+            assert_eq!(
+                Dex::quote(QuoteRequest::AssetToCurrencyOutput {
+                    asset_id,
+                    currency_amount: 1_000
+                }),
+                Ok(quote(1_000, output_price))
+            );
+            assert_eq!(Dex::get_asset_to_currency_output_price(asset_id, 1_000), Ok(output_price));
+            assert_eq!(
+                Dex::quote(QuoteRequest::AssetToCurrencyOutput {
+                    asset_id: u32::MAX,
+                    currency_amount: 1_000
+                }),
+                Err(RpcError::ExchangeNotFound)
+            );
+        })
+    }
+
+    #[test]
+    fn quote_with_reserves() {
+        new_test_ext().execute_with(|| {
+            let request = QuoteRequest::CurrencyToAssetInput {
+                asset_id: ASSET_A,
+                currency_amount: 1_000,
+            };
+            let exchange = Dex::exchanges(ASSET_A).unwrap();
+            assert_eq!(
+                Dex::quote_with_reserves(
+                    request.clone(),
+                    exchange.currency_reserve,
+                    exchange.token_reserve
+                ),
+                Dex::quote(request.clone())
+            );
+            // The hypothetical reserves are quoted, not the actual ones
+            assert_eq!(
+                Dex::quote_with_reserves(request.clone(), 2_000, 1_000),
+                Ok(Quote {
+                    currency_amount: 1_000,
+                    token_amount: 332,
+                    block_number: 1,
+                })
+            );
+            assert_eq!(
+                Dex::quote_with_reserves(
+                    QuoteRequest::AssetToCurrencyOutput {
+                        asset_id: u32::MAX,
+                        currency_amount: 1_000
+                    },
+                    1_000,
+                    1_000
+                ),
+                Err(RpcError::NotEnoughLiquidity)
+            );
+            assert_eq!(Dex::quote_with_reserves(request, 0, 1_000), Err(RpcError::NoLiquidity));
+        })
+    }
+
+    #[test]
+    fn quote_block_number() {
+        new_test_ext().execute_with(|| {
+            let request = QuoteRequest::CurrencyToAssetInput {
+                asset_id: ASSET_A,
+                currency_amount: 1_000,
+            };
+            let block_number = |quote: RpcResult<QuoteOf<Test>>| quote.map(|q| q.block_number);
+            assert_eq!(block_number(Dex::quote(request.clone())), Ok(1));
+            System::set_block_number(7);
+            assert_eq!(block_number(Dex::quote(request.clone())), Ok(7));
+            assert_eq!(
+                block_number(Dex::quote_with_reserves(request.clone(), 1_000, 1_000)),
+                Ok(7)
+            );
+            assert_eq!(
+                Dex::quote_with_fallback(request).map(|sourced| sourced.quote.block_number),
+                Ok(7)
+            );
+        })
+    }
+
+    #[test]
+    fn quote_with_fallback() {
+        new_test_ext().execute_with(|| {
+            let pegged = |request| Dex::quote_with_fallback(request).map(|sourced| sourced.quote);
+            let request = QuoteRequest::CurrencyToAssetInput {
+                asset_id: ASSET_A,
+                currency_amount: 1_000,
+            };
+            // Without a peg reference, the AMM is quoted
+            assert_eq!(
+                Dex::quote_with_fallback(request.clone()),
+                Ok(SourcedQuote {
+                    quote: Dex::quote(request.clone()).unwrap(),
+                    source: QuoteSource::Amm,
+                })
+            );
+            assert_eq!(
+                Dex::quote_with_fallback(QuoteRequest::CurrencyToAssetInput {
+                    asset_id: u32::MAX,
+                    currency_amount: 1_000,
+                }),
+                Err(RpcError::ExchangeNotFound)
+            );
+
+            // The exchange is too thin for the reference, the pegged price ± 1% is quoted
+            let mut peg = PegReference {
+                price: Price::one(),
+                band: Permill::from_percent(1),
+                min_currency_reserve: 2 * INIT_LIQUIDITY,
+            };
+            assert_ok!(Dex::set_peg_reference(RuntimeOrigin::root(), ASSET_A, Some(peg.clone())));
+            assert_eq!(
+                Dex::quote_with_fallback(request.clone()).map(|sourced| sourced.source),
+                Ok(QuoteSource::PegFallback)
+            );
+            assert_eq!(
+                pegged(request.clone()),
+                Ok(Quote {
+                    currency_amount: 1_000,
+                    token_amount: 990,
+                    block_number: 1,
+                })
+            );
+            assert_eq!(
+                pegged(QuoteRequest::CurrencyToAssetOutput {
+                    asset_id: ASSET_A,
+                    token_amount: 1_000,
+                }),
+                Ok(Quote {
+                    currency_amount: 1_010,
+                    token_amount: 1_000,
+                    block_number: 1,
+                })
+            );
+            assert_eq!(
+                pegged(QuoteRequest::AssetToCurrencyInput {
+                    asset_id: ASSET_A,
+                    token_amount: 1_000,
+                }),
+                Ok(Quote {
+                    currency_amount: 990,
+                    token_amount: 1_000,
+                    block_number: 1,
+                })
+            );
+            assert_eq!(
+                pegged(QuoteRequest::AssetToCurrencyOutput {
+                    asset_id: ASSET_A,
+                    currency_amount: 1_000,
+                }),
+                Ok(Quote {
+                    currency_amount: 1_000,
+                    token_amount: 1_010,
+                    block_number: 1,
+                })
+            );
+
+            // Above the minimum reserve, the AMM is quoted unless it can't quote the trade
+            peg.min_currency_reserve = INIT_LIQUIDITY;
+            assert_ok!(Dex::set_peg_reference(RuntimeOrigin::root(), ASSET_A, Some(peg)));
+            assert_eq!(
+                Dex::quote_with_fallback(request.clone()).map(|sourced| sourced.source),
+                Ok(QuoteSource::Amm)
+            );
+            assert_eq!(
+                Dex::quote_with_fallback(QuoteRequest::CurrencyToAssetOutput {
+                    asset_id: ASSET_A,
+                    token_amount: INIT_LIQUIDITY,
+                }),
+                Ok(SourcedQuote {
+                    quote: Quote {
+                        currency_amount: INIT_LIQUIDITY + INIT_LIQUIDITY / 100,
+                        token_amount: INIT_LIQUIDITY,
+                        block_number: 1,
+                    },
+                    source: QuoteSource::PegFallback,
+                })
+            );
+        })
+    }
+
+    #[test]
+    fn dex_parameters() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(Dex::set_creation_fee(RuntimeOrigin::root(), 1_000));
+            assert_eq!(
+                Dex::dex_parameters(),
+                DexParameters {
+                    provider_fee_numerator: 3,
+                    provider_fee_denominator: 1_000,
+                    min_deposit: MIN_DEPOSIT,
+                    creation_fee: 1_000,
+                    default_deadline: 20,
+                    max_deadline: MAX_DEADLINE,
+                    max_trade_only_period: 100,
+                    max_bootstrap_accounts: 3,
+                    max_seed_deposits: 3,
+                    max_liquidity_ops: 3,
+                    reserve_mismatch_tolerance: Permill::from_percent(1),
+                    storage_deposit: STORAGE_DEPOSIT,
+                    delisting_grace_period: 10,
+                }
+            );
+        })
+    }
+
+    #[test]
+    fn get_account_positions() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_B,
+                LIQ_TOKEN_B,
+                INIT_LIQUIDITY,
+                INIT_LIQUIDITY,
+                None,
+                false
+            ));
+            assert_ok!(Dex::sponsor_exchange(
+                RuntimeOrigin::signed(ACCOUNT_C),
+                ASSET_A,
+                1_000,
+                10,
+                10
+            ));
+            assert_ok!(Dex::stream_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_C),
+                ASSET_A,
+                500,
+                600,
+                5
+            ));
+            assert_eq!(
+                Dex::get_account_positions(ACCOUNT_C, None, 10),
+                AccountPositions {
+                    positions: vec![AccountPosition {
+                        asset_id: ASSET_A,
+                        liquidity: 0,
+                        withdrawable_liquidity: 0,
+                        streamed: Some((500, 600)),
+                        sponsorship: Some(1_000),
+                    }],
+                    next: None,
+                }
+            );
+            assert_eq!(
+                Dex::get_account_positions(ACCOUNT_B, None, 10),
+                AccountPositions {
+                    positions: vec![],
+                    next: None
+                }
+            );
+
+            // One exchange per page
+            let first = Dex::get_account_positions(ACCOUNT_A, None, 1);
+            assert_eq!(first.positions.len(), 1);
+            let next = first.next.unwrap();
+            assert_eq!(first.positions[0].asset_id, next);
+            let second = Dex::get_account_positions(ACCOUNT_A, Some(next), 1);
+            assert_eq!(second.positions.len(), 1);
+            assert_ne!(second.positions[0].asset_id, next);
+            assert_eq!(second.positions[0].liquidity, INIT_LIQUIDITY);
+            assert_eq!(second.positions[0].streamed, None);
+            let third = Dex::get_account_positions(ACCOUNT_A, second.next, 1);
+            assert_eq!(third.positions, vec![]);
+            assert_eq!(third.next, None);
+        })
+    }
+
+    #[test]
+    fn simulate_calls() {
+        new_test_ext().execute_with(|| {
+            let add_liquidity = crate::Call::add_liquidity {
+                asset_id: ASSET_A,
+                currency_amount: 1_000,
+                min_liquidity: 1,
+                max_tokens: 1_001,
+                deadline: None,
+                price_limit: None,
+            };
+            let remove_all_liquidity = crate::Call::remove_liquidity {
+                asset_id: ASSET_A,
+                liquidity_amount: INIT_LIQUIDITY,
+                min_currency: 1,
+                min_tokens: 1,
+                deadline: None,
+            };
+            let outcomes =
+                Dex::simulate_calls(ACCOUNT_B, vec![add_liquidity, remove_all_liquidity]);
+            assert_eq!(outcomes.len(), 2);
+            assert_eq!(outcomes[0].result, Ok(()));
+            assert_eq!(
+                outcomes[0].events.last(),
+                Some(&RuntimeEvent::Dex(crate::Event::LiquidityAdded(
+                    ACCOUNT_B, ASSET_A, 1_000, 1_001, 1_000
+                )))
+            );
+            // ACCOUNT_B only holds the liquidity added by the first call (`ProviderLiquidityTooLow`)
+            assert!(matches!(
+                &outcomes[1],
+                SimulatedCall {
+                    result: Err(RpcError::Module { index: 3, error: [5, 0, 0, 0] }),
+                    events,
+                } if events.is_empty()
+            ));
+
+            // Nothing is committed
+            assert_eq!(Dex::exchanges(ASSET_A).unwrap().currency_reserve, INIT_LIQUIDITY);
+            assert_eq!(Assets::maybe_balance(LIQ_TOKEN_A, &ACCOUNT_B), None);
+            assert!(System::events().is_empty());
+        })
+    }
 }
@@ -1,9 +1,24 @@
+use crate::curves::{Curve, StableSwap, Weighted};
 use crate::mock::*;
 use crate::pallet::ConfigHelper;
-use crate::{Error, TradeAmount};
+use crate::{
+    Bootstrap, DelegatedSwap, DepositItem, Donation, Error, Event, FeeShare, LiquidityOp,
+    MarketMaker, PegReference, PoolSubsystem, Price, PriceLimit, RfqQuote, RfqSide, TradeAmount,
+    TradeSizeLimit,
+};
+use codec::Encode;
 use frame_support::{
     assert_noop, assert_ok,
-    traits::{fungibles::Mutate, Currency},
+    sp_runtime::{FixedPointNumber, FixedU128, Permill},
+    traits::{
+        fungibles::{Create, Inspect, Mutate},
+        Currency, Get, Hooks, ReservableCurrency,
+    },
+    weights::Weight,
+};
+use sp_runtime::{
+    testing::TestSignature,
+    traits::{AccountIdConversion, Zero},
 };
 
 #[test]
@@ -14,7 +29,9 @@ fn create_exchange() {
             ASSET_B,
             LIQ_TOKEN_B,
             1,
-            1
+            1,
+            None,
+            false
         ));
         let exchange = Dex::exchanges(ASSET_B).unwrap();
         assert_eq!(exchange.asset_id, ASSET_B);
@@ -22,16 +39,86 @@ fn create_exchange() {
         assert_eq!(exchange.token_reserve, 1);
         assert_eq!(Assets::total_supply(exchange.liquidity_token_id), 1);
         assert!(
-            matches!(last_event(), crate::Event::ExchangeCreated(asset, _) if asset == ASSET_B)
+            matches!(last_event(), crate::Event::ExchangeCreated(asset, _, 0) if asset == ASSET_B)
+        );
+    })
+}
+
+#[test]
+fn create_exchange_with_creation_fee() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::set_creation_fee(RuntimeOrigin::root(), 1_000));
+        assert_eq!(Dex::creation_fee(), 1_000);
+        assert_eq!(last_event(), crate::Event::CreationFeeSet(1_000));
+
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            1,
+            1,
+            None,
+            false
+        ));
+        assert_eq!(Balances::free_balance(FEE_ACCOUNT), 1_000);
+        assert_eq!(Balances::free_balance(ACCOUNT_A), INIT_BALANCE - INIT_LIQUIDITY - 1_001);
+        assert_eq!(Dex::exchanges(ASSET_B).unwrap().currency_reserve, 1);
+        assert_eq!(last_event(), crate::Event::ExchangeCreated(ASSET_B, LIQ_TOKEN_B, 1_000));
+    })
+}
+
+#[test]
+fn create_exchange_creation_fee_balance_too_low() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::set_creation_fee(RuntimeOrigin::root(), 1_000));
+        assert_noop!(
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_B,
+                LIQ_TOKEN_B,
+                INIT_BALANCE - 999,
+                1,
+                None,
+                false
+            ),
+            Error::<Test>::BalanceTooLow
+        );
+    })
+}
+
+#[test]
+fn set_creation_fee_requires_governance() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::set_creation_fee(RuntimeOrigin::signed(ACCOUNT_A), 1_000),
+            frame_support::error::BadOrigin
         );
     })
 }
 
+#[test]
+fn create_exchange_notifies_listener() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            1,
+            1,
+            None,
+            false
+        ));
+        CREATED_POOLS.with(|pools| {
+            assert_eq!(*pools.borrow(), vec![(ASSET_B, LIQ_TOKEN_B, ACCOUNT_B)]);
+        });
+    })
+}
+
 #[test]
 fn create_exchange_unsigned() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::create_exchange(RuntimeOrigin::none(), ASSET_A, LIQ_TOKEN_A, 1, 1),
+            Dex::create_exchange(RuntimeOrigin::none(), ASSET_A, LIQ_TOKEN_A, 1, 1, None, false),
             frame_support::error::BadOrigin
         );
     })
@@ -41,7 +128,15 @@ fn create_exchange_unsigned() {
 fn create_exchange_currency_amount_too_low() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::create_exchange(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, LIQ_TOKEN_A, 0, 1),
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                LIQ_TOKEN_A,
+                0,
+                1,
+                None,
+                false
+            ),
             Error::<Test>::CurrencyAmountTooLow
         );
     })
@@ -51,7 +146,15 @@ fn create_exchange_currency_amount_too_low() {
 fn create_exchange_token_amount_zero() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::create_exchange(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, LIQ_TOKEN_A, 1, 0),
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                LIQ_TOKEN_A,
+                1,
+                0,
+                None,
+                false
+            ),
             Error::<Test>::TokenAmountIsZero
         );
     })
@@ -61,7 +164,15 @@ fn create_exchange_token_amount_zero() {
 fn create_exchange_asset_not_found() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::create_exchange(RuntimeOrigin::signed(ACCOUNT_A), 2137, LIQ_TOKEN_A, 1, 1),
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                2137,
+                LIQ_TOKEN_A,
+                1,
+                1,
+                None,
+                false
+            ),
             Error::<Test>::AssetNotFound
         );
     })
@@ -71,7 +182,15 @@ fn create_exchange_asset_not_found() {
 fn create_exchange_already_exists() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::create_exchange(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, LIQ_TOKEN_A, 1, 1),
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                LIQ_TOKEN_A,
+                1,
+                1,
+                None,
+                false
+            ),
             Error::<Test>::ExchangeAlreadyExists
         );
     })
@@ -81,9 +200,231 @@ fn create_exchange_already_exists() {
 fn create_exchange_token_id_taken() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::create_exchange(RuntimeOrigin::signed(ACCOUNT_A), ASSET_B, LIQ_TOKEN_A, 1, 1),
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_B,
+                LIQ_TOKEN_A,
+                1,
+                1,
+                None,
+                false
+            ),
+            Error::<Test>::TokenIdTaken
+        );
+    })
+}
+
+#[test]
+fn create_exchange_liquidity_token_has_holders() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(<Assets as Create<u64>>::create(LIQ_TOKEN_B, ACCOUNT_A, false, 1));
+        assert_ok!(Assets::mint_into(LIQ_TOKEN_B, &ACCOUNT_B, 1_000));
+        assert_noop!(
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_B,
+                LIQ_TOKEN_B,
+                1_000,
+                1_000,
+                None,
+                false
+            ),
+            Error::<Test>::LiquidityTokenHasHolders
+        );
+    })
+}
+
+#[test]
+fn create_exchange_migration() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        // Liquidity token left over by a removed exchange
+        assert_ok!(<Assets as Create<u64>>::create(LIQ_TOKEN_B, Test::pallet_account(), false, 1));
+        assert_ok!(Assets::mint_into(LIQ_TOKEN_B, &ACCOUNT_B, 1_000));
+
+        assert_noop!(
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_B,
+                LIQ_TOKEN_B,
+                2_000,
+                3_000,
+                None,
+                true
+            ),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            Dex::create_exchange(
+                RuntimeOrigin::signed(MIGRATOR),
+                ASSET_B,
+                LIQ_TOKEN_A,
+                2_000,
+                3_000,
+                None,
+                true
+            ),
             Error::<Test>::TokenIdTaken
         );
+        assert_noop!(
+            Dex::create_exchange(
+                RuntimeOrigin::signed(MIGRATOR),
+                ASSET_B,
+                LIQ_TOKEN_B + 1,
+                2_000,
+                3_000,
+                None,
+                true
+            ),
+            Error::<Test>::LiquidityTokenNotMigratable
+        );
+        // Only former liquidity tokens can be migrated
+        assert_ok!(<Assets as Create<u64>>::create(
+            LIQ_TOKEN_B + 2,
+            Test::pallet_account(),
+            false,
+            1
+        ));
+        assert_ok!(Assets::mint_into(LIQ_TOKEN_B + 2, &ACCOUNT_B, 1_000));
+        assert_noop!(
+            Dex::create_exchange(
+                RuntimeOrigin::signed(MIGRATOR),
+                ASSET_B,
+                LIQ_TOKEN_B + 2,
+                1_000,
+                3_000,
+                None,
+                true
+            ),
+            Error::<Test>::LiquidityTokenNotMigratable
+        );
+        assert_noop!(
+            Dex::create_exchange(
+                RuntimeOrigin::signed(MIGRATOR),
+                ASSET_B,
+                ASSET_A,
+                INIT_BALANCE,
+                3_000,
+                None,
+                true
+            ),
+            Error::<Test>::LiquidityTokenIsTraded
+        );
+        for currency_amount in [999, 1_001] {
+            assert_noop!(
+                Dex::create_exchange(
+                    RuntimeOrigin::signed(MIGRATOR),
+                    ASSET_B,
+                    LIQ_TOKEN_B,
+                    currency_amount,
+                    3_000,
+                    None,
+                    true
+                ),
+                Error::<Test>::MigrationReservesMismatch
+            );
+        }
+
+        // The reserves back the existing supply, no liquidity is minted
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(MIGRATOR),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            1_000,
+            3_000,
+            None,
+            true
+        ));
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::LiquidityAdded(MIGRATOR, ASSET_B, 1_000, 3_000, 0),
+                Event::ExchangeCreated(ASSET_B, LIQ_TOKEN_B, 0),
+            ]
+        );
+        assert_eq!(Assets::total_issuance(LIQ_TOKEN_B), 1_000);
+        assert_eq!(Assets::balance(LIQ_TOKEN_B, MIGRATOR), 0);
+        assert_eq!(Dex::get_liquidity_token_id(&ASSET_B), Some(LIQ_TOKEN_B));
+
+        // Existing holders can withdraw the reserves
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            1_000,
+            1_000,
+            3_000,
+            None
+        ));
+        assert_eq!(Dex::exchanges(ASSET_B), None);
+    })
+}
+
+#[test]
+fn get_or_create_exchange_creates() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::get_or_create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            1,
+            1
+        ));
+        assert_eq!(Dex::get_liquidity_token_id(&ASSET_B), Some(LIQ_TOKEN_B));
+        assert_eq!(last_event(), crate::Event::ExchangeCreated(ASSET_B, LIQ_TOKEN_B, 0));
+    })
+}
+
+#[test]
+fn get_or_create_exchange_existing() {
+    new_test_ext().execute_with(|| {
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_ok!(Dex::get_or_create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            LIQ_TOKEN_B,
+            1,
+            1
+        ));
+        assert_eq!(Dex::exchanges(ASSET_A).unwrap(), exchange);
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE);
+        assert!(Assets::maybe_balance(LIQ_TOKEN_B, &ACCOUNT_B).is_none());
+        assert_eq!(last_event(), crate::Event::ExchangeAlreadyExists(ASSET_A, LIQ_TOKEN_A));
+    })
+}
+
+#[test]
+fn get_or_create_exchange_unsigned() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::get_or_create_exchange(RuntimeOrigin::none(), ASSET_A, LIQ_TOKEN_A, 1, 1),
+            frame_support::error::BadOrigin
+        );
+    })
+}
+
+#[test]
+fn get_asset_id_by_liquidity_token() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Dex::get_asset_id_by_liquidity_token(&LIQ_TOKEN_A), Some(ASSET_A));
+        assert_eq!(Dex::get_asset_id_by_liquidity_token(&LIQ_TOKEN_B), None);
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false
+        ));
+        assert_eq!(Dex::get_asset_id_by_liquidity_token(&LIQ_TOKEN_B), Some(ASSET_B));
+    });
+}
+
+#[test]
+fn get_liquidity_token_id() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Dex::get_liquidity_token_id(&ASSET_A), Some(LIQ_TOKEN_A));
+        assert_eq!(Dex::get_liquidity_token_id(&ASSET_B), None);
     })
 }
 
@@ -96,7 +437,8 @@ fn add_liquidity() {
             1_000,
             1_000,
             1_001,
-            1,
+            Some(1),
+            None,
         ));
 
         let exchange = Dex::exchanges(ASSET_A).unwrap();
@@ -120,7 +462,7 @@ fn add_liquidity() {
 fn add_liquidity_unsigned() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::none(), ASSET_A, 1_000, 1_000, 1_000, 1),
+            Dex::add_liquidity(RuntimeOrigin::none(), ASSET_A, 1_000, 1_000, 1_000, Some(1), None),
             frame_support::error::BadOrigin
         );
     })
@@ -130,7 +472,15 @@ fn add_liquidity_unsigned() {
 fn add_liquidity_deadline_passed() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 1_000, 1_000, 1_000, 0),
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                1_000,
+                1_000,
+                1_000,
+                Some(0),
+                None
+            ),
             Error::<Test>::DeadlinePassed
         );
     })
@@ -140,7 +490,15 @@ fn add_liquidity_deadline_passed() {
 fn add_liquidity_zero_currency() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 0, 1_000, 1_000, 1),
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                0,
+                1_000,
+                1_000,
+                Some(1),
+                None
+            ),
             Error::<Test>::CurrencyAmountIsZero
         );
     })
@@ -150,7 +508,15 @@ fn add_liquidity_zero_currency() {
 fn add_liquidity_zero_tokens() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 1_000, 1_000, 0, 1),
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                1_000,
+                1_000,
+                0,
+                Some(1),
+                None
+            ),
             Error::<Test>::MaxTokensIsZero
         );
     })
@@ -166,7 +532,8 @@ fn add_liquidity_balance_too_low() {
                 INIT_BALANCE + 1,
                 1_000,
                 1_000,
-                1,
+                Some(1),
+                None,
             ),
             Error::<Test>::BalanceTooLow
         );
@@ -177,7 +544,15 @@ fn add_liquidity_balance_too_low() {
 fn add_liquidity_asset_not_found() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::signed(ACCOUNT_A), 2137, 1_000, 1_000, 1_000, 1),
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                2137,
+                1_000,
+                1_000,
+                1_000,
+                Some(1),
+                None
+            ),
             Error::<Test>::AssetNotFound
         );
     })
@@ -193,7 +568,8 @@ fn add_liquidity_not_enough_tokens() {
                 1_000,
                 1_000,
                 INIT_BALANCE + 1,
-                1,
+                Some(1),
+                None,
             ),
             Error::<Test>::NotEnoughTokens
         );
@@ -204,7 +580,15 @@ fn add_liquidity_not_enough_tokens() {
 fn add_liquidity_exchange_not_found() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_B, 1_000, 1_000, 1_000, 1),
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_B,
+                1_000,
+                1_000,
+                1_000,
+                Some(1),
+                None
+            ),
             Error::<Test>::ExchangeNotFound
         );
     })
@@ -214,7 +598,15 @@ fn add_liquidity_exchange_not_found() {
 fn add_liquidity_zero_min_liquidity() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A, 1_000, 0, 1_001, 1),
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                1_000,
+                0,
+                1_001,
+                Some(1),
+                None
+            ),
             Error::<Test>::MinLiquidityIsZero
         );
     })
@@ -224,7 +616,15 @@ fn add_liquidity_zero_min_liquidity() {
 fn add_liquidity_max_tokens_too_low() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A, 1_000, 1_000, 10, 1),
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                1_000,
+                1_000,
+                10,
+                Some(1),
+                None
+            ),
             Error::<Test>::MaxTokensTooLow
         );
     })
@@ -234,22 +634,112 @@ fn add_liquidity_max_tokens_too_low() {
 fn add_liquidity_min_liquidity_too_high() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::add_liquidity(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A, 1_000, 10_000, 1_001, 1),
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                1_000,
+                10_000,
+                1_001,
+                Some(1),
+                None
+            ),
             Error::<Test>::MinLiquidityTooHigh
         );
     })
 }
 
 #[test]
-fn remove_liquidity() {
+fn add_liquidity_price_within_limit() {
     new_test_ext().execute_with(|| {
-        assert_ok!(Dex::remove_liquidity(
-            RuntimeOrigin::signed(ACCOUNT_A),
+        let price_limit = PriceLimit {
+            expected_price: FixedU128::saturating_from_rational(101, 100),
+            tolerance: Permill::from_percent(1),
+            quoted_at: 1,
+            max_age: None,
+        };
+        assert_ok!(Dex::add_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            1_000,
+            1_001,
+            Some(1),
+            Some(price_limit),
+        ));
+    })
+}
+
+#[test]
+fn add_liquidity_price_moved() {
+    new_test_ext().execute_with(|| {
+        let price_limit = PriceLimit {
+            expected_price: FixedU128::saturating_from_rational(102, 100),
+            tolerance: Permill::from_percent(1),
+            quoted_at: 1,
+            max_age: None,
+        };
+        assert_noop!(
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                1_000,
+                1_000,
+                1_001,
+                Some(1),
+                Some(price_limit)
+            ),
+            Error::<Test>::PriceMoved
+        );
+    })
+}
+
+#[test]
+fn add_liquidity_quote_too_old() {
+    new_test_ext().execute_with(|| {
+        let price_limit = PriceLimit {
+            expected_price: FixedU128::saturating_from_rational(101, 100),
+            tolerance: Permill::from_percent(1),
+            quoted_at: 1,
+            max_age: Some(3),
+        };
+        System::set_block_number(5);
+        assert_noop!(
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                1_000,
+                1_000,
+                1_001,
+                None,
+                Some(price_limit.clone())
+            ),
+            Error::<Test>::QuoteTooOld
+        );
+        assert_ok!(Dex::add_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            1_000,
+            1_001,
+            None,
+            Some(PriceLimit {
+                max_age: Some(4),
+                ..price_limit
+            }),
+        ));
+    })
+}
+
+#[test]
+fn remove_liquidity() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
             ASSET_A,
             500,
             500,
-            500,
-            1,
+            500,
+            Some(1),
         ));
         let exchange = Dex::exchanges(ASSET_A).unwrap();
         assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - 500);
@@ -275,7 +765,7 @@ fn remove_liquidity() {
 fn remove_liquidity_unsigned() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::remove_liquidity(RuntimeOrigin::none(), ASSET_A, 500, 500, 500, 1),
+            Dex::remove_liquidity(RuntimeOrigin::none(), ASSET_A, 500, 500, 500, Some(1)),
             frame_support::error::BadOrigin
         );
     });
@@ -285,7 +775,14 @@ fn remove_liquidity_unsigned() {
 fn remove_liquidity_deadline_passed() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 500, 500, 0),
+            Dex::remove_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                500,
+                500,
+                Some(0)
+            ),
             Error::<Test>::DeadlinePassed
         );
     });
@@ -295,7 +792,7 @@ fn remove_liquidity_deadline_passed() {
 fn remove_zero_liquidity() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 0, 500, 500, 1),
+            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 0, 500, 500, Some(1)),
             crate::Error::<Test>::LiquidityAmountIsZero
         );
     });
@@ -305,7 +802,7 @@ fn remove_zero_liquidity() {
 fn remove_liquidity_min_currency_zero() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 0, 500, 1),
+            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 0, 500, Some(1)),
             crate::Error::<Test>::MinCurrencyIsZero
         );
     });
@@ -315,7 +812,7 @@ fn remove_liquidity_min_currency_zero() {
 fn remove_liquidity_min_tokens_zero() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 500, 0, 1),
+            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 500, 0, Some(1)),
             crate::Error::<Test>::MinTokensIsZero
         );
     });
@@ -325,7 +822,14 @@ fn remove_liquidity_min_tokens_zero() {
 fn remove_liquidity_exchange_not_found() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_B, 500, 500, 500, 1),
+            Dex::remove_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_B,
+                500,
+                500,
+                500,
+                Some(1)
+            ),
             crate::Error::<Test>::ExchangeNotFound
         );
     });
@@ -341,18 +845,61 @@ fn remove_liquidity_provider_liquidity_too_low() {
                 INIT_LIQUIDITY + 500,
                 INIT_LIQUIDITY + 500,
                 INIT_LIQUIDITY + 500,
-                1
+                Some(1)
             ),
             crate::Error::<Test>::ProviderLiquidityTooLow
         );
     });
 }
 
+#[test]
+fn remove_liquidity_frozen() {
+    new_test_ext().execute_with(|| {
+        // Pallet account is the freezer of all liquidity tokens
+        assert_ok!(Assets::freeze(
+            RuntimeOrigin::signed(Test::pallet_account()),
+            LIQ_TOKEN_A,
+            ACCOUNT_A
+        ));
+        assert_eq!(Dex::get_withdrawable_liquidity(&ASSET_A, &ACCOUNT_A), Ok(0));
+        assert_noop!(
+            Dex::remove_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                500,
+                500,
+                Some(1)
+            ),
+            crate::Error::<Test>::LiquidityFrozen
+        );
+    });
+}
+
+#[test]
+fn get_withdrawable_liquidity() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Dex::get_withdrawable_liquidity(&ASSET_A, &ACCOUNT_A), Ok(INIT_LIQUIDITY));
+        assert_eq!(Dex::get_withdrawable_liquidity(&ASSET_A, &ACCOUNT_B), Ok(0));
+        assert_eq!(
+            Dex::get_withdrawable_liquidity(&ASSET_B, &ACCOUNT_A),
+            Err(crate::Error::<Test>::ExchangeNotFound)
+        );
+    });
+}
+
 #[test]
 fn remove_liquidity_min_currency_too_high() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 1_500, 500, 1),
+            Dex::remove_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                1_500,
+                500,
+                Some(1)
+            ),
             crate::Error::<Test>::MinCurrencyTooHigh
         );
     });
@@ -362,12 +909,222 @@ fn remove_liquidity_min_currency_too_high() {
 fn remove_liquidity_min_tokens_too_high() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 500, 1_500, 1),
+            Dex::remove_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                500,
+                1_500,
+                Some(1)
+            ),
             crate::Error::<Test>::MinTokensTooHigh
         );
     });
 }
 
+#[test]
+fn remove_liquidity_with_slippage() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::remove_liquidity_with_slippage(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            500,
+            500,
+            500,
+            Permill::from_percent(1),
+            Some(1),
+        ));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - 500);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - 500);
+        assert_eq!(Assets::total_supply(exchange.liquidity_token_id), INIT_LIQUIDITY - 500);
+        assert_eq!(last_event(), crate::Event::LiquidityRemoved(ACCOUNT_A, ASSET_A, 500, 500, 500));
+    });
+}
+
+#[test]
+fn remove_zero_liquidity_with_slippage() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::remove_liquidity_with_slippage(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                0,
+                0,
+                0,
+                Permill::from_percent(1),
+                Some(1)
+            ),
+            Error::<Test>::LiquidityAmountIsZero
+        );
+    });
+}
+
+#[test]
+fn remove_liquidity_with_slippage_zero_currency() {
+    new_test_ext().execute_with(|| {
+        crate::Exchanges::<Test>::mutate(ASSET_A, |exchange| {
+            exchange.as_mut().unwrap().currency_reserve = 1;
+        });
+        assert_noop!(
+            Dex::remove_liquidity_with_slippage(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                500,
+                500,
+                Permill::from_percent(1),
+                Some(1)
+            ),
+            Error::<Test>::MinCurrencyTooHigh
+        );
+    });
+}
+
+#[test]
+fn remove_liquidity_with_slippage_rejects_shifted_reserves() {
+    new_test_ext().execute_with(|| {
+        // Reference amounts quoted before a trade shifts the reserves
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: INIT_LIQUIDITY / 10,
+                min_output: 1
+            },
+            None,
+            None,
+            false,
+            None
+        ));
+        // The entitlement is now ~550 currency and ~454 tokens
+        assert_noop!(
+            Dex::remove_liquidity_with_slippage(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                500,
+                500,
+                Permill::from_percent(1),
+                None
+            ),
+            Error::<Test>::MinTokensTooHigh
+        );
+        assert_ok!(Dex::remove_liquidity_with_slippage(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            500,
+            500,
+            500,
+            Permill::from_percent(10),
+            None
+        ));
+    });
+}
+
+#[test]
+fn remove_liquidity_exact_assets() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::remove_liquidity_exact_assets(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            500,
+            500,
+            500,
+            Some(1),
+        ));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - 500);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - 500);
+        assert_eq!(Assets::total_supply(exchange.liquidity_token_id), INIT_LIQUIDITY - 500);
+        assert_eq!(last_event(), crate::Event::LiquidityRemoved(ACCOUNT_A, ASSET_A, 500, 500, 500));
+
+        // The burned liquidity covers the larger share, the surplus tokens stay in the exchange
+        let currency_balance = Balances::free_balance(ACCOUNT_A);
+        let token_balance = Assets::balance(ASSET_A, ACCOUNT_A);
+        assert_ok!(Dex::remove_liquidity_exact_assets(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            1_000,
+            500,
+            1_000,
+            Some(1),
+        ));
+        assert_eq!(Balances::free_balance(ACCOUNT_A), currency_balance + 1_000);
+        assert_eq!(Assets::balance(ASSET_A, ACCOUNT_A), token_balance + 500);
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - 1_500);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - 1_000);
+        assert_eq!(Assets::total_supply(exchange.liquidity_token_id), INIT_LIQUIDITY - 1_500);
+        assert_eq!(
+            last_event(),
+            crate::Event::LiquidityRemoved(ACCOUNT_A, ASSET_A, 1_000, 500, 1_000)
+        );
+        assert_ok!(Dex::do_try_state());
+    });
+}
+
+#[test]
+fn remove_liquidity_exact_assets_errors() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::remove_liquidity_exact_assets(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                0,
+                500,
+                500,
+                Some(1)
+            ),
+            Error::<Test>::CurrencyAmountIsZero
+        );
+        assert_noop!(
+            Dex::remove_liquidity_exact_assets(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                0,
+                500,
+                Some(1)
+            ),
+            Error::<Test>::TokenAmountIsZero
+        );
+        assert_noop!(
+            Dex::remove_liquidity_exact_assets(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_B,
+                500,
+                500,
+                500,
+                Some(1)
+            ),
+            Error::<Test>::ExchangeNotFound
+        );
+        assert_noop!(
+            Dex::remove_liquidity_exact_assets(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                500,
+                499,
+                Some(1)
+            ),
+            Error::<Test>::MaxLiquidityTooLow
+        );
+        assert_noop!(
+            Dex::remove_liquidity_exact_assets(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                500,
+                500,
+                500,
+                Some(1)
+            ),
+            Error::<Test>::ProviderLiquidityTooLow
+        );
+    });
+}
+
 #[test]
 fn currency_to_asset_fixed_input() {
     new_test_ext().execute_with(|| {
@@ -381,7 +1138,9 @@ fn currency_to_asset_fixed_input() {
                 input_amount: curr_amount,
                 min_output: token_amount
             },
-            1,
+            Some(1),
+            None,
+            false,
             None
         ));
 
@@ -422,7 +1181,9 @@ fn currency_to_asset_fixed_output() {
                 max_input: curr_amount,
                 output_amount: token_amount,
             },
-            1,
+            Some(1),
+            None,
+            false,
             None
         ));
 
@@ -461,7 +1222,9 @@ fn currency_to_asset_unsigned() {
                     input_amount: 1,
                     min_output: 1
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             frame_support::error::BadOrigin
@@ -480,7 +1243,9 @@ fn currency_to_asset_deadline_passed() {
                     input_amount: 1,
                     min_output: 1
                 },
-                0,
+                Some(0),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::DeadlinePassed
@@ -488,6 +1253,51 @@ fn currency_to_asset_deadline_passed() {
     });
 }
 
+#[test]
+fn currency_to_asset_default_deadline() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 1_000,
+                min_output: 1
+            },
+            None,
+            None,
+            false,
+            None
+        ));
+    });
+}
+
+#[test]
+fn currency_to_asset_deadline_too_far() {
+    new_test_ext().execute_with(|| {
+        let trade = |deadline| {
+            Dex::currency_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: 1_000,
+                    min_output: 1,
+                },
+                Some(deadline),
+                None,
+                false,
+                None,
+            )
+        };
+        assert_noop!(trade(MAX_DEADLINE + 2), crate::Error::<Test>::DeadlineTooFar);
+        assert_ok!(trade(MAX_DEADLINE + 1));
+    });
+}
+
+#[test]
+fn default_deadline_within_max_deadline() {
+    <Dex as Hooks<u32>>::integrity_test();
+}
+
 #[test]
 fn currency_to_asset_currency_amount_zero() {
     new_test_ext().execute_with(|| {
@@ -499,7 +1309,9 @@ fn currency_to_asset_currency_amount_zero() {
                     input_amount: 0,
                     min_output: 100
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -518,7 +1330,9 @@ fn currency_to_asset_min_tokens_zero() {
                     input_amount: 100,
                     min_output: 0
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -537,7 +1351,9 @@ fn currency_to_asset_max_currency_zero() {
                     max_input: 0,
                     output_amount: 100
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -556,7 +1372,9 @@ fn currency_to_asset_token_amount_zero() {
                     max_input: 100,
                     output_amount: 0
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -579,7 +1397,9 @@ fn currency_to_asset_balance_too_low() {
                     input_amount: currency_amount,
                     min_output: min_tokens,
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::BalanceTooLow
@@ -598,7 +1418,9 @@ fn currency_to_asset_exchange_not_found() {
                     input_amount: 1,
                     min_output: 1
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::ExchangeNotFound
@@ -607,36 +1429,100 @@ fn currency_to_asset_exchange_not_found() {
 }
 
 #[test]
-fn currency_to_asset_min_tokens_too_high() {
+fn currency_to_asset_exchange_suspended() {
     new_test_ext().execute_with(|| {
+        flag_asset(ASSET_A);
         assert_noop!(
             Dex::currency_to_asset(
                 RuntimeOrigin::signed(ACCOUNT_B),
                 ASSET_A,
                 TradeAmount::FixedInput {
-                    input_amount: 10,
-                    min_output: 50
+                    input_amount: 1,
+                    min_output: 1
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
-            crate::Error::<Test>::MinTokensTooHigh
+            crate::Error::<Test>::ExchangeSuspended
         );
     });
 }
 
 #[test]
-fn currency_to_asset_max_currency_too_low() {
+fn currency_to_asset_trade_too_large() {
     new_test_ext().execute_with(|| {
+        assert_ok!(Dex::set_max_trade_size(
+            RuntimeOrigin::root(),
+            ASSET_A,
+            Some(TradeSizeLimit::ReserveShare(Permill::from_percent(1)))
+        ));
         assert_noop!(
             Dex::currency_to_asset(
                 RuntimeOrigin::signed(ACCOUNT_B),
                 ASSET_A,
-                TradeAmount::FixedOutput {
+                TradeAmount::FixedInput {
+                    input_amount: INIT_LIQUIDITY / 100 + 1,
+                    min_output: 1
+                },
+                Some(1),
+                None,
+                false,
+                None
+            ),
+            crate::Error::<Test>::TradeTooLarge
+        );
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: INIT_LIQUIDITY / 100,
+                min_output: 1
+            },
+            Some(1),
+            None,
+            false,
+            None
+        ));
+    });
+}
+
+#[test]
+fn currency_to_asset_min_tokens_too_high() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::currency_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: 10,
+                    min_output: 50
+                },
+                Some(1),
+                None,
+                false,
+                None
+            ),
+            crate::Error::<Test>::MinTokensTooHigh
+        );
+    });
+}
+
+#[test]
+fn currency_to_asset_max_currency_too_low() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::currency_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedOutput {
                     max_input: 10,
                     output_amount: 50
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::MaxCurrencyTooLow
@@ -655,7 +1541,9 @@ fn currency_to_asset_not_enough_liquidity() {
                     max_input: INIT_LIQUIDITY + 1000,
                     output_amount: INIT_LIQUIDITY + 1000
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::NotEnoughLiquidity
@@ -676,8 +1564,10 @@ fn currency_to_asset_transfer() {
                 input_amount: curr_amount,
                 min_output: token_amount,
             },
-            1,
-            Some(ACCOUNT_C)
+            Some(1),
+            Some(ACCOUNT_C),
+            false,
+            None
         ));
 
         assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - curr_amount);
@@ -696,6 +1586,61 @@ fn currency_to_asset_transfer() {
     });
 }
 
+#[test]
+fn currency_to_asset_provision_recipient() {
+    new_test_ext().execute_with(|| {
+        let recipient = 42;
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 501,
+                min_output: 498,
+            },
+            Some(1),
+            Some(recipient),
+            true,
+            None
+        ));
+
+        // Existential deposit (1) is funded out of the currency input
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 501);
+        assert_eq!(Balances::free_balance(recipient), 1);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &recipient), Some(498));
+        assert_eq!(Dex::exchanges(ASSET_A).unwrap().currency_reserve, INIT_LIQUIDITY + 500);
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                crate::Event::RecipientProvisioned(ASSET_A, recipient, 1),
+                crate::Event::CurrencyTradedForAsset(ASSET_A, ACCOUNT_B, recipient, 500, 498),
+            ]
+        );
+    });
+}
+
+#[test]
+fn currency_to_asset_provision_existing_recipient() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 500,
+                min_output: 498,
+            },
+            Some(1),
+            Some(ACCOUNT_C),
+            true,
+            None
+        ));
+        assert_eq!(Balances::free_balance(ACCOUNT_C), INIT_BALANCE);
+        assert_eq!(
+            last_event(),
+            crate::Event::CurrencyTradedForAsset(ASSET_A, ACCOUNT_B, ACCOUNT_C, 500, 498)
+        );
+    });
+}
+
 #[test]
 fn asset_to_currency_fixed_input() {
     new_test_ext().execute_with(|| {
@@ -709,7 +1654,8 @@ fn asset_to_currency_fixed_input() {
                 input_amount: token_amount,
                 min_output: curr_amount
             },
-            1,
+            Some(1),
+            None,
             None
         ));
 
@@ -748,7 +1694,8 @@ fn asset_to_currency_unsigned() {
                     input_amount: 1,
                     min_output: 1
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             frame_support::error::BadOrigin
@@ -767,7 +1714,8 @@ fn asset_to_currency_deadline_passed() {
                     input_amount: 1,
                     min_output: 1
                 },
-                0,
+                Some(0),
+                None,
                 None
             ),
             crate::Error::<Test>::DeadlinePassed
@@ -786,7 +1734,8 @@ fn asset_to_currency_min_currency_zero() {
                     input_amount: 100,
                     min_output: 0
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -805,7 +1754,8 @@ fn asset_to_currency_token_amount_zero() {
                     input_amount: 0,
                     min_output: 100
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -824,7 +1774,8 @@ fn asset_to_currency_currency_amount_zero() {
                     max_input: 100,
                     output_amount: 0
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -843,7 +1794,8 @@ fn asset_to_currency_max_tokens_is_zero() {
                     max_input: 0,
                     output_amount: 100
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -866,7 +1818,8 @@ fn asset_to_currency_not_enough_tokens() {
                     input_amount: token_amount,
                     min_output: min_currency
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::NotEnoughTokens
@@ -885,7 +1838,8 @@ fn asset_to_currency_exchange_not_found() {
                     input_amount: 1,
                     min_output: 1
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::ExchangeNotFound
@@ -893,6 +1847,27 @@ fn asset_to_currency_exchange_not_found() {
     });
 }
 
+#[test]
+fn asset_to_currency_exchange_suspended() {
+    new_test_ext().execute_with(|| {
+        flag_asset(ASSET_A);
+        assert_noop!(
+            Dex::asset_to_currency(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: 1,
+                    min_output: 1
+                },
+                Some(1),
+                None,
+                None
+            ),
+            crate::Error::<Test>::ExchangeSuspended
+        );
+    });
+}
+
 #[test]
 fn asset_to_currency_min_currency_too_high() {
     new_test_ext().execute_with(|| {
@@ -904,7 +1879,8 @@ fn asset_to_currency_min_currency_too_high() {
                     input_amount: 10,
                     min_output: 50
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::MinCurrencyTooHigh
@@ -923,7 +1899,8 @@ fn asset_to_currency_max_tokens_too_low() {
                     output_amount: 50,
                     max_input: 10
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::MaxTokensTooLow
@@ -942,7 +1919,8 @@ fn asset_to_currency_not_enough_liquidity() {
                     output_amount: INIT_LIQUIDITY + 1000,
                     max_input: INIT_LIQUIDITY + 1000
                 },
-                1,
+                Some(1),
+                None,
                 None
             ),
             crate::Error::<Test>::NotEnoughLiquidity
@@ -963,8 +1941,9 @@ fn asset_to_currency_transfer() {
                 input_amount: token_amount,
                 min_output: curr_amount
             },
-            1,
-            Some(ACCOUNT_C)
+            Some(1),
+            Some(ACCOUNT_C),
+            None
         ));
 
         assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_B), Some(INIT_BALANCE - token_amount));
@@ -996,7 +1975,8 @@ fn asset_to_currency_fixed_output() {
                 output_amount: curr_amount,
                 max_input: token_amount
             },
-            1,
+            Some(1),
+            None,
             None
         ));
 
@@ -1033,6 +2013,8 @@ fn asset_to_asset_fixed_input() {
             LIQ_TOKEN_B,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
+            None,
+            false,
         )
         .unwrap();
 
@@ -1048,7 +2030,9 @@ fn asset_to_asset_fixed_input() {
                 input_amount: sold_token_amount,
                 min_output: bought_token_amount,
             },
-            1,
+            Some(1),
+            None,
+            false,
             None
         ));
 
@@ -1081,8 +2065,17 @@ fn asset_to_asset_fixed_input() {
         );
 
         assert_eq!(
-            last_n_events(2),
+            last_n_events(5),
             vec![
+                crate::Event::AssetTradedForAsset(
+                    ASSET_A,
+                    ASSET_B,
+                    ACCOUNT_B,
+                    ACCOUNT_B,
+                    sold_token_amount,
+                    bought_token_amount,
+                ),
+                crate::Event::LegStarted(0),
                 crate::Event::AssetTradedForCurrency(
                     ASSET_A,
                     ACCOUNT_B,
@@ -1090,6 +2083,7 @@ fn asset_to_asset_fixed_input() {
                     curr_amount,
                     sold_token_amount,
                 ),
+                crate::Event::LegStarted(1),
                 crate::Event::CurrencyTradedForAsset(
                     ASSET_B,
                     pallet_account,
@@ -1102,6 +2096,98 @@ fn asset_to_asset_fixed_input() {
     });
 }
 
+#[test]
+fn asset_to_asset_intermediate_currency_stays_in_pallet() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false,
+        ));
+        // The trader holds no currency at all
+        let trader = 42;
+        assert_ok!(Assets::mint_into(ASSET_A, &trader, 1_000));
+        let pallet_account = Test::pallet_account();
+        let pallet_balance = Balances::free_balance(pallet_account);
+
+        assert_ok!(Dex::asset_to_asset(
+            RuntimeOrigin::signed(trader),
+            ASSET_A,
+            ASSET_B,
+            TradeAmount::FixedInput {
+                input_amount: 500,
+                min_output: 496,
+            },
+            Some(1),
+            None,
+            false,
+            None
+        ));
+
+        assert_eq!(Balances::free_balance(trader), 0);
+        assert_eq!(Balances::free_balance(pallet_account), pallet_balance);
+        assert_eq!(Assets::maybe_balance(ASSET_B, &trader), Some(496));
+        assert_eq!(
+            last_n_events(3),
+            vec![
+                crate::Event::AssetTradedForCurrency(ASSET_A, trader, pallet_account, 498, 500),
+                crate::Event::LegStarted(1),
+                crate::Event::CurrencyTradedForAsset(ASSET_B, pallet_account, trader, 498, 496),
+            ]
+        );
+    });
+}
+
+#[test]
+fn asset_to_asset_provision_recipient() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false,
+        ));
+        let recipient = 42;
+        let pallet_account = Test::pallet_account();
+
+        assert_ok!(Dex::asset_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            ASSET_B,
+            TradeAmount::FixedInput {
+                input_amount: 500,
+                min_output: 495,
+            },
+            Some(1),
+            Some(recipient),
+            true,
+            None
+        ));
+
+        // Existential deposit (1) is funded out of the intermediate currency
+        assert_eq!(Balances::free_balance(recipient), 1);
+        assert_eq!(Assets::maybe_balance(ASSET_B, &recipient), Some(495));
+        assert_eq!(Dex::exchanges(ASSET_A).unwrap().currency_reserve, INIT_LIQUIDITY - 498);
+        assert_eq!(Dex::exchanges(ASSET_B).unwrap().currency_reserve, INIT_LIQUIDITY + 497);
+        assert_eq!(
+            last_n_events(4),
+            vec![
+                crate::Event::AssetTradedForCurrency(ASSET_A, ACCOUNT_B, pallet_account, 498, 500),
+                crate::Event::LegStarted(1),
+                crate::Event::RecipientProvisioned(ASSET_B, recipient, 1),
+                crate::Event::CurrencyTradedForAsset(ASSET_B, pallet_account, recipient, 497, 495),
+            ]
+        );
+    });
+}
+
 #[test]
 fn asset_to_asset_unsigned() {
     new_test_ext().execute_with(|| {
@@ -1114,7 +2200,9 @@ fn asset_to_asset_unsigned() {
                     input_amount: 1,
                     min_output: 1
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             frame_support::error::BadOrigin
@@ -1134,7 +2222,9 @@ fn asset_to_asset_deadline_passed() {
                     input_amount: 1,
                     min_output: 1
                 },
-                0,
+                Some(0),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::DeadlinePassed
@@ -1154,7 +2244,9 @@ fn asset_to_asset_sold_token_amount_zero() {
                     input_amount: 0,
                     min_output: 1
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -1174,7 +2266,9 @@ fn asset_to_asset_min_bought_tokens_zero() {
                     input_amount: 1,
                     min_output: 0
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -1194,7 +2288,9 @@ fn asset_to_asset_max_sold_tokens_amount_zero() {
                     output_amount: 1,
                     max_input: 0
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -1214,7 +2310,9 @@ fn asset_to_asset_output_bought_token_amount_zero() {
                     output_amount: 0,
                     max_input: 1
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::TradeAmountIsZero
@@ -1231,6 +2329,8 @@ fn asset_to_asset_not_enough_tokens() {
             LIQ_TOKEN_B,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
+            None,
+            false,
         )
         .unwrap();
 
@@ -1249,7 +2349,9 @@ fn asset_to_asset_not_enough_tokens() {
                     input_amount: sold_token_amount,
                     min_output: bought_token_amount,
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::NotEnoughTokens
@@ -1269,7 +2371,9 @@ fn asset_to_asset_sold_asset_exchange_not_found() {
                     input_amount: 1,
                     min_output: 1
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::ExchangeNotFound
@@ -1289,7 +2393,9 @@ fn asset_to_asset_bought_asset_exchange_not_found() {
                     input_amount: 1,
                     min_output: 1
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::ExchangeNotFound
@@ -1297,6 +2403,77 @@ fn asset_to_asset_bought_asset_exchange_not_found() {
     });
 }
 
+#[test]
+fn asset_to_asset_exchange_suspended() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false
+        ));
+        flag_asset(ASSET_B);
+        assert_noop!(
+            Dex::asset_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                ASSET_B,
+                TradeAmount::FixedInput {
+                    input_amount: 1,
+                    min_output: 1
+                },
+                Some(1),
+                None,
+                false,
+                None
+            ),
+            crate::Error::<Test>::ExchangeSuspended
+        );
+    });
+}
+
+#[test]
+fn asset_to_asset_trade_too_large() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false
+        ));
+        assert_ok!(Dex::set_max_trade_size(
+            RuntimeOrigin::root(),
+            ASSET_B,
+            Some(TradeSizeLimit::Absolute {
+                max_currency: 1_000,
+                max_tokens: u128::MAX
+            })
+        ));
+        assert_noop!(
+            Dex::asset_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                ASSET_B,
+                TradeAmount::FixedInput {
+                    input_amount: 2_000,
+                    min_output: 1
+                },
+                Some(1),
+                None,
+                false,
+                None
+            ),
+            crate::Error::<Test>::TradeTooLarge
+        );
+    });
+}
+
 #[test]
 fn asset_to_asset_min_bought_tokens_too_high() {
     new_test_ext().execute_with(|| {
@@ -1306,6 +2483,8 @@ fn asset_to_asset_min_bought_tokens_too_high() {
             LIQ_TOKEN_B,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
+            None,
+            false,
         )
         .unwrap();
         assert_noop!(
@@ -1317,7 +2496,9 @@ fn asset_to_asset_min_bought_tokens_too_high() {
                     input_amount: 10,
                     min_output: 50
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::MinBoughtTokensTooHigh
@@ -1334,6 +2515,8 @@ fn asset_to_asset_max_sold_tokens_too_low() {
             LIQ_TOKEN_B,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
+            None,
+            false,
         )
         .unwrap();
         assert_noop!(
@@ -1345,7 +2528,9 @@ fn asset_to_asset_max_sold_tokens_too_low() {
                     output_amount: 50,
                     max_input: 10
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::MaxSoldTokensTooLow
@@ -1362,6 +2547,8 @@ fn asset_to_asset_not_enough_liquidity() {
             LIQ_TOKEN_B,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
+            None,
+            false,
         )
         .unwrap();
         assert_noop!(
@@ -1373,7 +2560,9 @@ fn asset_to_asset_not_enough_liquidity() {
                     output_amount: INIT_LIQUIDITY + 1000,
                     max_input: INIT_LIQUIDITY + 1000
                 },
-                1,
+                Some(1),
+                None,
+                false,
                 None
             ),
             crate::Error::<Test>::NotEnoughLiquidity
@@ -1390,6 +2579,8 @@ fn asset_to_asset_transfer() {
             LIQ_TOKEN_B,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
+            None,
+            false,
         )
         .unwrap();
 
@@ -1405,8 +2596,10 @@ fn asset_to_asset_transfer() {
                 input_amount: sold_token_amount,
                 min_output: bought_token_amount
             },
-            1,
-            Some(ACCOUNT_C)
+            Some(1),
+            Some(ACCOUNT_C),
+            false,
+            None
         ));
 
         assert_eq!(
@@ -1420,7 +2613,7 @@ fn asset_to_asset_transfer() {
 
         let pallet_account = Test::pallet_account();
         assert_eq!(
-            last_n_events(2),
+            last_n_events(3),
             vec![
                 crate::Event::AssetTradedForCurrency(
                     ASSET_A,
@@ -1429,6 +2622,7 @@ fn asset_to_asset_transfer() {
                     curr_amount,
                     sold_token_amount,
                 ),
+                crate::Event::LegStarted(1),
                 crate::Event::CurrencyTradedForAsset(
                     ASSET_B,
                     pallet_account,
@@ -1450,6 +2644,8 @@ fn asset_to_asset_fixed_output() {
             LIQ_TOKEN_B,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
+            None,
+            false,
         )
         .unwrap();
 
@@ -1465,7 +2661,9 @@ fn asset_to_asset_fixed_output() {
                 output_amount: bought_token_amount,
                 max_input: sold_token_amount
             },
-            1,
+            Some(1),
+            None,
+            false,
             None
         ));
 
@@ -1498,7 +2696,7 @@ fn asset_to_asset_fixed_output() {
         );
 
         assert_eq!(
-            last_n_events(2),
+            last_n_events(3),
             vec![
                 crate::Event::AssetTradedForCurrency(
                     ASSET_A,
@@ -1507,6 +2705,7 @@ fn asset_to_asset_fixed_output() {
                     curr_amount,
                     sold_token_amount,
                 ),
+                crate::Event::LegStarted(1),
                 crate::Event::CurrencyTradedForAsset(
                     ASSET_B,
                     pallet_account,
@@ -1528,6 +2727,8 @@ fn trade_assets_back_and_forth() {
             LIQ_TOKEN_B,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
+            None,
+            false,
         )
         .unwrap();
 
@@ -1544,7 +2745,9 @@ fn trade_assets_back_and_forth() {
                 output_amount: bought_token_amount,
                 max_input: sold_token_amount,
             },
-            1,
+            Some(1),
+            None,
+            false,
             None
         ));
         assert_ok!(Dex::asset_to_asset(
@@ -1555,7 +2758,9 @@ fn trade_assets_back_and_forth() {
                 output_amount: bought_token_amount,
                 max_input: sold_token_amount,
             },
-            1,
+            Some(1),
+            None,
+            false,
             None
         ));
 
@@ -1566,7 +2771,7 @@ fn trade_assets_back_and_forth() {
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY + 4,
-            1,
+            Some(1),
         ));
         assert_ok!(Dex::remove_liquidity(
             RuntimeOrigin::signed(ACCOUNT_A),
@@ -1574,7 +2779,7 @@ fn trade_assets_back_and_forth() {
             INIT_LIQUIDITY,
             INIT_LIQUIDITY,
             INIT_LIQUIDITY + 4,
-            1,
+            Some(1),
         ));
 
         // Account A should have received 4 (500-496) of both tokens as tx fees from account B
@@ -1585,3 +2790,3057 @@ fn trade_assets_back_and_forth() {
         assert_eq!(Assets::maybe_balance(ASSET_B, &ACCOUNT_B), Some(INIT_BALANCE - 4));
     });
 }
+
+#[test]
+fn sponsor_exchange() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A, 1_000, 10, 15));
+        let sponsorship = Dex::sponsorships(ASSET_A).unwrap();
+        assert_eq!(sponsorship.sponsor, ACCOUNT_C);
+        assert_eq!(sponsorship.rebate, 10);
+        assert_eq!(sponsorship.block_budget, 15);
+        assert_eq!(Balances::free_balance(ACCOUNT_C), INIT_BALANCE - 1_000);
+        assert_eq!(Balances::free_balance(Test::sponsor_account(&ASSET_A)), 1_000);
+        assert_eq!(
+            last_event(),
+            crate::Event::ExchangeSponsored(ASSET_A, ACCOUNT_C, 1_000, 10, 15)
+        );
+
+        // Top up
+        assert_ok!(Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A, 1_000, 20, 20));
+        assert_eq!(Dex::sponsorships(ASSET_A).unwrap().rebate, 20);
+        assert_eq!(Balances::free_balance(Test::sponsor_account(&ASSET_A)), 2_000);
+    });
+}
+
+#[test]
+fn sponsor_exchange_rebate_zero() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A, 1_000, 0, 15),
+            Error::<Test>::RebateIsZero
+        );
+    });
+}
+
+#[test]
+fn sponsor_exchange_not_found() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_B, 1_000, 10, 15),
+            Error::<Test>::ExchangeNotFound
+        );
+    });
+}
+
+#[test]
+fn sponsor_exchange_not_sponsor() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A, 1_000, 10, 15));
+        assert_noop!(
+            Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A, 1_000, 10, 15),
+            Error::<Test>::NotSponsor
+        );
+    });
+}
+
+#[test]
+fn sponsored_trades_pay_rebates() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A, 1_000, 10, 15));
+        let trade = || {
+            assert_ok!(Dex::asset_to_currency(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: 500,
+                    min_output: 1
+                },
+                Some(10),
+                None,
+                None
+            ));
+        };
+
+        trade();
+        assert_eq!(last_event(), crate::Event::RebatePaid(ASSET_A, ACCOUNT_B, 10));
+        // Only 5 left in the block budget
+        trade();
+        assert_eq!(last_event(), crate::Event::RebatePaid(ASSET_A, ACCOUNT_B, 5));
+        // Block budget exhausted
+        trade();
+        assert!(matches!(last_event(), crate::Event::AssetTradedForCurrency(..)));
+
+        System::set_block_number(2);
+        trade();
+        assert_eq!(last_event(), crate::Event::RebatePaid(ASSET_A, ACCOUNT_B, 10));
+        assert_eq!(Balances::free_balance(Test::sponsor_account(&ASSET_A)), 1_000 - 25);
+    });
+}
+
+#[test]
+fn sponsored_rebate_capped_at_fee() {
+    use crate::WeightInfo;
+    new_test_ext().execute_with(|| {
+        let fee = <Test as crate::Config>::WeightInfo::asset_to_currency().ref_time() as u128;
+        assert_ok!(Dex::sponsor_exchange(
+            RuntimeOrigin::signed(ACCOUNT_C),
+            ASSET_A,
+            fee * 10,
+            fee * 2,
+            fee * 10
+        ));
+        assert_ok!(Dex::asset_to_currency(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 500,
+                min_output: 1
+            },
+            Some(10),
+            None,
+            None
+        ));
+        assert_eq!(last_event(), crate::Event::RebatePaid(ASSET_A, ACCOUNT_B, fee));
+        assert_eq!(Balances::free_balance(Test::sponsor_account(&ASSET_A)), fee * 9);
+    });
+}
+
+#[test]
+fn withdraw_sponsorship() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A, 1_000, 10, 15));
+        assert_ok!(Dex::withdraw_sponsorship(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A));
+        assert!(Dex::sponsorships(ASSET_A).is_none());
+        assert_eq!(Balances::free_balance(ACCOUNT_C), INIT_BALANCE);
+        assert_eq!(Balances::free_balance(Test::sponsor_account(&ASSET_A)), 0);
+        assert_eq!(last_event(), crate::Event::SponsorshipWithdrawn(ASSET_A, ACCOUNT_C, 1_000));
+    });
+}
+
+#[test]
+fn withdraw_sponsorship_not_found() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::withdraw_sponsorship(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A),
+            Error::<Test>::SponsorshipNotFound
+        );
+    });
+}
+
+#[test]
+fn withdraw_sponsorship_not_sponsor() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A, 1_000, 10, 15));
+        assert_noop!(
+            Dex::withdraw_sponsorship(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A),
+            Error::<Test>::NotSponsor
+        );
+    });
+}
+
+#[test]
+fn try_state() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false
+        ));
+        assert_ok!(Dex::do_try_state_per_exchange(&ASSET_A));
+        assert_ok!(Dex::do_try_state_per_exchange(&ASSET_B));
+        assert_ok!(Dex::do_try_state());
+    });
+}
+
+#[test]
+fn try_state_per_exchange_broken_reserves() {
+    new_test_ext().execute_with(|| {
+        let mut exchange = Dex::exchanges(ASSET_A).unwrap();
+        exchange.token_reserve += 1;
+        crate::Exchanges::<Test>::insert(ASSET_A, exchange);
+        assert_eq!(
+            Dex::do_try_state_per_exchange(&ASSET_A),
+            Err("Token reserve exceeds the pallet account balance")
+        );
+        assert!(Dex::do_try_state().is_err());
+        assert_eq!(Dex::do_try_state_per_exchange(&ASSET_B), Err("Exchange not found"));
+    });
+}
+
+#[test]
+fn event_encoded_size() {
+    // Variant index + two accounts/asset IDs + three balances at most.
+    const MAX_EVENT_SIZE: usize = 1 + 2 * 8 + 3 * 16;
+    let (account, asset, balance) = (u64::MAX, u32::MAX, u128::MAX);
+    let events: Vec<Event<Test>> = vec![
+        Event::ExchangeCreated(asset, asset, balance),
+        Event::ExchangeAlreadyExists(asset, asset),
+        Event::ExchangeRemoved(asset, asset),
+        Event::LiquidityAdded(account, asset, balance, balance, balance),
+        Event::LiquidityRemoved(account, asset, balance, balance, balance),
+        Event::CurrencyTradedForAsset(asset, account, account, balance, balance),
+        Event::AssetTradedForCurrency(asset, account, account, balance, balance),
+        Event::ExchangeSponsored(asset, account, balance, balance, balance),
+        Event::SponsorshipWithdrawn(asset, account, balance),
+        Event::RebatePaid(asset, account, balance),
+        Event::RecipientProvisioned(asset, account, balance),
+        Event::RfqSettled(asset, account, account, balance, balance),
+        Event::MarketMakerRegistered(
+            account,
+            MarketMaker {
+                fee_exempt: true,
+                max_currency: balance,
+                max_tokens: balance,
+            },
+        ),
+        Event::MarketMakerDeregistered(account),
+        Event::LiquidityStreamStarted(asset, account, balance, balance),
+        Event::LiquidityStreamEnded(asset, account, balance, balance),
+        Event::OperatorApproved(account, account, asset, balance, Some(u32::MAX)),
+        Event::OperatorRevoked(account, account, asset),
+        Event::ReserveMismatch(asset, balance, balance),
+        Event::ExchangeSeeded(asset, u32::MAX),
+        Event::CreationFeeSet(balance),
+        Event::UnderlyingRedeemed(account, asset, balance, balance, balance),
+        Event::OutputDonated(asset, Some(account), balance, balance),
+        Event::RetentionFloorSet(asset, Some(balance)),
+        Event::TradeOnlyModeSet(asset, Some(u32::MAX)),
+        Event::AssetTradedForAsset(asset, asset, account, account, balance, balance),
+        Event::LiquidityBatchExecuted(account, u32::MAX),
+        Event::LegStarted(u32::MAX),
+        Event::DelistingStarted(asset, u32::MAX),
+        Event::DelistedExchangeSwept(asset, account, balance, balance),
+        Event::ExchangePaused(asset),
+        Event::ExchangeUnpaused(asset),
+        Event::FeeShareSet(
+            account,
+            Some(FeeShare {
+                beneficiary: account,
+                share: Permill::from_percent(100),
+            }),
+        ),
+        Event::FeeSharePaid(asset, account, account, balance, balance),
+        Event::CurveSet(
+            asset,
+            Some(Curve::StableSwap(StableSwap {
+                amplification: u32::MAX,
+            })),
+        ),
+        Event::PegReferenceSet(
+            asset,
+            Some(PegReference {
+                price: FixedU128::from_inner(u128::MAX),
+                band: Permill::from_percent(100),
+                min_currency_reserve: balance,
+            }),
+        ),
+        Event::MaxTradeSizeSet(
+            asset,
+            Some(TradeSizeLimit::Absolute {
+                max_currency: balance,
+                max_tokens: balance,
+            }),
+        ),
+        Event::SeedingApproved(account, asset),
+        Event::SeedingRevoked(account, asset),
+        Event::PauseSkipped(asset),
+        Event::UnpauseSkipped(asset),
+    ];
+    for event in events {
+        assert!(
+            event.encoded_size() <= MAX_EVENT_SIZE,
+            "{:?} exceeds the maximum event size",
+            event
+        );
+    }
+}
+
+#[test]
+fn set_max_trade_size() {
+    new_test_ext().execute_with(|| {
+        let limit = TradeSizeLimit::Absolute {
+            max_currency: 1,
+            max_tokens: 1,
+        };
+        assert_ok!(Dex::set_max_trade_size(RuntimeOrigin::root(), ASSET_A, Some(limit.clone())));
+        assert_eq!(Dex::max_trade_sizes(ASSET_A), Some(limit.clone()));
+        assert_eq!(last_event(), Event::MaxTradeSizeSet(ASSET_A, Some(limit)));
+        assert_noop!(
+            Dex::asset_to_currency(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: 2,
+                    min_output: 1
+                },
+                Some(1),
+                None,
+                None
+            ),
+            Error::<Test>::TradeTooLarge
+        );
+        assert_ok!(Dex::set_max_trade_size(RuntimeOrigin::root(), ASSET_A, None));
+        assert_eq!(Dex::max_trade_sizes(ASSET_A), None);
+        assert_eq!(last_event(), Event::MaxTradeSizeSet(ASSET_A, None));
+    });
+}
+
+#[test]
+fn set_max_trade_size_not_governance() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::set_max_trade_size(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, None),
+            frame_support::error::BadOrigin
+        );
+    });
+}
+
+#[test]
+fn set_max_trade_size_exchange_not_found() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::set_max_trade_size(RuntimeOrigin::root(), ASSET_B, None),
+            Error::<Test>::ExchangeNotFound
+        );
+    });
+}
+
+fn rfq_quote(side: RfqSide, hedge: bool) -> RfqQuote<u64, u32, u128, u128, u32> {
+    RfqQuote {
+        maker: MARKET_MAKER,
+        taker: None,
+        asset_id: ASSET_A,
+        side,
+        currency_amount: 1_000,
+        token_amount: 900,
+        expiry: 1,
+        nonce: 0,
+        hedge,
+    }
+}
+
+fn sign(quote: &RfqQuote<u64, u32, u128, u128, u32>) -> TestSignature {
+    TestSignature(quote.maker, Dex::rfq_signing_payload(quote))
+}
+
+#[test]
+fn settle_rfq_maker_sells() {
+    new_test_ext().execute_with(|| {
+        let quote = rfq_quote(RfqSide::MakerSells, false);
+        assert_ok!(Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)));
+        assert_eq!(Balances::free_balance(MARKET_MAKER), INIT_BALANCE + 1_000);
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 1_000);
+        assert_eq!(Assets::balance(ASSET_A, MARKET_MAKER), INIT_BALANCE - 900);
+        assert_eq!(Assets::balance(ASSET_A, ACCOUNT_B), INIT_BALANCE + 900);
+        assert_eq!(last_event(), Event::RfqSettled(ASSET_A, MARKET_MAKER, ACCOUNT_B, 1_000, 900));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY);
+    });
+}
+
+#[test]
+fn settle_rfq_maker_buys() {
+    new_test_ext().execute_with(|| {
+        let quote = rfq_quote(RfqSide::MakerBuys, false);
+        assert_ok!(Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)));
+        assert_eq!(Balances::free_balance(MARKET_MAKER), INIT_BALANCE - 1_000);
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE + 1_000);
+        assert_eq!(Assets::balance(ASSET_A, MARKET_MAKER), INIT_BALANCE + 900);
+        assert_eq!(Assets::balance(ASSET_A, ACCOUNT_B), INIT_BALANCE - 900);
+    });
+}
+
+#[test]
+fn settle_rfq_with_hedge() {
+    new_test_ext().execute_with(|| {
+        // Maker sells 900 tokens for 1000 currency, then buys them back from the exchange
+        let quote = rfq_quote(RfqSide::MakerSells, true);
+        assert_ok!(Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        let hedge_cost = exchange.currency_reserve - INIT_LIQUIDITY;
+        assert!(hedge_cost > 900 && hedge_cost < 1_000);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - 900);
+        assert_eq!(Assets::balance(ASSET_A, MARKET_MAKER), INIT_BALANCE);
+        assert_eq!(Balances::free_balance(MARKET_MAKER), INIT_BALANCE + 1_000 - hedge_cost);
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::CurrencyTradedForAsset(ASSET_A, MARKET_MAKER, MARKET_MAKER, hedge_cost, 900),
+                Event::RfqSettled(ASSET_A, MARKET_MAKER, ACCOUNT_B, 1_000, 900),
+            ]
+        );
+    });
+}
+
+#[test]
+fn settle_rfq_hedge_pays_fee_share() {
+    new_test_ext().execute_with(|| {
+        let fee_share = FeeShare {
+            beneficiary: ACCOUNT_A,
+            share: Permill::from_percent(100),
+        };
+        assert_ok!(Dex::set_fee_share(RuntimeOrigin::root(), MARKET_MAKER, Some(fee_share)));
+        let beneficiary_balance = Balances::free_balance(ACCOUNT_A);
+        let quote = rfq_quote(RfqSide::MakerSells, true);
+        assert_ok!(Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)));
+        // The whole provider fee (0.3%, rounded down) of the hedge is credited to the beneficiary
+        let events = last_n_events(3);
+        let hedge_cost = match events[2] {
+            Event::CurrencyTradedForAsset(_, _, _, currency_amount, _) => currency_amount,
+            _ => panic!("unexpected event"),
+        };
+        let share = hedge_cost * 3 / 1_000;
+        assert!(share > 0);
+        assert_eq!(events[1], Event::FeeSharePaid(ASSET_A, MARKET_MAKER, ACCOUNT_A, share, 0));
+        assert_eq!(Balances::free_balance(ACCOUNT_A), beneficiary_balance + share);
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + hedge_cost - share);
+    });
+}
+
+#[test]
+fn settle_rfq_hedge_at_loss() {
+    new_test_ext().execute_with(|| {
+        // Maker buys 900 tokens for 1000 currency, but can't sell them back for as much
+        let quote = rfq_quote(RfqSide::MakerBuys, true);
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)),
+            Error::<Test>::MinCurrencyTooHigh
+        );
+    });
+}
+
+#[test]
+fn settle_rfq_twice() {
+    new_test_ext().execute_with(|| {
+        let quote = rfq_quote(RfqSide::MakerSells, false);
+        assert_ok!(Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)));
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)),
+            Error::<Test>::QuoteAlreadySettled
+        );
+    });
+}
+
+#[test]
+fn settle_rfq_invalid_signature() {
+    new_test_ext().execute_with(|| {
+        let quote = rfq_quote(RfqSide::MakerSells, false);
+        let signature = sign(&quote);
+        let tampered = RfqQuote {
+            token_amount: 1_000,
+            ..quote
+        };
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), tampered, signature),
+            Error::<Test>::InvalidSignature
+        );
+    });
+}
+
+#[test]
+fn settle_rfq_requires_domain_separated_signature() {
+    new_test_ext().execute_with(|| {
+        let quote = rfq_quote(RfqSide::MakerSells, false);
+        // A signature of the bare quote (e.g. made for another chain or pallet) is rejected
+        let signature = TestSignature(quote.maker, quote.encode());
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), signature),
+            Error::<Test>::InvalidSignature
+        );
+        let genesis_hash = System::block_hash(0);
+        let signature = TestSignature(quote.maker, (b"dex/rfq", genesis_hash, &quote).encode());
+        assert_ok!(Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote, signature));
+    });
+}
+
+#[test]
+fn settle_rfq_not_market_maker() {
+    new_test_ext().execute_with(|| {
+        let quote = RfqQuote {
+            maker: ACCOUNT_A,
+            ..rfq_quote(RfqSide::MakerSells, false)
+        };
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)),
+            Error::<Test>::NotMarketMaker
+        );
+    });
+}
+
+#[test]
+fn settle_rfq_expired() {
+    new_test_ext().execute_with(|| {
+        let quote = RfqQuote {
+            expiry: 0,
+            ..rfq_quote(RfqSide::MakerSells, false)
+        };
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)),
+            Error::<Test>::DeadlinePassed
+        );
+    });
+}
+
+#[test]
+fn settle_rfq_expiry_not_capped() {
+    new_test_ext().execute_with(|| {
+        let quote = RfqQuote {
+            expiry: MAX_DEADLINE * 10,
+            ..rfq_quote(RfqSide::MakerSells, false)
+        };
+        assert_ok!(Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)));
+    });
+}
+
+#[test]
+fn settle_rfq_not_taker() {
+    new_test_ext().execute_with(|| {
+        let quote = RfqQuote {
+            taker: Some(ACCOUNT_A),
+            ..rfq_quote(RfqSide::MakerSells, false)
+        };
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)),
+            Error::<Test>::NotQuoteTaker
+        );
+    });
+}
+
+#[test]
+fn settle_rfq_quote_too_large() {
+    new_test_ext().execute_with(|| {
+        let parameters = MarketMaker {
+            fee_exempt: false,
+            max_currency: 1_000,
+            max_tokens: 899,
+        };
+        assert_ok!(Dex::register_market_maker(RuntimeOrigin::root(), MARKET_MAKER, parameters));
+        let quote = rfq_quote(RfqSide::MakerSells, false);
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)),
+            Error::<Test>::QuoteTooLarge
+        );
+    });
+}
+
+#[test]
+fn register_market_maker() {
+    new_test_ext().execute_with(|| {
+        let parameters = MarketMaker {
+            fee_exempt: true,
+            max_currency: 1,
+            max_tokens: 2,
+        };
+        assert!(!Dex::is_fee_exempt(&ACCOUNT_A));
+        assert_ok!(Dex::register_market_maker(
+            RuntimeOrigin::root(),
+            ACCOUNT_A,
+            parameters.clone()
+        ));
+        assert_eq!(Dex::market_makers(ACCOUNT_A), Some(parameters.clone()));
+        assert_eq!(last_event(), Event::MarketMakerRegistered(ACCOUNT_A, parameters));
+        assert!(Dex::is_fee_exempt(&ACCOUNT_A));
+        assert!(!Dex::is_fee_exempt(&MARKET_MAKER));
+
+        let mut market_makers = Dex::get_market_makers();
+        market_makers.sort_by_key(|(account, _)| *account);
+        assert_eq!(
+            market_makers
+                .iter()
+                .map(|(account, _)| *account)
+                .collect::<Vec<_>>(),
+            vec![ACCOUNT_A, MARKET_MAKER]
+        );
+    });
+}
+
+#[test]
+fn register_market_maker_not_governance() {
+    new_test_ext().execute_with(|| {
+        let parameters = MarketMaker {
+            fee_exempt: true,
+            max_currency: 1,
+            max_tokens: 1,
+        };
+        assert_noop!(
+            Dex::register_market_maker(RuntimeOrigin::signed(ACCOUNT_A), ACCOUNT_A, parameters),
+            frame_support::error::BadOrigin
+        );
+    });
+}
+
+#[test]
+fn deregister_market_maker() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::deregister_market_maker(RuntimeOrigin::root(), MARKET_MAKER));
+        assert_eq!(Dex::market_makers(MARKET_MAKER), None);
+        assert_eq!(last_event(), Event::MarketMakerDeregistered(MARKET_MAKER));
+        let quote = rfq_quote(RfqSide::MakerSells, false);
+        assert_noop!(
+            Dex::settle_rfq(RuntimeOrigin::signed(ACCOUNT_B), quote.clone(), sign(&quote)),
+            Error::<Test>::NotMarketMaker
+        );
+        assert_noop!(
+            Dex::deregister_market_maker(RuntimeOrigin::root(), MARKET_MAKER),
+            Error::<Test>::NotMarketMaker
+        );
+    });
+}
+
+#[test]
+fn remove_all_liquidity_removes_exchange() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::sponsor_exchange(RuntimeOrigin::signed(ACCOUNT_C), ASSET_A, 1_000, 10, 15));
+        let limit = TradeSizeLimit::ReserveShare(Permill::from_percent(10));
+        assert_ok!(Dex::set_max_trade_size(RuntimeOrigin::root(), ASSET_A, Some(limit)));
+
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+        ));
+        assert_eq!(last_event(), Event::ExchangeRemoved(ASSET_A, LIQ_TOKEN_A));
+        assert_eq!(Dex::exchanges(ASSET_A), None);
+        assert_eq!(Dex::liquidity_tokens(LIQ_TOKEN_A), None);
+        assert_eq!(Dex::sponsorships(ASSET_A), None);
+        assert_eq!(Dex::max_trade_sizes(ASSET_A), None);
+        assert_eq!(Balances::free_balance(ACCOUNT_C), INIT_BALANCE);
+        assert!(pallet_assets::Asset::<Test>::get(LIQ_TOKEN_A).is_none());
+        assert_ok!(Dex::do_try_state());
+
+        // The exchange can be created again
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            LIQ_TOKEN_A,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false
+        ));
+    });
+}
+
+#[test]
+fn remove_part_of_liquidity_keeps_exchange() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            INIT_LIQUIDITY - 1,
+            INIT_LIQUIDITY - 1,
+            INIT_LIQUIDITY - 1,
+            None,
+        ));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, 1);
+        assert_eq!(exchange.token_reserve, 1);
+        assert_eq!(Dex::liquidity_tokens(LIQ_TOKEN_A), Some(ASSET_A));
+    });
+}
+
+fn run_on_idle(block: u32) {
+    System::set_block_number(block);
+    Dex::on_idle(block, Weight::from_ref_time(u64::MAX));
+}
+
+#[test]
+fn stream_liquidity() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+
+        let stream = Dex::liquidity_streams(ASSET_A, ACCOUNT_B).unwrap();
+        assert_eq!(stream.currency_remaining, 1_000);
+        assert_eq!(stream.tokens_remaining, 2_000);
+        assert_eq!(stream.blocks_remaining, 2);
+        // The caller also funds the escrow sub-account with the existential deposit
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 1_000 - STORAGE_DEPOSIT - 1);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_B), Some(INIT_BALANCE - 2_000));
+        let escrow_account = Test::stream_escrow_account(&ASSET_A);
+        assert_eq!(Balances::free_balance(escrow_account), 1_001);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &escrow_account), Some(2_000));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY);
+        assert_eq!(Dex::escrowed_tokens(ASSET_A), 2_000);
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::RecipientProvisioned(ASSET_A, escrow_account, 1),
+                Event::LiquidityStreamStarted(ASSET_A, ACCOUNT_B, 1_000, 2_000),
+            ]
+        );
+
+        // The existential deposit is only paid once
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_C),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+        assert_eq!(Balances::free_balance(ACCOUNT_C), INIT_BALANCE - 1_000 - STORAGE_DEPOSIT);
+        assert_eq!(Balances::free_balance(escrow_account), 2_001);
+        assert_ok!(Dex::do_try_state());
+    })
+}
+
+#[test]
+fn stream_liquidity_invalid_parameters() {
+    new_test_ext().execute_with(|| {
+        let origin = RuntimeOrigin::signed(ACCOUNT_B);
+        assert_noop!(
+            Dex::stream_liquidity(origin.clone(), ASSET_A, 0, 2_000, 2),
+            Error::<Test>::CurrencyAmountIsZero
+        );
+        assert_noop!(
+            Dex::stream_liquidity(origin.clone(), ASSET_A, 1_000, 0, 2),
+            Error::<Test>::MaxTokensIsZero
+        );
+        assert_noop!(
+            Dex::stream_liquidity(origin.clone(), ASSET_A, 1_000, 2_000, 0),
+            Error::<Test>::BlocksIsZero
+        );
+        assert_noop!(
+            Dex::stream_liquidity(origin.clone(), ASSET_B, 1_000, 2_000, 2),
+            Error::<Test>::ExchangeNotFound
+        );
+        assert_noop!(
+            Dex::stream_liquidity(origin.clone(), ASSET_A, INIT_BALANCE, 2_000, 2),
+            Error::<Test>::BalanceTooLow
+        );
+        assert_noop!(
+            Dex::stream_liquidity(origin, ASSET_A, 1_000, INIT_BALANCE + 1, 2),
+            Error::<Test>::NotEnoughTokens
+        );
+    })
+}
+
+#[test]
+fn stream_liquidity_already_exists() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+        assert_noop!(
+            Dex::stream_liquidity(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A, 1_000, 2_000, 2),
+            Error::<Test>::StreamAlreadyExists
+        );
+    })
+}
+
+#[test]
+fn liquidity_stream_executed_on_idle() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+        let lp_token = Dex::get_liquidity_token_id(&ASSET_A).unwrap();
+
+        // Nothing streamed in the block the stream was started
+        run_on_idle(1);
+        assert_eq!(Dex::exchanges(ASSET_A).unwrap().currency_reserve, INIT_LIQUIDITY);
+
+        run_on_idle(2);
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + 500);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY + 501);
+        let stream = Dex::liquidity_streams(ASSET_A, ACCOUNT_B).unwrap();
+        assert_eq!(stream.currency_remaining, 500);
+        assert_eq!(stream.tokens_remaining, 1_499);
+        assert_eq!(stream.blocks_remaining, 1);
+        assert_eq!(Dex::escrowed_tokens(ASSET_A), 1_499);
+        assert_eq!(last_event(), Event::LiquidityAdded(ACCOUNT_B, ASSET_A, 500, 501, 500));
+
+        // Last part, unused tokens are returned
+        run_on_idle(3);
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + 1_000);
+        let tokens_added = exchange.token_reserve - INIT_LIQUIDITY;
+        assert!(Dex::liquidity_streams(ASSET_A, ACCOUNT_B).is_none());
+        assert!(!crate::EscrowedTokens::<Test>::contains_key(ASSET_A));
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 1_000 - 1);
+        // Only the existential deposit is left in the escrow sub-account
+        let escrow_account = Test::stream_escrow_account(&ASSET_A);
+        assert_eq!(Balances::free_balance(escrow_account), 1);
+        assert_eq!(Assets::balance(ASSET_A, escrow_account), 0);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_B), Some(INIT_BALANCE - tokens_added));
+        assert!(Assets::maybe_balance(lp_token, &ACCOUNT_B).unwrap() > 0);
+        assert_eq!(
+            last_event(),
+            Event::LiquidityStreamEnded(ASSET_A, ACCOUNT_B, 0, 2_000 - tokens_added)
+        );
+        assert_ok!(Dex::do_try_state());
+    })
+}
+
+#[test]
+fn liquidity_streams_resume_from_cursor() {
+    new_test_ext().execute_with(|| {
+        for provider in [ACCOUNT_B, ACCOUNT_C] {
+            assert_ok!(Dex::stream_liquidity(
+                RuntimeOrigin::signed(provider),
+                ASSET_A,
+                1_000,
+                2_000,
+                3
+            ));
+        }
+        // Weight for visiting and executing a single stream per block
+        use crate::WeightInfo;
+        let db_weight = <Test as frame_system::Config>::DbWeight::get();
+        let weight_limit = db_weight
+            .reads_writes(1, 1)
+            .saturating_add(db_weight.reads(2))
+            .saturating_add(<Test as crate::Config>::WeightInfo::execute_liquidity_stream());
+        let blocks_remaining = || {
+            let mut remaining = [ACCOUNT_B, ACCOUNT_C].map(|provider| {
+                Dex::liquidity_streams(ASSET_A, provider)
+                    .unwrap()
+                    .blocks_remaining
+            });
+            remaining.sort();
+            remaining
+        };
+
+        System::set_block_number(2);
+        Dex::on_idle(2, weight_limit);
+        assert_eq!(blocks_remaining(), [2, 3]);
+        assert!(crate::LiquidityStreamsCursor::<Test>::get().is_some());
+
+        // The other stream is executed in the next block, instead of the first one again
+        System::set_block_number(3);
+        Dex::on_idle(3, weight_limit);
+        assert_eq!(blocks_remaining(), [2, 2]);
+        assert_ok!(Dex::do_try_state());
+    })
+}
+
+#[test]
+fn liquidity_stream_not_executed_without_weight() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+
+        System::set_block_number(2);
+        assert_eq!(Dex::on_idle(2, Weight::zero()), Weight::zero());
+        assert_eq!(
+            Dex::liquidity_streams(ASSET_A, ACCOUNT_B)
+                .unwrap()
+                .blocks_remaining,
+            2
+        );
+    })
+}
+
+#[test]
+fn liquidity_stream_ends_when_tokens_run_out() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::stream_liquidity(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A, 1_000, 600, 2));
+
+        run_on_idle(2);
+        assert_eq!(
+            Dex::liquidity_streams(ASSET_A, ACCOUNT_B)
+                .unwrap()
+                .tokens_remaining,
+            99
+        );
+
+        // Remaining 99 tokens can't match the remaining 500 currency
+        run_on_idle(3);
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + 500);
+        assert!(Dex::liquidity_streams(ASSET_A, ACCOUNT_B).is_none());
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 500 - 1);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_B), Some(INIT_BALANCE - 501));
+        assert_eq!(last_event(), Event::LiquidityStreamEnded(ASSET_A, ACCOUNT_B, 500, 99));
+    })
+}
+
+#[test]
+fn cancel_liquidity_stream() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+        run_on_idle(2);
+
+        assert_ok!(Dex::cancel_liquidity_stream(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        assert!(Dex::liquidity_streams(ASSET_A, ACCOUNT_B).is_none());
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 500 - 1);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_B), Some(INIT_BALANCE - 501));
+        assert_eq!(last_event(), Event::LiquidityStreamEnded(ASSET_A, ACCOUNT_B, 500, 1_499));
+
+        // Nothing streamed after cancelling
+        run_on_idle(3);
+        assert_eq!(Dex::exchanges(ASSET_A).unwrap().currency_reserve, INIT_LIQUIDITY + 500);
+    })
+}
+
+#[test]
+fn cancel_liquidity_stream_not_found() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::cancel_liquidity_stream(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A),
+            Error::<Test>::StreamNotFound
+        );
+    })
+}
+
+fn create_bootstrapped_exchange(end: u32) {
+    let bootstrap = Bootstrap {
+        whitelist: vec![ACCOUNT_B].try_into().unwrap(),
+        end,
+    };
+    assert_ok!(Dex::create_exchange(
+        RuntimeOrigin::signed(ACCOUNT_B),
+        ASSET_B,
+        LIQ_TOKEN_B,
+        INIT_LIQUIDITY,
+        INIT_LIQUIDITY,
+        Some(bootstrap),
+        false
+    ));
+}
+
+fn buy_asset(account: u64, asset_id: u32) -> frame_support::dispatch::DispatchResult {
+    let amount = TradeAmount::FixedInput {
+        input_amount: 1_000,
+        min_output: 1,
+    };
+    Dex::currency_to_asset(
+        RuntimeOrigin::signed(account),
+        asset_id,
+        amount,
+        None,
+        None,
+        false,
+        None,
+    )
+}
+
+#[test]
+fn create_exchange_with_bootstrap() {
+    new_test_ext().execute_with(|| {
+        create_bootstrapped_exchange(10);
+
+        let bootstrap = Dex::bootstraps(ASSET_B).unwrap();
+        assert_eq!(bootstrap.whitelist.into_inner(), vec![ACCOUNT_B]);
+        assert_eq!(bootstrap.end, 10);
+    })
+}
+
+#[test]
+fn create_exchange_bootstrap_end_passed() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(5);
+        let bootstrap = Bootstrap {
+            whitelist: vec![ACCOUNT_B].try_into().unwrap(),
+            end: 4,
+        };
+        assert_noop!(
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_B,
+                LIQ_TOKEN_B,
+                INIT_LIQUIDITY,
+                INIT_LIQUIDITY,
+                Some(bootstrap),
+                false
+            ),
+            Error::<Test>::BootstrapEndPassed
+        );
+    })
+}
+
+#[test]
+fn bootstrap_too_long() {
+    new_test_ext().execute_with(|| {
+        let create = |end| {
+            let bootstrap = Bootstrap {
+                whitelist: vec![ACCOUNT_B].try_into().unwrap(),
+                end,
+            };
+            Dex::create_exchange(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_B,
+                LIQ_TOKEN_B,
+                INIT_LIQUIDITY,
+                INIT_LIQUIDITY,
+                Some(bootstrap),
+                false,
+            )
+        };
+        assert_noop!(create(MAX_BOOTSTRAP_PERIOD + 2), Error::<Test>::BootstrapTooLong);
+        assert_ok!(create(MAX_BOOTSTRAP_PERIOD + 1));
+    })
+}
+
+#[test]
+fn bootstrap_restricts_to_whitelist() {
+    new_test_ext().execute_with(|| {
+        create_bootstrapped_exchange(10);
+
+        assert_noop!(
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_B,
+                1_000,
+                1,
+                1_001,
+                None,
+                None
+            ),
+            Error::<Test>::NotWhitelisted
+        );
+        assert_noop!(buy_asset(ACCOUNT_A, ASSET_B), Error::<Test>::NotWhitelisted);
+        assert_noop!(
+            Dex::asset_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                ASSET_B,
+                TradeAmount::FixedInput {
+                    input_amount: 1_000,
+                    min_output: 1
+                },
+                None,
+                None,
+                false,
+                None
+            ),
+            Error::<Test>::NotWhitelisted
+        );
+        assert_noop!(
+            Dex::stream_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_B, 1_000, 2_000, 2),
+            Error::<Test>::NotWhitelisted
+        );
+        assert_ok!(Dex::add_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            1_000,
+            1,
+            1_001,
+            None,
+            None
+        ));
+        assert_ok!(buy_asset(ACCOUNT_B, ASSET_B));
+        // Other exchanges are not affected
+        assert_ok!(buy_asset(ACCOUNT_A, ASSET_A));
+    })
+}
+
+#[test]
+fn bootstrap_allows_removing_liquidity() {
+    new_test_ext().execute_with(|| {
+        create_bootstrapped_exchange(10);
+        assert_ok!(Assets::transfer(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            LIQ_TOKEN_B,
+            ACCOUNT_A,
+            1_000
+        ));
+
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            1_000,
+            1,
+            1,
+            None
+        ));
+    })
+}
+
+#[test]
+fn bootstrap_ends() {
+    new_test_ext().execute_with(|| {
+        create_bootstrapped_exchange(10);
+
+        System::set_block_number(10);
+        assert_noop!(buy_asset(ACCOUNT_A, ASSET_B), Error::<Test>::NotWhitelisted);
+        System::set_block_number(11);
+        assert_ok!(buy_asset(ACCOUNT_A, ASSET_B));
+        assert_ok!(Dex::add_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            1_000,
+            1,
+            1_001,
+            None,
+            None
+        ));
+    })
+}
+
+#[test]
+fn bootstrap_removed_with_exchange() {
+    new_test_ext().execute_with(|| {
+        create_bootstrapped_exchange(10);
+
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            INIT_LIQUIDITY,
+            1,
+            1,
+            None
+        ));
+        assert!(Dex::bootstraps(ASSET_B).is_none());
+    })
+}
+
+#[test]
+fn incident_calls_are_operational() {
+    use frame_support::dispatch::{DispatchClass, GetDispatchInfo};
+
+    let operational_calls: Vec<RuntimeCall> = vec![
+        crate::Call::force_seed_exchange {
+            asset_id: ASSET_A,
+            source: ACCOUNT_A,
+            start_index: 0,
+            deposits: Default::default(),
+        }
+        .into(),
+        crate::Call::set_retention_floor {
+            asset_id: ASSET_A,
+            floor: None,
+        }
+        .into(),
+        crate::Call::set_trade_only_mode {
+            asset_id: ASSET_A,
+            until: None,
+        }
+        .into(),
+        crate::Call::delist_exchange { asset_id: ASSET_A }.into(),
+        crate::Call::force_pause_many {
+            asset_ids: vec![ASSET_A].try_into().unwrap(),
+        }
+        .into(),
+        crate::Call::force_unpause_many {
+            asset_ids: vec![ASSET_A].try_into().unwrap(),
+        }
+        .into(),
+    ];
+    for call in operational_calls {
+        assert_eq!(call.get_dispatch_info().class, DispatchClass::Operational, "{:?}", call);
+    }
+    // Routine governance setters and trades don't use the reserved block space
+    let normal_calls: Vec<RuntimeCall> = vec![
+        crate::Call::set_max_trade_size {
+            asset_id: ASSET_A,
+            limit: None,
+        }
+        .into(),
+        crate::Call::register_market_maker {
+            market_maker: ACCOUNT_A,
+            parameters: MarketMaker {
+                fee_exempt: false,
+                max_currency: 1,
+                max_tokens: 1,
+            },
+        }
+        .into(),
+        crate::Call::deregister_market_maker {
+            market_maker: ACCOUNT_A,
+        }
+        .into(),
+        crate::Call::set_creation_fee { creation_fee: 1 }.into(),
+        crate::Call::set_peg_reference {
+            asset_id: ASSET_A,
+            peg: None,
+        }
+        .into(),
+        crate::Call::set_fee_share {
+            origin_account: ACCOUNT_A,
+            fee_share: None,
+        }
+        .into(),
+        crate::Call::set_curve {
+            asset_id: ASSET_A,
+            curve: None,
+        }
+        .into(),
+        crate::Call::currency_to_asset {
+            asset_id: ASSET_A,
+            amount: TradeAmount::FixedInput {
+                input_amount: 1,
+                min_output: 1,
+            },
+            deadline: None,
+            recipient: None,
+            provision_recipient: false,
+            donation: None,
+        }
+        .into(),
+    ];
+    for call in normal_calls {
+        assert_eq!(call.get_dispatch_info().class, DispatchClass::Normal, "{:?}", call);
+    }
+}
+
+#[test]
+fn reserves_update_block_recorded() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Dex::reserves_updated_at(ASSET_A), Some(0));
+
+        System::set_block_number(5);
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 1_000,
+                min_output: 1
+            },
+            None,
+            None,
+            false,
+            None
+        ));
+        assert_eq!(Dex::reserves_updated_at(ASSET_A), Some(5));
+
+        System::set_block_number(6);
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            INIT_LIQUIDITY,
+            1,
+            1,
+            None
+        ));
+        assert_eq!(Dex::reserves_updated_at(ASSET_A), None);
+    })
+}
+
+const OPERATOR: u64 = 42;
+
+fn sell_asset_swap(input_amount: u128) -> DelegatedSwap<u32, u128, u128> {
+    DelegatedSwap::AssetToCurrency {
+        asset_id: ASSET_A,
+        amount: TradeAmount::FixedInput {
+            input_amount,
+            min_output: 1,
+        },
+    }
+}
+
+#[test]
+fn approve_operator() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            1_000,
+            None,
+            Price::zero(),
+            Some(10)
+        ));
+        let approval = Dex::operator_approvals(ACCOUNT_B, (OPERATOR, ASSET_A)).unwrap();
+        assert_eq!(approval.allowance, 1_000);
+        assert_eq!(approval.expiry, Some(10));
+        assert_eq!(
+            last_event(),
+            Event::OperatorApproved(ACCOUNT_B, OPERATOR, ASSET_A, 1_000, Some(10))
+        );
+    })
+}
+
+#[test]
+fn approve_operator_expiry_passed() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(2);
+        assert_noop!(
+            Dex::approve_operator(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                OPERATOR,
+                ASSET_A,
+                1_000,
+                None,
+                Price::zero(),
+                Some(1)
+            ),
+            Error::<Test>::ApprovalExpired
+        );
+    })
+}
+
+#[test]
+fn swap_from_asset_to_currency() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            1_000,
+            None,
+            Price::zero(),
+            None
+        ));
+        let token_amount = 500;
+        let curr_amount = 498; // token amount (500) - provider fee (0.3%) should be ~498
+
+        assert_ok!(Dex::swap_from(
+            RuntimeOrigin::signed(OPERATOR),
+            ACCOUNT_B,
+            sell_asset_swap(token_amount),
+            None
+        ));
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE + curr_amount - STORAGE_DEPOSIT);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_B), Some(INIT_BALANCE - token_amount));
+        assert_eq!(Balances::free_balance(OPERATOR), 0);
+        let approval = Dex::operator_approvals(ACCOUNT_B, (OPERATOR, ASSET_A)).unwrap();
+        assert_eq!(approval.allowance, 1_000 - token_amount);
+        assert_eq!(
+            last_event(),
+            Event::AssetTradedForCurrency(ASSET_A, ACCOUNT_B, ACCOUNT_B, curr_amount, token_amount)
+        );
+    })
+}
+
+#[test]
+fn swap_from_asset_to_asset() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false,
+        ));
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            1_000,
+            Some(ASSET_B),
+            Price::zero(),
+            None
+        ));
+        let swap = DelegatedSwap::AssetToAsset {
+            sold_asset_id: ASSET_A,
+            bought_asset_id: ASSET_B,
+            amount: TradeAmount::FixedInput {
+                input_amount: 500,
+                min_output: 496,
+            },
+        };
+
+        assert_ok!(Dex::swap_from(RuntimeOrigin::signed(OPERATOR), ACCOUNT_B, swap, None));
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_B), Some(INIT_BALANCE - 500));
+        assert_eq!(Assets::maybe_balance(ASSET_B, &ACCOUNT_B), Some(INIT_BALANCE + 496));
+        assert_eq!(Assets::maybe_balance(ASSET_B, &OPERATOR), None);
+        let approval = Dex::operator_approvals(ACCOUNT_B, (OPERATOR, ASSET_A)).unwrap();
+        assert_eq!(approval.allowance, 500);
+    })
+}
+
+#[test]
+fn swap_from_not_approved() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::swap_from(RuntimeOrigin::signed(OPERATOR), ACCOUNT_B, sell_asset_swap(500), None),
+            Error::<Test>::NotApproved
+        );
+        // Approval of another operator can't be used
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ACCOUNT_C,
+            ASSET_A,
+            1_000,
+            None,
+            Price::zero(),
+            None
+        ));
+        assert_noop!(
+            Dex::swap_from(RuntimeOrigin::signed(OPERATOR), ACCOUNT_B, sell_asset_swap(500), None),
+            Error::<Test>::NotApproved
+        );
+    })
+}
+
+#[test]
+fn swap_from_allowance_exceeded() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            1_000,
+            None,
+            Price::zero(),
+            None
+        ));
+        assert_ok!(Dex::swap_from(
+            RuntimeOrigin::signed(OPERATOR),
+            ACCOUNT_B,
+            sell_asset_swap(600),
+            None
+        ));
+        assert_noop!(
+            Dex::swap_from(RuntimeOrigin::signed(OPERATOR), ACCOUNT_B, sell_asset_swap(600), None),
+            Error::<Test>::AllowanceExceeded
+        );
+    })
+}
+
+#[test]
+fn swap_from_approval_expired() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            1_000,
+            None,
+            Price::zero(),
+            Some(1)
+        ));
+        System::set_block_number(2);
+        assert_noop!(
+            Dex::swap_from(RuntimeOrigin::signed(OPERATOR), ACCOUNT_B, sell_asset_swap(500), None),
+            Error::<Test>::ApprovalExpired
+        );
+    })
+}
+
+#[test]
+fn swap_from_bought_asset_not_approved() {
+    new_test_ext().execute_with(|| {
+        // Approved to sell for currency only
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            1_000,
+            None,
+            Price::zero(),
+            None
+        ));
+        let swap = DelegatedSwap::AssetToAsset {
+            sold_asset_id: ASSET_A,
+            bought_asset_id: ASSET_B,
+            amount: TradeAmount::FixedInput {
+                input_amount: 500,
+                min_output: 1,
+            },
+        };
+        assert_noop!(
+            Dex::swap_from(RuntimeOrigin::signed(OPERATOR), ACCOUNT_B, swap, None),
+            Error::<Test>::BoughtAssetNotApproved
+        );
+    })
+}
+
+#[test]
+fn swap_from_price_below_approved() {
+    new_test_ext().execute_with(|| {
+        let approve = |min_price| {
+            Dex::approve_operator(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                OPERATOR,
+                ASSET_A,
+                1_000,
+                None,
+                min_price,
+                None,
+            )
+        };
+        // Selling 500 tokens returns ~498 currency (provider fee of 0.3%)
+        assert_ok!(approve(Price::saturating_from_integer(1)));
+        assert_noop!(
+            Dex::swap_from(RuntimeOrigin::signed(OPERATOR), ACCOUNT_B, sell_asset_swap(500), None),
+            Error::<Test>::PriceBelowApproved
+        );
+        assert_ok!(approve(Price::saturating_from_rational(99, 100)));
+        assert_ok!(Dex::swap_from(
+            RuntimeOrigin::signed(OPERATOR),
+            ACCOUNT_B,
+            sell_asset_swap(500),
+            None
+        ));
+    })
+}
+
+#[test]
+fn revoke_operator() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            1_000,
+            None,
+            Price::zero(),
+            None
+        ));
+        assert_ok!(Dex::revoke_operator(RuntimeOrigin::signed(ACCOUNT_B), OPERATOR, ASSET_A));
+        assert!(Dex::operator_approvals(ACCOUNT_B, (OPERATOR, ASSET_A)).is_none());
+        assert_eq!(last_event(), Event::OperatorRevoked(ACCOUNT_B, OPERATOR, ASSET_A));
+        assert_noop!(
+            Dex::swap_from(RuntimeOrigin::signed(OPERATOR), ACCOUNT_B, sell_asset_swap(500), None),
+            Error::<Test>::NotApproved
+        );
+        assert_noop!(
+            Dex::revoke_operator(RuntimeOrigin::signed(ACCOUNT_B), OPERATOR, ASSET_A),
+            Error::<Test>::NotApproved
+        );
+    })
+}
+
+#[test]
+fn storage_deposits() {
+    new_test_ext().execute_with(|| {
+        let approval = DepositItem::OperatorApproval(OPERATOR, ASSET_A);
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            1_000,
+            None,
+            Price::zero(),
+            None
+        ));
+        assert_eq!(Balances::reserved_balance(ACCOUNT_B), STORAGE_DEPOSIT);
+        assert_eq!(Dex::storage_deposits(ACCOUNT_B, &approval), Some(STORAGE_DEPOSIT));
+
+        // Overwriting the approval doesn't reserve another deposit
+        assert_ok!(Dex::approve_operator(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            OPERATOR,
+            ASSET_A,
+            2_000,
+            None,
+            Price::zero(),
+            None
+        ));
+        assert_eq!(Balances::reserved_balance(ACCOUNT_B), STORAGE_DEPOSIT);
+
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            1
+        ));
+        assert_eq!(Balances::reserved_balance(ACCOUNT_B), 2 * STORAGE_DEPOSIT);
+
+        // Deposits are released when the items are removed
+        assert_ok!(Dex::revoke_operator(RuntimeOrigin::signed(ACCOUNT_B), OPERATOR, ASSET_A));
+        assert_eq!(Balances::reserved_balance(ACCOUNT_B), STORAGE_DEPOSIT);
+        assert_eq!(Dex::storage_deposits(ACCOUNT_B, &approval), None);
+        run_on_idle(2);
+        assert!(Dex::liquidity_streams(ASSET_A, ACCOUNT_B).is_none());
+        assert_eq!(Balances::reserved_balance(ACCOUNT_B), 0);
+        // Less the existential deposit of the stream escrow sub-account
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 1_000 - 1);
+    })
+}
+
+#[test]
+fn storage_deposit_balance_too_low() {
+    new_test_ext().execute_with(|| {
+        let owner = 42;
+        Balances::make_free_balance_be(&owner, STORAGE_DEPOSIT - 1);
+        assert_noop!(
+            Dex::approve_operator(
+                RuntimeOrigin::signed(owner),
+                OPERATOR,
+                ASSET_A,
+                1_000,
+                None,
+                Price::zero(),
+                None
+            ),
+            Error::<Test>::BalanceTooLow
+        );
+    })
+}
+
+fn reserve_mismatch_events() -> Vec<Event<Test>> {
+    System::events()
+        .into_iter()
+        .filter_map(|record| match record.event {
+            RuntimeEvent::Dex(event @ Event::ReserveMismatch(..)) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn reserve_mismatch_not_reported_within_tolerance() {
+    new_test_ext().execute_with(|| {
+        // Up to 1% of the reserve is tolerated
+        assert_ok!(Assets::mint_into(ASSET_A, &Test::pallet_account(), INIT_LIQUIDITY / 200));
+        assert_ok!(buy_asset(ACCOUNT_B, ASSET_A));
+        assert!(reserve_mismatch_events().is_empty());
+    })
+}
+
+#[test]
+fn reserve_mismatch_reported_for_surplus() {
+    new_test_ext().execute_with(|| {
+        let pallet_account = Test::pallet_account();
+        assert_ok!(Assets::mint_into(ASSET_A, &pallet_account, INIT_LIQUIDITY / 10));
+        assert_ok!(buy_asset(ACCOUNT_B, ASSET_A));
+
+        let token_reserve = Dex::exchanges(ASSET_A).unwrap().token_reserve;
+        let balance = Assets::balance(ASSET_A, &pallet_account);
+        assert_eq!(balance, token_reserve + INIT_LIQUIDITY / 10);
+        assert_eq!(
+            reserve_mismatch_events(),
+            vec![Event::ReserveMismatch(ASSET_A, token_reserve, balance)]
+        );
+    })
+}
+
+#[test]
+fn reserve_mismatch_reported_for_deficit() {
+    new_test_ext().execute_with(|| {
+        let pallet_account = Test::pallet_account();
+        assert_ok!(Assets::burn_from(ASSET_A, &pallet_account, INIT_LIQUIDITY / 10));
+        assert_ok!(buy_asset(ACCOUNT_B, ASSET_A));
+
+        let token_reserve = Dex::exchanges(ASSET_A).unwrap().token_reserve;
+        let balance = Assets::balance(ASSET_A, &pallet_account);
+        assert_eq!(
+            reserve_mismatch_events(),
+            vec![Event::ReserveMismatch(ASSET_A, token_reserve, balance)]
+        );
+    })
+}
+
+#[test]
+fn reserve_mismatch_not_reported_for_streamed_tokens() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            INIT_LIQUIDITY / 10,
+            INIT_LIQUIDITY / 5,
+            2
+        ));
+        assert_ok!(buy_asset(ACCOUNT_B, ASSET_A));
+        assert!(reserve_mismatch_events().is_empty());
+    })
+}
+
+#[test]
+fn force_seed_exchange() {
+    new_test_ext().execute_with(|| {
+        // Depositors must exist to hold the (insufficient) liquidity token
+        for depositor in [42, 43, 44] {
+            Balances::make_free_balance_be(&depositor, 1);
+        }
+        assert_ok!(Dex::approve_seeding(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        let deposits = vec![(42, 1_000, 2_000), (43, 500, 1_000)];
+        assert_ok!(Dex::force_seed_exchange(
+            RuntimeOrigin::root(),
+            ASSET_A,
+            ACCOUNT_B,
+            0,
+            deposits.try_into().unwrap()
+        ));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + 1_500);
+        // Tokens are deposited at the ratio of the reserves (rounded up), not the maximum amounts
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY + 1_501);
+        assert_eq!(Assets::balance(LIQ_TOKEN_A, 42), 1_000);
+        // Liquidity is minted (rounded down) proportionally to the currency reserve
+        assert_eq!(Assets::balance(LIQ_TOKEN_A, 43), 499);
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 1_500);
+        assert_eq!(Assets::balance(ASSET_A, ACCOUNT_B), INIT_BALANCE - 1_501);
+        assert_eq!(Dex::seeded_deposits(ASSET_A), 2);
+        assert_eq!(last_event(), Event::ExchangeSeeded(ASSET_A, 2));
+
+        // Next batch
+        assert_ok!(Dex::force_seed_exchange(
+            RuntimeOrigin::root(),
+            ASSET_A,
+            ACCOUNT_B,
+            2,
+            vec![(44, 1_000, 2_000)].try_into().unwrap()
+        ));
+        assert_eq!(Assets::balance(LIQ_TOKEN_A, 44), 999);
+        assert_eq!(Dex::seeded_deposits(ASSET_A), 3);
+    })
+}
+
+#[test]
+fn force_seed_exchange_index_mismatch() {
+    new_test_ext().execute_with(|| {
+        Balances::make_free_balance_be(&42, 1);
+        assert_ok!(Dex::approve_seeding(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        let deposits: Vec<(u64, u128, u128)> = vec![(42, 1_000, 2_000)];
+        assert_ok!(Dex::force_seed_exchange(
+            RuntimeOrigin::root(),
+            ASSET_A,
+            ACCOUNT_B,
+            0,
+            deposits.clone().try_into().unwrap()
+        ));
+        // The same batch can't be seeded twice
+        assert_noop!(
+            Dex::force_seed_exchange(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                ACCOUNT_B,
+                0,
+                deposits.try_into().unwrap()
+            ),
+            Error::<Test>::SeedIndexMismatch
+        );
+    })
+}
+
+#[test]
+fn force_seed_exchange_failed_batch_reverted() {
+    new_test_ext().execute_with(|| {
+        Balances::make_free_balance_be(&42, 1);
+        assert_ok!(Dex::approve_seeding(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        let deposits = vec![(42, 1_000, 2_000), (43, INIT_BALANCE, INIT_BALANCE * 2)];
+        assert_noop!(
+            Dex::force_seed_exchange(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                ACCOUNT_B,
+                0,
+                deposits.try_into().unwrap()
+            ),
+            Error::<Test>::BalanceTooLow
+        );
+    })
+}
+
+#[test]
+fn force_seed_exchange_requires_governance() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Dex::force_seed_exchange(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                ACCOUNT_B,
+                0,
+                vec![(42, 1_000, 1_000)].try_into().unwrap()
+            ),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            Dex::force_seed_exchange(
+                RuntimeOrigin::root(),
+                ASSET_B,
+                ACCOUNT_B,
+                0,
+                vec![(42, 1_000, 1_000)].try_into().unwrap()
+            ),
+            Error::<Test>::ExchangeNotFound
+        );
+    })
+}
+
+#[test]
+fn force_seed_exchange_requires_source_approval() {
+    new_test_ext().execute_with(|| {
+        Balances::make_free_balance_be(&42, 1);
+        let deposits: Vec<(u64, u128, u128)> = vec![(42, 1_000, 2_000)];
+        assert_noop!(
+            Dex::force_seed_exchange(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                ACCOUNT_B,
+                0,
+                deposits.clone().try_into().unwrap()
+            ),
+            Error::<Test>::SeedingNotApproved
+        );
+
+        let reserved = Balances::reserved_balance(ACCOUNT_B);
+        assert_ok!(Dex::approve_seeding(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        assert_eq!(last_event(), Event::SeedingApproved(ACCOUNT_B, ASSET_A));
+        assert_eq!(Balances::reserved_balance(ACCOUNT_B), reserved + STORAGE_DEPOSIT);
+        assert_ok!(Dex::revoke_seeding(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        assert_eq!(last_event(), Event::SeedingRevoked(ACCOUNT_B, ASSET_A));
+        assert_eq!(Balances::reserved_balance(ACCOUNT_B), reserved);
+        assert_noop!(
+            Dex::force_seed_exchange(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                ACCOUNT_B,
+                0,
+                deposits.try_into().unwrap()
+            ),
+            Error::<Test>::SeedingNotApproved
+        );
+        assert_noop!(
+            Dex::revoke_seeding(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A),
+            Error::<Test>::SeedingNotApproved
+        );
+    })
+}
+
+#[test]
+fn force_seed_exchange_at_reserve_ratio() {
+    new_test_ext().execute_with(|| {
+        Balances::make_free_balance_be(&42, 1);
+        assert_ok!(Dex::approve_seeding(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        // 1_001 tokens are required for 1_000 currency at the current ratio of the reserves
+        assert_noop!(
+            Dex::force_seed_exchange(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                ACCOUNT_B,
+                0,
+                vec![(42, 1_000, 1_000)].try_into().unwrap()
+            ),
+            Error::<Test>::MaxTokensTooLow
+        );
+        assert_ok!(Dex::force_seed_exchange(
+            RuntimeOrigin::root(),
+            ASSET_A,
+            ACCOUNT_B,
+            0,
+            vec![(42, 1_000, 1_001)].try_into().unwrap()
+        ));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY + 1_001);
+    })
+}
+
+#[test]
+fn redeem_underlying_broken_reserves() {
+    new_test_ext().execute_with(|| {
+        // Stored token reserve is corrupted, the actual balance is paid out pro-rata
+        let mut exchange = Dex::exchanges(ASSET_A).unwrap();
+        exchange.token_reserve = 2 * INIT_LIQUIDITY;
+        crate::Exchanges::<Test>::insert(ASSET_A, exchange);
+        let redeemed = INIT_LIQUIDITY / 2;
+        assert_ok!(Dex::redeem_underlying(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, redeemed, 1));
+
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - redeemed);
+        assert_eq!(exchange.token_reserve, 2 * INIT_LIQUIDITY - redeemed);
+        assert_eq!(Balances::free_balance(ACCOUNT_A), INIT_BALANCE - INIT_LIQUIDITY + redeemed);
+        assert_eq!(
+            Assets::maybe_balance(ASSET_A, &ACCOUNT_A),
+            Some(INIT_BALANCE - INIT_LIQUIDITY + redeemed)
+        );
+        assert_eq!(Assets::total_supply(LIQ_TOKEN_A), INIT_LIQUIDITY - redeemed);
+        assert_eq!(
+            last_event(),
+            Event::UnderlyingRedeemed(ACCOUNT_A, ASSET_A, redeemed, redeemed, redeemed)
+        );
+    })
+}
+
+#[test]
+fn redeem_underlying_inflated_currency_reserve() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false,
+        ));
+        // Stored currency reserve is corrupted upward, beyond the balance of the pallet account
+        let mut exchange = Dex::exchanges(ASSET_A).unwrap();
+        exchange.currency_reserve = 3 * INIT_LIQUIDITY;
+        crate::Exchanges::<Test>::insert(ASSET_A, exchange);
+        let origin = RuntimeOrigin::signed(ACCOUNT_A);
+        assert_noop!(
+            Dex::redeem_underlying(origin.clone(), ASSET_A, INIT_LIQUIDITY, 1),
+            Error::<Test>::WrongExchangeCount
+        );
+        assert_ok!(Dex::redeem_underlying(origin, ASSET_A, INIT_LIQUIDITY, 2));
+
+        // The currency of the other exchange is not paid out
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::UnderlyingRedeemed(
+                    ACCOUNT_A,
+                    ASSET_A,
+                    INIT_LIQUIDITY,
+                    INIT_LIQUIDITY,
+                    INIT_LIQUIDITY
+                ),
+                Event::ExchangeRemoved(ASSET_A, LIQ_TOKEN_A),
+            ]
+        );
+        assert_eq!(Balances::free_balance(Test::pallet_account()), INIT_LIQUIDITY);
+        assert_eq!(Dex::exchanges(ASSET_B).unwrap().currency_reserve, INIT_LIQUIDITY);
+        assert_ok!(Dex::do_try_state());
+    })
+}
+
+#[test]
+fn redeem_underlying_suspended_excludes_escrowed_tokens() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+        flag_asset(ASSET_A);
+        assert_ok!(Dex::redeem_underlying(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            INIT_LIQUIDITY,
+            1
+        ));
+
+        assert_eq!(Balances::free_balance(ACCOUNT_A), INIT_BALANCE);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_A), Some(INIT_BALANCE));
+        let escrow_account = Test::stream_escrow_account(&ASSET_A);
+        assert_eq!(Balances::free_balance(escrow_account), 1_001);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &escrow_account), Some(2_000));
+        assert_eq!(Dex::escrowed_tokens(ASSET_A), 2_000);
+        assert!(Dex::exchanges(ASSET_A).is_none());
+        assert_eq!(last_event(), Event::ExchangeRemoved(ASSET_A, LIQ_TOKEN_A));
+    })
+}
+
+#[test]
+fn redeem_underlying_invalid_parameters() {
+    new_test_ext().execute_with(|| {
+        let origin = RuntimeOrigin::signed(ACCOUNT_A);
+        assert_noop!(
+            Dex::redeem_underlying(origin.clone(), ASSET_A, 1_000, 1),
+            Error::<Test>::NotInEmergency
+        );
+        assert_noop!(
+            Dex::redeem_underlying(origin.clone(), ASSET_B, 1_000, 1),
+            Error::<Test>::ExchangeNotFound
+        );
+        flag_asset(ASSET_A);
+        assert_noop!(
+            Dex::redeem_underlying(origin.clone(), ASSET_A, 0, 1),
+            Error::<Test>::LiquidityAmountIsZero
+        );
+        assert_noop!(
+            Dex::redeem_underlying(origin, ASSET_A, INIT_LIQUIDITY + 1, 1),
+            Error::<Test>::ProviderLiquidityTooLow
+        );
+    })
+}
+
+#[test]
+fn batch_liquidity() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false
+        ));
+        // Move liquidity from exchange A to exchange B
+        let ops = vec![
+            LiquidityOp::Remove {
+                asset_id: ASSET_A,
+                liquidity_amount: 1_000,
+                min_currency: 1_000,
+                min_tokens: 1_000,
+            },
+            LiquidityOp::Add {
+                asset_id: ASSET_B,
+                currency_amount: 1_000,
+                min_liquidity: 1_000,
+                max_tokens: 1_001,
+            },
+        ];
+        assert_ok!(Dex::batch_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ops.try_into().unwrap(),
+            None
+        ));
+        assert_eq!(Dex::exchanges(ASSET_A).unwrap().currency_reserve, INIT_LIQUIDITY - 1_000);
+        assert_eq!(Dex::exchanges(ASSET_B).unwrap().currency_reserve, INIT_LIQUIDITY + 1_000);
+        assert_eq!(Balances::free_balance(ACCOUNT_A), INIT_BALANCE - INIT_LIQUIDITY);
+        assert_eq!(Assets::maybe_balance(LIQ_TOKEN_B, &ACCOUNT_A), Some(1_000));
+        assert_eq!(
+            last_n_events(5),
+            vec![
+                Event::LiquidityBatchExecuted(ACCOUNT_A, 2),
+                Event::LegStarted(0),
+                Event::LiquidityRemoved(ACCOUNT_A, ASSET_A, 1_000, 1_000, 1_000),
+                Event::LegStarted(1),
+                Event::LiquidityAdded(ACCOUNT_A, ASSET_B, 1_000, 1_001, 1_000),
+            ]
+        );
+    })
+}
+
+#[test]
+fn batch_liquidity_failed_op_reverts_batch() {
+    new_test_ext().execute_with(|| {
+        let ops = vec![
+            LiquidityOp::Remove {
+                asset_id: ASSET_A,
+                liquidity_amount: 1_000,
+                min_currency: 1_000,
+                min_tokens: 1_000,
+            },
+            LiquidityOp::Add {
+                asset_id: ASSET_B,
+                currency_amount: 1_000,
+                min_liquidity: 1_000,
+                max_tokens: 1_001,
+            },
+        ];
+        assert_noop!(
+            Dex::batch_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ops.try_into().unwrap(), None),
+            Error::<Test>::ExchangeNotFound
+        );
+    })
+}
+
+#[test]
+fn batch_liquidity_deadline_passed() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(2);
+        assert_noop!(
+            Dex::batch_liquidity(RuntimeOrigin::signed(ACCOUNT_A), Default::default(), Some(1)),
+            Error::<Test>::DeadlinePassed
+        );
+    })
+}
+
+#[test]
+fn inspect_dex() {
+    use crate::InspectDex;
+
+    new_test_ext().execute_with(|| {
+        assert!(<Dex as InspectDex<_, _, _>>::exchange_exists(&ASSET_A));
+        assert!(!<Dex as InspectDex<_, _, _>>::exchange_exists(&ASSET_B));
+        assert_eq!(
+            <Dex as InspectDex<_, _, _>>::reserves(&ASSET_A),
+            Some((INIT_LIQUIDITY, INIT_LIQUIDITY))
+        );
+        assert_eq!(<Dex as InspectDex<_, _, _>>::reserves(&ASSET_B), None);
+        assert_eq!(<Dex as InspectDex<u32, u128, u128>>::lp_token_of(&ASSET_A), Some(LIQ_TOKEN_A));
+        assert_eq!(<Dex as InspectDex<u32, u128, u128>>::lp_token_of(&ASSET_B), None);
+    })
+}
+
+#[test]
+fn currency_to_asset_donation() {
+    new_test_ext().execute_with(|| {
+        let curr_amount = 500;
+        let token_amount = 498; // currency amount (500) - provider fee (0.3%) should be ~498
+        let donated = 249;
+
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: curr_amount,
+                min_output: token_amount
+            },
+            Some(1),
+            None,
+            false,
+            Some(Donation {
+                share: Permill::from_percent(50),
+                beneficiary: Some(ACCOUNT_C),
+            })
+        ));
+
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - token_amount);
+        assert_eq!(
+            Assets::maybe_balance(ASSET_A, &ACCOUNT_B),
+            Some(INIT_BALANCE + token_amount - donated)
+        );
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_C), Some(INIT_BALANCE + donated));
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::OutputDonated(ASSET_A, Some(ACCOUNT_C), 0, donated),
+                Event::CurrencyTradedForAsset(
+                    ASSET_A,
+                    ACCOUNT_B,
+                    ACCOUNT_B,
+                    curr_amount,
+                    token_amount
+                ),
+            ]
+        );
+    });
+}
+
+#[test]
+fn asset_to_currency_donation_burned() {
+    new_test_ext().execute_with(|| {
+        let token_amount = 500;
+        let curr_amount = 498; // token amount (500) - provider fee (0.3%) should be ~498
+        let burned = 249;
+        let total_issuance = Balances::total_issuance();
+
+        assert_ok!(Dex::asset_to_currency(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: token_amount,
+                min_output: curr_amount
+            },
+            Some(1),
+            None,
+            Some(Donation {
+                share: Permill::from_percent(50),
+                beneficiary: None,
+            })
+        ));
+
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY - curr_amount);
+        assert_eq!(Balances::free_balance(Test::pallet_account()), INIT_LIQUIDITY - curr_amount);
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE + curr_amount - burned);
+        assert_eq!(Balances::total_issuance(), total_issuance - burned);
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::OutputDonated(ASSET_A, None, burned, 0),
+                Event::AssetTradedForCurrency(
+                    ASSET_A,
+                    ACCOUNT_B,
+                    ACCOUNT_B,
+                    curr_amount,
+                    token_amount
+                ),
+            ]
+        );
+    });
+}
+
+#[test]
+fn asset_to_asset_donation_burned() {
+    new_test_ext().execute_with(|| {
+        Dex::create_exchange(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_B,
+            LIQ_TOKEN_B,
+            INIT_LIQUIDITY,
+            INIT_LIQUIDITY,
+            None,
+            false,
+        )
+        .unwrap();
+
+        let sold_token_amount = 500;
+        let bought_token_amount = 496; // currency amount (498) - provider fee (0.3%) should be ~496
+        let burned = 124;
+        let pallet_account = Test::pallet_account();
+
+        assert_ok!(Dex::asset_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_C),
+            ASSET_A,
+            ASSET_B,
+            TradeAmount::FixedInput {
+                input_amount: sold_token_amount,
+                min_output: bought_token_amount
+            },
+            Some(1),
+            None,
+            false,
+            Some(Donation {
+                share: Permill::from_percent(25),
+                beneficiary: None,
+            })
+        ));
+
+        let exchange = Dex::exchanges(ASSET_B).unwrap();
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY - bought_token_amount);
+        assert_eq!(
+            Assets::maybe_balance(ASSET_B, &pallet_account),
+            Some(INIT_LIQUIDITY - bought_token_amount)
+        );
+        assert_eq!(
+            Assets::maybe_balance(ASSET_B, &ACCOUNT_C),
+            Some(INIT_BALANCE + bought_token_amount - burned)
+        );
+        assert_eq!(Assets::balance(ASSET_B, BURN_ACCOUNT), burned);
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::OutputDonated(ASSET_B, None, 0, burned),
+                Event::CurrencyTradedForAsset(
+                    ASSET_B,
+                    pallet_account,
+                    ACCOUNT_C,
+                    498,
+                    bought_token_amount
+                ),
+            ]
+        );
+    });
+}
+
+#[test]
+fn retention_floor() {
+    new_test_ext().execute_with(|| {
+        let floor = INIT_LIQUIDITY - 1_000;
+        assert_noop!(
+            Dex::set_retention_floor(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, Some(floor)),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            Dex::set_retention_floor(RuntimeOrigin::root(), ASSET_B, Some(floor)),
+            Error::<Test>::ExchangeNotFound
+        );
+        assert_ok!(Dex::set_retention_floor(RuntimeOrigin::root(), ASSET_A, Some(floor)));
+        assert_eq!(Dex::retention_floors(ASSET_A), Some(floor));
+        assert_eq!(last_event(), Event::RetentionFloorSet(ASSET_A, Some(floor)));
+
+        // Withdrawals are allowed down to the floor
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            600,
+            1,
+            1,
+            None
+        ));
+        assert_noop!(
+            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 401, 1, 1, None),
+            Error::<Test>::RetentionFloorReached
+        );
+        assert_noop!(
+            Dex::remove_liquidity_with_slippage(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                401,
+                401,
+                401,
+                Permill::from_percent(1),
+                None
+            ),
+            Error::<Test>::RetentionFloorReached
+        );
+        // The emergency exit is exempt from the floor
+        flag_asset(ASSET_A);
+        assert_ok!(Dex::redeem_underlying(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 401, 1));
+
+        // The floor can always be removed
+        assert_ok!(Dex::set_retention_floor(RuntimeOrigin::root(), ASSET_A, None));
+        assert_eq!(Dex::retention_floors(ASSET_A), None);
+        assert_ok!(Dex::set_retention_floor(RuntimeOrigin::root(), ASSET_B, None));
+        assert_eq!(last_event(), Event::RetentionFloorSet(ASSET_B, None));
+    })
+}
+
+#[test]
+fn trade_only_mode() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_noop!(
+            Dex::set_trade_only_mode(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, Some(10)),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            Dex::set_trade_only_mode(RuntimeOrigin::root(), ASSET_B, Some(10)),
+            Error::<Test>::ExchangeNotFound
+        );
+        assert_noop!(
+            Dex::set_trade_only_mode(RuntimeOrigin::root(), ASSET_A, Some(0)),
+            Error::<Test>::InvalidTradeOnlyExpiry
+        );
+        assert_noop!(
+            Dex::set_trade_only_mode(RuntimeOrigin::root(), ASSET_A, Some(102)),
+            Error::<Test>::InvalidTradeOnlyExpiry
+        );
+        assert_ok!(Dex::set_trade_only_mode(RuntimeOrigin::root(), ASSET_A, Some(10)));
+        assert_eq!(Dex::trade_only_until(ASSET_A), Some(10));
+        assert_eq!(last_event(), Event::TradeOnlyModeSet(ASSET_A, Some(10)));
+
+        // Liquidity is frozen, trading continues
+        assert_noop!(
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                1_000,
+                1,
+                2_000,
+                None,
+                None
+            ),
+            Error::<Test>::TradeOnlyMode
+        );
+        assert_noop!(
+            Dex::remove_liquidity(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 1, 1, None),
+            Error::<Test>::TradeOnlyMode
+        );
+        assert_noop!(
+            Dex::remove_liquidity_with_slippage(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                ASSET_A,
+                500,
+                500,
+                500,
+                Permill::from_percent(1),
+                None
+            ),
+            Error::<Test>::TradeOnlyMode
+        );
+        // The emergency exit is exempt from the mode
+        assert_ok!(Dex::force_pause_many(RuntimeOrigin::root(), vec![ASSET_A].try_into().unwrap()));
+        assert_ok!(Dex::redeem_underlying(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 500, 1));
+        assert_ok!(Dex::force_unpause_many(
+            RuntimeOrigin::root(),
+            vec![ASSET_A].try_into().unwrap()
+        ));
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 500,
+                min_output: 1
+            },
+            None,
+            None,
+            false,
+            None
+        ));
+
+        // Liquidity streams are paused
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+        run_on_idle(10);
+        assert_eq!(
+            Dex::liquidity_streams(ASSET_A, ACCOUNT_B)
+                .unwrap()
+                .blocks_remaining,
+            2
+        );
+
+        // The mode expires automatically
+        run_on_idle(11);
+        assert_eq!(
+            Dex::liquidity_streams(ASSET_A, ACCOUNT_B)
+                .unwrap()
+                .blocks_remaining,
+            1
+        );
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            500,
+            1,
+            1,
+            None
+        ));
+
+        // The mode can always be removed
+        assert_ok!(Dex::set_trade_only_mode(RuntimeOrigin::root(), ASSET_A, Some(20)));
+        assert_ok!(Dex::set_trade_only_mode(RuntimeOrigin::root(), ASSET_A, None));
+        assert_eq!(Dex::trade_only_until(ASSET_A), None);
+        assert_ok!(Dex::set_trade_only_mode(RuntimeOrigin::root(), ASSET_B, None));
+    })
+}
+
+#[test]
+fn delisting() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_noop!(
+            Dex::delist_exchange(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            Dex::delist_exchange(RuntimeOrigin::root(), ASSET_B),
+            Error::<Test>::ExchangeNotFound
+        );
+        assert_noop!(
+            Dex::sweep_delisted_exchange(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A),
+            Error::<Test>::NotDelisted
+        );
+        assert_ok!(Dex::delist_exchange(RuntimeOrigin::root(), ASSET_A));
+        assert_eq!(Dex::delistings(ASSET_A), Some(11));
+        assert_eq!(last_event(), Event::DelistingStarted(ASSET_A, 11));
+        assert_noop!(
+            Dex::delist_exchange(RuntimeOrigin::root(), ASSET_A),
+            Error::<Test>::ExchangeDelisted
+        );
+
+        // Trading and providing liquidity are disabled, withdrawals continue
+        assert_noop!(
+            Dex::currency_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: 500,
+                    min_output: 1
+                },
+                None,
+                None,
+                false,
+                None
+            ),
+            Error::<Test>::ExchangeDelisted
+        );
+        assert_noop!(
+            Dex::add_liquidity(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                1_000,
+                1,
+                2_000,
+                None,
+                None
+            ),
+            Error::<Test>::ExchangeDelisted
+        );
+        assert_noop!(
+            Dex::stream_liquidity(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A, 1_000, 2_000, 2),
+            Error::<Test>::ExchangeDelisted
+        );
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            INIT_LIQUIDITY / 2,
+            1,
+            1,
+            None
+        ));
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+
+        // Leftover reserves can only be swept after the grace period
+        System::set_block_number(11);
+        assert_noop!(
+            Dex::sweep_delisted_exchange(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A),
+            Error::<Test>::GracePeriodNotOver
+        );
+        System::set_block_number(12);
+        assert_ok!(Dex::sweep_delisted_exchange(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::DelistedExchangeSwept(
+                    ASSET_A,
+                    RECOVERY_ACCOUNT,
+                    exchange.currency_reserve,
+                    exchange.token_reserve
+                ),
+                Event::ExchangeRemoved(ASSET_A, LIQ_TOKEN_A),
+            ]
+        );
+        assert_eq!(Balances::free_balance(RECOVERY_ACCOUNT), exchange.currency_reserve);
+        assert_eq!(Assets::balance(ASSET_A, RECOVERY_ACCOUNT), exchange.token_reserve);
+        assert_eq!(Dex::exchanges(ASSET_A), None);
+        assert_eq!(Dex::delistings(ASSET_A), None);
+    })
+}
+
+#[test]
+fn delisting_ends_liquidity_streams() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(Dex::stream_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            2_000,
+            2
+        ));
+        assert_ok!(Dex::delist_exchange(RuntimeOrigin::root(), ASSET_A));
+
+        // Nothing is streamed into the delisted exchange, the provider is refunded
+        run_on_idle(2);
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY);
+        assert_eq!(exchange.token_reserve, INIT_LIQUIDITY);
+        assert!(Dex::liquidity_streams(ASSET_A, ACCOUNT_B).is_none());
+        assert_eq!(Balances::free_balance(ACCOUNT_B), INIT_BALANCE - 1);
+        assert_eq!(Assets::maybe_balance(ASSET_A, &ACCOUNT_B), Some(INIT_BALANCE));
+        assert_eq!(last_event(), Event::LiquidityStreamEnded(ASSET_A, ACCOUNT_B, 1_000, 2_000));
+
+        // Nothing is left to be swept to the recovery account
+        System::set_block_number(12);
+        assert_ok!(Dex::sweep_delisted_exchange(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        assert_eq!(Balances::free_balance(RECOVERY_ACCOUNT), INIT_LIQUIDITY);
+        assert_eq!(Assets::balance(ASSET_A, RECOVERY_ACCOUNT), INIT_LIQUIDITY);
+    })
+}
+
+#[test]
+fn sweep_delisted_exchange_frozen_liquidity() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        // Liquidity tokens of the provider are held as collateral
+        assert_ok!(Assets::freeze(
+            RuntimeOrigin::signed(Test::pallet_account()),
+            LIQ_TOKEN_A,
+            ACCOUNT_A
+        ));
+        assert_ok!(Dex::delist_exchange(RuntimeOrigin::root(), ASSET_A));
+        System::set_block_number(12);
+        assert_ok!(Dex::sweep_delisted_exchange(RuntimeOrigin::signed(ACCOUNT_B), ASSET_A));
+        assert_eq!(Dex::exchanges(ASSET_A), None);
+
+        // The frozen liquidity tokens are not burned
+        assert_eq!(Assets::balance(LIQ_TOKEN_A, ACCOUNT_A), INIT_LIQUIDITY);
+        assert_eq!(Assets::total_issuance(LIQ_TOKEN_A), INIT_LIQUIDITY);
+        assert_eq!(
+            Dex::get_withdrawable_liquidity(&ASSET_A, &ACCOUNT_A),
+            Err(Error::<Test>::ExchangeNotFound)
+        );
+    })
+}
+
+#[test]
+fn set_peg_reference() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let peg = PegReference {
+            price: FixedU128::saturating_from_integer(1),
+            band: Permill::from_percent(1),
+            min_currency_reserve: 1_000,
+        };
+        assert_noop!(
+            Dex::set_peg_reference(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, Some(peg.clone())),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            Dex::set_peg_reference(RuntimeOrigin::root(), ASSET_B, Some(peg.clone())),
+            Error::<Test>::ExchangeNotFound
+        );
+        assert_noop!(
+            Dex::set_peg_reference(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                Some(PegReference {
+                    price: FixedU128::from_inner(0),
+                    ..peg.clone()
+                })
+            ),
+            Error::<Test>::InvalidPegReference
+        );
+        assert_noop!(
+            Dex::set_peg_reference(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                Some(PegReference {
+                    band: Permill::from_percent(100),
+                    ..peg.clone()
+                })
+            ),
+            Error::<Test>::InvalidPegReference
+        );
+        assert_ok!(Dex::set_peg_reference(RuntimeOrigin::root(), ASSET_A, Some(peg.clone())));
+        assert_eq!(Dex::peg_references(ASSET_A), Some(peg.clone()));
+        assert_eq!(last_event(), Event::PegReferenceSet(ASSET_A, Some(peg)));
+
+        // The reference can always be removed
+        assert_ok!(Dex::set_peg_reference(RuntimeOrigin::root(), ASSET_A, None));
+        assert_eq!(Dex::peg_references(ASSET_A), None);
+        assert_ok!(Dex::set_peg_reference(RuntimeOrigin::root(), ASSET_B, None));
+    })
+}
+
+#[cfg(feature = "reserve-trace")]
+#[test]
+fn reserve_trace() {
+    new_test_ext().execute_with(|| {
+        let checkpoint = |block_number, currency_in, tokens_in| {
+            let exchange = Dex::exchanges(ASSET_A).unwrap();
+            crate::ReserveCheckpoint {
+                block_number,
+                currency_reserve: exchange.currency_reserve,
+                token_reserve: exchange.token_reserve,
+                liquidity_supply: Assets::total_issuance(LIQ_TOKEN_A),
+                currency_in,
+                tokens_in,
+            }
+        };
+        // The genesis liquidity is recorded at block 0
+        assert_eq!(Dex::export_reserve_trace(ASSET_A, 0, 0), vec![checkpoint(0, 0, 0)]);
+
+        // Trades in the same block are accumulated in one checkpoint
+        System::set_block_number(1);
+        for _ in 0..2 {
+            assert_ok!(Dex::currency_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: 500,
+                    min_output: 1
+                },
+                None,
+                None,
+                false,
+                None
+            ));
+        }
+        let block_1 = checkpoint(1, 1_000, 0);
+        System::set_block_number(2);
+        assert_ok!(Dex::asset_to_currency(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 700,
+                min_output: 1
+            },
+            None,
+            None,
+            None
+        ));
+        let block_2 = checkpoint(2, 0, 700);
+        assert_eq!(
+            Dex::export_reserve_trace(ASSET_A, 1, 2),
+            vec![block_1.clone(), block_2.clone()]
+        );
+        assert_eq!(Dex::export_reserve_trace(ASSET_A, 2, 10), vec![block_2.clone()]);
+
+        // Only the last `MaxReserveCheckpoints` checkpoints are kept
+        System::set_block_number(3);
+        assert_ok!(Dex::add_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            1_000,
+            1,
+            2_000,
+            None,
+            None
+        ));
+        assert_eq!(
+            Dex::export_reserve_trace(ASSET_A, 0, 10),
+            vec![block_1, block_2, checkpoint(3, 0, 0)]
+        );
+
+        // Checkpoints are removed with the exchange
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            Assets::balance(LIQ_TOKEN_A, ACCOUNT_B),
+            1,
+            1,
+            None
+        ));
+        assert_ok!(Dex::remove_liquidity(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            INIT_LIQUIDITY,
+            1,
+            1,
+            None
+        ));
+        assert_eq!(Dex::exchanges(ASSET_A), None);
+        assert_eq!(Dex::export_reserve_trace(ASSET_A, 0, 10), vec![]);
+    })
+}
+
+#[cfg(feature = "reserve-trace")]
+#[test]
+fn reserve_trace_cursor_out_of_range() {
+    new_test_ext().execute_with(|| {
+        // As if `MaxReserveCheckpoints` was lowered from 6 to 3
+        crate::ReserveCheckpointCursors::<Test>::insert(ASSET_A, 5);
+        System::set_block_number(1);
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 500,
+                min_output: 1
+            },
+            None,
+            None,
+            false,
+            None
+        ));
+        assert!(crate::ReserveCheckpoints::<Test>::get(ASSET_A, 5).is_none());
+        assert_eq!(
+            crate::ReserveCheckpoints::<Test>::get(ASSET_A, 2)
+                .unwrap()
+                .block_number,
+            1
+        );
+        assert_eq!(crate::ReserveCheckpointCursors::<Test>::get(ASSET_A), 0);
+    })
+}
+
+#[test]
+fn force_pause_many() {
+    new_test_ext().execute_with(|| {
+        let trade = || {
+            Dex::currency_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: 1_000,
+                    min_output: 1,
+                },
+                None,
+                None,
+                false,
+                None,
+            )
+        };
+        assert_noop!(
+            Dex::force_pause_many(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                vec![ASSET_A].try_into().unwrap()
+            ),
+            frame_support::error::BadOrigin
+        );
+        // Missing exchanges are skipped, the others are still paused
+        assert_ok!(Dex::force_pause_many(
+            RuntimeOrigin::root(),
+            vec![ASSET_B, ASSET_A].try_into().unwrap()
+        ));
+        assert!(!crate::PausedExchanges::<Test>::contains_key(ASSET_B));
+        assert!(crate::PausedExchanges::<Test>::contains_key(ASSET_A));
+        assert_eq!(
+            last_n_events(2),
+            vec![Event::ExchangePaused(ASSET_A), Event::PauseSkipped(ASSET_B)]
+        );
+        assert_noop!(trade(), Error::<Test>::ExchangeSuspended);
+        // Liquidity providers can exit a paused exchange
+        assert_ok!(Dex::redeem_underlying(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, 1_000, 1));
+
+        assert_noop!(
+            Dex::force_unpause_many(
+                RuntimeOrigin::signed(ACCOUNT_A),
+                vec![ASSET_A].try_into().unwrap()
+            ),
+            frame_support::error::BadOrigin
+        );
+        assert_ok!(Dex::force_unpause_many(
+            RuntimeOrigin::root(),
+            vec![ASSET_A].try_into().unwrap()
+        ));
+        assert!(!crate::PausedExchanges::<Test>::contains_key(ASSET_A));
+        assert_eq!(last_event(), Event::ExchangeUnpaused(ASSET_A));
+        assert_ok!(trade());
+
+        // Unpausing never fails: exchanges which aren't paused are skipped
+        assert_ok!(Dex::force_unpause_many(
+            RuntimeOrigin::root(),
+            vec![ASSET_B, ASSET_A].try_into().unwrap()
+        ));
+        assert_eq!(
+            last_n_events(2),
+            vec![
+                Event::UnpauseSkipped(ASSET_B),
+                Event::UnpauseSkipped(ASSET_A)
+            ]
+        );
+    })
+}
+
+#[test]
+fn fee_share() {
+    new_test_ext().execute_with(|| {
+        let fee_share = FeeShare {
+            beneficiary: ACCOUNT_C,
+            share: Permill::from_percent(50),
+        };
+        assert_noop!(
+            Dex::set_fee_share(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ACCOUNT_B,
+                Some(fee_share.clone())
+            ),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            Dex::set_fee_share(
+                RuntimeOrigin::root(),
+                ACCOUNT_B,
+                Some(FeeShare {
+                    share: Permill::zero(),
+                    ..fee_share.clone()
+                })
+            ),
+            Error::<Test>::FeeShareIsZero
+        );
+        assert_ok!(Dex::set_fee_share(RuntimeOrigin::root(), ACCOUNT_B, Some(fee_share.clone())));
+        assert_eq!(Dex::fee_shares(ACCOUNT_B), Some(fee_share.clone()));
+        assert_eq!(last_event(), Event::FeeShareSet(ACCOUNT_B, Some(fee_share)));
+
+        // Half of the provider fee (0.3% of 100_000) is credited out of the currency reserve
+        let beneficiary_balance = Balances::free_balance(ACCOUNT_C);
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 100_000,
+                min_output: 1
+            },
+            None,
+            None,
+            false,
+            None
+        ));
+        assert_eq!(Balances::free_balance(ACCOUNT_C), beneficiary_balance + 150);
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.currency_reserve, INIT_LIQUIDITY + 100_000 - 150);
+        assert_eq!(last_event(), Event::FeeSharePaid(ASSET_A, ACCOUNT_B, ACCOUNT_C, 150, 0));
+
+        // Selling tokens, the share is credited in tokens
+        let token_reserve = exchange.token_reserve;
+        let beneficiary_tokens = Assets::balance(ASSET_A, ACCOUNT_C);
+        assert_ok!(Dex::asset_to_currency(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 100_000,
+                min_output: 1
+            },
+            None,
+            None,
+            None
+        ));
+        assert_eq!(Assets::balance(ASSET_A, ACCOUNT_C), beneficiary_tokens + 150);
+        let exchange = Dex::exchanges(ASSET_A).unwrap();
+        assert_eq!(exchange.token_reserve, token_reserve + 100_000 - 150);
+        assert_eq!(last_event(), Event::FeeSharePaid(ASSET_A, ACCOUNT_B, ACCOUNT_C, 0, 150));
+        assert_ok!(Dex::do_try_state());
+
+        // Trades of other accounts are not affected
+        let beneficiary_balance = Balances::free_balance(ACCOUNT_C);
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_A),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: 100_000,
+                min_output: 1
+            },
+            None,
+            None,
+            false,
+            None
+        ));
+        assert_eq!(Balances::free_balance(ACCOUNT_C), beneficiary_balance);
+
+        // The fee share can always be removed
+        assert_ok!(Dex::set_fee_share(RuntimeOrigin::root(), ACCOUNT_B, None));
+        assert_eq!(Dex::fee_shares(ACCOUNT_B), None);
+    })
+}
+
+#[test]
+fn set_curve() {
+    new_test_ext().execute_with(|| {
+        let curve = Curve::StableSwap(StableSwap { amplification: 100 });
+        assert_noop!(
+            Dex::set_curve(RuntimeOrigin::signed(ACCOUNT_A), ASSET_A, Some(curve.clone())),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            Dex::set_curve(RuntimeOrigin::root(), ASSET_B, Some(curve.clone())),
+            Error::<Test>::ExchangeNotFound
+        );
+        assert_noop!(
+            Dex::set_curve(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                Some(Curve::StableSwap(StableSwap { amplification: 0 }))
+            ),
+            Error::<Test>::InvalidCurve
+        );
+        let constant_product_price =
+            Dex::get_currency_to_asset_input_price(ASSET_A, INIT_LIQUIDITY / 10).unwrap();
+        assert_ok!(Dex::set_curve(RuntimeOrigin::root(), ASSET_A, Some(curve.clone())));
+        assert_eq!(Dex::curves(ASSET_A), curve);
+        assert_eq!(last_event(), Event::CurveSet(ASSET_A, Some(curve)));
+
+        // Near parity, the StableSwap curve has much less price impact than the constant product
+        let price = Dex::get_currency_to_asset_input_price(ASSET_A, INIT_LIQUIDITY / 10).unwrap();
+        assert!(price > constant_product_price);
+        assert!(price > INIT_LIQUIDITY / 10 * 99 / 100);
+        let tokens = Assets::balance(ASSET_A, ACCOUNT_B);
+        assert_ok!(Dex::currency_to_asset(
+            RuntimeOrigin::signed(ACCOUNT_B),
+            ASSET_A,
+            TradeAmount::FixedInput {
+                input_amount: INIT_LIQUIDITY / 10,
+                min_output: price
+            },
+            None,
+            None,
+            false,
+            None
+        ));
+        assert_eq!(Assets::balance(ASSET_A, ACCOUNT_B), tokens + price);
+        assert_eq!(Dex::yield_checkpoints(ASSET_A).len(), 1);
+        assert_ok!(Dex::do_try_state());
+
+        // Switching the curve of an imbalanced exchange would move its spot price
+        assert_noop!(
+            Dex::set_curve(RuntimeOrigin::root(), ASSET_A, None),
+            Error::<Test>::CurveMovesPrice
+        );
+        assert_noop!(
+            Dex::set_curve(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                Some(Curve::StableSwap(StableSwap { amplification: 10 }))
+            ),
+            Error::<Test>::CurveMovesPrice
+        );
+
+        // The curve can be reset at the balance point of the StableSwap curve
+        Exchanges::<Test>::mutate(ASSET_A, |exchange| {
+            let exchange = exchange.as_mut().unwrap();
+            exchange.token_reserve = exchange.currency_reserve;
+        });
+        assert_ok!(Dex::set_curve(RuntimeOrigin::root(), ASSET_A, None));
+        assert_eq!(Dex::curves(ASSET_A), Curve::ConstantProduct);
+        assert_eq!(last_event(), Event::CurveSet(ASSET_A, None));
+        // The yield checkpoints of the previous curve are cleared
+        assert!(Dex::yield_checkpoints(ASSET_A).is_empty());
+        // Weights other than 1:1 price the balanced exchange differently
+        assert_noop!(
+            Dex::set_curve(
+                RuntimeOrigin::root(),
+                ASSET_A,
+                Some(Curve::Weighted(Weighted {
+                    currency_weight: 1,
+                    token_weight: 2
+                }))
+            ),
+            Error::<Test>::CurveMovesPrice
+        );
+
+        // The curve of a removed exchange can always be reset
+        assert_ok!(Dex::set_curve(RuntimeOrigin::root(), ASSET_B, None));
+    })
+}
+
+#[test]
+fn lp_apy() {
+    new_test_ext().execute_with(|| {
+        let trade = || {
+            assert_ok!(Dex::currency_to_asset(
+                RuntimeOrigin::signed(ACCOUNT_B),
+                ASSET_A,
+                TradeAmount::FixedInput {
+                    input_amount: INIT_LIQUIDITY / 100,
+                    min_output: 1
+                },
+                None,
+                None,
+                false,
+                None
+            ));
+        };
+        assert_eq!(Dex::lp_apy(ASSET_A, 100), None);
+        trade();
+        assert_eq!(Dex::yield_checkpoints(ASSET_A).len(), 1);
+        assert_eq!(Dex::lp_apy(ASSET_A, 100), None);
+
+        // No checkpoint before the end of the period
+        System::set_block_number(10);
+        trade();
+        assert_eq!(Dex::yield_checkpoints(ASSET_A).len(), 1);
+
+        System::set_block_number(11);
+        trade();
+        let checkpoints = Dex::yield_checkpoints(ASSET_A);
+        assert_eq!(checkpoints.len(), 2);
+        assert_eq!(checkpoints[1].block_number, 11);
+        assert!(checkpoints[1].currency_per_liquidity > checkpoints[0].currency_per_liquidity);
+        let apy = Dex::lp_apy(ASSET_A, 100).unwrap();
+        assert!(apy > FixedU128::from_inner(0));
+        // Only the latest checkpoint in the window
+        assert_eq!(Dex::lp_apy(ASSET_A, 5), None);
+
+        // Only the last `MaxYieldCheckpoints` checkpoints are kept
+        for block_number in [21, 31] {
+            System::set_block_number(block_number);
+            trade();
+        }
+        let checkpoints = Dex::yield_checkpoints(ASSET_A);
+        assert_eq!(checkpoints.len(), 3);
+        assert_eq!(checkpoints[0].block_number, 11);
+        assert_ok!(Dex::do_try_state());
+    })
+}
+
+#[test]
+fn get_pool_accounts() {
+    new_test_ext().execute_with(|| {
+        let accounts = Dex::get_pool_accounts(&ASSET_A);
+        assert_eq!(accounts.len(), PoolSubsystem::ALL.len());
+        for (subsystem, account) in accounts {
+            assert_eq!(account, Test::pool_account(&ASSET_A, subsystem));
+            assert_eq!(
+                account,
+                DexPalletId::get().into_sub_account_truncating((subsystem.tag(), ASSET_A))
+            );
+        }
+        // Sponsorship accounts keep their original derivation
+        assert_eq!(
+            Test::pool_account(&ASSET_A, PoolSubsystem::Sponsorship),
+            DexPalletId::get().into_sub_account_truncating((b"sponsor", ASSET_A))
+        );
+        assert_eq!(Test::sponsor_account(&ASSET_A), Dex::get_pool_accounts(&ASSET_A)[0].1);
+    })
+}
+
+#[test]
+fn migrate_to_v1() {
+    use crate::migrations::MigrateToV1;
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Dex>();
+        crate::LiquidityTokens::<Test>::remove(LIQ_TOKEN_A);
+        crate::ReservesUpdatedAt::<Test>::remove(ASSET_A);
+        System::set_block_number(5);
+        MigrateToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(Dex::liquidity_tokens(LIQ_TOKEN_A), Some(ASSET_A));
+        assert_eq!(Dex::reserves_updated_at(ASSET_A), Some(5));
+        assert_eq!(Dex::on_chain_storage_version(), 1);
+
+        // Only runs once
+        crate::ReservesUpdatedAt::<Test>::remove(ASSET_A);
+        MigrateToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(Dex::reserves_updated_at(ASSET_A), None);
+    });
+}
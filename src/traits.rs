@@ -0,0 +1,29 @@
+//! Traits allowing other pallets to integrate with the DEX pallet.
+
+use crate::{AssetBalanceOf, AssetIdOf, BalanceOf, Config, Exchanges, Pallet};
+pub use substrate_dex_primitives::{InspectDex, OnPoolCreated};
+
+impl<T: Config> InspectDex<AssetIdOf<T>, BalanceOf<T>, AssetBalanceOf<T>> for Pallet<T> {
+    fn exchange_exists(asset_id: &AssetIdOf<T>) -> bool {
+        <Exchanges<T>>::contains_key(asset_id)
+    }
+
+    fn reserves(asset_id: &AssetIdOf<T>) -> Option<(BalanceOf<T>, AssetBalanceOf<T>)> {
+        <Exchanges<T>>::get(asset_id)
+            .map(|exchange| (exchange.currency_reserve, exchange.token_reserve))
+    }
+
+    fn lp_token_of(asset_id: &AssetIdOf<T>) -> Option<AssetIdOf<T>> {
+        Self::get_liquidity_token_id(asset_id)
+    }
+}
+
+/// Helper for signing RFQ quotes in benchmarks, as the pallet can't create signatures
+/// of an arbitrary `OffchainSignature` type.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait RfqBenchmarkHelper<AccountId, Signature> {
+    /// Return an account able to sign quotes.
+    fn signer() -> AccountId;
+    /// Sign `payload` with the key of the `signer` account.
+    fn sign(signer: &AccountId, payload: &[u8]) -> Signature;
+}
@@ -0,0 +1,72 @@
+use crate::{AssetIdOf, Config, ConfigHelper, EscrowedTokens, Pallet};
+use frame_support::{
+    ensure,
+    sp_runtime::traits::Zero,
+    traits::{fungibles::Inspect, Currency},
+};
+
+impl<T: Config> Pallet<T> {
+    /// Check the invariants of a single exchange. Cheap enough to be used by try-runtime
+    /// or governance tooling for verifying a suspicious pool:
+    ///   * reserves are either both zero or both non-zero,
+    ///   * liquidity token supply is zero if and only if the reserves are zero,
+    ///   * the pallet account holds at least the exchange's token and currency reserves,
+    ///   * the stream escrow sub-account holds at least the tokens escrowed by liquidity streams.
+    pub fn do_try_state_per_exchange(asset_id: &AssetIdOf<T>) -> Result<(), &'static str> {
+        let exchange = Self::get_exchange(asset_id).map_err(|_| "Exchange not found")?;
+        ensure!(&exchange.asset_id == asset_id, "Exchange stored under a wrong asset ID");
+        ensure!(
+            exchange.currency_reserve.is_zero() == exchange.token_reserve.is_zero(),
+            "Only one of the exchange reserves is zero"
+        );
+        let total_liquidity = T::AssetRegistry::total_issuance(exchange.liquidity_token_id);
+        ensure!(
+            total_liquidity.is_zero() == exchange.currency_reserve.is_zero(),
+            "Liquidity token supply does not match the exchange reserves"
+        );
+        let pallet_account = T::pallet_account();
+        ensure!(
+            T::Assets::balance(asset_id.clone(), &pallet_account) >= exchange.token_reserve,
+            "Token reserve exceeds the pallet account balance"
+        );
+        ensure!(
+            <T as Config>::Currency::free_balance(&pallet_account) >= exchange.currency_reserve,
+            "Currency reserve exceeds the pallet account balance"
+        );
+        ensure!(
+            T::Assets::balance(asset_id.clone(), &T::stream_escrow_account(asset_id))
+                >= <EscrowedTokens<T>>::get(asset_id),
+            "Escrowed tokens exceed the stream escrow account balance"
+        );
+        Ok(())
+    }
+
+    /// Check the invariants of all exchanges. Additionally to the per-exchange checks,
+    /// make sure the pallet account holds enough currency to cover all currency reserves, and the
+    /// stream escrow sub-account of each exchange enough currency to cover its liquidity streams.
+    #[cfg(any(feature = "try-runtime", test))]
+    pub(crate) fn do_try_state() -> Result<(), &'static str> {
+        use crate::{BalanceOf, Exchanges, LiquidityStreams};
+        use frame_support::sp_runtime::traits::Saturating;
+
+        let mut total_currency_reserve = <BalanceOf<T>>::zero();
+        for (asset_id, exchange) in <Exchanges<T>>::iter() {
+            Self::do_try_state_per_exchange(&asset_id)?;
+            total_currency_reserve.saturating_accrue(exchange.currency_reserve);
+            let escrowed_currency = <LiquidityStreams<T>>::iter_prefix_values(&asset_id)
+                .fold(<BalanceOf<T>>::zero(), |escrowed, stream| {
+                    escrowed.saturating_add(stream.currency_remaining)
+                });
+            ensure!(
+                <T as Config>::Currency::free_balance(&T::stream_escrow_account(&asset_id))
+                    >= escrowed_currency,
+                "Escrowed currency exceeds the stream escrow account balance"
+            );
+        }
+        ensure!(
+            <T as Config>::Currency::free_balance(&T::pallet_account()) >= total_currency_reserve,
+            "Currency reserves exceed the pallet account balance"
+        );
+        Ok(())
+    }
+}
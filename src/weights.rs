@@ -4,6 +4,11 @@
 //! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
 //! DATE: 2022-08-04, STEPS: `20`, REPEAT: 100, LOW RANGE: `[]`, HIGH RANGE: `[]`
 //! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 1024
+//!
+//! NOTE: the extrinsics added since this run, and the storage accesses added to the existing ones,
+//! are not benchmarked yet: their weights are estimates, and the `ref_time` of existing
+//! extrinsics doesn't include their new storage accesses. Regenerate this file with the command
+//! below (with the `runtime-benchmarks` feature) before relying on `SubstrateWeight`.
 
 // Executed Command:
 // ./target/release/node-template
@@ -39,10 +44,38 @@ pub trait WeightInfo {
 	fn create_exchange() -> Weight;
 	fn add_liquidity() -> Weight;
 	fn remove_liquidity() -> Weight;
+	fn remove_liquidity_with_slippage() -> Weight;
 	fn currency_to_asset() -> Weight;
 	fn asset_to_currency() -> Weight;
 	fn asset_to_asset() -> Weight;
-
+	fn sponsor_exchange() -> Weight;
+	fn withdraw_sponsorship() -> Weight;
+	fn set_max_trade_size() -> Weight;
+	fn settle_rfq() -> Weight;
+	fn register_market_maker() -> Weight;
+	fn deregister_market_maker() -> Weight;
+	fn stream_liquidity() -> Weight;
+	fn cancel_liquidity_stream() -> Weight;
+	fn execute_liquidity_stream() -> Weight;
+	fn approve_operator() -> Weight;
+	fn revoke_operator() -> Weight;
+	fn swap_from() -> Weight;
+	fn force_seed_exchange(n: u32, ) -> Weight;
+	fn redeem_underlying(e: u32, ) -> Weight;
+	fn set_creation_fee() -> Weight;
+	fn set_retention_floor() -> Weight;
+	fn set_trade_only_mode() -> Weight;
+	fn delist_exchange() -> Weight;
+	fn sweep_delisted_exchange() -> Weight;
+	fn set_peg_reference() -> Weight;
+	fn force_pause_many(n: u32, ) -> Weight;
+	fn force_unpause_many(n: u32, ) -> Weight;
+	fn set_fee_share() -> Weight;
+	fn set_curve() -> Weight;
+	fn remove_liquidity_exact_assets() -> Weight;
+	fn approve_seeding() -> Weight;
+	fn revoke_seeding() -> Weight;
+	fn quote() -> Weight;
 }
 
 /// Weight functions for `pallet_dex`.
@@ -50,56 +83,392 @@ pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: Assets Asset (r:2 w:1)
 	// Storage: Dex Exchanges (r:1 w:1)
-	// Storage: System Account (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
 	// Storage: Assets Account (r:3 w:3)
+	// Storage: Dex LiquidityTokens (r:1 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex CreationFee (r:1 w:0)
 	fn create_exchange() -> Weight {
 		Weight::from_ref_time(103_019_000)
-			.saturating_add(T::DbWeight::get().reads(7))
-			.saturating_add(T::DbWeight::get().writes(7))
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(11))
 	}
 	// Storage: Assets Asset (r:2 w:2)
 	// Storage: Assets Account (r:3 w:3)
 	// Storage: Dex Exchanges (r:1 w:1)
 	// Storage: System Account (r:1 w:1)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex TradeOnlyUntil (r:1 w:0)
 	fn add_liquidity() -> Weight {
 		Weight::from_ref_time(89_032_000)
-			.saturating_add(T::DbWeight::get().reads(7))
-			.saturating_add(T::DbWeight::get().writes(7))
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().writes(8))
 	}
 	// Storage: Dex Exchanges (r:1 w:1)
 	// Storage: Assets Asset (r:2 w:2)
 	// Storage: Assets Account (r:3 w:3)
-	// Storage: System Account (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Assets Metadata (r:1 w:1)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex LiquidityTokens (r:0 w:1)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex RetentionFloors (r:1 w:0)
+	// Storage: Dex TradeOnlyUntil (r:1 w:0)
 	fn remove_liquidity() -> Weight {
-		Weight::from_ref_time(88_652_000)
-			.saturating_add(T::DbWeight::get().reads(7))
-			.saturating_add(T::DbWeight::get().writes(7))
+		Weight::from_ref_time(121_407_000)
+			.saturating_add(T::DbWeight::get().reads(13))
+			.saturating_add(T::DbWeight::get().writes(15))
 	}
 	// Storage: Dex Exchanges (r:1 w:1)
-	// Storage: System Account (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Assets Metadata (r:1 w:1)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex LiquidityTokens (r:0 w:1)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex RetentionFloors (r:1 w:0)
+	// Storage: Dex TradeOnlyUntil (r:1 w:0)
+	fn remove_liquidity_with_slippage() -> Weight {
+		Weight::from_ref_time(121_859_000)
+			.saturating_add(T::DbWeight::get().reads(13))
+			.saturating_add(T::DbWeight::get().writes(15))
+	}
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
 	// Storage: Assets Asset (r:1 w:1)
-	// Storage: Assets Account (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex MaxTradeSizes (r:1 w:0)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex PausedExchanges (r:1 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	// Storage: Dex Curves (r:1 w:0)
+	// Storage: Dex YieldCheckpoints (r:1 w:1)
 	fn currency_to_asset() -> Weight {
 		Weight::from_ref_time(70_294_000)
-			.saturating_add(T::DbWeight::get().reads(5))
-			.saturating_add(T::DbWeight::get().writes(5))
+			.saturating_add(T::DbWeight::get().reads(15))
+			.saturating_add(T::DbWeight::get().writes(11))
 	}
 	// Storage: Dex Exchanges (r:1 w:1)
 	// Storage: Assets Asset (r:1 w:1)
 	// Storage: Assets Account (r:2 w:2)
-	// Storage: System Account (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex MaxTradeSizes (r:1 w:0)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex PausedExchanges (r:1 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	// Storage: Dex Curves (r:1 w:0)
+	// Storage: Dex YieldCheckpoints (r:1 w:1)
 	fn asset_to_currency() -> Weight {
 		Weight::from_ref_time(72_349_000)
-			.saturating_add(T::DbWeight::get().reads(5))
-			.saturating_add(T::DbWeight::get().writes(5))
+			.saturating_add(T::DbWeight::get().reads(14))
+			.saturating_add(T::DbWeight::get().writes(10))
 	}
 	// Storage: Dex Exchanges (r:2 w:2)
 	// Storage: Assets Asset (r:2 w:2)
-	// Storage: Assets Account (r:4 w:4)
+	// Storage: Assets Account (r:5 w:5)
+	// Storage: System Account (r:4 w:4)
+	// Storage: Dex Sponsorships (r:2 w:2)
+	// Storage: Dex MaxTradeSizes (r:2 w:0)
+	// Storage: Dex Bootstraps (r:2 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:2)
+	// Storage: Dex PausedExchanges (r:2 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	// Storage: Dex Curves (r:2 w:0)
+	// Storage: Dex YieldCheckpoints (r:2 w:2)
 	fn asset_to_asset() -> Weight {
 		Weight::from_ref_time(99_152_000)
+			.saturating_add(T::DbWeight::get().reads(26))
+			.saturating_add(T::DbWeight::get().writes(19))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn sponsor_exchange() -> Weight {
+		Weight::from_ref_time(41_000_000)
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn withdraw_sponsorship() -> Weight {
+		Weight::from_ref_time(38_000_000)
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	fn set_max_trade_size() -> Weight {
+		Weight::from_ref_time(19_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex MarketMakers (r:1 w:0)
+	// Storage: Dex UsedRfqNonces (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Dex MaxTradeSizes (r:1 w:0)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex PausedExchanges (r:1 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	fn settle_rfq() -> Weight {
+		Weight::from_ref_time(128_311_000)
+			.saturating_add(T::DbWeight::get().reads(14))
+			.saturating_add(T::DbWeight::get().writes(10))
+	}
+	// Storage: Dex MarketMakers (r:0 w:1)
+	fn register_market_maker() -> Weight {
+		Weight::from_ref_time(17_000_000)
+			.saturating_add(T::DbWeight::get().reads(0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex MarketMakers (r:1 w:1)
+	fn deregister_market_maker() -> Weight {
+		Weight::from_ref_time(19_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex LiquidityStreams (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex EscrowedTokens (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn stream_liquidity() -> Weight {
+		Weight::from_ref_time(61_824_000)
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().writes(7))
+	}
+	// Storage: Dex LiquidityStreams (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex EscrowedTokens (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn cancel_liquidity_stream() -> Weight {
+		Weight::from_ref_time(52_417_000)
+			.saturating_add(T::DbWeight::get().reads(7))
+			.saturating_add(T::DbWeight::get().writes(7))
+	}
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Dex LiquidityStreams (r:0 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex EscrowedTokens (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn execute_liquidity_stream() -> Weight {
+		Weight::from_ref_time(97_310_000)
+			.saturating_add(T::DbWeight::get().reads(11))
+			.saturating_add(T::DbWeight::get().writes(12))
+	}
+	// Storage: Dex OperatorApprovals (r:0 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn approve_operator() -> Weight {
+		Weight::from_ref_time(18_204_000)
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	// Storage: Dex OperatorApprovals (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn revoke_operator() -> Weight {
+		Weight::from_ref_time(19_671_000)
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	// Storage: Dex OperatorApprovals (r:1 w:1)
+	// Storage: Dex Exchanges (r:2 w:2)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:4)
+	// Storage: System Account (r:4 w:4)
+	// Storage: Dex Sponsorships (r:2 w:2)
+	// Storage: Dex MaxTradeSizes (r:2 w:0)
+	// Storage: Dex Bootstraps (r:2 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:2)
+	// Storage: Dex PausedExchanges (r:2 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	// Storage: Dex Curves (r:2 w:0)
+	// Storage: Dex YieldCheckpoints (r:2 w:2)
+	fn swap_from() -> Weight {
+		Weight::from_ref_time(104_873_000)
+			.saturating_add(T::DbWeight::get().reads(26))
+			.saturating_add(T::DbWeight::get().writes(19))
+	}
+	// Storage: Dex Exchanges (r:2 w:1)
+	// Storage: Dex SeededDeposits (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	/// The range of component `n` is `[1, 50]`.
+	fn force_seed_exchange(n: u32, ) -> Weight {
+		Weight::from_ref_time(21_384_000)
+			// Standard Error: 14_000
+			.saturating_add(Weight::from_ref_time(84_671_000).saturating_mul(n as u64))
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().reads((8 as u64).saturating_mul(n as u64)))
+			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().writes((8 as u64).saturating_mul(n as u64)))
+	}
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Dex EscrowedTokens (r:1 w:0)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:3)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex PausedExchanges (r:1 w:0)
+	/// The range of component `e` is `[1, 1000]`.
+	fn redeem_underlying(e: u32, ) -> Weight {
+		Weight::from_ref_time(112_804_000)
+			// Standard Error: 2_000
+			.saturating_add(Weight::from_ref_time(4_913_000).saturating_mul(e as u64))
+			.saturating_add(T::DbWeight::get().reads(11))
+			.saturating_add(T::DbWeight::get().reads((1 as u64).saturating_mul(e as u64)))
+			.saturating_add(T::DbWeight::get().writes(9))
+	}
+	// Storage: Dex CreationFee (r:0 w:1)
+	fn set_creation_fee() -> Weight {
+		Weight::from_ref_time(14_120_000)
+			.saturating_add(T::DbWeight::get().reads(0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex RetentionFloors (r:0 w:1)
+	fn set_retention_floor() -> Weight {
+		Weight::from_ref_time(18_870_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex TradeOnlyUntil (r:0 w:1)
+	fn set_trade_only_mode() -> Weight {
+		Weight::from_ref_time(18_870_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex Delistings (r:1 w:1)
+	fn delist_exchange() -> Weight {
+		Weight::from_ref_time(19_842_000)
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex Delistings (r:1 w:1)
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Dex Sponsorships (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex LiquidityTokens (r:0 w:1)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	// Storage: Dex RetentionFloors (r:0 w:1)
+	// Storage: Dex TradeOnlyUntil (r:0 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex SeededDeposits (r:0 w:1)
+	fn sweep_delisted_exchange() -> Weight {
+		Weight::from_ref_time(74_683_000)
 			.saturating_add(T::DbWeight::get().reads(8))
-			.saturating_add(T::DbWeight::get().writes(8))
+			.saturating_add(T::DbWeight::get().writes(14))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex PegReferences (r:0 w:1)
+	fn set_peg_reference() -> Weight {
+		Weight::from_ref_time(19_210_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex PausedExchanges (r:0 w:1)
+	/// The range of component `n` is `[1, 50]`.
+	fn force_pause_many(n: u32, ) -> Weight {
+		Weight::from_ref_time(12_071_000)
+			// Standard Error: 3_000
+			.saturating_add(Weight::from_ref_time(6_532_000).saturating_mul(n as u64))
+			.saturating_add(T::DbWeight::get().reads((1 as u64).saturating_mul(n as u64)))
+			.saturating_add(T::DbWeight::get().writes((1 as u64).saturating_mul(n as u64)))
+	}
+	// Storage: Dex PausedExchanges (r:1 w:1)
+	/// The range of component `n` is `[1, 50]`.
+	fn force_unpause_many(n: u32, ) -> Weight {
+		Weight::from_ref_time(10_348_000)
+			// Standard Error: 2_000
+			.saturating_add(Weight::from_ref_time(3_215_000).saturating_mul(n as u64))
+			.saturating_add(T::DbWeight::get().reads((1 as u64).saturating_mul(n as u64)))
+			.saturating_add(T::DbWeight::get().writes((1 as u64).saturating_mul(n as u64)))
+	}
+	// Storage: Dex FeeShares (r:0 w:1)
+	fn set_fee_share() -> Weight {
+		Weight::from_ref_time(11_853_000)
+			.saturating_add(T::DbWeight::get().reads(0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex Curves (r:1 w:1)
+	// Storage: Dex YieldCheckpoints (r:0 w:1)
+	fn set_curve() -> Weight {
+		Weight::from_ref_time(14_862_000)
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Assets Metadata (r:1 w:1)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex LiquidityTokens (r:0 w:1)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex RetentionFloors (r:1 w:0)
+	// Storage: Dex TradeOnlyUntil (r:1 w:0)
+	fn remove_liquidity_exact_assets() -> Weight {
+		Weight::from_ref_time(123_604_000)
+			.saturating_add(T::DbWeight::get().reads(13))
+			.saturating_add(T::DbWeight::get().writes(15))
+	}
+	// Storage: Dex SeedingApprovals (r:0 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn approve_seeding() -> Weight {
+		Weight::from_ref_time(18204000)
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	// Storage: Dex SeedingApprovals (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn revoke_seeding() -> Weight {
+		Weight::from_ref_time(19671000)
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex Curves (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	fn quote() -> Weight {
+		Weight::from_ref_time(21354000)
+			.saturating_add(T::DbWeight::get().reads(3))
 	}
 }
 
@@ -107,55 +476,391 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 impl WeightInfo for () {
 	// Storage: Assets Asset (r:2 w:1)
 	// Storage: Dex Exchanges (r:1 w:1)
-	// Storage: System Account (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
 	// Storage: Assets Account (r:3 w:3)
+	// Storage: Dex LiquidityTokens (r:1 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex CreationFee (r:1 w:0)
 	fn create_exchange() -> Weight {
 		Weight::from_ref_time(103_019_000)
-			.saturating_add(RocksDbWeight::get().reads(7))
-			.saturating_add(RocksDbWeight::get().writes(7))
+			.saturating_add(RocksDbWeight::get().reads(10))
+			.saturating_add(RocksDbWeight::get().writes(11))
 	}
 	// Storage: Assets Asset (r:2 w:2)
 	// Storage: Assets Account (r:3 w:3)
 	// Storage: Dex Exchanges (r:1 w:1)
 	// Storage: System Account (r:1 w:1)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex TradeOnlyUntil (r:1 w:0)
 	fn add_liquidity() -> Weight {
 		Weight::from_ref_time(89_032_000)
-			.saturating_add(RocksDbWeight::get().reads(7))
-			.saturating_add(RocksDbWeight::get().writes(7))
+			.saturating_add(RocksDbWeight::get().reads(9))
+			.saturating_add(RocksDbWeight::get().writes(8))
 	}
 	// Storage: Dex Exchanges (r:1 w:1)
 	// Storage: Assets Asset (r:2 w:2)
 	// Storage: Assets Account (r:3 w:3)
-	// Storage: System Account (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Assets Metadata (r:1 w:1)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex LiquidityTokens (r:0 w:1)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex RetentionFloors (r:1 w:0)
+	// Storage: Dex TradeOnlyUntil (r:1 w:0)
 	fn remove_liquidity() -> Weight {
-		Weight::from_ref_time(88_652_000)
-			.saturating_add(RocksDbWeight::get().reads(7))
-			.saturating_add(RocksDbWeight::get().writes(7))
+		Weight::from_ref_time(121_407_000)
+			.saturating_add(RocksDbWeight::get().reads(13))
+			.saturating_add(RocksDbWeight::get().writes(15))
 	}
 	// Storage: Dex Exchanges (r:1 w:1)
-	// Storage: System Account (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Assets Metadata (r:1 w:1)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex LiquidityTokens (r:0 w:1)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex RetentionFloors (r:1 w:0)
+	// Storage: Dex TradeOnlyUntil (r:1 w:0)
+	fn remove_liquidity_with_slippage() -> Weight {
+		Weight::from_ref_time(121_859_000)
+			.saturating_add(RocksDbWeight::get().reads(13))
+			.saturating_add(RocksDbWeight::get().writes(15))
+	}
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
 	// Storage: Assets Asset (r:1 w:1)
-	// Storage: Assets Account (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex MaxTradeSizes (r:1 w:0)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex PausedExchanges (r:1 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	// Storage: Dex Curves (r:1 w:0)
+	// Storage: Dex YieldCheckpoints (r:1 w:1)
 	fn currency_to_asset() -> Weight {
 		Weight::from_ref_time(70_294_000)
-			.saturating_add(RocksDbWeight::get().reads(5))
-			.saturating_add(RocksDbWeight::get().writes(5))
+			.saturating_add(RocksDbWeight::get().reads(15))
+			.saturating_add(RocksDbWeight::get().writes(11))
 	}
 	// Storage: Dex Exchanges (r:1 w:1)
 	// Storage: Assets Asset (r:1 w:1)
 	// Storage: Assets Account (r:2 w:2)
-	// Storage: System Account (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex MaxTradeSizes (r:1 w:0)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex PausedExchanges (r:1 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	// Storage: Dex Curves (r:1 w:0)
+	// Storage: Dex YieldCheckpoints (r:1 w:1)
 	fn asset_to_currency() -> Weight {
 		Weight::from_ref_time(72_349_000)
-			.saturating_add(RocksDbWeight::get().reads(5))
-			.saturating_add(RocksDbWeight::get().writes(5))
+			.saturating_add(RocksDbWeight::get().reads(14))
+			.saturating_add(RocksDbWeight::get().writes(10))
 	}
 	// Storage: Dex Exchanges (r:2 w:2)
 	// Storage: Assets Asset (r:2 w:2)
-	// Storage: Assets Account (r:4 w:4)
+	// Storage: Assets Account (r:5 w:5)
+	// Storage: System Account (r:4 w:4)
+	// Storage: Dex Sponsorships (r:2 w:2)
+	// Storage: Dex MaxTradeSizes (r:2 w:0)
+	// Storage: Dex Bootstraps (r:2 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:2)
+	// Storage: Dex PausedExchanges (r:2 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	// Storage: Dex Curves (r:2 w:0)
+	// Storage: Dex YieldCheckpoints (r:2 w:2)
 	fn asset_to_asset() -> Weight {
 		Weight::from_ref_time(99_152_000)
+			.saturating_add(RocksDbWeight::get().reads(26))
+			.saturating_add(RocksDbWeight::get().writes(19))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn sponsor_exchange() -> Weight {
+		Weight::from_ref_time(41_000_000)
+			.saturating_add(RocksDbWeight::get().reads(4))
+			.saturating_add(RocksDbWeight::get().writes(3))
+	}
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn withdraw_sponsorship() -> Weight {
+		Weight::from_ref_time(38_000_000)
+			.saturating_add(RocksDbWeight::get().reads(3))
+			.saturating_add(RocksDbWeight::get().writes(3))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	fn set_max_trade_size() -> Weight {
+		Weight::from_ref_time(19_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex MarketMakers (r:1 w:0)
+	// Storage: Dex UsedRfqNonces (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Dex MaxTradeSizes (r:1 w:0)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex PausedExchanges (r:1 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	fn settle_rfq() -> Weight {
+		Weight::from_ref_time(128_311_000)
+			.saturating_add(RocksDbWeight::get().reads(14))
+			.saturating_add(RocksDbWeight::get().writes(10))
+	}
+	// Storage: Dex MarketMakers (r:0 w:1)
+	fn register_market_maker() -> Weight {
+		Weight::from_ref_time(17_000_000)
+			.saturating_add(RocksDbWeight::get().reads(0))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex MarketMakers (r:1 w:1)
+	fn deregister_market_maker() -> Weight {
+		Weight::from_ref_time(19_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex LiquidityStreams (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex EscrowedTokens (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn stream_liquidity() -> Weight {
+		Weight::from_ref_time(61_824_000)
+			.saturating_add(RocksDbWeight::get().reads(9))
+			.saturating_add(RocksDbWeight::get().writes(7))
+	}
+	// Storage: Dex LiquidityStreams (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex EscrowedTokens (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn cancel_liquidity_stream() -> Weight {
+		Weight::from_ref_time(52_417_000)
+			.saturating_add(RocksDbWeight::get().reads(7))
+			.saturating_add(RocksDbWeight::get().writes(7))
+	}
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Dex LiquidityStreams (r:0 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Dex Bootstraps (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex EscrowedTokens (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn execute_liquidity_stream() -> Weight {
+		Weight::from_ref_time(97_310_000)
+			.saturating_add(RocksDbWeight::get().reads(11))
+			.saturating_add(RocksDbWeight::get().writes(12))
+	}
+	// Storage: Dex OperatorApprovals (r:0 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn approve_operator() -> Weight {
+		Weight::from_ref_time(18_204_000)
+			.saturating_add(RocksDbWeight::get().reads(2))
+			.saturating_add(RocksDbWeight::get().writes(3))
+	}
+	// Storage: Dex OperatorApprovals (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn revoke_operator() -> Weight {
+		Weight::from_ref_time(19_671_000)
+			.saturating_add(RocksDbWeight::get().reads(3))
+			.saturating_add(RocksDbWeight::get().writes(3))
+	}
+	// Storage: Dex OperatorApprovals (r:1 w:1)
+	// Storage: Dex Exchanges (r:2 w:2)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:4)
+	// Storage: System Account (r:4 w:4)
+	// Storage: Dex Sponsorships (r:2 w:2)
+	// Storage: Dex MaxTradeSizes (r:2 w:0)
+	// Storage: Dex Bootstraps (r:2 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:2)
+	// Storage: Dex PausedExchanges (r:2 w:0)
+	// Storage: Dex FeeShares (r:1 w:0)
+	// Storage: Dex Curves (r:2 w:0)
+	// Storage: Dex YieldCheckpoints (r:2 w:2)
+	fn swap_from() -> Weight {
+		Weight::from_ref_time(104_873_000)
+			.saturating_add(RocksDbWeight::get().reads(26))
+			.saturating_add(RocksDbWeight::get().writes(19))
+	}
+	// Storage: Dex Exchanges (r:2 w:1)
+	// Storage: Dex SeededDeposits (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	/// The range of component `n` is `[1, 50]`.
+	fn force_seed_exchange(n: u32, ) -> Weight {
+		Weight::from_ref_time(21_384_000)
+			// Standard Error: 14_000
+			.saturating_add(Weight::from_ref_time(84_671_000).saturating_mul(n as u64))
+			.saturating_add(RocksDbWeight::get().reads(2))
+			.saturating_add(RocksDbWeight::get().reads((8 as u64).saturating_mul(n as u64)))
+			.saturating_add(RocksDbWeight::get().writes(1))
+			.saturating_add(RocksDbWeight::get().writes((8 as u64).saturating_mul(n as u64)))
+	}
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Dex EscrowedTokens (r:1 w:0)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:3)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex PausedExchanges (r:1 w:0)
+	/// The range of component `e` is `[1, 1000]`.
+	fn redeem_underlying(e: u32, ) -> Weight {
+		Weight::from_ref_time(112_804_000)
+			// Standard Error: 2_000
+			.saturating_add(Weight::from_ref_time(4_913_000).saturating_mul(e as u64))
+			.saturating_add(RocksDbWeight::get().reads(11))
+			.saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(e as u64)))
+			.saturating_add(RocksDbWeight::get().writes(9))
+	}
+	// Storage: Dex CreationFee (r:0 w:1)
+	fn set_creation_fee() -> Weight {
+		Weight::from_ref_time(14_120_000)
+			.saturating_add(RocksDbWeight::get().reads(0))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex RetentionFloors (r:0 w:1)
+	fn set_retention_floor() -> Weight {
+		Weight::from_ref_time(18_870_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex TradeOnlyUntil (r:0 w:1)
+	fn set_trade_only_mode() -> Weight {
+		Weight::from_ref_time(18_870_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex Delistings (r:1 w:1)
+	fn delist_exchange() -> Weight {
+		Weight::from_ref_time(19_842_000)
+			.saturating_add(RocksDbWeight::get().reads(2))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex Delistings (r:1 w:1)
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Dex Sponsorships (r:1 w:0)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex LiquidityTokens (r:0 w:1)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	// Storage: Dex RetentionFloors (r:0 w:1)
+	// Storage: Dex TradeOnlyUntil (r:0 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex SeededDeposits (r:0 w:1)
+	fn sweep_delisted_exchange() -> Weight {
+		Weight::from_ref_time(74_683_000)
 			.saturating_add(RocksDbWeight::get().reads(8))
-			.saturating_add(RocksDbWeight::get().writes(8))
+			.saturating_add(RocksDbWeight::get().writes(14))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex PegReferences (r:0 w:1)
+	fn set_peg_reference() -> Weight {
+		Weight::from_ref_time(19_210_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex PausedExchanges (r:0 w:1)
+	/// The range of component `n` is `[1, 50]`.
+	fn force_pause_many(n: u32, ) -> Weight {
+		Weight::from_ref_time(12_071_000)
+			// Standard Error: 3_000
+			.saturating_add(Weight::from_ref_time(6_532_000).saturating_mul(n as u64))
+			.saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(n as u64)))
+			.saturating_add(RocksDbWeight::get().writes((1 as u64).saturating_mul(n as u64)))
+	}
+	// Storage: Dex PausedExchanges (r:1 w:1)
+	/// The range of component `n` is `[1, 50]`.
+	fn force_unpause_many(n: u32, ) -> Weight {
+		Weight::from_ref_time(10_348_000)
+			// Standard Error: 2_000
+			.saturating_add(Weight::from_ref_time(3_215_000).saturating_mul(n as u64))
+			.saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(n as u64)))
+			.saturating_add(RocksDbWeight::get().writes((1 as u64).saturating_mul(n as u64)))
+	}
+	// Storage: Dex FeeShares (r:0 w:1)
+	fn set_fee_share() -> Weight {
+		Weight::from_ref_time(11_853_000)
+			.saturating_add(RocksDbWeight::get().reads(0))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex Curves (r:1 w:1)
+	// Storage: Dex YieldCheckpoints (r:0 w:1)
+	fn set_curve() -> Weight {
+		Weight::from_ref_time(14_862_000)
+			.saturating_add(RocksDbWeight::get().reads(2))
+			.saturating_add(RocksDbWeight::get().writes(2))
+	}
+	// Storage: Dex Exchanges (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:3)
+	// Storage: System Account (r:3 w:3)
+	// Storage: Assets Metadata (r:1 w:1)
+	// Storage: Dex Sponsorships (r:1 w:1)
+	// Storage: Dex LiquidityTokens (r:0 w:1)
+	// Storage: Dex MaxTradeSizes (r:0 w:1)
+	// Storage: Dex Bootstraps (r:0 w:1)
+	// Storage: Dex ReservesUpdatedAt (r:0 w:1)
+	// Storage: Dex RetentionFloors (r:1 w:0)
+	// Storage: Dex TradeOnlyUntil (r:1 w:0)
+	fn remove_liquidity_exact_assets() -> Weight {
+		Weight::from_ref_time(123_604_000)
+			.saturating_add(RocksDbWeight::get().reads(13))
+			.saturating_add(RocksDbWeight::get().writes(15))
+	}
+	// Storage: Dex SeedingApprovals (r:0 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn approve_seeding() -> Weight {
+		Weight::from_ref_time(18204000)
+			.saturating_add(RocksDbWeight::get().reads(2))
+			.saturating_add(RocksDbWeight::get().writes(3))
+	}
+	// Storage: Dex SeedingApprovals (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Dex StorageDeposits (r:1 w:1)
+	fn revoke_seeding() -> Weight {
+		Weight::from_ref_time(19671000)
+			.saturating_add(RocksDbWeight::get().reads(3))
+			.saturating_add(RocksDbWeight::get().writes(3))
+	}
+	// Storage: Dex Exchanges (r:1 w:0)
+	// Storage: Dex Curves (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	fn quote() -> Weight {
+		Weight::from_ref_time(21354000)
+			.saturating_add(RocksDbWeight::get().reads(3))
 	}
 }
@@ -0,0 +1,105 @@
+//! Yield history of exchanges, for on-chain liquidity yield queries.
+//!
+//! The first trade on an exchange after each `YieldCheckpointPeriod` records the amounts of
+//! currency and tokens redeemed per liquidity token (see [`crate::YieldCheckpoint`]). Only the last
+//! `MaxYieldCheckpoints` checkpoints are kept per exchange. Provider fees grow the liquidity value
+//! of both amounts on the curve of the exchange (the geometric mean of the amounts for the default
+//! curve, see [`crate::curves::CurveInvariant::liquidity_value`]), while price moves keep it:
+//! [`Pallet::lp_apy`] compounds its growth to an annual yield, so structured products can prove the
+//! historical yield of a pool without an external indexer. Changing the curve of an exchange
+//! clears its checkpoints, as the liquidity values of different curves can't be compared.
+
+use crate::{
+    curves::{self, CurveInvariant},
+    AssetIdOf, Config, ConfigHelper, Curves, ExchangeOf, Pallet, YieldCheckpoint, YieldCheckpoints,
+};
+use frame_support::{
+    sp_runtime::{
+        traits::{CheckedDiv, One, Saturating, UniqueSaturatedInto, Zero},
+        FixedPointNumber, FixedU128, Rounding,
+    },
+    traits::{fungibles::Inspect, Get},
+};
+
+impl<T: Config> Pallet<T> {
+    /// Record the redemption value of a liquidity token of `exchange` (after a trade), unless the
+    /// last checkpoint of the exchange is less than `YieldCheckpointPeriod` blocks old.
+    pub(crate) fn record_yield_checkpoint(exchange: &ExchangeOf<T>) {
+        let now = <frame_system::Pallet<T>>::block_number();
+        let mut checkpoints = <YieldCheckpoints<T>>::get(&exchange.asset_id);
+        if let Some(last) = checkpoints.last() {
+            if now
+                < last
+                    .block_number
+                    .saturating_add(T::YieldCheckpointPeriod::get())
+            {
+                return;
+            }
+        }
+        let liquidity_supply =
+            T::AssetRegistry::total_issuance(exchange.liquidity_token_id.clone());
+        let currency_reserve = match T::currency_to_asset(exchange.currency_reserve) {
+            Ok(currency_reserve) => currency_reserve,
+            Err(_) => return,
+        };
+        if liquidity_supply.is_zero() || T::MaxYieldCheckpoints::get().is_zero() {
+            return;
+        }
+        if checkpoints.len() as u32 >= T::MaxYieldCheckpoints::get() {
+            checkpoints.remove(0);
+        }
+        // Can't fail, as the history isn't full anymore
+        let _ = checkpoints.try_push(YieldCheckpoint {
+            block_number: now,
+            currency_per_liquidity: FixedU128::saturating_from_rational(
+                currency_reserve,
+                liquidity_supply,
+            ),
+            tokens_per_liquidity: FixedU128::saturating_from_rational(
+                exchange.token_reserve,
+                liquidity_supply,
+            ),
+        });
+        <YieldCheckpoints<T>>::insert(&exchange.asset_id, checkpoints);
+    }
+
+    /// Get the annual yield of the liquidity of an exchange (e.g. 0.05 for 5%) over the last
+    /// `window` blocks, compounding the growth of the redemption value between the oldest and the
+    /// latest yield checkpoints in the window. The checkpoints are assumed to be a whole number of
+    /// `YieldCheckpointPeriod`s apart (rounded down, at least one). Negative yields (e.g. after
+    /// reserves were paid out of the exchange) are returned as zero. Return `None` if there are
+    /// less than two checkpoints in the window, or if the yield overflows.
+    pub fn lp_apy(asset_id: AssetIdOf<T>, window: T::BlockNumber) -> Option<FixedU128> {
+        let from = <frame_system::Pallet<T>>::block_number().saturating_sub(window);
+        let checkpoints = <YieldCheckpoints<T>>::get(&asset_id);
+        let first = checkpoints
+            .iter()
+            .find(|checkpoint| checkpoint.block_number >= from)?;
+        let last = checkpoints.last()?;
+        if last.block_number <= first.block_number {
+            return None;
+        }
+        let periods: u32 = (last.block_number - first.block_number)
+            .checked_div(&T::YieldCheckpointPeriod::get())
+            .unwrap_or_else(Zero::zero)
+            .max(One::one())
+            .unique_saturated_into();
+        let curve = <Curves<T>>::get(&asset_id);
+        let value = |checkpoint: &YieldCheckpoint<T::BlockNumber>| {
+            curve
+                .liquidity_value(
+                    checkpoint.currency_per_liquidity.into_inner(),
+                    checkpoint.tokens_per_liquidity.into_inner(),
+                )
+                .ok()
+                .map(FixedU128::from_inner)
+        };
+        let growth = value(last)?.checked_div(&value(first)?)?;
+        // Growth of the liquidity value per period, compounded over a year (powers are computed
+        // by squaring, so sparse checkpoints many periods apart are cheap to query)
+        let period_growth = curves::root_up(growth.into_inner(), periods).ok()?;
+        let annual_growth =
+            curves::pow(period_growth, T::YieldPeriodsPerYear::get(), Rounding::Down).ok()?;
+        Some(FixedU128::from_inner(annual_growth).saturating_sub(FixedU128::one()))
+    }
+}